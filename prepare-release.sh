@@ -41,7 +41,8 @@ sed -i -E "s/^${PROST_CRATE_MATCHER} = \{ version = \"${VERSION_MATCHER}\"/\1 =
   "$DIR/prost/Cargo.toml" \
   "$DIR/prost-derive/Cargo.toml" \
   "$DIR/prost-build/Cargo.toml" \
-  "$DIR/prost-types/Cargo.toml"
+  "$DIR/prost-types/Cargo.toml" \
+  "$DIR/prost-build-plugin/Cargo.toml"
 
 git commit -a -m "release ${VERSION}"
 git tag -a "v${VERSION}" -m "release ${VERSION}"
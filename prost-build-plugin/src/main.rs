@@ -0,0 +1,51 @@
+//! `protoc-gen-prost-build` is a `protoc` plugin which generates Rust code with `prost-build`.
+//!
+//! It reads a `CodeGeneratorRequest` from standard input and writes a `CodeGeneratorResponse`
+//! with one Rust file per Protobuf package to standard output, so it can be used with `protoc`,
+//! `buf generate` or Bazel instead of a Cargo build script:
+//!
+//! ```bash
+//! $ protoc --prost-build_out=src/generated --prost-build_opt=bytes=.,include_file=mod.rs -I proto proto/*.proto
+//! ```
+//!
+//! ## Parameters
+//!
+//! The plugin parameter is a comma-separated list of the options supported by
+//! [`prost_build::Config::apply_options`]. Commas and backslashes in values are escaped with a
+//! backslash, for example `type_attribute=.=#[derive(Eq\, Hash)]`. The `include_file` option
+//! generates a file which includes the generated files in a module tree.
+
+use std::io::{self, Read, Write};
+
+use prost::Message;
+use prost_types::compiler::{code_generator_response, CodeGeneratorRequest, CodeGeneratorResponse};
+
+mod parameter;
+
+fn main() -> io::Result<()> {
+    let mut request = Vec::new();
+    io::stdin().read_to_end(&mut request)?;
+
+    // An unreadable request is a problem with `protoc` rather than with the `.proto` files, so it
+    // is reported by exiting with an error.
+    let parameter = CodeGeneratorRequest::decode(request.as_slice())
+        .map_err(|error| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid CodeGeneratorRequest: {}", error),
+            )
+        })?
+        .parameter
+        .unwrap_or_default();
+
+    let mut config = prost_build::Config::new();
+    let response = parameter::configure(&mut config, &parameter)
+        .and_then(|()| config.compile_request(&request))
+        .unwrap_or_else(|error| CodeGeneratorResponse {
+            error: Some(error.to_string()),
+            supported_features: Some(code_generator_response::Feature::Proto3Optional as u64),
+            file: Vec::new(),
+        });
+
+    io::stdout().write_all(&response.encode_to_vec())
+}
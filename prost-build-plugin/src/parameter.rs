@@ -0,0 +1,116 @@
+//! Maps the plugin parameter onto `prost_build::Config` options.
+
+use std::io::Result;
+
+use prost_build::Config;
+
+/// Configures `config` from a comma-separated plugin parameter.
+pub(crate) fn configure(config: &mut Config, parameter: &str) -> Result<()> {
+    config.apply_options(split(parameter).iter().filter(|option| !option.is_empty()))?;
+    Ok(())
+}
+
+/// Splits a parameter on commas, unescaping `\,` and `\\`.
+fn split(parameter: &str) -> Vec<String> {
+    let mut options = vec![String::new()];
+    let mut chars = parameter.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped @ (',' | '\\')) => options.last_mut().unwrap().push(escaped),
+                Some(other) => {
+                    let option = options.last_mut().unwrap();
+                    option.push('\\');
+                    option.push(other);
+                }
+                None => options.last_mut().unwrap().push('\\'),
+            },
+            ',' => options.push(String::new()),
+            c => options.last_mut().unwrap().push(c),
+        }
+    }
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use prost::Message;
+    use prost_types::compiler::CodeGeneratorRequest;
+    use prost_types::field_descriptor_proto::{Label, Type};
+    use prost_types::{DescriptorProto, FieldDescriptorProto, FileDescriptorProto};
+
+    #[test]
+    fn split_parameter() {
+        assert_eq!(split(""), vec![""]);
+        assert_eq!(
+            split(r"bytes=.,type_attribute=.=#[derive(Eq\, Hash)]"),
+            vec!["bytes=.", "type_attribute=.=#[derive(Eq, Hash)]"]
+        );
+        assert_eq!(split(r"a\\,b\n"), vec![r"a\", r"b\n"]);
+    }
+
+    #[test]
+    fn invalid_parameter() {
+        let error = configure(&mut Config::new(), "bytes=.,unknown").unwrap_err();
+        assert_eq!(error.to_string(), "unknown option `unknown`");
+    }
+
+    fn request(parameter: &str) -> Vec<u8> {
+        let file = |name: &str, package: &str| FileDescriptorProto {
+            name: Some(name.to_string()),
+            package: Some(package.to_string()),
+            message_type: vec![DescriptorProto {
+                name: Some("Message".to_string()),
+                field: vec![FieldDescriptorProto {
+                    name: Some("data".to_string()),
+                    number: Some(1),
+                    label: Some(Label::Optional as i32),
+                    r#type: Some(Type::Bytes as i32),
+                    json_name: Some("data".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            syntax: Some("proto3".to_string()),
+            ..Default::default()
+        };
+        CodeGeneratorRequest {
+            file_to_generate: vec!["foo/a.proto".to_string(), "foo/bar/b.proto".to_string()],
+            parameter: Some(parameter.to_string()),
+            proto_file: vec![
+                file("dependency.proto", "dependency"),
+                file("foo/a.proto", "foo"),
+                file("foo/bar/b.proto", "foo.bar"),
+            ],
+            compiler_version: None,
+        }
+        .encode_to_vec()
+    }
+
+    #[test]
+    fn generate_files() {
+        let mut config = Config::new();
+        configure(
+            &mut config,
+            r"bytes=.foo.bar,include_file=mod.rs,type_attribute=.foo.Message=#[derive(Eq\, Hash)]",
+        )
+        .unwrap();
+        let response = config.compile_request(&request("")).unwrap();
+
+        assert_eq!(response.error, None);
+        let files = response
+            .file
+            .iter()
+            .map(|file| (file.name(), file.content()))
+            .collect::<Vec<_>>();
+        let names = files.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        assert_eq!(names, ["foo.bar.rs", "foo.rs", "mod.rs"]);
+
+        assert!(files[0].1.contains("::prost::bytes::Bytes"));
+        assert!(files[1].1.contains("#[derive(Eq, Hash)]"));
+        assert!(files[1].1.contains("::prost::alloc::vec::Vec<u8>"));
+        assert!(files[2].1.contains("include!(\"foo.bar.rs\");"));
+    }
+}
@@ -43,6 +43,31 @@ impl Comments {
     ///
     /// Each level of indentation corresponds to four space (' ') characters.
     pub fn append_with_indent(&self, indent_level: u8, buf: &mut String) {
+        self.append(indent_level, buf, &|_| None, false);
+    }
+
+    /// Appends the comments to a buffer with indentation, turning references in square brackets
+    /// and plain mentions of types into intra-doc links.
+    ///
+    /// `resolve` maps the text of a reference to the path of the item it links to. References in
+    /// square brackets which don't resolve are escaped, and plain mentions which don't resolve
+    /// are left as they are. Mentions in code spans and code blocks are not linked.
+    pub(crate) fn append_with_links(
+        &self,
+        indent_level: u8,
+        buf: &mut String,
+        resolve: &dyn Fn(&str) -> Option<String>,
+    ) {
+        self.append(indent_level, buf, resolve, true);
+    }
+
+    fn append(
+        &self,
+        indent_level: u8,
+        buf: &mut String,
+        resolve: &dyn Fn(&str) -> Option<String>,
+        link_mentions: bool,
+    ) {
         // Append blocks of detached comments.
         for detached_block in &self.leading_detached {
             for line in detached_block {
@@ -50,19 +75,25 @@ impl Comments {
                     buf.push_str("    ");
                 }
                 buf.push_str("//");
-                buf.push_str(&Self::sanitize_line(line));
+                buf.push_str(&Self::sanitize_line(line, &|_| None));
                 buf.push('\n');
             }
             buf.push('\n');
         }
 
         // Append leading comments.
+        let mut in_code_block = false;
         for line in &self.leading {
             for _ in 0..indent_level {
                 buf.push_str("    ");
             }
             buf.push_str("///");
-            buf.push_str(&Self::sanitize_line(line));
+            buf.push_str(&Self::sanitize_doc_line(
+                line,
+                resolve,
+                link_mentions,
+                &mut in_code_block,
+            ));
             buf.push('\n');
         }
 
@@ -75,16 +106,84 @@ impl Comments {
         }
 
         // Append trailing comments.
+        let mut in_code_block = false;
         for line in &self.trailing {
             for _ in 0..indent_level {
                 buf.push_str("    ");
             }
             buf.push_str("///");
-            buf.push_str(&Self::sanitize_line(line));
+            buf.push_str(&Self::sanitize_doc_line(
+                line,
+                resolve,
+                link_mentions,
+                &mut in_code_block,
+            ));
             buf.push('\n');
         }
     }
 
+    /// Sanitizes a line of documentation, linking references with `resolve`, and plain mentions
+    /// of types outside of fenced code blocks if `link_mentions` is set. `in_code_block` tracks
+    /// whether the line is in a fenced code block.
+    fn sanitize_doc_line(
+        line: &str,
+        resolve: &dyn Fn(&str) -> Option<String>,
+        link_mentions: bool,
+        in_code_block: &mut bool,
+    ) -> String {
+        if !link_mentions {
+            return Self::sanitize_line(line, resolve);
+        }
+        if line.trim_start().starts_with("```") {
+            *in_code_block = !*in_code_block;
+            return Self::sanitize_line(line, resolve);
+        }
+        let sanitized = Self::sanitize_line(line, resolve);
+        if *in_code_block {
+            sanitized
+        } else {
+            Self::link_mentions(&sanitized, resolve)
+        }
+    }
+
+    /// Links the mentions of types in a sanitized line to the path returned by `resolve`, if any.
+    ///
+    /// Mentions are qualified names, like `foo.Bar`, and names in code spans, like `` `Bar` ``.
+    /// Other words are never linked, even if they match the name of a type, since they are usually
+    /// prose, like "Status" at the start of a sentence. Names in autolinks and square brackets are
+    /// skipped.
+    fn link_mentions(line: &str, resolve: &dyn Fn(&str) -> Option<String>) -> String {
+        static RULE_MENTION: Lazy<Regex> = Lazy::new(|| {
+            Regex::new(
+                r"`(?P<code>[^`]*)`|<[^>]*>|\\?\[[^\]]*\](\([^)]*\)|\[[^\]]*\])?|(?P<name>\.?[A-Za-z_][A-Za-z0-9_]*(\.[A-Za-z_][A-Za-z0-9_]*)+)",
+            )
+            .unwrap()
+        });
+        static RULE_NAME: Lazy<Regex> = Lazy::new(|| {
+            Regex::new(r"^\.?[A-Za-z_][A-Za-z0-9_]*(\.[A-Za-z_][A-Za-z0-9_]*)*$").unwrap()
+        });
+
+        RULE_MENTION
+            .replace_all(line, |captures: &regex::Captures<'_>| {
+                if let Some(name) = captures.name("name").map(|name| name.as_str()) {
+                    return match resolve(name) {
+                        Some(path) if path == name => format!("[{}]", name),
+                        Some(path) => format!("[{}]({})", name, path),
+                        None => name.to_string(),
+                    };
+                }
+                match captures.name("code").map(|code| code.as_str()) {
+                    Some(name) if RULE_NAME.is_match(name) => match resolve(name) {
+                        Some(path) if path == name => format!("[`{}`]", name),
+                        Some(path) => format!("[`{}`]({})", name, path),
+                        None => captures[0].to_string(),
+                    },
+                    _ => captures[0].to_string(),
+                }
+            })
+            .to_string()
+    }
+
     /// Checks whether a RustDoc line should be indented.
     ///
     /// Lines should be indented if:
@@ -108,14 +207,29 @@ impl Comments {
 
     /// Sanitizes the line for rustdoc by performing the following operations:
     ///     - escape urls as <http://foo.com>
+    ///     - link `[foo.Bar]` to the path returned by `resolve`, if any
     ///     - escape `[` & `]` if not already escaped and not followed by a parenthesis or bracket
-    fn sanitize_line(line: &str) -> String {
+    fn sanitize_line(line: &str, resolve: &dyn Fn(&str) -> Option<String>) -> String {
         static RULE_URL: Lazy<Regex> = Lazy::new(|| Regex::new(r"https?://[^\s)]+").unwrap());
         static RULE_BRACKETS: Lazy<Regex> =
             Lazy::new(|| Regex::new(r"(^|[^\]\\])\[(([^\]]*[^\\])?)\]([^(\[]|$)").unwrap());
 
         let mut s = RULE_URL.replace_all(line, r"<$0>").to_string();
-        s = RULE_BRACKETS.replace_all(&s, r"$1\[$2\]$4").to_string();
+        s = RULE_BRACKETS
+            .replace_all(&s, |captures: &regex::Captures<'_>| {
+                let text = captures.get(2).map_or("", |text| text.as_str());
+                match resolve(text) {
+                    // An explicit target matching the text is redundant.
+                    Some(path) if path == text => {
+                        format!("{}[{}]{}", &captures[1], text, &captures[4])
+                    }
+                    Some(path) => {
+                        format!("{}[{}]({}){}", &captures[1], text, path, &captures[4])
+                    }
+                    None => format!("{}\\[{}\\]{}", &captures[1], text, &captures[4]),
+                }
+            })
+            .to_string();
         if Self::should_indent(&s) {
             s.insert(0, ' ');
         }
@@ -165,6 +279,93 @@ pub struct Method {
     pub server_streaming: bool,
 }
 
+/// A message descriptor.
+#[derive(Debug, Clone)]
+pub struct Message {
+    /// The message name in Rust style.
+    pub name: String,
+    /// The message name as it appears in the .proto file.
+    pub proto_name: String,
+    /// The package name as it appears in the .proto file.
+    pub package: String,
+    /// The fully-qualified message name, without a leading period.
+    pub full_name: String,
+    /// The path of the Rust type, relative to the module of the package.
+    pub rust_path: String,
+    /// The message comments.
+    pub comments: Comments,
+    /// The message fields, excluding the members of oneofs.
+    pub fields: Vec<MessageField>,
+    /// The message oneofs.
+    pub oneofs: Vec<Oneof>,
+    /// The message descriptor.
+    pub descriptor: prost_types::DescriptorProto,
+}
+
+/// A message field descriptor.
+#[derive(Debug, Clone)]
+pub struct MessageField {
+    /// The field name in Rust style, or the variant name for members of a oneof.
+    pub name: String,
+    /// The field name as it appears in the .proto file.
+    pub proto_name: String,
+    /// The field comments.
+    pub comments: Comments,
+    /// The field descriptor.
+    pub descriptor: prost_types::FieldDescriptorProto,
+}
+
+/// A oneof descriptor.
+#[derive(Debug, Clone)]
+pub struct Oneof {
+    /// The name of the message field holding the oneof, in Rust style.
+    pub name: String,
+    /// The oneof name as it appears in the .proto file.
+    pub proto_name: String,
+    /// The path of the Rust enum, relative to the module of the package.
+    pub rust_path: String,
+    /// The oneof comments.
+    pub comments: Comments,
+    /// The oneof members.
+    pub fields: Vec<MessageField>,
+    /// The oneof descriptor.
+    pub descriptor: prost_types::OneofDescriptorProto,
+}
+
+/// An enum descriptor.
+#[derive(Debug, Clone)]
+pub struct Enum {
+    /// The enum name in Rust style.
+    pub name: String,
+    /// The enum name as it appears in the .proto file.
+    pub proto_name: String,
+    /// The package name as it appears in the .proto file.
+    pub package: String,
+    /// The fully-qualified enum name, without a leading period.
+    pub full_name: String,
+    /// The path of the Rust type, relative to the module of the package.
+    pub rust_path: String,
+    /// The enum comments.
+    pub comments: Comments,
+    /// The enum variants. Aliases of an earlier value are not included.
+    pub variants: Vec<EnumVariant>,
+    /// The enum descriptor.
+    pub descriptor: prost_types::EnumDescriptorProto,
+}
+
+/// An enum value descriptor.
+#[derive(Debug, Clone)]
+pub struct EnumVariant {
+    /// The variant name in Rust style.
+    pub name: String,
+    /// The value name as it appears in the .proto file.
+    pub proto_name: String,
+    /// The value number.
+    pub number: i32,
+    /// The value comments.
+    pub comments: Comments,
+}
+
 #[cfg(not(feature = "cleanup-markdown"))]
 fn get_lines<S>(comments: S) -> Vec<String>
 where
@@ -403,6 +604,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_comment_append_with_links() {
+        let input = Comments {
+            leading_detached: vec![vec![" See [Bar].".to_string()]],
+            leading: vec![
+                " See [Bar], [Baz] and [foo.Baz], but not [Qux].".to_string(),
+                " Already linked: [Bar](Bar), [Bar][bar].".to_string(),
+                " Mentions: Bar, foo.Baz, `Baz`, `foo.Baz()`, `Qux`, <Baz> and baz.".to_string(),
+                " ```".to_string(),
+                " Baz".to_string(),
+                " ```".to_string(),
+            ],
+            trailing: vec![],
+        };
+
+        let mut actual = String::new();
+        input.append_with_links(0, &mut actual, &|text| match text {
+            "Bar" => Some("Bar".to_string()),
+            "Baz" | "baz" => Some("foo::Baz".to_string()),
+            "foo.Baz" => Some("super::foo::Baz".to_string()),
+            _ => None,
+        });
+
+        assert_eq!(
+            actual,
+            "// See \\[Bar\\].\n\n\
+             /// See [Bar], [Baz](foo::Baz) and [foo.Baz](super::foo::Baz), but not \\[Qux\\].\n\
+             /// Already linked: [Bar](Bar), [Bar][bar].\n\
+             /// Mentions: Bar, [foo.Baz](super::foo::Baz), [`Baz`](foo::Baz), `foo.Baz()`, `Qux`, <Baz> and baz.\n\
+             /// ```\n\
+             /// Baz\n\
+             /// ```\n"
+        );
+    }
+
     #[test]
     fn test_codeblocks() {
         struct TestCase {
@@ -0,0 +1,226 @@
+//! A minimal line-based diff, used to show why checked-in code is out of date.
+
+use std::fmt::Write;
+
+/// The number of unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+
+/// The largest number of line pairs which are compared to find a minimal diff. Beyond this, the
+/// changed region is shown as entirely replaced.
+const MAX_COMPARISONS: usize = 16 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Line<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+/// Returns a unified diff between `old` and `new`, or an empty string if they are the same.
+pub(crate) fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    let lines = diff_lines(old, new);
+    let mut output = String::new();
+    if lines.iter().all(|line| matches!(line, Line::Equal(_))) {
+        return output;
+    }
+    writeln!(output, "--- {}", old_name).unwrap();
+    writeln!(output, "+++ {}", new_name).unwrap();
+
+    let mut i = 0;
+    // The line numbers of `lines[i]` in the old and new text.
+    let (mut old_line, mut new_line) = (0, 0);
+    while i < lines.len() {
+        let Some(change) = lines[i..]
+            .iter()
+            .position(|line| !matches!(line, Line::Equal(_)))
+        else {
+            break;
+        };
+
+        // Extend the hunk until there are more than twice the context of unchanged lines.
+        let start = (i + change).saturating_sub(CONTEXT).max(i);
+        let mut end = i + change;
+        let mut equal = 0;
+        for (j, line) in lines.iter().enumerate().skip(i + change) {
+            if matches!(line, Line::Equal(_)) {
+                equal += 1;
+                if equal > 2 * CONTEXT {
+                    break;
+                }
+            } else {
+                equal = 0;
+                end = j + 1;
+            }
+        }
+        let end = (end + CONTEXT).min(lines.len());
+
+        for line in &lines[i..start] {
+            advance(line, &mut old_line, &mut new_line);
+        }
+        let hunk = &lines[start..end];
+        let old_len = hunk
+            .iter()
+            .filter(|line| !matches!(line, Line::Insert(_)))
+            .count();
+        let new_len = hunk
+            .iter()
+            .filter(|line| !matches!(line, Line::Delete(_)))
+            .count();
+        writeln!(
+            output,
+            "@@ -{} +{} @@",
+            range(old_line, old_len),
+            range(new_line, new_len)
+        )
+        .unwrap();
+        for line in hunk {
+            match line {
+                Line::Equal(text) => writeln!(output, " {}", text),
+                Line::Delete(text) => writeln!(output, "-{}", text),
+                Line::Insert(text) => writeln!(output, "+{}", text),
+            }
+            .unwrap();
+            advance(line, &mut old_line, &mut new_line);
+        }
+        i = end;
+    }
+    output
+}
+
+fn advance(line: &Line<'_>, old_line: &mut usize, new_line: &mut usize) {
+    match line {
+        Line::Equal(_) => {
+            *old_line += 1;
+            *new_line += 1;
+        }
+        Line::Delete(_) => *old_line += 1,
+        Line::Insert(_) => *new_line += 1,
+    }
+}
+
+/// Formats a hunk range, where `start` is zero-based.
+fn range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+/// Computes a line diff, using the longest common subsequence of the lines which differ.
+fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<Line<'a>> {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+
+    let prefix = old
+        .iter()
+        .zip(&new)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let old_changed = &old[prefix..old.len() - suffix];
+    let new_changed = &new[prefix..new.len() - suffix];
+
+    let mut lines = old[..prefix]
+        .iter()
+        .map(|line| Line::Equal(line))
+        .collect::<Vec<_>>();
+    if old_changed.len() * new_changed.len() > MAX_COMPARISONS {
+        lines.extend(old_changed.iter().map(|line| Line::Delete(line)));
+        lines.extend(new_changed.iter().map(|line| Line::Insert(line)));
+    } else {
+        lines.extend(longest_common_subsequence(old_changed, new_changed));
+    }
+    lines.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|line| Line::Equal(line)),
+    );
+    lines
+}
+
+fn longest_common_subsequence<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Line<'a>> {
+    // `lengths[i][j]` is the length of the longest common subsequence of `old[i..]` and
+    // `new[j..]`.
+    let width = new.len() + 1;
+    let mut lengths = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * width + j] = if old[i] == new[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::with_capacity(old.len() + new.len());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            lines.push(Line::Equal(old[i]));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            lines.push(Line::Delete(old[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Insert(new[j]));
+            j += 1;
+        }
+    }
+    lines.extend(old[i..].iter().map(|line| Line::Delete(line)));
+    lines.extend(new[j..].iter().map(|line| Line::Insert(line)));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "old", "new"), "");
+    }
+
+    #[test]
+    fn changed_line() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n";
+        assert_eq!(
+            unified_diff(old, new, "old", "new"),
+            "--- old\n+++ new\n@@ -2,7 +2,7 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n"
+        );
+    }
+
+    #[test]
+    fn separate_hunks() {
+        let old = (1..=20).map(|i| format!("{}\n", i)).collect::<String>();
+        let new = (1..=20)
+            .filter(|i| *i != 19)
+            .map(|i| match i {
+                2 => "two\n".to_string(),
+                i => format!("{}\n", i),
+            })
+            .collect::<String>();
+        assert_eq!(
+            unified_diff(&old, &new, "old", "new"),
+            "--- old\n+++ new\n\
+             @@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n 4\n 5\n\
+             @@ -16,5 +16,4 @@\n 16\n 17\n 18\n-19\n 20\n"
+        );
+    }
+
+    #[test]
+    fn added_file() {
+        assert_eq!(
+            unified_diff("", "a\nb\n", "old", "new"),
+            "--- old\n+++ new\n@@ -0,0 +1,2 @@\n+a\n+b\n"
+        );
+    }
+}
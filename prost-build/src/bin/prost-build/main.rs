@@ -0,0 +1,379 @@
+//! `prost-build` generates Rust code from `.proto` files outside of a Cargo build script, for
+//! projects which check the generated code into their source tree.
+//!
+//! ```text
+//! prost-build [OPTIONS] --out-dir <DIR> <PROTO>...
+//! ```
+//!
+//! One Rust file is written per Protobuf package, along with a `mod.rs` which includes them in a
+//! module tree, unless the `include_file` option names a different file. The generated files are
+//! listed in a `.prost-build-manifest` file, so that the files of a previous run which are no
+//! longer produced are removed; other files in the output directory are never touched. With
+//! `--check`, nothing is written; instead, a diff is printed and the exit status is non-zero if
+//! the output directory is not up to date.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use prost_build::Config;
+
+mod diff;
+
+const USAGE: &str = "\
+Usage: prost-build [OPTIONS] --out-dir <DIR> <PROTO>...
+
+Options:
+  -I, --include <DIR>    Adds a directory to search for imports
+  -c, --config <FILE>    Reads `prost_build::Config` options from a file, one per line
+      --option <OPTION>  Applies a `prost_build::Config` option, like `bytes=.`
+  -o, --out-dir <DIR>    Writes the generated files to a directory
+      --check            Checks that the output directory is up to date without writing to it
+  -h, --help             Prints this message
+";
+
+/// The file listing the files generated in the output directory, one per line.
+const MANIFEST: &str = ".prost-build-manifest";
+
+/// The first line of the manifest, followed by the names of the generated files.
+const MANIFEST_HEADER: &str =
+    "# Files generated by prost-build, which it removes once they are no \
+                               longer generated.";
+
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    protos: Vec<PathBuf>,
+    includes: Vec<PathBuf>,
+    config: Option<PathBuf>,
+    options: Vec<String>,
+    out_dir: PathBuf,
+    check: bool,
+}
+
+impl Args {
+    /// Parses the command-line arguments, or returns `None` if help was requested.
+    fn parse(args: impl IntoIterator<Item = String>) -> std::result::Result<Option<Args>, String> {
+        let mut parsed = Args::default();
+        let mut out_dir = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+                _ => match arg.strip_prefix("-I") {
+                    Some(value) if !value.is_empty() => ("-I", Some(value.to_string())),
+                    _ => (arg.as_str(), None),
+                },
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("missing value for `{}`", flag))
+            };
+            match flag {
+                "-I" | "--include" => parsed.includes.push(value()?.into()),
+                "-c" | "--config" => parsed.config = Some(value()?.into()),
+                "--option" => parsed.options.push(value()?),
+                "-o" | "--out-dir" => out_dir = Some(value()?.into()),
+                "--check" => parsed.check = true,
+                "-h" | "--help" => return Ok(None),
+                flag if flag.starts_with('-') => {
+                    return Err(format!("unknown argument `{}`", flag))
+                }
+                _ => parsed.protos.push(arg.into()),
+            }
+        }
+
+        parsed.out_dir = out_dir.ok_or("missing `--out-dir`")?;
+        if parsed.protos.is_empty() {
+            return Err("no `.proto` files given".to_string());
+        }
+        Ok(Some(parsed))
+    }
+}
+
+/// Reads the options in a config file, ignoring blank lines and `#` comments.
+fn read_config(path: &Path) -> Result<Vec<String>> {
+    let contents = fs::read_to_string(path).map_err(|error| {
+        Error::new(
+            error.kind(),
+            format!("failed to read {}: {}", path.display(), error),
+        )
+    })?;
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// A generated file whose contents differ from the output directory.
+struct Change {
+    file_name: String,
+    old: Option<String>,
+    new: Option<String>,
+}
+
+/// Generates code, and returns the files in the output directory which need to change.
+fn changes(args: &Args) -> Result<Vec<Change>> {
+    let mut config = Config::new();
+    config.include_file("mod.rs");
+    if let Some(path) = &args.config {
+        config.apply_options(read_config(path)?)?;
+    }
+    config.apply_options(&args.options)?;
+
+    let tempdir = tempfile::tempdir()?;
+    config
+        .out_dir(tempdir.path())
+        .compile_protos(&args.protos, &args.includes)?;
+
+    let mut generated = BTreeMap::new();
+    for file_name in file_names(tempdir.path())? {
+        let contents = fs::read_to_string(tempdir.path().join(&file_name))?;
+        generated.insert(file_name, contents);
+    }
+    let mut manifest = format!("{}\n", MANIFEST_HEADER);
+    for file_name in generated.keys() {
+        manifest.push_str(file_name);
+        manifest.push('\n');
+    }
+
+    // Files listed in the manifest of a previous run, for packages which no longer exist, are
+    // stale. Only names of files directly in the output directory are trusted.
+    let old_manifest = read_if_exists(&args.out_dir.join(MANIFEST))?;
+    let stale = old_manifest
+        .iter()
+        .flat_map(|manifest| manifest.lines().skip(1))
+        .filter(|file_name| {
+            !file_name.is_empty()
+                && !file_name.contains(['/', '\\'])
+                && *file_name != ".."
+                && !generated.contains_key(*file_name)
+        })
+        .map(str::to_string)
+        .collect::<BTreeSet<_>>();
+
+    let mut changes = Vec::new();
+    for (file_name, new) in generated
+        .into_iter()
+        .chain([(MANIFEST.to_string(), manifest)])
+    {
+        let old = read_if_exists(&args.out_dir.join(&file_name))?;
+        if old.as_ref() != Some(&new) {
+            changes.push(Change {
+                file_name,
+                old,
+                new: Some(new),
+            });
+        }
+    }
+    for file_name in stale {
+        if let Some(old) = read_if_exists(&args.out_dir.join(&file_name))? {
+            changes.push(Change {
+                file_name,
+                old: Some(old),
+                new: None,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+/// Returns the sorted names of the files in a directory.
+fn file_names(dir: &Path) -> Result<Vec<String>> {
+    let mut file_names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            file_names.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+    file_names.sort();
+    Ok(file_names)
+}
+
+fn read_if_exists(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+/// Writes the changes to the output directory.
+fn write(out_dir: &Path, changes: &[Change]) -> Result<()> {
+    fs::create_dir_all(out_dir)?;
+    for change in changes {
+        let path = out_dir.join(&change.file_name);
+        match &change.new {
+            Some(new) => fs::write(&path, new)?,
+            None => fs::remove_file(&path)?,
+        }
+    }
+    Ok(())
+}
+
+/// Returns a diff of the changes.
+fn diff(out_dir: &Path, changes: &[Change]) -> String {
+    let mut output = String::new();
+    for change in changes {
+        let path = out_dir.join(&change.file_name);
+        let path = path.display();
+        let old_name = match change.old {
+            Some(_) => path.to_string(),
+            None => "/dev/null".to_string(),
+        };
+        let new_name = match change.new {
+            Some(_) => format!("{} (generated)", path),
+            None => "/dev/null".to_string(),
+        };
+        output.push_str(&diff::unified_diff(
+            change.old.as_deref().unwrap_or_default(),
+            change.new.as_deref().unwrap_or_default(),
+            &old_name,
+            &new_name,
+        ));
+    }
+    output
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("error: {}\n\n{}", error, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = changes(&args).and_then(|changes| {
+        if args.check {
+            print!("{}", diff(&args.out_dir, &changes));
+            Ok(changes.is_empty())
+        } else {
+            write(&args.out_dir, &changes).map(|()| true)
+        }
+    });
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => {
+            eprintln!(
+                "error: the generated code in {} is out of date; run prost-build without `--check` \
+                 to update it",
+                args.out_dir.display()
+            );
+            ExitCode::FAILURE
+        }
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> std::result::Result<Option<Args>, String> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parse_args() {
+        assert_eq!(
+            parse(&[
+                "-Iproto",
+                "--include",
+                "third_party",
+                "--config=prost.conf",
+                "--option",
+                "bytes=.",
+                "-o",
+                "src/generated",
+                "--check",
+                "proto/a.proto",
+                "proto/b.proto",
+            ]),
+            Ok(Some(Args {
+                protos: vec!["proto/a.proto".into(), "proto/b.proto".into()],
+                includes: vec!["proto".into(), "third_party".into()],
+                config: Some("prost.conf".into()),
+                options: vec!["bytes=.".to_string()],
+                out_dir: "src/generated".into(),
+                check: true,
+            }))
+        );
+        assert_eq!(parse(&["--help"]), Ok(None));
+        assert_eq!(parse(&["a.proto"]), Err("missing `--out-dir`".to_string()));
+        assert_eq!(
+            parse(&["-o", "out"]),
+            Err("no `.proto` files given".to_string())
+        );
+        assert_eq!(parse(&["-o"]), Err("missing value for `-o`".to_string()));
+        assert_eq!(
+            parse(&["--unknown"]),
+            Err("unknown argument `--unknown`".to_string())
+        );
+    }
+
+    #[test]
+    fn check_and_write() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let out_dir = tempdir.path().join("generated");
+        let config = tempdir.path().join("prost.conf");
+        fs::write(&config, "# Comments are ignored.\n\nbtree_map=.\n").unwrap();
+        let args = Args {
+            protos: vec!["src/fixtures/alphabet/a.proto".into()],
+            includes: vec!["src/fixtures/alphabet".into()],
+            config: Some(config),
+            options: vec!["default_package_filename=root".to_string()],
+            out_dir: out_dir.clone(),
+            check: true,
+        };
+
+        // Nothing has been generated yet.
+        let initial = changes(&args).unwrap();
+        let file_names = initial
+            .iter()
+            .map(|change| change.file_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(file_names, ["a.rs", "mod.rs", MANIFEST]);
+        assert!(diff(&out_dir, &initial).contains("+pub mod a {"));
+
+        write(&out_dir, &initial).unwrap();
+        assert!(changes(&args).unwrap().is_empty());
+
+        // Edited and stale files are found.
+        fs::write(out_dir.join("a.rs"), "// edited\n").unwrap();
+        fs::write(out_dir.join("old.rs"), "pub struct Old {}\n").unwrap();
+        let mut manifest = fs::read_to_string(out_dir.join(MANIFEST)).unwrap();
+        manifest.push_str("old.rs\nremoved.rs\n../outside.rs\n");
+        fs::write(out_dir.join(MANIFEST), manifest).unwrap();
+        fs::write(
+            out_dir.join("handwritten.rs"),
+            "pub struct Handwritten {}\n",
+        )
+        .unwrap();
+        let stale = changes(&args).unwrap();
+        let diff = diff(&out_dir, &stale);
+        assert!(diff.contains("-// edited\n"));
+        assert!(diff.contains("-pub struct Old {}\n"));
+        assert!(diff.contains("-old.rs\n"));
+        assert!(!diff.contains("Handwritten"));
+
+        write(&out_dir, &stale).unwrap();
+        assert!(changes(&args).unwrap().is_empty());
+        assert!(!out_dir.join("old.rs").exists());
+        assert!(out_dir.join("handwritten.rs").exists());
+    }
+}
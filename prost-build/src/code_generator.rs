@@ -14,11 +14,15 @@ use prost_types::{
     SourceCodeInfo,
 };
 
-use crate::ast::{Comments, Method, Service};
+use crate::ast::{Comments, Enum, EnumVariant, Message, MessageField, Method, Oneof, Service};
+use crate::collections::BytesType;
 use crate::extern_paths::ExternPaths;
 use crate::ident::{strip_enum_prefix, to_snake, to_upper_camel};
 use crate::message_graph::MessageGraph;
+use crate::naming::Names;
+use crate::validation::CheckWriter;
 use crate::Config;
+use crate::Error;
 
 mod c_escaping;
 use c_escaping::unescape_c_escape_string;
@@ -26,6 +30,8 @@ use c_escaping::unescape_c_escape_string;
 mod syntax;
 use syntax::Syntax;
 
+mod arbitrary;
+
 pub struct CodeGenerator<'a> {
     config: &'a mut Config,
     package: String,
@@ -34,9 +40,11 @@ pub struct CodeGenerator<'a> {
     syntax: Syntax,
     message_graph: &'a MessageGraph,
     extern_paths: &'a ExternPaths,
+    names: &'a Names,
     depth: u8,
     path: Vec<i32>,
     buf: &'a mut String,
+    registered_types: Vec<String>,
 }
 
 fn push_indent(buf: &mut String, depth: u8) {
@@ -45,6 +53,34 @@ fn push_indent(buf: &mut String, depth: u8) {
     }
 }
 
+/// Returns `true` if the field of a message is generated as an `Option`.
+pub(crate) fn is_optional(
+    config: &Config,
+    field: &FieldDescriptorProto,
+    fq_message_name: &str,
+    proto2: bool,
+) -> bool {
+    if field.proto3_optional.unwrap_or(false) {
+        return true;
+    }
+
+    if field.label() == Label::Required {
+        return config
+            .required_field_presence
+            .get_first_field(fq_message_name, field.name())
+            .is_some();
+    }
+
+    if field.label() != Label::Optional {
+        return false;
+    }
+
+    match field.r#type() {
+        Type::Message => true,
+        _ => proto2,
+    }
+}
+
 fn prost_path(config: &Config) -> &str {
     config.prost_path.as_deref().unwrap_or("::prost")
 }
@@ -52,18 +88,20 @@ fn prost_path(config: &Config) -> &str {
 struct Field {
     descriptor: FieldDescriptorProto,
     path_index: i32,
+    rust_name: String,
 }
 
 impl Field {
-    fn new(descriptor: FieldDescriptorProto, path_index: i32) -> Self {
+    fn new(descriptor: FieldDescriptorProto, path_index: i32, rust_name: &str) -> Self {
         Self {
             descriptor,
             path_index,
+            rust_name: rust_name.to_string(),
         }
     }
 
-    fn rust_name(&self) -> String {
-        to_snake(self.descriptor.name())
+    fn rust_name(&self) -> &str {
+        &self.rust_name
     }
 }
 
@@ -71,30 +109,46 @@ struct OneofField {
     descriptor: OneofDescriptorProto,
     fields: Vec<Field>,
     path_index: i32,
+    rust_name: String,
+    type_name: String,
 }
 
 impl OneofField {
-    fn new(descriptor: OneofDescriptorProto, fields: Vec<Field>, path_index: i32) -> Self {
-        Self {
+    fn new(
+        descriptor: OneofDescriptorProto,
+        fields: Vec<Field>,
+        path_index: i32,
+        names: &Names,
+        fq_message_name: &str,
+    ) -> Result<Self, Error> {
+        let fq_name = format!("{}.{}", fq_message_name, descriptor.name());
+        Ok(Self {
+            rust_name: names.field(&fq_name)?.to_string(),
+            type_name: names.type_name(&fq_name)?.to_string(),
             descriptor,
             fields,
             path_index,
-        }
+        })
     }
 
-    fn rust_name(&self) -> String {
-        to_snake(self.descriptor.name())
+    fn rust_name(&self) -> &str {
+        &self.rust_name
     }
 }
 
-impl CodeGenerator<'_> {
+impl<'a> CodeGenerator<'a> {
+    /// Generates the code for `file` into `buf`, and returns the paths of the generated messages
+    /// implementing `Name`, relative to the module.
+    ///
+    /// Returns an error if `names` doesn't name an element of `file` which is generated.
     pub fn generate(
         config: &mut Config,
         message_graph: &MessageGraph,
         extern_paths: &ExternPaths,
+        names: &Names,
         file: FileDescriptorProto,
         buf: &mut String,
-    ) {
+    ) -> Result<Vec<String>, Error> {
         let source_info = file.source_code_info.map(|mut s| {
             s.location.retain(|loc| {
                 let len = loc.path.len();
@@ -112,9 +166,11 @@ impl CodeGenerator<'_> {
             syntax: file.syntax.as_deref().into(),
             message_graph,
             extern_paths,
+            names,
             depth: 0,
             path: Vec::new(),
             buf,
+            registered_types: Vec::new(),
         };
 
         debug!(
@@ -126,7 +182,7 @@ impl CodeGenerator<'_> {
         code_gen.path.push(4);
         for (idx, message) in file.message_type.into_iter().enumerate() {
             code_gen.path.push(idx as i32);
-            code_gen.append_message(message);
+            code_gen.append_message(message)?;
             code_gen.path.pop();
         }
         code_gen.path.pop();
@@ -134,7 +190,7 @@ impl CodeGenerator<'_> {
         code_gen.path.push(5);
         for (idx, desc) in file.enum_type.into_iter().enumerate() {
             code_gen.path.push(idx as i32);
-            code_gen.append_enum(desc);
+            code_gen.append_enum(desc)?;
             code_gen.path.pop();
         }
         code_gen.path.pop();
@@ -153,19 +209,66 @@ impl CodeGenerator<'_> {
 
             code_gen.path.pop();
         }
+
+        Ok(code_gen.registered_types)
+    }
+
+    /// Appends a function registering the message types at `paths` with a type registry.
+    pub fn append_register_types(config: &Config, paths: &[String], buf: &mut String) {
+        // Packages without messages still get a function, so that callers can register every
+        // package uniformly.
+        let registry = if paths.is_empty() {
+            "_registry"
+        } else {
+            "registry"
+        };
+        buf.push_str("/// Registers the message types of this package with a type registry.\n");
+        buf.push_str(&format!(
+            "pub fn register_types({}: &mut {}::TypeRegistry) {{\n",
+            registry,
+            config
+                .prost_types_path
+                .as_deref()
+                .unwrap_or("::prost_types")
+        ));
+        for path in paths {
+            buf.push_str(&format!("    registry.register::<{}>();\n", path));
+        }
+        buf.push_str("}\n");
     }
 
-    fn append_message(&mut self, message: DescriptorProto) {
+    /// Appends a constant holding an encoded `FileDescriptorSet`.
+    pub fn append_file_descriptor_set(file_descriptor_set: &[u8], buf: &mut String) {
+        buf.push_str(
+            "/// The encoded `FileDescriptorSet` of this package, including its dependencies.\n",
+        );
+        buf.push_str("pub const FILE_DESCRIPTOR_SET: &[u8] = b\"");
+        for &byte in file_descriptor_set {
+            buf.extend(ascii::escape_default(byte).map(char::from));
+        }
+        buf.push_str("\";\n");
+    }
+
+    fn append_message(&mut self, message: DescriptorProto) -> Result<(), Error> {
         debug!("  message: {:?}", message.name());
 
         let message_name = message.name().to_string();
         let fq_message_name = self.fq_name(&message_name);
 
-        // Skip external types.
-        if self.extern_paths.resolve_ident(&fq_message_name).is_some() {
-            return;
+        // Skip external types, and types which are not reachable from the configured roots.
+        if self.extern_paths.resolve_ident(&fq_message_name).is_some()
+            || !self.message_graph.is_reachable(&fq_message_name)
+        {
+            return Ok(());
         }
 
+        // The message generator receives the complete descriptor.
+        let descriptor = self
+            .config
+            .message_generator
+            .is_some()
+            .then(|| message.clone());
+
         // Split the nested message types into a vector of normal nested message types, and a map
         // of the map field entry types. The path index of the nested message types is preserved so
         // that comments can be retrieved.
@@ -197,18 +300,24 @@ impl CodeGenerator<'_> {
         // Split the fields into a vector of the normal fields, and oneof fields.
         // Path indexes are preserved so that comments can be retrieved.
         type OneofFieldsByIndex = MultiMap<i32, Field>;
+        let names = self.names;
         let (fields, mut oneof_map): (Vec<Field>, OneofFieldsByIndex) = message
             .field
             .into_iter()
             .enumerate()
-            .partition_map(|(idx, proto)| {
-                let idx = idx as i32;
+            .map(|(idx, proto)| {
+                let name = names.field(&format!("{}.{}", fq_message_name, proto.name()))?;
+                Ok((idx as i32, proto, name))
+            })
+            .collect::<Result<Vec<_>, Error>>()?
+            .into_iter()
+            .partition_map(|(idx, proto, name)| {
                 if proto.proto3_optional.unwrap_or(false) {
-                    Either::Left(Field::new(proto, idx))
+                    Either::Left(Field::new(proto, idx, name))
                 } else if let Some(oneof_index) = proto.oneof_index {
-                    Either::Right((oneof_index, Field::new(proto, idx)))
+                    Either::Right((oneof_index, Field::new(proto, idx, name)))
                 } else {
-                    Either::Left(Field::new(proto, idx))
+                    Either::Left(Field::new(proto, idx, name))
                 }
             });
         // Optional fields create a synthetic oneof that we want to skip
@@ -216,31 +325,37 @@ impl CodeGenerator<'_> {
             .oneof_decl
             .into_iter()
             .enumerate()
-            .filter_map(move |(idx, proto)| {
+            .filter_map(|(idx, proto)| {
                 let idx = idx as i32;
                 oneof_map
                     .remove(&idx)
-                    .map(|fields| OneofField::new(proto, fields, idx))
+                    .map(|fields| OneofField::new(proto, fields, idx, self.names, &fq_message_name))
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
 
         self.append_doc(&fq_message_name, None);
         self.append_type_attributes(&fq_message_name);
         self.append_message_attributes(&fq_message_name);
         self.push_indent();
         self.buf.push_str(&format!(
-            "#[derive(Clone, {}PartialEq, {}::Message)]\n",
+            "#[derive(Clone, {}PartialEq, {}{}::Message)]\n",
             if self.message_graph.can_message_derive_copy(&fq_message_name) {
                 "Copy, "
             } else {
                 ""
             },
+            self.comparison_derives(&fq_message_name),
             prost_path(self.config)
         ));
         self.append_skip_debug(&fq_message_name);
+        self.append_required_fields(&fq_message_name);
+        if self.has_accessors(&fq_message_name) {
+            self.push_indent();
+            self.buf.push_str("#[prost(accessors)]\n");
+        }
         self.push_indent();
         self.buf.push_str("pub struct ");
-        self.buf.push_str(&to_upper_camel(&message_name));
+        self.buf.push_str(self.type_name(&message_name)?);
         self.buf.push_str(" {\n");
 
         self.depth += 1;
@@ -263,7 +378,7 @@ impl CodeGenerator<'_> {
         self.path.push(8);
         for oneof in &oneof_fields {
             self.path.push(oneof.path_index);
-            self.append_oneof_field(&message_name, &fq_message_name, oneof);
+            self.append_oneof_field(&message_name, &fq_message_name, oneof)?;
             self.path.pop();
         }
         self.path.pop();
@@ -273,11 +388,11 @@ impl CodeGenerator<'_> {
         self.buf.push_str("}\n");
 
         if !message.enum_type.is_empty() || !nested_types.is_empty() || !oneof_fields.is_empty() {
-            self.push_mod(&message_name);
+            self.push_mod(&message_name)?;
             self.path.push(3);
             for (nested_type, idx) in nested_types {
                 self.path.push(idx as i32);
-                self.append_message(nested_type);
+                self.append_message(nested_type)?;
                 self.path.pop();
             }
             self.path.pop();
@@ -285,28 +400,627 @@ impl CodeGenerator<'_> {
             self.path.push(4);
             for (idx, nested_enum) in message.enum_type.into_iter().enumerate() {
                 self.path.push(idx as i32);
-                self.append_enum(nested_enum);
+                self.append_enum(nested_enum)?;
                 self.path.pop();
             }
             self.path.pop();
 
             for oneof in &oneof_fields {
-                self.append_oneof(&fq_message_name, oneof);
+                self.append_oneof(&fq_message_name, oneof)?;
             }
 
             self.pop_mod();
         }
 
+        if self
+            .config
+            .validation
+            .get(&fq_message_name)
+            .next()
+            .is_some()
+        {
+            self.append_validate(
+                &message_name,
+                &fq_message_name,
+                &fields,
+                &map_types,
+                &oneof_fields,
+            )?;
+        }
+
+        if self.config.builders.get(&fq_message_name).next().is_some() {
+            self.append_builder(
+                &message_name,
+                &fq_message_name,
+                &fields,
+                &map_types,
+                &oneof_fields,
+            )?;
+        }
+
+        if fields.is_empty()
+            && oneof_fields.len() == 1
+            && self
+                .config
+                .oneof_conversions
+                .get(&fq_message_name)
+                .next()
+                .is_some()
+        {
+            let oneof = &oneof_fields[0];
+            let message_ty = self.type_name(&message_name)?;
+            let oneof_ty = self.oneof_type(&message_name, oneof)?;
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "impl ::core::convert::From<{oneof_ty}> for {message_ty} {{ \
+                 fn from(value: {oneof_ty}) -> Self {{ Self {{ {}: ::core::option::Option::Some(value) }} }} }}\n",
+                oneof.rust_name()
+            ));
+        }
+
+        if self.config.arbitrary.get(&fq_message_name).next().is_some() {
+            self.append_arbitrary(
+                &message_name,
+                &fq_message_name,
+                &fields,
+                &map_types,
+                &oneof_fields,
+            )?;
+        }
+
+        if self.config.proptest.get(&fq_message_name).next().is_some() {
+            self.append_proptest(
+                &message_name,
+                &fq_message_name,
+                &fields,
+                &map_types,
+                &oneof_fields,
+            )?;
+        }
+
         if self.config.enable_type_names {
-            self.append_type_name(&message_name, &fq_message_name);
+            self.append_type_name(&message_name, &fq_message_name)?;
+        }
+
+        if self.config.embed_file_descriptor_set {
+            self.append_message_descriptor(&message_name)?;
+        }
+
+        if let Some(descriptor) = descriptor {
+            self.append_generated_message(&message_name, &fq_message_name, descriptor)?;
+        }
+        Ok(())
+    }
+
+    /// Passes a message to the configured message generator.
+    fn append_generated_message(
+        &mut self,
+        message_name: &str,
+        fq_message_name: &str,
+        descriptor: DescriptorProto,
+    ) -> Result<(), Error> {
+        let name = self.type_name(message_name)?;
+        let mut fields = Vec::new();
+        let mut oneofs: Vec<Oneof> = Vec::new();
+        for (idx, field) in descriptor.oneof_decl.iter().enumerate() {
+            // Skip the synthetic oneofs of proto3 optional fields.
+            let synthetic = descriptor
+                .field
+                .iter()
+                .any(|member| member.oneof_index == Some(idx as i32) && member.proto3_optional());
+            if synthetic {
+                continue;
+            }
+            let fq_name = format!("{}.{}", fq_message_name, field.name());
+            oneofs.push(Oneof {
+                name: self.names.field(&fq_name)?.to_string(),
+                proto_name: field.name().to_string(),
+                rust_path: self.rust_path(&format!(
+                    "{}::{}",
+                    self.names.module(fq_message_name)?,
+                    self.names.type_name(&fq_name)?
+                ))?,
+                comments: self.comments_at(&[8, idx as i32]),
+                fields: Vec::new(),
+                descriptor: field.clone(),
+            });
+        }
+        for (idx, field) in descriptor.field.iter().enumerate() {
+            let oneof_index = field.oneof_index.filter(|_| !field.proto3_optional());
+            let name = match oneof_index {
+                Some(_) => self.oneof_variant(fq_message_name, field)?,
+                None => self
+                    .names
+                    .field(&format!("{}.{}", fq_message_name, field.name()))?,
+            };
+            let field_info = MessageField {
+                name: name.to_string(),
+                proto_name: field.name().to_string(),
+                comments: self.comments_at(&[2, idx as i32]),
+                descriptor: field.clone(),
+            };
+            match oneof_index {
+                Some(oneof_index) => {
+                    let oneof_name = descriptor.oneof_decl[oneof_index as usize].name();
+                    if let Some(oneof) = oneofs.iter_mut().find(|o| o.proto_name == oneof_name) {
+                        oneof.fields.push(field_info);
+                    }
+                }
+                None => fields.push(field_info),
+            }
+        }
+
+        let message = Message {
+            name: name.to_string(),
+            proto_name: message_name.to_string(),
+            package: self.package.clone(),
+            full_name: self.full_name(message_name),
+            rust_path: self.rust_path(name)?,
+            comments: self.comments_at(&[]),
+            fields,
+            oneofs,
+            descriptor,
+        };
+        if let Some(message_generator) = self.config.message_generator.as_mut() {
+            message_generator.generate_message(&message, self.buf);
+        }
+        Ok(())
+    }
+
+    fn append_message_descriptor(&mut self, message_name: &str) -> Result<(), Error> {
+        // The constant is defined at the root of the package module.
+        let file_descriptor_set = format!(
+            "{}FILE_DESCRIPTOR_SET",
+            "super::".repeat(self.type_path.len())
+        );
+        self.push_indent();
+        self.buf
+            .push_str(&format!("impl {} {{\n", self.type_name(message_name)?));
+        self.depth += 1;
+        self.push_indent();
+        self.buf.push_str(
+            "/// Returns the fully-qualified name of this message, and the encoded \
+             `FileDescriptorSet` which defines it.\n",
+        );
+        self.push_indent();
+        self.buf
+            .push_str("pub fn message_descriptor() -> (&'static str, &'static [u8]) {\n");
+        self.depth += 1;
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "(\"{}\", {})\n",
+            self.full_name(message_name),
+            file_descriptor_set
+        ));
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+        Ok(())
+    }
+
+    fn append_builder(
+        &mut self,
+        message_name: &str,
+        fq_message_name: &str,
+        fields: &[Field],
+        map_types: &HashMap<String, (FieldDescriptorProto, FieldDescriptorProto)>,
+        oneof_fields: &[OneofField],
+    ) -> Result<(), Error> {
+        let message_ty = self.type_name(message_name)?;
+        let builder_ty = format!("{}Builder", message_ty);
+
+        self.push_indent();
+        self.buf
+            .push_str(&format!("/// A builder for [`{}`].\n", message_ty));
+        self.push_indent();
+        self.buf.push_str("#[derive(Clone, Default)]\n");
+        self.push_indent();
+        self.buf
+            .push_str(&format!("pub struct {} {{\n", builder_ty));
+        self.depth += 1;
+        self.push_indent();
+        self.buf.push_str(&format!("inner: {},\n", message_ty));
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+
+        self.push_indent();
+        self.buf.push_str(&format!("impl {} {{\n", message_ty));
+        self.depth += 1;
+        self.push_indent();
+        self.buf
+            .push_str(&format!("/// Returns a builder for [`{}`].\n", message_ty));
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "pub fn builder() -> {} {{ {}::default() }}\n",
+            builder_ty, builder_ty
+        ));
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+
+        let mut methods = Vec::new();
+        for field in fields {
+            let descriptor = &field.descriptor;
+            let name = field.rust_name();
+            let base_name = name.trim_start_matches("r#");
+            let member = format!("self.inner.{}", name);
+
+            if let Some((key, value)) = descriptor
+                .type_name
+                .as_ref()
+                .and_then(|type_name| map_types.get(type_name))
+            {
+                let (key_ty, key_conv) = self.builder_value(key, fq_message_name, "key", false);
+                let (value_ty, value_conv) =
+                    self.builder_value(value, fq_message_name, "value", false);
+                let entries = if key_conv == "key" && value_conv == "value" {
+                    "entries.into_iter()".to_string()
+                } else {
+                    format!("entries.into_iter().map(|(key, value)| ({key_conv}, {value_conv}))")
+                };
+                methods.push(format!(
+                    "pub fn {name}(mut self, entries: impl ::core::iter::IntoIterator<Item = ({key_ty}, {value_ty})>) -> Self {{ \
+                     {member} = {entries}.collect(); self }}"
+                ));
+                methods.push(format!(
+                    "pub fn insert_{base_name}(mut self, key: {key_ty}, value: {value_ty}) -> Self {{ \
+                     {member}.insert({key_conv}, {value_conv}); self }}"
+                ));
+                continue;
+            }
+
+            let boxed = self.boxed(descriptor, fq_message_name, None);
+            let (value_ty, conv) = self.builder_value(descriptor, fq_message_name, "value", boxed);
+            let nested_builder = self.nested_builder(descriptor);
+            if descriptor.label() == Label::Repeated {
+                let values = if conv == "value" {
+                    "values.into_iter()".to_string()
+                } else {
+                    format!("values.into_iter().map(|value| {conv})")
+                };
+                methods.push(format!(
+                    "pub fn {name}(mut self, values: impl ::core::iter::IntoIterator<Item = {value_ty}>) -> Self {{ \
+                     {member} = {values}.collect(); self }}"
+                ));
+                methods.push(format!(
+                    "pub fn push_{base_name}(mut self, value: {value_ty}) -> Self {{ \
+                     {member}.push({conv}); self }}"
+                ));
+                if let Some((message, builder)) = nested_builder {
+                    methods.push(format!(
+                        "pub fn push_{base_name}_with(self, build: impl ::core::ops::FnOnce({builder}) -> {builder}) -> Self {{ \
+                         self.push_{base_name}(build({message}::builder()).build()) }}"
+                    ));
+                }
+            } else {
+                let stored = if self.optional(descriptor, fq_message_name) {
+                    format!("::core::option::Option::Some({})", conv)
+                } else {
+                    conv
+                };
+                methods.push(format!(
+                    "pub fn {name}(mut self, value: {value_ty}) -> Self {{ {member} = {stored}; self }}"
+                ));
+                if let Some((message, builder)) = nested_builder {
+                    methods.push(format!(
+                        "pub fn {base_name}_with(self, build: impl ::core::ops::FnOnce({builder}) -> {builder}) -> Self {{ \
+                         self.{name}(build({message}::builder()).build()) }}"
+                    ));
+                }
+            }
+        }
+
+        for oneof in oneof_fields {
+            let oneof_ty = self.oneof_type(message_name, oneof)?;
+            for field in &oneof.fields {
+                let descriptor = &field.descriptor;
+                let name = field.rust_name();
+                let base_name = name.trim_start_matches("r#");
+                let boxed = self.boxed(descriptor, fq_message_name, Some(oneof.descriptor.name()));
+                let (value_ty, conv) =
+                    self.builder_value(descriptor, fq_message_name, "value", boxed);
+                methods.push(format!(
+                    "pub fn {name}(mut self, value: {value_ty}) -> Self {{ \
+                     self.inner.{} = ::core::option::Option::Some({oneof_ty}::{}({conv})); self }}",
+                    oneof.rust_name(),
+                    self.oneof_variant(fq_message_name, descriptor)?,
+                ));
+                if let Some((message, builder)) = self.nested_builder(descriptor) {
+                    methods.push(format!(
+                        "pub fn {base_name}_with(self, build: impl ::core::ops::FnOnce({builder}) -> {builder}) -> Self {{ \
+                         self.{name}(build({message}::builder()).build()) }}"
+                    ));
+                }
+            }
+        }
+
+        self.push_indent();
+        self.buf.push_str(&format!("impl {} {{\n", builder_ty));
+        self.depth += 1;
+        for method in methods {
+            self.push_indent();
+            self.buf.push_str(&method);
+            self.buf.push('\n');
+        }
+        self.push_indent();
+        self.buf
+            .push_str(&format!("/// Returns the built [`{}`].\n", message_ty));
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "pub fn build(self) -> {} {{ self.inner }}\n",
+            message_ty
+        ));
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "impl ::core::convert::From<{builder_ty}> for {message_ty} {{ \
+             fn from(builder: {builder_ty}) -> Self {{ builder.build() }} }}\n"
+        ));
+        Ok(())
+    }
+
+    /// Returns the type taken by a builder setter for a single value of the field, and the
+    /// expression converting `var` to the stored value.
+    fn builder_value(
+        &self,
+        field: &FieldDescriptorProto,
+        fq_message_name: &str,
+        var: &str,
+        boxed: bool,
+    ) -> (String, String) {
+        let (ty, conv) = match field.r#type() {
+            Type::String | Type::Bytes => (
+                format!(
+                    "impl ::core::convert::Into<{}>",
+                    self.resolve_type(field, fq_message_name)
+                ),
+                format!("{}.into()", var),
+            ),
+            Type::Enum => (
+                self.resolve_ident(field.type_name()),
+                format!("{} as i32", var),
+            ),
+            _ => (self.resolve_type(field, fq_message_name), var.to_string()),
+        };
+        if boxed {
+            (
+                ty,
+                format!(
+                    "{}::alloc::boxed::Box::new({})",
+                    prost_path(self.config),
+                    conv
+                ),
+            )
+        } else {
+            (ty, conv)
+        }
+    }
+
+    /// Returns the Rust paths of the message type of the field and of its builder, if the
+    /// message has a generated builder.
+    fn nested_builder(&self, field: &FieldDescriptorProto) -> Option<(String, String)> {
+        if !matches!(field.r#type(), Type::Message | Type::Group)
+            || self.extern_paths.resolve_ident(field.type_name()).is_some()
+            || self.config.builders.get(field.type_name()).next().is_none()
+        {
+            return None;
+        }
+        let message = self.resolve_ident(field.type_name());
+        let builder = format!("{}Builder", message);
+        Some((message, builder))
+    }
+
+    fn append_validate(
+        &mut self,
+        message_name: &str,
+        fq_message_name: &str,
+        fields: &[Field],
+        map_types: &HashMap<String, (FieldDescriptorProto, FieldDescriptorProto)>,
+        oneof_fields: &[OneofField],
+    ) -> Result<(), Error> {
+        let prost_path = prost_path(self.config).to_string();
+        let mut body = String::new();
+        let mut checks = CheckWriter {
+            buf: &mut body,
+            prost_path: &prost_path,
+        };
+
+        for field in fields {
+            let descriptor = &field.descriptor;
+            let constraints = self.config.field_constraints.get(&format!(
+                "{}.{}",
+                fq_message_name,
+                descriptor.name()
+            ));
+            let member = format!("self.{}", field.rust_name());
+            let path = checks.field_path(descriptor.name());
+
+            if let Some((_, value)) = descriptor
+                .type_name
+                .as_ref()
+                .and_then(|type_name| map_types.get(type_name))
+            {
+                let map = constraints.and_then(|c| c.map.as_deref());
+                if constraints.is_some_and(|c| c.required) {
+                    checks.push_violation(
+                        &format!("{}.is_empty()", member),
+                        &path,
+                        "required",
+                        "value is required",
+                    );
+                }
+                if let Some(rules) = map {
+                    checks.push_map_checks(rules, &member, &path);
+                }
+                let keys = map.and_then(|rules| rules.keys.as_ref());
+                let values = map.and_then(|rules| rules.values.as_ref());
+                let recurse = self.validates_message(value);
+                if keys.is_some() || values.is_some() || recurse {
+                    let entry_path = checks.element_path(&path, "format_args!(\"{:?}\", key)");
+                    checks
+                        .buf
+                        .push_str(&format!("for (key, value) in &{} {{\n", member));
+                    if let Some(keys) = keys {
+                        checks.push_type_checks(keys, "key", &entry_path, None);
+                    }
+                    if let Some(values) = values {
+                        let enum_type = self.enum_type(value);
+                        checks.push_type_checks(values, "value", &entry_path, enum_type.as_deref());
+                    }
+                    if recurse {
+                        checks.push_recurse("value", &entry_path);
+                    }
+                    checks.buf.push_str("}\n");
+                }
+            } else if descriptor.label() == Label::Repeated {
+                let repeated = constraints.and_then(|c| c.repeated.as_deref());
+                if constraints.is_some_and(|c| c.required) {
+                    checks.push_violation(
+                        &format!("{}.is_empty()", member),
+                        &path,
+                        "required",
+                        "value is required",
+                    );
+                }
+                if let Some(rules) = repeated {
+                    checks.push_repeated_checks(rules, &member, &path);
+                }
+                let items = repeated.and_then(|rules| rules.items.as_ref());
+                let recurse = self.validates_message(descriptor);
+                if items.is_some() || recurse {
+                    let item_path = checks.element_path(&path, "idx");
+                    checks.buf.push_str(&format!(
+                        "for (idx, value) in {}.iter().enumerate() {{\n",
+                        member
+                    ));
+                    if let Some(items) = items {
+                        let enum_type = self.enum_type(descriptor);
+                        checks.push_type_checks(items, "value", &item_path, enum_type.as_deref());
+                    }
+                    if recurse {
+                        checks.push_recurse("value", &item_path);
+                    }
+                    checks.buf.push_str("}\n");
+                }
+            } else {
+                let optional = self.optional(descriptor, fq_message_name);
+                if constraints.is_some_and(|c| c.required) {
+                    let condition = if optional {
+                        format!("{}.is_none()", member)
+                    } else {
+                        match descriptor.r#type() {
+                            Type::String | Type::Bytes => format!("{}.is_empty()", member),
+                            Type::Bool => format!("!{}", member),
+                            Type::Float | Type::Double => format!("{} == 0.0", member),
+                            Type::Message | Type::Group => String::new(),
+                            _ => format!("{} == 0", member),
+                        }
+                    };
+                    if !condition.is_empty() {
+                        checks.push_violation(&condition, &path, "required", "value is required");
+                    }
+                }
+                let recurse = self.validates_message(descriptor);
+                if constraints.is_some() || recurse {
+                    checks.buf.push_str(&if optional {
+                        format!("if let Some(value) = &{} {{\n", member)
+                    } else {
+                        format!("{{\nlet value = &{};\n", member)
+                    });
+                    if let Some(constraints) = constraints {
+                        let enum_type = self.enum_type(descriptor);
+                        checks.push_type_checks(constraints, "value", &path, enum_type.as_deref());
+                    }
+                    if recurse {
+                        checks.push_recurse("value", &path);
+                    }
+                    checks.buf.push_str("}\n");
+                }
+            }
+        }
+
+        for oneof in oneof_fields {
+            for field in &oneof.fields {
+                let descriptor = &field.descriptor;
+                let constraints = self.config.field_constraints.get(&format!(
+                    "{}.{}",
+                    fq_message_name,
+                    descriptor.name()
+                ));
+                let recurse = self.validates_message(descriptor);
+                if constraints.is_none() && !recurse {
+                    continue;
+                }
+                let path = checks.field_path(descriptor.name());
+                checks.buf.push_str(&format!(
+                    "if let Some({}::{}(value)) = &self.{} {{\n",
+                    self.oneof_type(message_name, oneof)?,
+                    self.oneof_variant(fq_message_name, descriptor)?,
+                    oneof.rust_name(),
+                ));
+                if let Some(constraints) = constraints {
+                    let enum_type = self.enum_type(descriptor);
+                    checks.push_type_checks(constraints, "value", &path, enum_type.as_deref());
+                }
+                if recurse {
+                    checks.push_recurse("value", &path);
+                }
+                checks.buf.push_str("}\n");
+            }
         }
+
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "impl {}::Validate for {} {{\n",
+            prost_path,
+            self.type_name(message_name)?
+        ));
+        self.depth += 1;
+        self.push_indent();
+        self.buf.push_str("#[allow(unused_variables)]\n");
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "fn validate_into(&self, path: &str, errors: &mut {}::ValidationErrors) {{\n",
+            prost_path
+        ));
+        self.buf.push_str(&body);
+        self.push_indent();
+        self.buf.push_str("}\n");
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+        Ok(())
+    }
+
+    /// Returns `true` if the field is a message which implements `Validate`.
+    fn validates_message(&self, field: &FieldDescriptorProto) -> bool {
+        matches!(field.r#type(), Type::Message | Type::Group)
+            && self.extern_paths.resolve_ident(field.type_name()).is_none()
+            && self
+                .config
+                .validation
+                .get(field.type_name())
+                .next()
+                .is_some()
+    }
+
+    /// Returns the Rust path of the enum type of the field, if it is an enumeration.
+    fn enum_type(&self, field: &FieldDescriptorProto) -> Option<String> {
+        (field.r#type() == Type::Enum).then(|| self.resolve_ident(field.type_name()))
     }
 
-    fn append_type_name(&mut self, message_name: &str, fq_message_name: &str) {
+    fn append_type_name(&mut self, message_name: &str, fq_message_name: &str) -> Result<(), Error> {
         self.buf.push_str(&format!(
             "impl {}::Name for {} {{\n",
             self.config.prost_path.as_deref().unwrap_or("::prost"),
-            to_upper_camel(message_name)
+            self.type_name(message_name)?
         ));
         self.depth += 1;
 
@@ -322,13 +1036,7 @@ impl CodeGenerator<'_> {
         let prost_path = self.config.prost_path.as_deref().unwrap_or("::prost");
         let string_path = format!("{prost_path}::alloc::string::String");
 
-        let full_name = format!(
-            "{}{}{}{}{message_name}",
-            self.package.trim_matches('.'),
-            if self.package.is_empty() { "" } else { "." },
-            self.type_path.join("."),
-            if self.type_path.is_empty() { "" } else { "." },
-        );
+        let full_name = self.full_name(message_name);
         let domain_name = self
             .config
             .type_name_domains
@@ -345,6 +1053,34 @@ impl CodeGenerator<'_> {
 
         self.depth -= 1;
         self.buf.push_str("}\n");
+
+        let path = self.rust_path(self.type_name(message_name)?)?;
+        self.registered_types.push(path);
+        Ok(())
+    }
+
+    /// Returns the path of an item in the current scope, relative to the module of the package.
+    fn rust_path(&self, name: &str) -> Result<String, Error> {
+        let mut path = Vec::new();
+        let mut scope = self.fq_package();
+        for message_name in &self.type_path {
+            scope = format!("{}.{}", scope, message_name);
+            path.push(self.names.module(&scope)?);
+        }
+        path.push(name);
+        Ok(path.join("::"))
+    }
+
+    /// Returns the fully-qualified name of a message in the current scope, without a leading
+    /// period.
+    fn full_name(&self, message_name: &str) -> String {
+        format!(
+            "{}{}{}{}{message_name}",
+            self.package.trim_matches('.'),
+            if self.package.is_empty() { "" } else { "." },
+            self.type_path.join("."),
+            if self.type_path.is_empty() { "" } else { "." },
+        )
     }
 
     fn append_type_attributes(&mut self, fq_message_name: &str) {
@@ -378,6 +1114,31 @@ impl CodeGenerator<'_> {
         }
     }
 
+    fn append_required_fields(&mut self, fq_message_name: &str) {
+        if self.message_graph.has_required_fields(fq_message_name) {
+            push_indent(self.buf, self.depth);
+            self.buf.push_str("#[prost(required_fields)]");
+            self.buf.push('\n');
+        }
+    }
+
+    fn has_accessors(&self, fq_message_name: &str) -> bool {
+        self.config.accessors.get(fq_message_name).next().is_some()
+    }
+
+    /// Appends the Protobuf name of a field when it differs from its Rust name, so that the paths
+    /// of missing required fields and the `field_name()` of oneofs use the Protobuf name.
+    fn append_field_name(&mut self, fq_message_name: &str, rust_name: &str, proto_name: &str) {
+        if (self.message_graph.has_required_fields(fq_message_name)
+            || self.has_accessors(fq_message_name))
+            && rust_name.trim_start_matches("r#") != proto_name
+        {
+            push_indent(self.buf, self.depth);
+            self.buf
+                .push_str(&format!("#[prost(name={:?})]\n", proto_name));
+        }
+    }
+
     fn append_enum_attributes(&mut self, fq_message_name: &str) {
         assert_eq!(b'.', fq_message_name.as_bytes()[0]);
         for attribute in self.config.enum_attributes.get(fq_message_name) {
@@ -404,7 +1165,7 @@ impl CodeGenerator<'_> {
         let type_ = field.descriptor.r#type();
         let repeated = field.descriptor.label == Some(Label::Repeated as i32);
         let deprecated = self.deprecated(&field.descriptor);
-        let optional = self.optional(&field.descriptor);
+        let optional = self.optional(&field.descriptor, fq_message_name);
         let boxed = self.boxed(&field.descriptor, fq_message_name, None);
         let ty = self.resolve_type(&field.descriptor, fq_message_name);
 
@@ -428,12 +1189,7 @@ impl CodeGenerator<'_> {
         self.buf.push_str(&type_tag);
 
         if type_ == Type::Bytes {
-            let bytes_type = self
-                .config
-                .bytes_type
-                .get_first_field(fq_message_name, field.descriptor.name())
-                .copied()
-                .unwrap_or_default();
+            let bytes_type = self.bytes_type(&field.descriptor, fq_message_name);
             self.buf
                 .push_str(&format!("={:?}", bytes_type.annotation()));
         }
@@ -444,7 +1200,12 @@ impl CodeGenerator<'_> {
                     self.buf.push_str(", optional");
                 }
             }
-            Label::Required => self.buf.push_str(", required"),
+            Label::Required => {
+                self.buf.push_str(", required");
+                if optional {
+                    self.buf.push_str(", presence");
+                }
+            }
             Label::Repeated => {
                 self.buf.push_str(", repeated");
                 if can_pack(&field.descriptor)
@@ -476,8 +1237,11 @@ impl CodeGenerator<'_> {
                 }
                 self.buf.push_str("\\\"");
             } else if type_ == Type::Enum {
+                let fq_value = format!("{}.{}", field.descriptor.type_name(), default);
                 let mut enum_value = to_upper_camel(default);
-                if self.config.strip_enum_prefix {
+                if let Some(variant) = self.names.find_variant(&fq_value) {
+                    enum_value = variant.to_string();
+                } else if self.config.strip_enum_prefix {
                     // Field types are fully qualified, so we extract
                     // the last segment and strip it from the left
                     // side of the default value.
@@ -497,10 +1261,11 @@ impl CodeGenerator<'_> {
         }
 
         self.buf.push_str("\")]\n");
+        self.append_field_name(fq_message_name, field.rust_name(), field.descriptor.name());
         self.append_field_attributes(fq_message_name, field.descriptor.name());
         self.push_indent();
         self.buf.push_str("pub ");
-        self.buf.push_str(&field.rust_name());
+        self.buf.push_str(field.rust_name());
         self.buf.push_str(": ");
 
         let prost_path = prost_path(self.config);
@@ -561,6 +1326,7 @@ impl CodeGenerator<'_> {
             value_tag,
             field.descriptor.number()
         ));
+        self.append_field_name(fq_message_name, field.rust_name(), field.descriptor.name());
         self.append_field_attributes(fq_message_name, field.descriptor.name());
         self.push_indent();
         self.buf.push_str(&format!(
@@ -577,12 +1343,8 @@ impl CodeGenerator<'_> {
         message_name: &str,
         fq_message_name: &str,
         oneof: &OneofField,
-    ) {
-        let type_name = format!(
-            "{}::{}",
-            to_snake(message_name),
-            to_upper_camel(oneof.descriptor.name())
-        );
+    ) -> Result<(), Error> {
+        let type_name = self.oneof_type(message_name, oneof)?;
         self.append_doc(fq_message_name, None);
         self.push_indent();
         self.buf.push_str(&format!(
@@ -601,9 +1363,10 @@ impl CodeGenerator<'_> {
             oneof.rust_name(),
             type_name
         ));
+        Ok(())
     }
 
-    fn append_oneof(&mut self, fq_message_name: &str, oneof: &OneofField) {
+    fn append_oneof(&mut self, fq_message_name: &str, oneof: &OneofField) -> Result<(), Error> {
         self.path.push(8);
         self.path.push(oneof.path_index);
         self.append_doc(fq_message_name, None);
@@ -620,14 +1383,23 @@ impl CodeGenerator<'_> {
                 .can_field_derive_copy(fq_message_name, &field.descriptor)
         });
         self.buf.push_str(&format!(
-            "#[derive(Clone, {}PartialEq, {}::Oneof)]\n",
+            "#[derive(Clone, {}PartialEq, {}{}::Oneof)]\n",
             if can_oneof_derive_copy { "Copy, " } else { "" },
+            self.comparison_derives(fq_message_name),
             prost_path(self.config)
         ));
         self.append_skip_debug(fq_message_name);
+        if self.has_accessors(fq_message_name) {
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "#[prost(accessors(message=\"super::{}\", field=\"{}\"))]\n",
+                self.names.type_name(fq_message_name)?,
+                oneof.rust_name()
+            ));
+        }
         self.push_indent();
         self.buf.push_str("pub enum ");
-        self.buf.push_str(&to_upper_camel(oneof.descriptor.name()));
+        self.buf.push_str(&oneof.type_name);
         self.buf.push_str(" {\n");
 
         self.path.push(2);
@@ -644,6 +1416,8 @@ impl CodeGenerator<'_> {
                 ty_tag,
                 field.descriptor.number()
             ));
+            let variant = self.oneof_variant(fq_message_name, &field.descriptor)?;
+            self.append_field_name(fq_message_name, variant, field.descriptor.name());
             self.append_field_attributes(&oneof_name, field.descriptor.name());
 
             self.push_indent();
@@ -665,13 +1439,13 @@ impl CodeGenerator<'_> {
             if boxed {
                 self.buf.push_str(&format!(
                     "{}(::prost::alloc::boxed::Box<{}>),\n",
-                    to_upper_camel(field.descriptor.name()),
+                    self.oneof_variant(fq_message_name, &field.descriptor)?,
                     ty
                 ));
             } else {
                 self.buf.push_str(&format!(
                     "{}({}),\n",
-                    to_upper_camel(field.descriptor.name()),
+                    self.oneof_variant(fq_message_name, &field.descriptor)?,
                     ty
                 ));
             }
@@ -681,6 +1455,88 @@ impl CodeGenerator<'_> {
 
         self.push_indent();
         self.buf.push_str("}\n");
+
+        if self
+            .config
+            .oneof_conversions
+            .get(fq_message_name)
+            .next()
+            .is_some()
+        {
+            self.append_oneof_conversions(fq_message_name, oneof)?;
+        }
+        Ok(())
+    }
+
+    /// Appends the conversions between a oneof and the types of its members which are unique
+    /// within the oneof.
+    fn append_oneof_conversions(
+        &mut self,
+        fq_message_name: &str,
+        oneof: &OneofField,
+    ) -> Result<(), Error> {
+        let variants = oneof
+            .fields
+            .iter()
+            .map(|field| (field, self.resolve_type(&field.descriptor, fq_message_name)))
+            .collect::<Vec<_>>();
+        let wrapped = self
+            .message_graph
+            .get_message(fq_message_name)
+            .is_some_and(|message| {
+                message.field.iter().all(|field| {
+                    field.oneof_index == Some(oneof.path_index) && !field.proto3_optional()
+                })
+            });
+        for (field, ty) in &variants {
+            // Enums are represented as `i32`, which doesn't identify the variant.
+            if field.descriptor.r#type() == Type::Enum
+                || variants.iter().filter(|(_, other)| other == ty).count() > 1
+            {
+                continue;
+            }
+            let oneof_ty = &oneof.type_name;
+            let variant = self.oneof_variant(fq_message_name, &field.descriptor)?;
+            let boxed = self.boxed(
+                &field.descriptor,
+                fq_message_name,
+                Some(oneof.descriptor.name()),
+            );
+            let (wrap, unwrap) = if boxed {
+                (
+                    format!("{}::alloc::boxed::Box::new(value)", prost_path(self.config)),
+                    "*value",
+                )
+            } else {
+                ("value".to_string(), "value")
+            };
+
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "impl ::core::convert::From<{ty}> for {oneof_ty} {{ \
+                 fn from(value: {ty}) -> Self {{ Self::{variant}({wrap}) }} }}\n"
+            ));
+
+            // A message whose only field is this oneof already converts from it, and so has a
+            // blanket `TryFrom` impl.
+            if wrapped && field.descriptor.type_name() == fq_message_name {
+                continue;
+            }
+            let fallback = if oneof.fields.len() > 1 {
+                "value => ::core::result::Result::Err(value), "
+            } else {
+                ""
+            };
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "impl ::core::convert::TryFrom<{oneof_ty}> for {ty} {{ \
+                 type Error = {oneof_ty}; \
+                 fn try_from(value: {oneof_ty}) -> ::core::result::Result<Self, Self::Error> {{ \
+                 match value {{ {oneof_ty}::{variant}(value) => ::core::result::Result::Ok({unwrap}), \
+                 {fallback}}} }} }}\n"
+            ));
+        }
+        Ok(())
     }
 
     fn location(&self) -> Option<&Location> {
@@ -688,10 +1544,23 @@ impl CodeGenerator<'_> {
         let idx = source_info
             .location
             .binary_search_by_key(&&self.path[..], |location| &location.path[..])
-            .unwrap();
+            .ok()?;
         Some(&source_info.location[idx])
     }
 
+    /// Returns the comments of the item at `path`, relative to the current location.
+    fn comments_at(&self, path: &[i32]) -> Comments {
+        let Some(source_info) = self.source_info.as_ref() else {
+            return Comments::default();
+        };
+        let path = [&self.path[..], path].concat();
+        source_info
+            .location
+            .binary_search_by_key(&&path[..], |location| &location.path[..])
+            .map(|idx| Comments::from_location(&source_info.location[idx]))
+            .unwrap_or_default()
+    }
+
     fn append_doc(&mut self, fq_name: &str, field_name: Option<&str>) {
         let append_doc = if let Some(field_name) = field_name {
             self.config
@@ -703,16 +1572,59 @@ impl CodeGenerator<'_> {
         };
         if append_doc {
             if let Some(comments) = self.location().map(Comments::from_location) {
-                comments.append_with_indent(self.depth, self.buf);
+                if self.config.intra_doc_links {
+                    let mut buf = String::new();
+                    comments.append_with_links(self.depth, &mut buf, &|reference| {
+                        self.resolve_doc_link(fq_name, reference)
+                    });
+                    self.buf.push_str(&buf);
+                } else {
+                    comments.append_with_indent(self.depth, self.buf);
+                }
             }
         }
     }
 
-    fn append_enum(&mut self, desc: EnumDescriptorProto) {
+    /// Resolves a reference to a type in a comment, like `[Bar]` or `[foo.Bar]`, to the path of
+    /// the Rust type, using the scoping rules of Protobuf relative to `scope`.
+    fn resolve_doc_link(&self, scope: &str, reference: &str) -> Option<String> {
+        let (absolute, name) = match reference.strip_prefix('.') {
+            Some(name) => (true, name),
+            None => (false, reference),
+        };
+        let is_ident = |segment: &str| {
+            segment.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        };
+        if !name.split('.').all(is_ident) {
+            return None;
+        }
+
+        let mut scope = if absolute { "" } else { scope };
+        loop {
+            let fq_name = format!("{}.{}", scope, name);
+            let extern_path = self.extern_paths.resolve_ident(&fq_name);
+            // Extern types mapped to Rust primitives, like the wrapper types, aren't linked.
+            if extern_path.is_some_and(|path| path.contains("::")) {
+                return Some(self.resolve_ident(&fq_name));
+            }
+            // Types dropped by `retain_reachable_types` are named, but not generated.
+            if self.names.find_type(&fq_name).is_some() {
+                return self
+                    .message_graph
+                    .is_reachable(&fq_name)
+                    .then(|| self.resolve_ident(&fq_name));
+            }
+            scope = &scope[..scope.rfind('.')?];
+        }
+    }
+
+    fn append_enum(&mut self, desc: EnumDescriptorProto) -> Result<(), Error> {
         debug!("  enum: {:?}", desc.name());
 
         let proto_enum_name = desc.name();
-        let enum_name = to_upper_camel(proto_enum_name);
 
         let enum_values = &desc.value;
         let fq_proto_enum_name = self.fq_name(proto_enum_name);
@@ -721,10 +1633,12 @@ impl CodeGenerator<'_> {
             .extern_paths
             .resolve_ident(&fq_proto_enum_name)
             .is_some()
+            || !self.message_graph.is_reachable(&fq_proto_enum_name)
         {
-            return;
+            return Ok(());
         }
 
+        let enum_name = self.names.type_name(&fq_proto_enum_name)?;
         self.append_doc(&fq_proto_enum_name, None);
         self.append_type_attributes(&fq_proto_enum_name);
         self.append_enum_attributes(&fq_proto_enum_name);
@@ -743,11 +1657,11 @@ impl CodeGenerator<'_> {
         self.buf.push_str("#[repr(i32)]\n");
         self.push_indent();
         self.buf.push_str("pub enum ");
-        self.buf.push_str(&enum_name);
+        self.buf.push_str(enum_name);
         self.buf.push_str(" {\n");
 
         let variant_mappings =
-            build_enum_value_mappings(&enum_name, self.config.strip_enum_prefix, enum_values);
+            build_enum_value_mappings(self.names, &fq_proto_enum_name, enum_values)?;
 
         self.depth += 1;
         self.path.push(2);
@@ -757,7 +1671,7 @@ impl CodeGenerator<'_> {
             self.append_doc(&fq_proto_enum_name, Some(variant.proto_name));
             self.append_field_attributes(&fq_proto_enum_name, variant.proto_name);
             self.push_indent();
-            self.buf.push_str(&variant.generated_variant_name);
+            self.buf.push_str(variant.generated_variant_name);
             self.buf.push_str(" = ");
             self.buf.push_str(&variant.proto_number.to_string());
             self.buf.push_str(",\n");
@@ -773,7 +1687,7 @@ impl CodeGenerator<'_> {
 
         self.push_indent();
         self.buf.push_str("impl ");
-        self.buf.push_str(&enum_name);
+        self.buf.push_str(enum_name);
         self.buf.push_str(" {\n");
         self.depth += 1;
         self.path.push(2);
@@ -804,7 +1718,7 @@ impl CodeGenerator<'_> {
         for variant in variant_mappings.iter() {
             self.push_indent();
             self.buf.push_str("Self::");
-            self.buf.push_str(&variant.generated_variant_name);
+            self.buf.push_str(variant.generated_variant_name);
             self.buf.push_str(" => \"");
             self.buf.push_str(variant.proto_name);
             self.buf.push_str("\",\n");
@@ -836,7 +1750,7 @@ impl CodeGenerator<'_> {
             self.buf.push('\"');
             self.buf.push_str(variant.proto_name);
             self.buf.push_str("\" => Some(Self::");
-            self.buf.push_str(&variant.generated_variant_name);
+            self.buf.push_str(variant.generated_variant_name);
             self.buf.push_str("),\n");
         }
         self.push_indent();
@@ -850,16 +1764,150 @@ impl CodeGenerator<'_> {
         self.push_indent();
         self.buf.push_str("}\n"); // End of from_str_name()
 
+        let helpers = self
+            .config
+            .enum_helpers
+            .get(&fq_proto_enum_name)
+            .next()
+            .is_some();
+        if helpers {
+            self.append_enum_helper_methods(enum_values, &variant_mappings);
+        }
+
         self.path.pop();
         self.depth -= 1;
         self.push_indent();
         self.buf.push_str("}\n"); // End of impl
+
+        if helpers {
+            self.append_enum_helper_impls(enum_name);
+        }
+
+        let variants = variant_mappings
+            .iter()
+            .map(|variant| variant.generated_variant_name)
+            .collect::<Vec<_>>();
+        self.append_enum_arbitrary(&fq_proto_enum_name, enum_name, &variants);
+
+        if self.config.message_generator.is_some() {
+            let variants = variant_mappings
+                .iter()
+                .map(|variant| EnumVariant {
+                    name: variant.generated_variant_name.to_string(),
+                    proto_name: variant.proto_name.to_string(),
+                    number: variant.proto_number,
+                    comments: self.comments_at(&[2, variant.path_idx as i32]),
+                })
+                .collect();
+            let enum_ = Enum {
+                name: enum_name.to_string(),
+                proto_name: proto_enum_name.to_string(),
+                package: self.package.clone(),
+                full_name: self.full_name(proto_enum_name),
+                rust_path: self.rust_path(enum_name)?,
+                comments: self.comments_at(&[]),
+                variants,
+                descriptor: desc.clone(),
+            };
+            if let Some(message_generator) = self.config.message_generator.as_mut() {
+                message_generator.generate_enum(&enum_, self.buf);
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends the `VALUES` constant and the `iter` and `is_deprecated` methods of an enum.
+    fn append_enum_helper_methods(
+        &mut self,
+        enum_values: &[EnumValueDescriptorProto],
+        variant_mappings: &[EnumVariantMapping<'_>],
+    ) {
+        self.push_indent();
+        self.buf
+            .push_str("/// The values of the enum, in the order of the ProtoBuf definition.\n");
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "pub const VALUES: &'static [Self] = &[{}];\n",
+            variant_mappings
+                .iter()
+                .map(|variant| format!("Self::{}", variant.generated_variant_name))
+                .join(", ")
+        ));
+
+        self.push_indent();
+        self.buf.push_str(
+            "/// Returns an iterator over the values of the enum, in declaration order.\n",
+        );
+        self.push_indent();
+        self.buf.push_str(
+            "pub fn iter() -> ::core::iter::Copied<::core::slice::Iter<'static, Self>> \
+             { Self::VALUES.iter().copied() }\n",
+        );
+
+        let deprecated = variant_mappings
+            .iter()
+            .filter(|variant| {
+                enum_values[variant.path_idx]
+                    .options
+                    .as_ref()
+                    .is_some_and(|options| options.deprecated())
+            })
+            .map(|variant| format!("Self::{}", variant.generated_variant_name))
+            .collect::<Vec<_>>();
+        self.push_indent();
+        self.buf.push_str(
+            "/// Returns `true` if the value is marked as deprecated in the ProtoBuf definition.\n",
+        );
+        self.push_indent();
+        if deprecated.is_empty() {
+            self.buf
+                .push_str("pub fn is_deprecated(&self) -> bool { false }\n");
+        } else {
+            self.buf.push_str(&format!(
+                "pub fn is_deprecated(&self) -> bool {{ match self {{ {} => true, _ => false }} }}\n",
+                deprecated.join(" | ")
+            ));
+        }
+    }
+
+    /// Appends the `Display`, `FromStr` and `ProtoEnum` impls of an enum.
+    fn append_enum_helper_impls(&mut self, enum_name: &str) {
+        let prost_path = prost_path(self.config).to_string();
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "impl ::core::fmt::Display for {enum_name} {{ \
+             fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {{ \
+             f.write_str(self.as_str_name()) }} }}\n"
+        ));
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "impl ::core::str::FromStr for {enum_name} {{ \
+             type Err = {prost_path}::UnknownEnumName; \
+             fn from_str(value: &str) -> ::core::result::Result<Self, Self::Err> {{ \
+             Self::from_str_name(value).ok_or_else(|| \
+             {prost_path}::UnknownEnumName({prost_path}::alloc::string::String::from(value))) }} }}\n"
+        ));
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "impl {prost_path}::ProtoEnum for {enum_name} {{ \
+             const VALUES: &'static [Self] = {enum_name}::VALUES; \
+             fn as_str_name(&self) -> &'static str {{ {enum_name}::as_str_name(self) }} \
+             fn from_str_name(name: &str) -> ::core::option::Option<Self> {{ {enum_name}::from_str_name(name) }} \
+             fn is_deprecated(&self) -> bool {{ {enum_name}::is_deprecated(self) }} }}\n"
+        ));
     }
 
     fn push_service(&mut self, service: ServiceDescriptorProto) {
         let name = service.name().to_owned();
         debug!("  service: {:?}", name);
 
+        if !self
+            .message_graph
+            .is_reachable(&format!("{}.{}", self.fq_package(), name))
+        {
+            return;
+        }
+
         let comments = self
             .location()
             .map(Comments::from_location)
@@ -922,7 +1970,7 @@ impl CodeGenerator<'_> {
         push_indent(self.buf, self.depth);
     }
 
-    fn push_mod(&mut self, module: &str) {
+    fn push_mod(&mut self, module: &str) -> Result<(), Error> {
         self.push_indent();
         self.buf.push_str("/// Nested message and enum types in `");
         self.buf.push_str(module);
@@ -930,12 +1978,13 @@ impl CodeGenerator<'_> {
 
         self.push_indent();
         self.buf.push_str("pub mod ");
-        self.buf.push_str(&to_snake(module));
+        self.buf.push_str(self.names.module(&self.fq_name(module))?);
         self.buf.push_str(" {\n");
 
         self.type_path.push(module.into());
 
         self.depth += 1;
+        Ok(())
     }
 
     fn pop_mod(&mut self) {
@@ -958,17 +2007,34 @@ impl CodeGenerator<'_> {
             Type::Bool => String::from("bool"),
             Type::String => format!("{}::alloc::string::String", prost_path(self.config)),
             Type::Bytes => self
-                .config
-                .bytes_type
-                .get_first_field(fq_message_name, field.name())
-                .copied()
-                .unwrap_or_default()
+                .bytes_type(field, fq_message_name)
                 .rust_type()
                 .to_owned(),
-            Type::Group | Type::Message => self.resolve_ident(field.type_name()),
+            Type::Group | Type::Message => {
+                let ty = self.resolve_ident(field.type_name());
+                // With `map_wrapper_types`, `BytesValue` fields follow the `bytes` configuration.
+                if self.config.wrapper_types
+                    && field.type_name() == ".google.protobuf.BytesValue"
+                    && ty == BytesType::Vec.rust_type()
+                {
+                    self.bytes_type(field, fq_message_name)
+                        .rust_type()
+                        .to_owned()
+                } else {
+                    ty
+                }
+            }
         }
     }
 
+    fn bytes_type(&self, field: &FieldDescriptorProto, fq_message_name: &str) -> BytesType {
+        self.config
+            .bytes_type
+            .get_first_field(fq_message_name, field.name())
+            .copied()
+            .unwrap_or_default()
+    }
+
     fn resolve_ident(&self, pb_ident: &str) -> String {
         // protoc should always give fully qualified identifiers.
         assert_eq!(".", &pb_ident[..1]);
@@ -995,15 +2061,31 @@ impl CodeGenerator<'_> {
         let mut ident_path = ident_path.peekable();
 
         // Skip path elements in common.
+        let mut scope = String::new();
         while local_path.peek().is_some() && local_path.peek() == ident_path.peek() {
             local_path.next();
-            ident_path.next();
+            scope.push('.');
+            scope.push_str(ident_path.next().unwrap());
         }
 
+        // Types which are not generated, like those of dependencies which are not compiled, get
+        // the default names.
+        let modules = ident_path.map(|segment| {
+            scope.push('.');
+            scope.push_str(segment);
+            self.names
+                .find_module(&scope)
+                .map_or_else(|| to_snake(segment), str::to_string)
+        });
+        let type_name = self
+            .names
+            .find_type(pb_ident)
+            .map_or_else(|| to_upper_camel(ident_type), str::to_string);
+
         local_path
             .map(|_| "super".to_string())
-            .chain(ident_path.map(to_snake))
-            .chain(iter::once(to_upper_camel(ident_type)))
+            .chain(modules)
+            .chain(iter::once(type_name))
             .join("::")
     }
 
@@ -1043,19 +2125,23 @@ impl CodeGenerator<'_> {
         }
     }
 
-    fn optional(&self, field: &FieldDescriptorProto) -> bool {
-        if field.proto3_optional.unwrap_or(false) {
-            return true;
-        }
-
-        if field.label() != Label::Optional {
-            return false;
-        }
+    fn optional(&self, field: &FieldDescriptorProto, fq_message_name: &str) -> bool {
+        is_optional(
+            self.config,
+            field,
+            fq_message_name,
+            self.syntax == Syntax::Proto2,
+        )
+    }
 
-        match field.r#type() {
-            Type::Message => true,
-            _ => self.syntax == Syntax::Proto2,
-        }
+    /// Returns `true` if the field is a required field which is configured to track its presence.
+    fn required_presence(&self, field: &FieldDescriptorProto, fq_message_name: &str) -> bool {
+        field.label() == Label::Required
+            && self
+                .config
+                .required_field_presence
+                .get_first_field(fq_message_name, field.name())
+                .is_some()
     }
 
     /// Returns whether the Rust type for this field needs to be `Box<_>`.
@@ -1098,7 +2184,25 @@ impl CodeGenerator<'_> {
             }
             return true;
         }
-        false
+        !repeated
+            && (fd_type == Type::Message || fd_type == Type::Group)
+            && self
+                .message_graph
+                .is_large_field(fq_message_name, field.name())
+    }
+
+    /// Returns the derives of `Eq`, `Hash`, `PartialOrd` and `Ord` for a message and its oneofs.
+    fn comparison_derives(&self, fq_message_name: &str) -> &'static str {
+        if self.message_graph.can_message_derive_ord(fq_message_name) {
+            "Eq, Hash, PartialOrd, Ord, "
+        } else if self
+            .message_graph
+            .can_message_derive_eq_hash(fq_message_name)
+        {
+            "Eq, Hash, "
+        } else {
+            ""
+        }
     }
 
     /// Returns `true` if the field options includes the `deprecated` option.
@@ -1106,6 +2210,40 @@ impl CodeGenerator<'_> {
         field.options.as_ref().is_some_and(FieldOptions::deprecated)
     }
 
+    /// Returns the name of a message or enum in the current scope.
+    fn type_name(&self, name: &str) -> Result<&'a str, Error> {
+        self.names.type_name(&self.fq_name(name))
+    }
+
+    /// Returns the path of the enum of a oneof of a message in the current scope.
+    fn oneof_type(&self, message_name: &str, oneof: &OneofField) -> Result<String, Error> {
+        Ok(format!(
+            "{}::{}",
+            self.names.module(&self.fq_name(message_name))?,
+            oneof.type_name
+        ))
+    }
+
+    /// Returns the name of the oneof variant of a field.
+    fn oneof_variant(
+        &self,
+        fq_message_name: &str,
+        field: &FieldDescriptorProto,
+    ) -> Result<&'a str, Error> {
+        self.names
+            .variant(&format!("{}.{}", fq_message_name, field.name()))
+    }
+
+    /// Returns the fully-qualified name of the package, starting with a dot, or an empty string
+    /// if there is no package.
+    fn fq_package(&self) -> String {
+        if self.package.is_empty() {
+            String::new()
+        } else {
+            format!(".{}", self.package.trim_matches('.'))
+        }
+    }
+
     /// Returns the fully-qualified name, starting with a dot
     fn fq_name(&self, message_name: &str) -> String {
         format!(
@@ -1144,16 +2282,15 @@ struct EnumVariantMapping<'a> {
     path_idx: usize,
     proto_name: &'a str,
     proto_number: i32,
-    generated_variant_name: String,
+    generated_variant_name: &'a str,
 }
 
 fn build_enum_value_mappings<'a>(
-    generated_enum_name: &str,
-    do_strip_enum_prefix: bool,
+    names: &'a Names,
+    fq_proto_enum_name: &str,
     enum_values: &'a [EnumValueDescriptorProto],
-) -> Vec<EnumVariantMapping<'a>> {
+) -> Result<Vec<EnumVariantMapping<'a>>, Error> {
     let mut numbers = HashSet::new();
-    let mut generated_names = HashMap::new();
     let mut mappings = Vec::new();
 
     for (idx, value) in enum_values.iter().enumerate() {
@@ -1163,24 +2300,16 @@ fn build_enum_value_mappings<'a>(
             continue;
         }
 
-        let mut generated_variant_name = to_upper_camel(value.name());
-        if do_strip_enum_prefix {
-            generated_variant_name =
-                strip_enum_prefix(generated_enum_name, &generated_variant_name);
-        }
-
-        if let Some(old_v) = generated_names.insert(generated_variant_name.to_owned(), value.name())
-        {
-            panic!("Generated enum variant names overlap: `{}` variant name to be used both by `{}` and `{}` ProtoBuf enum values",
-                generated_variant_name, old_v, value.name());
-        }
-
         mappings.push(EnumVariantMapping {
             path_idx: idx,
             proto_name: value.name(),
             proto_number: value.number(),
-            generated_variant_name,
+            generated_variant_name: names.variant(&format!(
+                "{}.{}",
+                fq_proto_enum_name,
+                value.name()
+            ))?,
         })
     }
-    mappings
+    Ok(mappings)
 }
@@ -0,0 +1,549 @@
+//! Generation of `arbitrary::Arbitrary` and `proptest::arbitrary::Arbitrary` implementations.
+//!
+//! Values are generated according to Protobuf semantics rather than the Rust representation:
+//! enumeration fields hold defined values (unless unknown values are enabled for the field),
+//! `Timestamp` and `Duration` values are normalized, and the nesting depth of message fields is
+//! bounded by `Config::arbitrary_max_depth` so that recursive messages stay finite.
+
+use std::collections::HashMap;
+
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::FieldDescriptorProto;
+
+use super::{prost_path, CodeGenerator, Field, OneofField};
+use crate::collections::BytesType;
+use crate::Error;
+
+type MapTypes = HashMap<String, (FieldDescriptorProto, FieldDescriptorProto)>;
+
+/// The maximum length of generated `proptest` collections.
+const PROPTEST_MAX_LEN: usize = 8;
+
+impl CodeGenerator<'_> {
+    pub(super) fn append_arbitrary(
+        &mut self,
+        message_name: &str,
+        fq_message_name: &str,
+        fields: &[Field],
+        map_types: &MapTypes,
+        oneof_fields: &[OneofField],
+    ) -> Result<(), Error> {
+        let max_depth = self.config.arbitrary_max_depth;
+        let message_ty = self.type_name(message_name)?;
+
+        let mut body = String::new();
+        for field in fields {
+            let descriptor = &field.descriptor;
+            let value = if let Some((key, value)) = descriptor
+                .type_name
+                .as_ref()
+                .and_then(|type_name| map_types.get(type_name))
+            {
+                let guarded = is_message(value);
+                let key = self.arbitrary_value(key, fq_message_name, false);
+                let value = self.arbitrary_value(value, fq_message_name, false);
+                let entries = depth_guarded(
+                    guarded,
+                    format!(
+                        "for _ in 0..u.arbitrary_len::<u8>()? {{ \
+                         ::core::iter::Extend::extend(&mut map, ::core::iter::once(({key}, {value}))); }}"
+                    ),
+                    max_depth,
+                );
+                format!("{{ let mut map = ::core::default::Default::default(); {entries} map }}")
+            } else {
+                let unknown_enum = self.unknown_enum_values(fq_message_name, descriptor);
+                let boxed = self.boxed(descriptor, fq_message_name, None);
+                let value = self.arbitrary_value(descriptor, fq_message_name, unknown_enum);
+                let value = self.box_value(value, boxed);
+                let guarded = is_message(descriptor);
+                if descriptor.label() == Label::Repeated {
+                    let values = depth_guarded(
+                        guarded,
+                        format!("for _ in 0..u.arbitrary_len::<u8>()? {{ values.push({value}); }}"),
+                        max_depth,
+                    );
+                    format!(
+                        "{{ let mut values = {}::alloc::vec::Vec::new(); {values} values }}",
+                        prost_path(self.config)
+                    )
+                } else if self.required_presence(descriptor, fq_message_name) {
+                    format!("::core::option::Option::Some({value})")
+                } else if self.optional(descriptor, fq_message_name) {
+                    let guard = if guarded {
+                        format!("depth < {} && ", max_depth)
+                    } else {
+                        String::new()
+                    };
+                    format!(
+                        "if {guard}u.arbitrary::<bool>()? {{ ::core::option::Option::Some({value}) }} else {{ ::core::option::Option::None }}"
+                    )
+                } else {
+                    value
+                }
+            };
+            body.push_str(&format!("{}: {},\n", field.rust_name(), value));
+        }
+
+        for oneof in oneof_fields {
+            let oneof_ty = self.oneof_type(message_name, oneof)?;
+            let mut arms = String::new();
+            for (idx, field) in oneof.fields.iter().enumerate() {
+                let descriptor = &field.descriptor;
+                let unknown_enum = self.unknown_enum_values(fq_message_name, descriptor);
+                let boxed = self.boxed(descriptor, fq_message_name, Some(oneof.descriptor.name()));
+                let value = self.arbitrary_value(descriptor, fq_message_name, unknown_enum);
+                arms.push_str(&format!(
+                    "{} => ::core::option::Option::Some({}::{}({})),\n",
+                    idx + 1,
+                    oneof_ty,
+                    self.oneof_variant(fq_message_name, descriptor)?,
+                    self.box_value(value, boxed)
+                ));
+            }
+            let choice = format!(
+                "match u.int_in_range(0..={})? {{\n{}_ => ::core::option::Option::None,\n}}",
+                oneof.fields.len(),
+                arms
+            );
+            let value = if oneof
+                .fields
+                .iter()
+                .any(|field| is_message(&field.descriptor))
+            {
+                format!(
+                    "if depth < {} {{ {} }} else {{ ::core::option::Option::None }}",
+                    max_depth, choice
+                )
+            } else {
+                choice
+            };
+            body.push_str(&format!("{}: {},\n", oneof.rust_name(), value));
+        }
+
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "impl<'a> ::arbitrary::Arbitrary<'a> for {} {{\n",
+            message_ty
+        ));
+        self.push_indent();
+        self.buf.push_str(
+            "    fn arbitrary(u: &mut ::arbitrary::Unstructured<'a>) -> ::arbitrary::Result<Self> { Self::arbitrary_with_depth(u, 0) }\n",
+        );
+        self.push_indent();
+        self.buf.push_str("}\n");
+
+        self.push_indent();
+        self.buf.push_str(&format!("impl {} {{\n", message_ty));
+        self.push_indent();
+        self.buf.push_str(
+            "    /// Generates an arbitrary message nested `depth` levels deep in the message being generated.\n",
+        );
+        self.push_indent();
+        self.buf.push_str("    #[doc(hidden)]\n");
+        self.push_indent();
+        self.buf.push_str("    #[allow(unused_variables)]\n");
+        self.push_indent();
+        self.buf.push_str(
+            "    pub fn arbitrary_with_depth(u: &mut ::arbitrary::Unstructured<'_>, depth: u32) -> ::arbitrary::Result<Self> {\n",
+        );
+        self.buf.push_str("::core::result::Result::Ok(Self {\n");
+        self.buf.push_str(&body);
+        self.buf.push_str("})\n");
+        self.push_indent();
+        self.buf.push_str("    }\n");
+        self.push_indent();
+        self.buf.push_str("}\n");
+        Ok(())
+    }
+
+    pub(super) fn append_proptest(
+        &mut self,
+        message_name: &str,
+        fq_message_name: &str,
+        fields: &[Field],
+        map_types: &MapTypes,
+        oneof_fields: &[OneofField],
+    ) -> Result<(), Error> {
+        let max_depth = self.config.arbitrary_max_depth;
+        let message_ty = self.type_name(message_name)?;
+
+        // Each member is a (field name, strategy) pair.
+        let mut members = Vec::new();
+        for field in fields {
+            let descriptor = &field.descriptor;
+            let strategy = if let Some((key, value)) = descriptor
+                .type_name
+                .as_ref()
+                .and_then(|type_name| map_types.get(type_name))
+            {
+                let map_type = self
+                    .config
+                    .map_type
+                    .get_first_field(fq_message_name, descriptor.name())
+                    .copied()
+                    .unwrap_or_default();
+                let collection = match map_type {
+                    crate::MapType::HashMap => "hash_map",
+                    crate::MapType::BTreeMap => "btree_map",
+                };
+                let key = self.proptest_value(key, fq_message_name, false);
+                let value_strategy = self.proptest_value(value, fq_message_name, false);
+                let strategy = format!(
+                    "::proptest::collection::{collection}({key}, {value_strategy}, 0..{PROPTEST_MAX_LEN})"
+                );
+                depth_guarded_strategy(is_message(value), strategy, max_depth)
+            } else {
+                let unknown_enum = self.unknown_enum_values(fq_message_name, descriptor);
+                let boxed = self.boxed(descriptor, fq_message_name, None);
+                let value = self.proptest_value(descriptor, fq_message_name, unknown_enum);
+                let value = if boxed {
+                    format!(
+                        "{}.prop_map({}::alloc::boxed::Box::new)",
+                        value,
+                        prost_path(self.config)
+                    )
+                } else {
+                    value
+                };
+                if descriptor.label() == Label::Repeated {
+                    let strategy =
+                        format!("::proptest::collection::vec({value}, 0..{PROPTEST_MAX_LEN})");
+                    depth_guarded_strategy(is_message(descriptor), strategy, max_depth)
+                } else if self.required_presence(descriptor, fq_message_name) {
+                    format!("{value}.prop_map(::core::option::Option::Some)")
+                } else if self.optional(descriptor, fq_message_name) {
+                    let strategy = format!("::proptest::option::of({value})");
+                    depth_guarded_strategy(is_message(descriptor), strategy, max_depth)
+                } else {
+                    value
+                }
+            };
+            members.push((field.rust_name(), strategy));
+        }
+
+        for oneof in oneof_fields {
+            let oneof_ty = self.oneof_type(message_name, oneof)?;
+            let mut arms =
+                vec!["::proptest::strategy::Just(::core::option::Option::None)".to_string()];
+            for field in &oneof.fields {
+                let descriptor = &field.descriptor;
+                let unknown_enum = self.unknown_enum_values(fq_message_name, descriptor);
+                let boxed = self.boxed(descriptor, fq_message_name, Some(oneof.descriptor.name()));
+                let value = self.proptest_value(descriptor, fq_message_name, unknown_enum);
+                let wrap = if boxed {
+                    format!("{}::alloc::boxed::Box::new(value)", prost_path(self.config))
+                } else {
+                    "value".to_string()
+                };
+                arms.push(format!(
+                    "{}.prop_map(|value| ::core::option::Option::Some({}::{}({})))",
+                    value,
+                    oneof_ty,
+                    self.oneof_variant(fq_message_name, descriptor)?,
+                    wrap
+                ));
+            }
+            let strategy = format!("::proptest::prop_oneof![{}]", arms.join(", "));
+            let has_message = oneof
+                .fields
+                .iter()
+                .any(|field| is_message(&field.descriptor));
+            members.push((
+                oneof.rust_name(),
+                depth_guarded_strategy(has_message, strategy, max_depth),
+            ));
+        }
+
+        // Strategies are combined as right-nested pairs, which have no arity limit.
+        let (strategy, pattern) = members.iter().enumerate().rev().fold(
+            (String::new(), String::new()),
+            |(strategy, pattern), (idx, (_, member))| {
+                if strategy.is_empty() {
+                    (member.clone(), format!("field_{}", idx))
+                } else {
+                    (
+                        format!("({}, {})", member, strategy),
+                        format!("(field_{}, {})", idx, pattern),
+                    )
+                }
+            },
+        );
+        let strategy = if members.is_empty() {
+            "::proptest::strategy::Just(Self::default()).boxed()".to_string()
+        } else {
+            format!(
+                "{}.prop_map(|{}| Self {{ {} }}).boxed()",
+                strategy,
+                pattern,
+                members
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, (name, _))| format!("{}: field_{}", name, idx))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        };
+
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "impl ::proptest::arbitrary::Arbitrary for {} {{\n",
+            message_ty
+        ));
+        self.push_indent();
+        self.buf.push_str("    type Parameters = ();\n");
+        self.push_indent();
+        self.buf
+            .push_str("    type Strategy = ::proptest::strategy::BoxedStrategy<Self>;\n");
+        self.push_indent();
+        self.buf.push_str(
+            "    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy { Self::arbitrary_strategy(0) }\n",
+        );
+        self.push_indent();
+        self.buf.push_str("}\n");
+
+        self.push_indent();
+        self.buf.push_str(&format!("impl {} {{\n", message_ty));
+        self.push_indent();
+        self.buf.push_str(
+            "    /// Returns a strategy for messages nested `depth` levels deep in the message being generated.\n",
+        );
+        self.push_indent();
+        self.buf.push_str("    #[doc(hidden)]\n");
+        self.push_indent();
+        self.buf.push_str(
+            "    pub fn arbitrary_strategy(depth: u32) -> ::proptest::strategy::BoxedStrategy<Self> {\n",
+        );
+        self.buf
+            .push_str("use ::proptest::strategy::Strategy as _;\n");
+        self.buf.push_str(&strategy);
+        self.buf.push('\n');
+        self.push_indent();
+        self.buf.push_str("    }\n");
+        self.push_indent();
+        self.buf.push_str("}\n");
+        Ok(())
+    }
+
+    /// Appends `arbitrary` and `proptest` implementations choosing among the defined values of
+    /// an enum.
+    pub(super) fn append_enum_arbitrary(
+        &mut self,
+        fq_enum_name: &str,
+        enum_name: &str,
+        variants: &[&str],
+    ) {
+        let values = variants
+            .iter()
+            .map(|variant| format!("Self::{}", variant))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if self.config.arbitrary.get(fq_enum_name).next().is_some() {
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "impl<'a> ::arbitrary::Arbitrary<'a> for {} {{\n",
+                enum_name
+            ));
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "    fn arbitrary(u: &mut ::arbitrary::Unstructured<'a>) -> ::arbitrary::Result<Self> {{ u.choose(&[{}]).copied() }}\n",
+                values
+            ));
+            self.push_indent();
+            self.buf.push_str("}\n");
+        }
+        if self.config.proptest.get(fq_enum_name).next().is_some() {
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "impl ::proptest::arbitrary::Arbitrary for {} {{\n",
+                enum_name
+            ));
+            self.push_indent();
+            self.buf.push_str("    type Parameters = ();\n");
+            self.push_indent();
+            self.buf
+                .push_str("    type Strategy = ::proptest::sample::Select<Self>;\n");
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {{ ::proptest::sample::select({}::alloc::vec![{}]) }}\n",
+                prost_path(self.config),
+                values
+            ));
+            self.push_indent();
+            self.buf.push_str("}\n");
+        }
+    }
+
+    /// Returns `true` if the enumeration field may hold values which are not defined by the enum.
+    fn unknown_enum_values(&self, fq_message_name: &str, field: &FieldDescriptorProto) -> bool {
+        field.r#type() == Type::Enum
+            && self
+                .config
+                .arbitrary_unknown_enum_values
+                .get_first_field(fq_message_name, field.name())
+                .is_some()
+    }
+
+    fn box_value(&self, value: String, boxed: bool) -> String {
+        if boxed {
+            format!(
+                "{}::alloc::boxed::Box::new({})",
+                prost_path(self.config),
+                value
+            )
+        } else {
+            value
+        }
+    }
+
+    /// Returns an expression generating a single arbitrary value of the field's type from `u`.
+    fn arbitrary_value(
+        &self,
+        field: &FieldDescriptorProto,
+        fq_message_name: &str,
+        unknown_enum: bool,
+    ) -> String {
+        let ty = self.resolve_type(field, fq_message_name);
+        match field.r#type() {
+            Type::Message | Type::Group => match field.type_name() {
+                ".google.protobuf.Timestamp" => format!(
+                    "{ty} {{ seconds: u.arbitrary()?, nanos: u.int_in_range(0..=999_999_999)? }}"
+                ),
+                ".google.protobuf.Duration" => format!(
+                    "{{ let seconds: i64 = u.arbitrary()?; let nanos: i32 = u.int_in_range(-999_999_999..=999_999_999)?; \
+                     {ty} {{ seconds, nanos: if (seconds < 0 && nanos > 0) || (seconds > 0 && nanos < 0) {{ -nanos }} else {{ nanos }} }} }}"
+                ),
+                _ if self.has_generated_impl(&self.config.arbitrary, field) => {
+                    format!("{}::arbitrary_with_depth(u, depth + 1)?", ty)
+                }
+                _ => format!("<{} as ::arbitrary::Arbitrary>::arbitrary(u)?", ty),
+            },
+            Type::Enum => {
+                let defined = format!("*u.choose(&[{}])?", self.enum_values(field));
+                if unknown_enum {
+                    format!(
+                        "if u.arbitrary::<bool>()? {{ u.arbitrary::<i32>()? }} else {{ {} }}",
+                        defined
+                    )
+                } else {
+                    defined
+                }
+            }
+            Type::Bytes if self.bytes_type(field, fq_message_name) == BytesType::Bytes => {
+                format!(
+                "{}::from(u.arbitrary::<{}::alloc::vec::Vec<u8>>()?)",
+                ty,
+                prost_path(self.config)
+            )
+            }
+            _ => format!("<{} as ::arbitrary::Arbitrary>::arbitrary(u)?", ty),
+        }
+    }
+
+    /// Returns a strategy generating a single value of the field's type.
+    fn proptest_value(
+        &self,
+        field: &FieldDescriptorProto,
+        fq_message_name: &str,
+        unknown_enum: bool,
+    ) -> String {
+        let ty = self.resolve_type(field, fq_message_name);
+        match field.r#type() {
+            Type::Message | Type::Group => match field.type_name() {
+                ".google.protobuf.Timestamp" => format!(
+                    "(::proptest::arbitrary::any::<i64>(), 0..=999_999_999i32).prop_map(|(seconds, nanos)| {ty} {{ seconds, nanos }})"
+                ),
+                ".google.protobuf.Duration" => format!(
+                    "(::proptest::arbitrary::any::<i64>(), -999_999_999..=999_999_999i32).prop_map(|(seconds, nanos)| \
+                     {ty} {{ seconds, nanos: if (seconds < 0 && nanos > 0) || (seconds > 0 && nanos < 0) {{ -nanos }} else {{ nanos }} }})"
+                ),
+                _ if self.has_generated_impl(&self.config.proptest, field) => {
+                    format!("{}::arbitrary_strategy(depth + 1)", ty)
+                }
+                _ => format!("::proptest::arbitrary::any::<{}>()", ty),
+            },
+            Type::Enum => {
+                let defined = format!(
+                    "::proptest::sample::select({}::alloc::vec![{}])",
+                    prost_path(self.config),
+                    self.enum_values(field)
+                );
+                if unknown_enum {
+                    format!(
+                        "::proptest::prop_oneof![3 => {}, 1 => ::proptest::arbitrary::any::<i32>()]",
+                        defined
+                    )
+                } else {
+                    defined
+                }
+            }
+            Type::Bytes if self.bytes_type(field, fq_message_name) == BytesType::Bytes => {
+                format!(
+                "::proptest::arbitrary::any::<{}::alloc::vec::Vec<u8>>().prop_map({}::from)",
+                prost_path(self.config),
+                ty
+            )
+            }
+            _ => format!("::proptest::arbitrary::any::<{}>()", ty),
+        }
+    }
+
+    /// Returns the defined values of the field's enum type, as a list of `i32` expressions.
+    ///
+    /// Values are named after their variant when the enum is generated, and written as numbers
+    /// otherwise.
+    fn enum_values(&self, field: &FieldDescriptorProto) -> String {
+        let enum_ty = self.resolve_ident(field.type_name());
+        let mut numbers = Vec::new();
+        let mut values = Vec::new();
+        let enum_type = self.message_graph.get_enum(field.type_name());
+        for value in enum_type.into_iter().flat_map(|enum_type| &enum_type.value) {
+            // Aliases share the variant of the first value with their number.
+            if numbers.contains(&value.number()) {
+                continue;
+            }
+            numbers.push(value.number());
+            let fq_value = format!("{}.{}", field.type_name(), value.name());
+            values.push(match self.names.find_variant(&fq_value) {
+                Some(variant) => format!("{}::{} as i32", enum_ty, variant),
+                None => value.number().to_string(),
+            });
+        }
+        values.join(", ")
+    }
+
+    /// Returns `true` if the field's message type is generated with an implementation
+    /// enabled by `paths`.
+    fn has_generated_impl(
+        &self,
+        paths: &crate::path::PathMap<()>,
+        field: &FieldDescriptorProto,
+    ) -> bool {
+        self.extern_paths.resolve_ident(field.type_name()).is_none()
+            && paths.get(field.type_name()).next().is_some()
+    }
+}
+
+fn is_message(field: &FieldDescriptorProto) -> bool {
+    matches!(field.r#type(), Type::Message | Type::Group)
+}
+
+/// Wraps `statement` so that it only runs while `depth` is below the maximum depth.
+fn depth_guarded(guarded: bool, statement: String, max_depth: u32) -> String {
+    if guarded {
+        format!("if depth < {} {{ {} }}", max_depth, statement)
+    } else {
+        statement
+    }
+}
+
+/// Replaces `strategy` by one generating empty values once `depth` reaches the maximum depth.
+fn depth_guarded_strategy(guarded: bool, strategy: String, max_depth: u32) -> String {
+    if guarded {
+        format!(
+            "if depth < {max_depth} {{ {strategy}.boxed() }} else {{ ::proptest::strategy::Just(::core::default::Default::default()).boxed() }}"
+        )
+    } else {
+        strategy
+    }
+}
@@ -0,0 +1,965 @@
+//! Checks whether a new version of a schema is compatible with an old one.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+    FileDescriptorSet,
+};
+
+use crate::message_graph::MessageGraph;
+use crate::path::PathMap;
+
+/// A kind of change between two versions of a schema, checked by a [`CompatibilityChecker`].
+///
+/// Rules are either wire breaking, meaning that messages encoded with one version can't be
+/// decoded correctly with the other, or source breaking, meaning that the Rust API generated by
+/// prost changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum Rule {
+    /// The package of a file changed.
+    PackageChanged,
+    /// A message or enum was removed.
+    TypeRemoved,
+    /// A field was removed.
+    FieldRemoved,
+    /// A field was removed without reserving its number.
+    NumberNotReserved,
+    /// A field kept its name, but changed its number.
+    FieldRenumbered,
+    /// A field kept its number, but changed its name.
+    FieldRenamed,
+    /// The type of a field changed to a type with an incompatible wire encoding, or to a different
+    /// message type. The fields of the two message types are not compared, so this is reported
+    /// even if their encodings are compatible.
+    WireTypeChanged,
+    /// The type of a field changed to a type with a compatible wire encoding.
+    TypeChanged,
+    /// A field changed between singular, repeated and required.
+    LabelChanged,
+    /// The presence of a field changed, for example by adding or removing `optional` or by moving
+    /// the field into or out of a oneof.
+    PresenceChanged,
+    /// A field was boxed or unboxed, because a message became or stopped being recursive.
+    BoxingChanged,
+    /// A field or enum value uses a number or name which was reserved.
+    ReservedReused,
+    /// An enum value was removed.
+    EnumValueRemoved,
+    /// An enum value kept its name, but changed its number.
+    EnumValueRenumbered,
+    /// An enum value kept its number, but changed its name.
+    EnumValueRenamed,
+}
+
+impl Rule {
+    /// All rules.
+    pub const ALL: &'static [Rule] = &[
+        Rule::PackageChanged,
+        Rule::TypeRemoved,
+        Rule::FieldRemoved,
+        Rule::NumberNotReserved,
+        Rule::FieldRenumbered,
+        Rule::FieldRenamed,
+        Rule::WireTypeChanged,
+        Rule::TypeChanged,
+        Rule::LabelChanged,
+        Rule::PresenceChanged,
+        Rule::BoxingChanged,
+        Rule::ReservedReused,
+        Rule::EnumValueRemoved,
+        Rule::EnumValueRenumbered,
+        Rule::EnumValueRenamed,
+    ];
+
+    /// Returns `true` if changes of this kind break the wire compatibility, rather than only the
+    /// generated Rust API.
+    pub fn is_wire_breaking(self) -> bool {
+        matches!(
+            self,
+            Rule::PackageChanged
+                | Rule::NumberNotReserved
+                | Rule::FieldRenumbered
+                | Rule::WireTypeChanged
+                | Rule::LabelChanged
+                | Rule::ReservedReused
+                | Rule::EnumValueRemoved
+                | Rule::EnumValueRenumbered
+        )
+    }
+}
+
+/// A breaking change found by a [`CompatibilityChecker`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// The rule which the change breaks.
+    pub rule: Rule,
+    /// The fully-qualified Protobuf path of the changed item, or the name of the changed file.
+    pub path: String,
+    /// A description of the change.
+    pub message: String,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} ({:?})", self.path, self.message, self.rule)
+    }
+}
+
+/// Compares two versions of a schema and reports breaking changes.
+///
+/// By default, all [rules](Rule) are checked. Use [`wire`](Self::wire) to only check the rules
+/// breaking the wire compatibility, and [`allow`](Self::allow) and [`deny`](Self::deny) to adjust
+/// the rules.
+///
+/// Boxing is checked for fields boxed automatically because of recursion; fields boxed with
+/// [`Config::boxed`](crate::Config::boxed) are not known to the checker.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use prost::Message;
+/// # use prost_build::{CompatibilityChecker, Rule};
+/// # use prost_types::FileDescriptorSet;
+/// let old = FileDescriptorSet::decode(&*std::fs::read("old.bin").unwrap()).unwrap();
+/// let new = FileDescriptorSet::decode(&*std::fs::read("new.bin").unwrap()).unwrap();
+/// let changes = CompatibilityChecker::new()
+///     .allow(Rule::FieldRenamed)
+///     .check(&old, &new);
+/// for change in &changes {
+///     eprintln!("{}", change);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CompatibilityChecker {
+    rules: BTreeSet<Rule>,
+}
+
+impl CompatibilityChecker {
+    /// Creates a checker for all rules.
+    pub fn new() -> CompatibilityChecker {
+        CompatibilityChecker {
+            rules: Rule::ALL.iter().copied().collect(),
+        }
+    }
+
+    /// Creates a checker for the rules breaking the wire compatibility.
+    pub fn wire() -> CompatibilityChecker {
+        CompatibilityChecker {
+            rules: Rule::ALL
+                .iter()
+                .copied()
+                .filter(|rule| rule.is_wire_breaking())
+                .collect(),
+        }
+    }
+
+    /// Stops reporting changes breaking `rule`.
+    pub fn allow(&mut self, rule: Rule) -> &mut Self {
+        self.rules.remove(&rule);
+        self
+    }
+
+    /// Reports changes breaking `rule`.
+    pub fn deny(&mut self, rule: Rule) -> &mut Self {
+        self.rules.insert(rule);
+        self
+    }
+
+    /// Compares the `old` and `new` versions of a schema, and returns the breaking changes in
+    /// the order of the old schema.
+    pub fn check(&self, old: &FileDescriptorSet, new: &FileDescriptorSet) -> Vec<Change> {
+        let mut check = Check {
+            rules: &self.rules,
+            old: Schema::new(old),
+            new: Schema::new(new),
+            changes: Vec::new(),
+        };
+        check.files(&old.file, &new.file);
+        check.changes
+    }
+}
+
+impl Default for CompatibilityChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The types of one version of a schema.
+struct Schema<'a> {
+    messages: HashMap<String, Message<'a>>,
+    enums: HashMap<String, &'a EnumDescriptorProto>,
+    graph: MessageGraph,
+}
+
+#[derive(Clone, Copy)]
+struct Message<'a> {
+    descriptor: &'a DescriptorProto,
+    proto3: bool,
+}
+
+impl<'a> Schema<'a> {
+    fn new(set: &'a FileDescriptorSet) -> Schema<'a> {
+        let mut schema = Schema {
+            messages: HashMap::new(),
+            enums: HashMap::new(),
+            graph: MessageGraph::new(set.file.iter(), PathMap::default()),
+        };
+        for file in &set.file {
+            let proto3 = file.syntax() == "proto3";
+            let package = fq_package(file);
+            for message in &file.message_type {
+                schema.add_message(&package, message, proto3);
+            }
+            for desc in &file.enum_type {
+                schema
+                    .enums
+                    .insert(format!("{}.{}", package, desc.name()), desc);
+            }
+        }
+        schema
+    }
+
+    fn add_message(&mut self, scope: &str, descriptor: &'a DescriptorProto, proto3: bool) {
+        let name = format!("{}.{}", scope, descriptor.name());
+        for nested in &descriptor.nested_type {
+            self.add_message(&name, nested, proto3);
+        }
+        for desc in &descriptor.enum_type {
+            self.enums.insert(format!("{}.{}", name, desc.name()), desc);
+        }
+        self.messages.insert(name, Message { descriptor, proto3 });
+    }
+}
+
+struct Check<'a> {
+    rules: &'a BTreeSet<Rule>,
+    old: Schema<'a>,
+    new: Schema<'a>,
+    changes: Vec<Change>,
+}
+
+impl Check<'_> {
+    fn report(&mut self, rule: Rule, path: &str, message: String) {
+        if self.rules.contains(&rule) {
+            self.changes.push(Change {
+                rule,
+                path: path.to_string(),
+                message,
+            });
+        }
+    }
+
+    fn files(&mut self, old: &[FileDescriptorProto], new: &[FileDescriptorProto]) {
+        for old_file in old {
+            if let Some(new_file) = new.iter().find(|file| file.name() == old_file.name()) {
+                if old_file.package() != new_file.package() {
+                    self.report(
+                        Rule::PackageChanged,
+                        old_file.name(),
+                        format!(
+                            "package changed from `{}` to `{}`",
+                            old_file.package(),
+                            new_file.package()
+                        ),
+                    );
+                }
+            }
+
+            let package = fq_package(old_file);
+            for message in &old_file.message_type {
+                self.message(&package, message);
+            }
+            for desc in &old_file.enum_type {
+                self.enumeration(&package, desc);
+            }
+        }
+    }
+
+    fn message(&mut self, scope: &str, old: &DescriptorProto) {
+        let name = format!("{}.{}", scope, old.name());
+        let Some(new) = self.new.messages.get(&name).copied() else {
+            // Removed map entries are reported as changes of their field.
+            if !is_map_entry(old) {
+                self.report(Rule::TypeRemoved, &name, "message removed".to_string());
+            }
+            return;
+        };
+        let old_message = self.old.messages[&name];
+
+        for old_field in &old.field {
+            let path = format!("{}.{}", name, old_field.name());
+            let by_number = new
+                .descriptor
+                .field
+                .iter()
+                .find(|field| field.number() == old_field.number());
+            match by_number {
+                Some(new_field) => {
+                    self.field(&name, &path, old_message, old_field, new, new_field);
+                }
+                None => {
+                    let by_name = new
+                        .descriptor
+                        .field
+                        .iter()
+                        .find(|field| field.name() == old_field.name());
+                    if let Some(new_field) = by_name {
+                        self.report(
+                            Rule::FieldRenumbered,
+                            &path,
+                            format!(
+                                "field number changed from {} to {}",
+                                old_field.number(),
+                                new_field.number()
+                            ),
+                        );
+                        continue;
+                    }
+                    self.report(
+                        Rule::FieldRemoved,
+                        &path,
+                        format!("field {} removed", old_field.number()),
+                    );
+                    if !is_reserved_number(new.descriptor, old_field.number()) {
+                        self.report(
+                            Rule::NumberNotReserved,
+                            &path,
+                            format!(
+                                "field {} removed without reserving its number",
+                                old_field.number()
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+
+        for new_field in &new.descriptor.field {
+            let reused_number = is_reserved_number(old, new_field.number());
+            let reused_name = old
+                .reserved_name
+                .iter()
+                .any(|reserved| reserved == new_field.name());
+            if reused_number || reused_name {
+                self.report(
+                    Rule::ReservedReused,
+                    &format!("{}.{}", name, new_field.name()),
+                    format!(
+                        "field uses the reserved {} `{}`",
+                        if reused_number { "number" } else { "name" },
+                        if reused_number {
+                            new_field.number().to_string()
+                        } else {
+                            new_field.name().to_string()
+                        }
+                    ),
+                );
+            }
+        }
+
+        for nested in &old.nested_type {
+            self.message(&name, nested);
+        }
+        for desc in &old.enum_type {
+            self.enumeration(&name, desc);
+        }
+    }
+
+    fn field(
+        &mut self,
+        message_name: &str,
+        path: &str,
+        old_message: Message<'_>,
+        old: &FieldDescriptorProto,
+        new_message: Message<'_>,
+        new: &FieldDescriptorProto,
+    ) {
+        if old.name() != new.name() {
+            self.report(
+                Rule::FieldRenamed,
+                path,
+                format!(
+                    "field {} renamed from `{}` to `{}`",
+                    old.number(),
+                    old.name(),
+                    new.name()
+                ),
+            );
+        }
+
+        if old.r#type() != new.r#type() {
+            let rule = if is_wire_compatible(old.r#type(), new.r#type()) {
+                Rule::TypeChanged
+            } else {
+                Rule::WireTypeChanged
+            };
+            self.report(
+                rule,
+                path,
+                format!(
+                    "type changed from {} to {}",
+                    type_description(old),
+                    type_description(new)
+                ),
+            );
+        } else if old.type_name() != new.type_name() {
+            // Enumerations are all encoded as varints, but a message type has its own fields.
+            let rule = if is_message(old) {
+                Rule::WireTypeChanged
+            } else {
+                Rule::TypeChanged
+            };
+            self.report(
+                rule,
+                path,
+                format!(
+                    "type changed from `{}` to `{}`",
+                    old.type_name(),
+                    new.type_name()
+                ),
+            );
+        }
+
+        if old.label() != new.label() {
+            self.report(
+                Rule::LabelChanged,
+                path,
+                format!(
+                    "label changed from {} to {}",
+                    label_description(old.label()),
+                    label_description(new.label())
+                ),
+            );
+        } else {
+            let old_presence = presence(old_message, old);
+            let new_presence = presence(new_message, new);
+            // Changing between message and scalar types also changes the presence, which is
+            // already reported as a type change.
+            if old_presence != new_presence && is_message(old) == is_message(new) {
+                self.report(
+                    Rule::PresenceChanged,
+                    path,
+                    format!("presence changed from {} to {}", old_presence, new_presence),
+                );
+            }
+        }
+
+        let old_boxed = is_boxed(&self.old.graph, message_name, old);
+        let new_boxed = is_boxed(&self.new.graph, message_name, new);
+        if old_boxed != new_boxed {
+            self.report(
+                Rule::BoxingChanged,
+                path,
+                if new_boxed {
+                    "field is boxed".to_string()
+                } else {
+                    "field is no longer boxed".to_string()
+                },
+            );
+        }
+    }
+
+    fn enumeration(&mut self, scope: &str, old: &EnumDescriptorProto) {
+        let name = format!("{}.{}", scope, old.name());
+        let Some(new) = self.new.enums.get(&name).copied() else {
+            self.report(Rule::TypeRemoved, &name, "enum removed".to_string());
+            return;
+        };
+
+        for old_value in &old.value {
+            let path = format!("{}.{}", name, old_value.name());
+            let by_number = new
+                .value
+                .iter()
+                .find(|value| value.number() == old_value.number());
+            match by_number {
+                Some(new_value) => {
+                    // Aliases may keep the old name with the same number.
+                    let aliased = new
+                        .value
+                        .iter()
+                        .any(|value| value.name() == old_value.name());
+                    if !aliased {
+                        self.report(
+                            Rule::EnumValueRenamed,
+                            &path,
+                            format!(
+                                "value {} renamed from `{}` to `{}`",
+                                old_value.number(),
+                                old_value.name(),
+                                new_value.name()
+                            ),
+                        );
+                    }
+                }
+                None => match new
+                    .value
+                    .iter()
+                    .find(|value| value.name() == old_value.name())
+                {
+                    Some(new_value) => self.report(
+                        Rule::EnumValueRenumbered,
+                        &path,
+                        format!(
+                            "value number changed from {} to {}",
+                            old_value.number(),
+                            new_value.number()
+                        ),
+                    ),
+                    None => self.report(
+                        Rule::EnumValueRemoved,
+                        &path,
+                        format!("value {} removed", old_value.number()),
+                    ),
+                },
+            }
+        }
+
+        for new_value in &new.value {
+            let reused_number = old.reserved_range.iter().any(|range| {
+                range.start() <= new_value.number() && new_value.number() <= range.end()
+            });
+            let reused_name = old
+                .reserved_name
+                .iter()
+                .any(|reserved| reserved == new_value.name());
+            if reused_number || reused_name {
+                self.report(
+                    Rule::ReservedReused,
+                    &format!("{}.{}", name, new_value.name()),
+                    format!(
+                        "value uses the reserved {} `{}`",
+                        if reused_number { "number" } else { "name" },
+                        if reused_number {
+                            new_value.number().to_string()
+                        } else {
+                            new_value.name().to_string()
+                        }
+                    ),
+                );
+            }
+        }
+    }
+}
+
+/// How the generated Rust code represents the presence of a field.
+#[derive(Debug, PartialEq, Eq)]
+enum Presence {
+    /// A plain value, which is not encoded when it is the default.
+    Implicit,
+    /// An `Option`.
+    Explicit,
+    /// A plain value, which is always encoded.
+    Required,
+    /// A `Vec` or map.
+    Repeated,
+    /// A variant of the named oneof.
+    Oneof(String),
+}
+
+impl fmt::Display for Presence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Presence::Implicit => f.write_str("implicit"),
+            Presence::Explicit => f.write_str("explicit"),
+            Presence::Required => f.write_str("required"),
+            Presence::Repeated => f.write_str("repeated"),
+            Presence::Oneof(name) => write!(f, "oneof `{}`", name),
+        }
+    }
+}
+
+fn presence(message: Message<'_>, field: &FieldDescriptorProto) -> Presence {
+    match field.label() {
+        Label::Repeated => return Presence::Repeated,
+        Label::Required => return Presence::Required,
+        Label::Optional => {}
+    }
+    if let Some(index) = field.oneof_index.filter(|_| !field.proto3_optional()) {
+        let oneof = message
+            .descriptor
+            .oneof_decl
+            .get(index as usize)
+            .map_or("", |oneof| oneof.name());
+        return Presence::Oneof(oneof.to_string());
+    }
+    if field.proto3_optional() || is_message(field) || !message.proto3 {
+        Presence::Explicit
+    } else {
+        Presence::Implicit
+    }
+}
+
+fn is_message(field: &FieldDescriptorProto) -> bool {
+    matches!(field.r#type(), Type::Message | Type::Group)
+}
+
+fn is_boxed(graph: &MessageGraph, fq_message_name: &str, field: &FieldDescriptorProto) -> bool {
+    field.label() != Label::Repeated
+        && is_message(field)
+        && graph.is_nested(field.type_name(), fq_message_name)
+}
+
+/// Returns `true` if values of type `old` can be decoded as type `new`.
+fn is_wire_compatible(old: Type, new: Type) -> bool {
+    fn varint(ty: Type) -> bool {
+        matches!(
+            ty,
+            Type::Int32 | Type::Int64 | Type::Uint32 | Type::Uint64 | Type::Bool | Type::Enum
+        )
+    }
+    match (old, new) {
+        (old, new) if varint(old) && varint(new) => true,
+        (Type::Sint32 | Type::Sint64, Type::Sint32 | Type::Sint64)
+        | (Type::Fixed32 | Type::Sfixed32, Type::Fixed32 | Type::Sfixed32)
+        | (Type::Fixed64 | Type::Sfixed64, Type::Fixed64 | Type::Sfixed64)
+        | (Type::String | Type::Bytes, Type::String | Type::Bytes)
+        | (Type::Message | Type::Bytes, Type::Message | Type::Bytes) => true,
+        _ => false,
+    }
+}
+
+fn type_description(field: &FieldDescriptorProto) -> String {
+    match field.r#type() {
+        Type::Message | Type::Enum | Type::Group => format!("`{}`", field.type_name()),
+        ty => ty.as_str_name().trim_start_matches("TYPE_").to_lowercase(),
+    }
+}
+
+fn label_description(label: Label) -> &'static str {
+    match label {
+        Label::Optional => "singular",
+        Label::Required => "required",
+        Label::Repeated => "repeated",
+    }
+}
+
+fn is_reserved_number(message: &DescriptorProto, number: i32) -> bool {
+    message
+        .reserved_range
+        .iter()
+        .any(|range| range.start() <= number && number < range.end())
+}
+
+fn is_map_entry(message: &DescriptorProto) -> bool {
+    message
+        .options
+        .as_ref()
+        .and_then(|options| options.map_entry)
+        .unwrap_or(false)
+}
+
+fn fq_package(file: &FileDescriptorProto) -> String {
+    if file.package().is_empty() {
+        String::new()
+    } else {
+        format!(".{}", file.package())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    use prost::Message as _;
+
+    fn schema(source: &str) -> FileDescriptorSet {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("schema.proto");
+        fs::write(&path, source).unwrap();
+        let encoded =
+            crate::parser::parse(&[&path], &[tempdir.path().to_path_buf()], false).unwrap();
+        FileDescriptorSet::decode(&*encoded).unwrap()
+    }
+
+    fn check(checker: &CompatibilityChecker, old: &str, new: &str) -> Vec<(Rule, String)> {
+        checker
+            .check(&schema(old), &schema(new))
+            .into_iter()
+            .map(|change| (change.rule, change.path))
+            .collect()
+    }
+
+    fn changes(old: &str, new: &str) -> Vec<(Rule, String)> {
+        check(&CompatibilityChecker::new(), old, new)
+    }
+
+    #[test]
+    fn unchanged() {
+        let source = r#"
+            syntax = "proto3";
+            package compat;
+            message Node {
+                int32 id = 1;
+                Node next = 2;
+                map<string, Node> children = 3;
+                enum Kind { KIND_UNSPECIFIED = 0; }
+            }
+        "#;
+        assert_eq!(changes(source, source), []);
+    }
+
+    #[test]
+    fn fields() {
+        let old = r#"
+            syntax = "proto3";
+            package compat;
+            message Other {}
+            message Msg {
+                reserved 9;
+                reserved "legacy";
+                int32 a = 1;
+                string b = 2;
+                int32 c = 3;
+                Other d = 4;
+                repeated int32 e = 5;
+                string f = 6;
+                string g = 7;
+                fixed32 h = 8;
+            }
+        "#;
+        let new = r#"
+            syntax = "proto3";
+            package compat;
+            message Other {}
+            message Msg {
+                reserved 7;
+                int32 alpha = 1;
+                int64 c = 3;
+                string d = 4;
+                int32 e = 5;
+                string f = 16;
+                uint32 h = 8;
+                bool legacy = 9;
+            }
+        "#;
+        assert_eq!(
+            changes(old, new),
+            [
+                (Rule::FieldRenamed, ".compat.Msg.a".to_string()),
+                (Rule::FieldRemoved, ".compat.Msg.b".to_string()),
+                (Rule::NumberNotReserved, ".compat.Msg.b".to_string()),
+                (Rule::TypeChanged, ".compat.Msg.c".to_string()),
+                (Rule::WireTypeChanged, ".compat.Msg.d".to_string()),
+                (Rule::LabelChanged, ".compat.Msg.e".to_string()),
+                (Rule::FieldRenumbered, ".compat.Msg.f".to_string()),
+                (Rule::FieldRemoved, ".compat.Msg.g".to_string()),
+                (Rule::WireTypeChanged, ".compat.Msg.h".to_string()),
+                (Rule::ReservedReused, ".compat.Msg.legacy".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn presence_and_boxing() {
+        let old = r#"
+            syntax = "proto3";
+            package compat;
+            message Tree {
+                int32 a = 1;
+                optional int32 b = 2;
+                int32 c = 3;
+                Tree child = 4;
+                Leaf leaf = 5;
+            }
+            message Leaf {}
+        "#;
+        let new = r#"
+            syntax = "proto3";
+            package compat;
+            message Tree {
+                optional int32 a = 1;
+                int32 b = 2;
+                oneof choice {
+                    int32 c = 3;
+                }
+                Leaf child = 4;
+                Leaf leaf = 5;
+            }
+            message Leaf {
+                Tree tree = 1;
+            }
+        "#;
+        assert_eq!(
+            changes(old, new),
+            [
+                (Rule::PresenceChanged, ".compat.Tree.a".to_string()),
+                (Rule::PresenceChanged, ".compat.Tree.b".to_string()),
+                (Rule::PresenceChanged, ".compat.Tree.c".to_string()),
+                (Rule::WireTypeChanged, ".compat.Tree.child".to_string()),
+                (Rule::BoxingChanged, ".compat.Tree.leaf".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn types_and_enums() {
+        let old = r#"
+            syntax = "proto3";
+            package compat;
+            message Gone {}
+            enum Status {
+                reserved 5;
+                STATUS_UNSPECIFIED = 0;
+                STATUS_OK = 1;
+                STATUS_FAILED = 2;
+                STATUS_UNKNOWN = 3;
+            }
+        "#;
+        let new = r#"
+            syntax = "proto3";
+            package compat.v2;
+            enum Status {
+                STATUS_UNSPECIFIED = 0;
+                STATUS_SUCCESS = 1;
+                STATUS_FAILED = 4;
+                STATUS_RETRY = 5;
+            }
+        "#;
+        assert_eq!(
+            changes(old, new),
+            [
+                (Rule::PackageChanged, "schema.proto".to_string()),
+                (Rule::TypeRemoved, ".compat.Gone".to_string()),
+                (Rule::TypeRemoved, ".compat.Status".to_string()),
+            ]
+        );
+
+        let new = new.replace("compat.v2", "compat");
+        assert_eq!(
+            changes(old, &new),
+            [
+                (Rule::TypeRemoved, ".compat.Gone".to_string()),
+                (
+                    Rule::EnumValueRenamed,
+                    ".compat.Status.STATUS_OK".to_string()
+                ),
+                (
+                    Rule::EnumValueRenumbered,
+                    ".compat.Status.STATUS_FAILED".to_string()
+                ),
+                (
+                    Rule::EnumValueRemoved,
+                    ".compat.Status.STATUS_UNKNOWN".to_string()
+                ),
+                (
+                    Rule::ReservedReused,
+                    ".compat.Status.STATUS_RETRY".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn rules() {
+        let old = r#"
+            syntax = "proto3";
+            package compat;
+            message Msg {
+                int32 a = 1;
+                int32 b = 2;
+            }
+        "#;
+        let new = r#"
+            syntax = "proto3";
+            package compat;
+            message Msg {
+                int32 alpha = 1;
+                int64 b = 2;
+                string c = 3;
+            }
+        "#;
+        assert_eq!(check(&CompatibilityChecker::wire(), old, new), []);
+        assert_eq!(
+            check(
+                CompatibilityChecker::new().allow(Rule::FieldRenamed),
+                old,
+                new
+            ),
+            [(Rule::TypeChanged, ".compat.Msg.b".to_string())]
+        );
+        assert_eq!(
+            check(
+                CompatibilityChecker::wire().deny(Rule::FieldRenamed),
+                old,
+                new
+            ),
+            [(Rule::FieldRenamed, ".compat.Msg.a".to_string())]
+        );
+    }
+
+    #[test]
+    fn wire_rules() {
+        let old = r#"
+            syntax = "proto3";
+            package compat;
+            message Foo {}
+            message Bar {}
+            enum Color {
+                COLOR_UNSPECIFIED = 0;
+                COLOR_RED = 1;
+            }
+            enum Shade {
+                SHADE_UNSPECIFIED = 0;
+            }
+            message Msg {
+                Foo message = 1;
+                Color color = 2;
+                int32 number = 3;
+                int32 renumbered = 4;
+                repeated int32 list = 5;
+                int32 removed = 6;
+                optional int32 optional = 7;
+            }
+        "#;
+        let new = r#"
+            syntax = "proto3";
+            package compat;
+            message Foo {}
+            message Bar {}
+            enum Color {
+                COLOR_UNSPECIFIED = 0;
+            }
+            enum Shade {
+                SHADE_UNSPECIFIED = 0;
+            }
+            message Msg {
+                Bar message = 1;
+                Shade color = 2;
+                string number = 3;
+                int32 renumbered = 14;
+                int32 list = 5;
+                int32 optional = 7;
+            }
+        "#;
+        let wire = [
+            (Rule::WireTypeChanged, ".compat.Msg.message".to_string()),
+            (Rule::WireTypeChanged, ".compat.Msg.number".to_string()),
+            (Rule::FieldRenumbered, ".compat.Msg.renumbered".to_string()),
+            (Rule::LabelChanged, ".compat.Msg.list".to_string()),
+            (Rule::NumberNotReserved, ".compat.Msg.removed".to_string()),
+            (
+                Rule::EnumValueRemoved,
+                ".compat.Color.COLOR_RED".to_string(),
+            ),
+        ];
+        let all = changes(old, new);
+        assert_eq!(
+            all.iter()
+                .filter(|(rule, _)| rule.is_wire_breaking())
+                .cloned()
+                .collect::<Vec<_>>(),
+            check(&CompatibilityChecker::wire(), old, new)
+        );
+        assert_eq!(check(&CompatibilityChecker::wire(), old, new), wire);
+        assert!(all.contains(&(Rule::TypeChanged, ".compat.Msg.color".to_string())));
+        assert!(all.contains(&(Rule::PresenceChanged, ".compat.Msg.optional".to_string())));
+    }
+}
@@ -9,26 +9,40 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use log::debug;
+use log::info;
 use log::trace;
+use log::warn;
 
 use prost::Message;
-use prost_types::{FileDescriptorProto, FileDescriptorSet};
+use prost_types::compiler::{code_generator_response, CodeGeneratorRequest, CodeGeneratorResponse};
+use prost_types::field_descriptor_proto::Type;
+use prost_types::{DescriptorProto, EnumDescriptorProto, FileDescriptorProto, FileDescriptorSet};
 
 use crate::code_generator::CodeGenerator;
+use crate::descriptor_set::EncodedFiles;
 use crate::extern_paths::ExternPaths;
 use crate::message_graph::MessageGraph;
+use crate::naming::{Names, Naming, Renames};
 use crate::path::PathMap;
+use crate::validation::{extract_field_constraints, FieldConstraints};
 use crate::BytesType;
 use crate::MapType;
+use crate::MessageGenerator;
 use crate::Module;
+use crate::NamingStrategy;
 use crate::ServiceGenerator;
 
+mod options;
+
 /// Configuration options for Protobuf code generation.
 ///
 /// This configuration builder can be used to set non-default code generation options.
 pub struct Config {
     pub(crate) file_descriptor_set_path: Option<PathBuf>,
     pub(crate) service_generator: Option<Box<dyn ServiceGenerator>>,
+    pub(crate) message_generator: Option<Box<dyn MessageGenerator>>,
+    pub(crate) naming_strategy: Option<Box<dyn NamingStrategy>>,
+    pub(crate) renames: Renames,
     pub(crate) map_type: PathMap<MapType>,
     pub(crate) bytes_type: PathMap<BytesType>,
     pub(crate) type_attributes: PathMap<String>,
@@ -36,23 +50,55 @@ pub struct Config {
     pub(crate) enum_attributes: PathMap<String>,
     pub(crate) field_attributes: PathMap<String>,
     pub(crate) boxed: PathMap<()>,
+    pub(crate) box_threshold: Option<usize>,
     pub(crate) prost_types: bool,
+    pub(crate) wrapper_types: bool,
     pub(crate) strip_enum_prefix: bool,
     pub(crate) out_dir: Option<PathBuf>,
     pub(crate) extern_paths: Vec<(String, String)>,
+    pub(crate) published_extern_path: Option<String>,
+    pub(crate) extern_path_manifests: Vec<PathBuf>,
+    pub(crate) discover_extern_paths: bool,
     pub(crate) default_package_filename: String,
     pub(crate) enable_type_names: bool,
     pub(crate) type_name_domains: PathMap<String>,
+    pub(crate) type_registry: bool,
+    pub(crate) intra_doc_links: bool,
+    pub(crate) embed_file_descriptor_set: bool,
+    pub(crate) strip_embedded_source_info: bool,
+    pub(crate) encoded_files: EncodedFiles,
     pub(crate) protoc_args: Vec<OsString>,
     pub(crate) protoc_executable: PathBuf,
     pub(crate) disable_comments: PathMap<()>,
     pub(crate) skip_debug: PathMap<()>,
     pub(crate) skip_protoc_run: bool,
     pub(crate) skip_source_info: bool,
+    pub(crate) builtin_parser: bool,
     pub(crate) include_file: Option<PathBuf>,
     pub(crate) prost_path: Option<String>,
+    pub(crate) prost_types_path: Option<String>,
+    pub(crate) validation: PathMap<()>,
+    pub(crate) builders: PathMap<()>,
+    pub(crate) accessors: PathMap<()>,
+    pub(crate) oneof_conversions: PathMap<()>,
+    pub(crate) enum_helpers: PathMap<()>,
+    pub(crate) roots: PathMap<()>,
+    pub(crate) derive_eq_hash: PathMap<()>,
+    pub(crate) derive_ord: PathMap<()>,
+    pub(crate) extern_eq_hash: PathMap<()>,
+    pub(crate) extern_ord: PathMap<()>,
+    pub(crate) required_field_presence: PathMap<()>,
+    pub(crate) arbitrary: PathMap<()>,
+    pub(crate) proptest: PathMap<()>,
+    pub(crate) arbitrary_unknown_enum_values: PathMap<()>,
+    pub(crate) arbitrary_max_depth: u32,
+    pub(crate) field_constraints: HashMap<String, FieldConstraints>,
+    pub(crate) unreachable_types: Vec<String>,
+    pub(crate) published_types: Vec<(String, String)>,
     #[cfg(feature = "format")]
     pub(crate) fmt: bool,
+    #[cfg(feature = "format")]
+    pub(crate) check_generated_code: bool,
 }
 
 impl Config {
@@ -192,7 +238,8 @@ impl Config {
     /// **`attribute`** - an arbitrary string that'll be placed before each matched field. The
     /// expected usage are additional attributes, usually in concert with whole-type
     /// attributes set with [`type_attribute`](Self::type_attribute), but it is not
-    /// checked and anything can be put there.
+    /// checked and anything can be put there, unless
+    /// [`check_generated_code`](Self::check_generated_code) is enabled.
     ///
     /// Note that the calls to this method are cumulative ‒ if multiple paths from multiple calls
     /// match the same field, the field gets all the corresponding attributes.
@@ -223,7 +270,8 @@ impl Config {
     /// [`btree_map`](Self::btree_map), just with the field name omitted.
     ///
     /// **`attribute`** - an arbitrary string to be placed before each matched type. The
-    /// expected usage are additional attributes, but anything is allowed.
+    /// expected usage are additional attributes, but anything is allowed, unless
+    /// [`check_generated_code`](Self::check_generated_code) is enabled.
     ///
     /// The calls to this method are cumulative. They don't overwrite previous calls and if a
     /// type is matched by multiple calls of the method, all relevant attributes are added to
@@ -272,7 +320,8 @@ impl Config {
     /// [`btree_map`](Self::btree_map), just with the field name omitted.
     ///
     /// **`attribute`** - an arbitrary string to be placed before each matched type. The
-    /// expected usage are additional attributes, but anything is allowed.
+    /// expected usage are additional attributes, but anything is allowed, unless
+    /// [`check_generated_code`](Self::check_generated_code) is enabled.
     ///
     /// The calls to this method are cumulative. They don't overwrite previous calls and if a
     /// type is matched by multiple calls of the method, all relevant attributes are added to
@@ -311,7 +360,8 @@ impl Config {
     /// [`btree_map`](Self::btree_map), just with the field name omitted.
     ///
     /// **`attribute`** - an arbitrary string to be placed before each matched type. The
-    /// expected usage are additional attributes, but anything is allowed.
+    /// expected usage are additional attributes, but anything is allowed, unless
+    /// [`check_generated_code`](Self::check_generated_code) is enabled.
     ///
     /// The calls to this method are cumulative. They don't overwrite previous calls and if a
     /// type is matched by multiple calls of the method, all relevant attributes are added to
@@ -373,12 +423,111 @@ impl Config {
         self
     }
 
+    /// Wrap message fields and oneof variants in a `Box` when their message type is estimated to
+    /// be larger than `threshold` bytes.
+    ///
+    /// A oneof enum is as large as its largest variant, and a message is as large as all of its
+    /// fields together, so a single large message type makes every message or oneof which
+    /// contains it large as well. This option boxes the non-repeated message fields and oneof
+    /// members whose type exceeds the threshold, so that the containing type only stores a
+    /// pointer.
+    ///
+    /// The sizes are estimated from the field types of the generated structs, taking into account
+    /// fields which are boxed because of recursion or [`boxed`](Self::boxed). The estimates are
+    /// independent of the target: they always use the sizes of a 64-bit target, like 8 bytes for
+    /// a pointer and 24 bytes for a `Vec` or a `String`, so the same fields are boxed, and the
+    /// same API is generated, whatever the target of the build. The sizes on 32-bit targets are
+    /// smaller, so fields may be boxed there which are below the threshold.
+    /// Each field which is boxed because of its size is reported when code is generated, with a
+    /// `cargo:warning` in a build script, and otherwise logged at the `warn` level.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Box the fields of message types larger than 256 bytes.
+    /// config.box_large_fields(256);
+    /// ```
+    pub fn box_large_fields(&mut self, threshold: usize) -> &mut Self {
+        self.box_threshold = Some(threshold);
+        self
+    }
+
+    /// Only generate the matched messages, enums and services, and the types reachable from them.
+    ///
+    /// Large schemas often define many more types than a crate uses. With this option, code is
+    /// only generated for the root types and the types they depend on: the types of the fields of
+    /// messages, including map values and oneof members, and the input and output types of the
+    /// methods of services. Nested types also keep their parent message, since they are
+    /// generated in its module. All other types of the compiled files are dropped. In a build
+    /// script, the number of dropped types is reported with a `cargo:warning`, and otherwise it
+    /// is logged at the `warn` level. The dropped types themselves are logged at the `info` level,
+    /// and returned by [`unreachable_types`](Self::unreachable_types) after code generation.
+    ///
+    /// Types of files which are not compiled, like imports, are never generated, and so should be
+    /// mapped with [`extern_path`](Self::extern_path) or compiled as well.
+    ///
+    /// # Arguments
+    ///
+    /// **`roots`** - paths to the root messages, enums or services, or to packages whose types
+    /// are all roots. For details about matching paths see [`btree_map`](Self::btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Only generate the `Library` service and the types it uses.
+    /// config.retain_reachable_types(&[".google.example.library.v1.LibraryService"]);
+    /// ```
+    pub fn retain_reachable_types<I, S>(&mut self, roots: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.roots.clear();
+        for root in roots {
+            self.roots.insert(root.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Returns the fully-qualified names of the types which were dropped by
+    /// [`retain_reachable_types`](Self::retain_reachable_types) in the last code generation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use std::io::Result;
+    /// fn main() -> Result<()> {
+    ///     let mut config = prost_build::Config::new();
+    ///     config
+    ///         .retain_reachable_types(&[".library.LibraryService"])
+    ///         .compile_protos(&["src/library.proto"], &["src/"])?;
+    ///     for name in config.unreachable_types() {
+    ///         println!("skipped {}", name);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn unreachable_types(&self) -> &[String] {
+        &self.unreachable_types
+    }
+
     /// Configures the code generator to use the provided service generator.
     pub fn service_generator(&mut self, service_generator: Box<dyn ServiceGenerator>) -> &mut Self {
         self.service_generator = Some(service_generator);
         self
     }
 
+    /// Configures the code generator to use the provided message generator.
+    ///
+    /// The message generator is called for every generated message and enum, and can append
+    /// additional code next to them. See [`MessageGenerator`] for details.
+    pub fn message_generator(&mut self, message_generator: Box<dyn MessageGenerator>) -> &mut Self {
+        self.message_generator = Some(message_generator);
+        self
+    }
+
     /// Configures the code generator to not use the `prost_types` crate for Protobuf well-known
     /// types, and instead generate Protobuf well-known types from their `.proto` definitions.
     pub fn compile_well_known_types(&mut self) -> &mut Self {
@@ -386,6 +535,29 @@ impl Config {
         self
     }
 
+    /// Configures the code generator to map fields of the wrapper well-known types, like
+    /// `google.protobuf.Int32Value` and `google.protobuf.StringValue`, to an `Option` of the
+    /// wrapped Rust type, like `Option<i32>` and `Option<String>`, even when
+    /// [`compile_well_known_types`](Self::compile_well_known_types) is set.
+    ///
+    /// This is always the case when the well-known types come from `prost_types`. `prost`
+    /// implements `Message` for the wrapped types, so the encoding is identical to the wrapper
+    /// messages. The wrapper messages themselves are not generated.
+    ///
+    /// With this option, fields of type `google.protobuf.BytesValue` also follow the
+    /// [`bytes`](Self::bytes) configuration, so they can be mapped to `Option<Bytes>`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.compile_well_known_types().map_wrapper_types();
+    /// ```
+    pub fn map_wrapper_types(&mut self) -> &mut Self {
+        self.wrapper_types = true;
+        self
+    }
+
     /// Configures the code generator to omit documentation comments on generated Protobuf types.
     ///
     /// # Example
@@ -435,6 +607,543 @@ impl Config {
         self
     }
 
+    /// Generate `prost::Validate` implementations for messages, checking their fields against
+    /// [protovalidate] (`buf.validate.field`) constraints.
+    ///
+    /// The constraints are read from the field options of the `.proto` files, which must import
+    /// `buf/validate/validate.proto`. The following rules are supported; any others are ignored:
+    ///
+    /// * `required`,
+    /// * `const`, `lt`, `lte`, `gt`, `gte`, `in` and `not_in` for numeric fields,
+    /// * `const`, `len`, `min_len`, `max_len`, `len_bytes`, `min_bytes`, `max_bytes`, `pattern`,
+    ///   `prefix`, `suffix`, `contains`, `not_contains`, `in` and `not_in` for strings,
+    /// * `const`, `len`, `min_len`, `max_len`, `prefix`, `suffix` and `contains` for bytes,
+    /// * `const`, `defined_only`, `in` and `not_in` for enumerations,
+    /// * `min_items`, `max_items`, `unique` and `items` for repeated fields,
+    /// * `min_pairs`, `max_pairs`, `keys` and `values` for maps.
+    ///
+    /// Validation recurses into message fields whose type also has a generated implementation.
+    ///
+    /// Generated code for `pattern` rules uses `::regex::Regex` and `::std::sync::OnceLock`, so
+    /// the crate including it must depend on the [`regex`] crate and use `std`. Patterns which
+    /// `regex` can not compile are reported as errors while generating the code.
+    ///
+    /// Since `prost-types` can not represent the constraint extension, constraints are only
+    /// available when the descriptors are loaded by `prost-build`, i.e. through
+    /// [`Config::compile_protos`] or [`Config::load_fds`], and not when a [`FileDescriptorSet`] is
+    /// passed directly to [`Config::compile_fds`].
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, or packages which should implement `Validate`.
+    /// For details about matching paths see [`btree_map`](Self::btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Validate all messages.
+    /// config.validation(&["."]);
+    /// // Validate only the messages of the `my_messages` package.
+    /// config.validation(&[".my_messages"]);
+    /// ```
+    ///
+    /// [protovalidate]: https://github.com/bufbuild/protovalidate
+    /// [`regex`]: https://docs.rs/regex
+    pub fn validation<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.validation.clear();
+        for matcher in paths {
+            self.validation.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Generate a builder for messages.
+    ///
+    /// For a message `Foo`, a `FooBuilder` type is generated alongside it, along with a
+    /// `Foo::builder()` constructor. The builder has a setter per field, named after the field,
+    /// and a `build()` method returning the message:
+    ///
+    /// * enumeration fields take the generated enum type rather than `i32`,
+    /// * string and bytes fields take any value which converts `Into` the field type,
+    /// * repeated fields take an iterator of values, and also have a `push_<field>` method
+    ///   appending a single value,
+    /// * map fields take an iterator of entries, and also have an `insert_<field>` method,
+    /// * each member of a oneof has its own setter, which selects that variant,
+    /// * message fields whose type also has a builder have an additional `<field>_with` method
+    ///   or `push_<field>_with` when repeated, which builds the value with a closure.
+    ///
+    /// Code generation fails if the name of a builder is also the name of another type, like a
+    /// message named `FooBuilder`. Either type can be renamed with [`rename`](Self::rename).
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, or packages which should have builders.
+    /// For details about matching paths see [`btree_map`](Self::btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Generate builders for all messages.
+    /// config.builders(&["."]);
+    /// ```
+    ///
+    /// With the configuration above, a message could be constructed as:
+    ///
+    /// ```rust,ignore
+    /// let user = User::builder()
+    ///     .name("alice")
+    ///     .role(Role::Admin)
+    ///     .push_tags("admin")
+    ///     .address_with(|address| address.city("Paris"))
+    ///     .build();
+    /// ```
+    pub fn builders<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.builders.clear();
+        for matcher in paths {
+            self.builders.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Generate accessor methods for the optional fields and the oneofs of matched messages.
+    ///
+    /// Matched messages and the enums of their oneofs get a `#[prost(accessors)]` attribute,
+    /// with which `prost-derive` generates the following methods of the message:
+    ///
+    /// * `has_<field>()`, `clear_<field>()` and `take_<field>()` for every field which is
+    ///   generated as an `Option`, and for every oneof,
+    /// * `which_<oneof>()` for every oneof, returning the Protobuf name of the field which is set,
+    /// * `is_<field>()`, `as_<field>()`, `as_<field>_mut()`, `set_<field>()` and
+    ///   `take_<field>()` for every field of a oneof, which select or extract that field. These
+    ///   are named after the snake case name of the enum variant of the field.
+    ///
+    /// The enums generated for the oneofs also get `field_name()` and `tag()` methods, returning
+    /// the Protobuf name and the number of the field which is set.
+    ///
+    /// Code generation fails if two methods of a message would have the same name, including the
+    /// getters of enumeration fields, like `has_foo()` for fields `foo` and `has_foo`, or
+    /// `as_foo_mut()` for oneof fields `foo` and `foo_mut`. Fields and the enum variants of the
+    /// fields of oneofs can be renamed with [`rename`](Self::rename), and oneofs with
+    /// [`rename_oneof_field`](Self::rename_oneof_field).
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, or packages which should have accessors.
+    /// For details about matching paths see [`btree_map`](Self::btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Generate accessors for all messages.
+    /// config.accessors(&["."]);
+    /// ```
+    ///
+    /// With the configuration above, a message could be used as:
+    ///
+    /// ```rust,ignore
+    /// shape.set_circle(Circle { radius: 1.0 });
+    /// assert_eq!(shape.which_kind(), Some("circle"));
+    /// assert_eq!(shape.take_circle(), Some(Circle { radius: 1.0 }));
+    /// assert!(!shape.has_kind());
+    /// ```
+    pub fn accessors<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.accessors.clear();
+        for matcher in paths {
+            self.accessors.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Generate conversions between the oneofs of matched messages and the types of their
+    /// members.
+    ///
+    /// For each oneof, the following impls are generated:
+    ///
+    /// * `From<T> for Oneof` for every member type `T` which no other member of the oneof has,
+    ///   selecting that variant,
+    /// * `TryFrom<Oneof> for T` for the same member types, extracting the value of the variant and
+    ///   returning the oneof itself as the error when another variant is set.
+    ///
+    /// Members of enum types are skipped, since they are represented as `i32` and a conversion
+    /// would not tell the enum apart from other integers. Boxed members convert from and to the
+    /// unboxed type.
+    ///
+    /// Messages whose only field is a oneof also get a `From<Oneof> for Message` impl, so that a
+    /// member can be converted into the message with `Message::from(Oneof::from(value))`.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, or packages whose oneofs should have
+    /// conversions. For details about matching paths see [`btree_map`](Self::btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Generate oneof conversions for all messages.
+    /// config.oneof_conversions(&["."]);
+    /// ```
+    ///
+    /// With the configuration above, a message could be constructed as:
+    ///
+    /// ```rust,ignore
+    /// let event = Event::from(event::Kind::from(Heartbeat { sequence: 1 }));
+    /// let heartbeat = Heartbeat::try_from(event.kind.unwrap())?;
+    /// ```
+    pub fn oneof_conversions<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.oneof_conversions.clear();
+        for matcher in paths {
+            self.oneof_conversions
+                .insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Generate additional helpers for matched enums.
+    ///
+    /// In addition to `as_str_name` and `from_str_name`, the enums get:
+    ///
+    /// * a `VALUES` constant listing the values in declaration order, and an `iter` method
+    ///   iterating over them,
+    /// * an `is_deprecated` method, returning `true` for values with the `deprecated` option,
+    /// * `Display` and `FromStr` impls using the names of the values in the .proto file, where
+    ///   parsing fails with [`prost::UnknownEnumName`],
+    /// * an impl of the [`prost::ProtoEnum`] trait, which gives generic code access to all of the
+    ///   above.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific enums, or packages whose enums should have the helpers.
+    /// For details about matching paths see [`btree_map`](Self::btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Generate helpers for all enums.
+    /// config.enum_helpers(&["."]);
+    /// ```
+    ///
+    /// With the configuration above, an enum could be used as:
+    ///
+    /// ```rust,ignore
+    /// let status: Status = "STATUS_ACTIVE".parse()?;
+    /// assert_eq!(status.to_string(), "STATUS_ACTIVE");
+    /// let names = Status::iter().map(|status| status.as_str_name()).collect::<Vec<_>>();
+    /// ```
+    pub fn enum_helpers<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.enum_helpers.clear();
+        for matcher in paths {
+            self.enum_helpers.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Derive `Eq` and `Hash` for messages, where the types of their fields allow it.
+    ///
+    /// A message derives `Eq` and `Hash` when it is matched by one of the paths and all of its
+    /// fields implement them, which is checked transitively through the types of message fields.
+    /// Messages with a `float` or `double` field, a map field generated as a `HashMap`, or a field
+    /// of a message which doesn't derive the traits are left as they are. Fields of [extern
+    /// types](Self::extern_path) only allow the derives if the types are declared to implement
+    /// the traits with [`extern_eq_hash`](Self::extern_eq_hash), or if they are well-known types
+    /// mapped to `prost_types` or Rust primitives which implement them, like `Timestamp` and the
+    /// integer wrapper types. The oneofs of a message derive the same traits as the message.
+    ///
+    /// Unlike adding the derives with [`type_attribute`](Self::type_attribute), this keeps
+    /// compiling when a floating point field is added to a nested message. The traits must not
+    /// also be derived with `type_attribute` for the same messages.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, or packages which should derive `Eq` and
+    /// `Hash`. For details about matching paths see [`btree_map`](Self::btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Derive `Eq` and `Hash` wherever possible.
+    /// config.derive_eq_hash(&["."]);
+    /// ```
+    pub fn derive_eq_hash<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.derive_eq_hash.clear();
+        for matcher in paths {
+            self.derive_eq_hash.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Derive `PartialOrd` and `Ord`, as well as `Eq` and `Hash`, for messages, where the types
+    /// of their fields allow it.
+    ///
+    /// The messages are chosen like for [`derive_eq_hash`](Self::derive_eq_hash). Messages are
+    /// ordered by comparing their fields in the order they are declared in, as with any derived
+    /// `Ord`. Map fields must be generated as a `BTreeMap`. Fields of extern types must be
+    /// declared to implement the traits with [`extern_ord`](Self::extern_ord).
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, or packages which should derive `PartialOrd`
+    /// and `Ord`. For details about matching paths see [`btree_map`](Self::btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.derive_ord(&[".my_messages.Version"]);
+    /// ```
+    pub fn derive_ord<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.derive_ord.clear();
+        for matcher in paths {
+            self.derive_ord.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Declare that the Rust types of [extern types](Self::extern_path) implement `Eq` and
+    /// `Hash`, so that messages with fields of these types can derive them with
+    /// [`derive_eq_hash`](Self::derive_eq_hash).
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific extern types, or packages whose extern types implement
+    /// `Eq` and `Hash`. For details about matching paths see [`btree_map`](Self::btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config
+    ///     .extern_path(".uuid", "::uuid")
+    ///     .extern_eq_hash(&[".uuid.Uuid"])
+    ///     .derive_eq_hash(&["."]);
+    /// ```
+    pub fn extern_eq_hash<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.extern_eq_hash.clear();
+        for matcher in paths {
+            self.extern_eq_hash.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Declare that the Rust types of [extern types](Self::extern_path) implement `PartialOrd`
+    /// and `Ord`, as well as `Eq` and `Hash`, so that messages with fields of these types can
+    /// derive them with [`derive_ord`](Self::derive_ord).
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific extern types, or packages whose extern types implement
+    /// the traits. For details about matching paths see [`btree_map`](Self::btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config
+    ///     .extern_path(".uuid", "::uuid")
+    ///     .extern_ord(&[".uuid"])
+    ///     .derive_ord(&["."]);
+    /// ```
+    pub fn extern_ord<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.extern_ord.clear();
+        for matcher in paths {
+            self.extern_ord.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Generate proto2 `required` fields as `Option`s, so that missing required fields can be
+    /// detected.
+    ///
+    /// By default, required fields are generated as plain values, and a required field missing
+    /// from the decoded data silently takes its default value. Required fields matched by this
+    /// option are instead generated as `Option<T>`, and decoding a message with [`Message::decode`]
+    /// or encoding it with [`Message::encode`] fails if one of them is not set, in the message or
+    /// any of its submessages. [`Message::decode_partial`] and [`Message::encode_partial`] skip
+    /// the check, and so do [`Message::encode_to_vec`] and
+    /// [`Message::encode_length_delimited_to_vec`], whose checked variants are
+    /// [`Message::try_encode_to_vec`] and [`Message::try_encode_length_delimited_to_vec`].
+    /// [`Message::missing_fields`] lists the required fields which are not set.
+    ///
+    /// Only the messages which contain such required fields, directly or through their
+    /// submessages, are checked, so other messages are not traversed. Messages containing types
+    /// which are not compiled along with them, like those of extern paths, are checked too.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific fields, messages, or packages which should track the
+    /// presence of their required fields. For details about matching fields see
+    /// [`btree_map`](Self::btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Check required fields in all messages.
+    /// config.required_field_presence(&["."]);
+    /// ```
+    ///
+    /// [`Message::decode`]: prost::Message::decode
+    /// [`Message::decode_partial`]: prost::Message::decode_partial
+    /// [`Message::encode`]: prost::Message::encode
+    /// [`Message::encode_partial`]: prost::Message::encode_partial
+    /// [`Message::encode_to_vec`]: prost::Message::encode_to_vec
+    /// [`Message::encode_length_delimited_to_vec`]: prost::Message::encode_length_delimited_to_vec
+    /// [`Message::try_encode_to_vec`]: prost::Message::try_encode_to_vec
+    /// [`Message::try_encode_length_delimited_to_vec`]: prost::Message::try_encode_length_delimited_to_vec
+    /// [`Message::missing_fields`]: prost::Message::missing_fields
+    pub fn required_field_presence<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.required_field_presence.clear();
+        for matcher in paths {
+            self.required_field_presence
+                .insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Generate [`arbitrary::Arbitrary`][1] implementations for messages and enums.
+    ///
+    /// Unlike deriving `Arbitrary` with a type attribute, the generated implementations produce
+    /// values which are valid Protobuf: enumeration fields hold values defined by their enum
+    /// (see [`arbitrary_unknown_enum_values`](Self::arbitrary_unknown_enum_values)),
+    /// `google.protobuf.Timestamp` and `google.protobuf.Duration` values are normalized, and
+    /// message fields are only populated up to [`arbitrary_max_depth`](Self::arbitrary_max_depth)
+    /// levels deep, which bounds recursive messages.
+    ///
+    /// Enumeration fields are sampled from the values of their enum, whether or not the enum
+    /// itself is matched. Message fields whose types are not matched, or which are [extern
+    /// paths](Self::extern_path), must implement `Arbitrary` themselves. The crate including the
+    /// generated code must depend on `arbitrary`.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, enums or packages which should implement
+    /// `Arbitrary`. For details about matching paths see [`btree_map`](Self::btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.arbitrary(&["."]);
+    /// ```
+    ///
+    /// [1]: https://docs.rs/arbitrary/latest/arbitrary/trait.Arbitrary.html
+    pub fn arbitrary<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.arbitrary.clear();
+        for matcher in paths {
+            self.arbitrary.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Generate [`proptest::arbitrary::Arbitrary`][1] implementations for messages and enums.
+    ///
+    /// The generated strategies follow the same rules as [`arbitrary`](Self::arbitrary), and
+    /// generate repeated and map fields with fewer than 8 elements. The crate including the
+    /// generated code must depend on `proptest`.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, enums or packages which should implement
+    /// `proptest::arbitrary::Arbitrary`. For details about matching paths see
+    /// [`btree_map`](Self::btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.proptest(&["."]);
+    /// ```
+    ///
+    /// [1]: https://docs.rs/proptest/latest/proptest/arbitrary/trait.Arbitrary.html
+    pub fn proptest<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.proptest.clear();
+        for matcher in paths {
+            self.proptest.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Allow generated `arbitrary` and `proptest` values of enumeration fields to hold values
+    /// which are not defined by the enum, as can be received from peers using a newer schema.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific enumeration fields, messages or packages. For details about
+    /// matching fields see [`btree_map`](Self::btree_map).
+    pub fn arbitrary_unknown_enum_values<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.arbitrary_unknown_enum_values.clear();
+        for matcher in paths {
+            self.arbitrary_unknown_enum_values
+                .insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Configures how deep generated `arbitrary` and `proptest` values may nest messages.
+    ///
+    /// Message fields of messages at this depth are left empty. Defaults to `4`.
+    pub fn arbitrary_max_depth(&mut self, depth: u32) -> &mut Self {
+        self.arbitrary_max_depth = depth;
+        self
+    }
+
     /// Declare an externally provided Protobuf package or type.
     ///
     /// `extern_path` allows `prost` types in external crates to be referenced in generated code.
@@ -547,6 +1256,82 @@ impl Config {
         self
     }
 
+    /// Writes a manifest of the generated messages and enums and their Rust paths, which
+    /// downstream crates can use as extern paths.
+    ///
+    /// The manifest is written to [`EXTERN_PATHS_MANIFEST`](crate::EXTERN_PATHS_MANIFEST) in the
+    /// output directory. `rust_path` is the path of the module tree holding the package modules,
+    /// as laid out by [`include_file`](Self::include_file), as seen from a downstream crate. For
+    /// example, a crate `items` including the generated code at its root would publish `::items`,
+    /// so that the message `snazzy.items.Shirt` is mapped to `::items::snazzy::items::Shirt`.
+    ///
+    /// Every type is published on its own, rather than its package, so that downstream crates
+    /// can generate other types of the same package, or of packages nested in it.
+    ///
+    /// In a build script, the path of the manifest is also published as the `prost_extern_paths`
+    /// metadata of the crate. Cargo passes it to the build scripts of dependent crates if the
+    /// crate sets the [`links`][1] key of its manifest, where
+    /// [`discover_extern_paths`](Self::discover_extern_paths) picks it up.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.include_file("_includes.rs").publish_extern_paths("::items");
+    /// ```
+    ///
+    /// [1]: https://doc.rust-lang.org/cargo/reference/build-scripts.html#the-links-manifest-key
+    pub fn publish_extern_paths<S>(&mut self, rust_path: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.published_extern_path = Some(rust_path.into());
+        self
+    }
+
+    /// Reads extern paths from a manifest written by
+    /// [`publish_extern_paths`](Self::publish_extern_paths).
+    ///
+    /// Extern paths configured with [`extern_path`](Self::extern_path) take precedence over those
+    /// read from manifests.
+    pub fn extern_path_manifest<P>(&mut self, path: P) -> &mut Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.extern_path_manifests.push(path.into());
+        self
+    }
+
+    /// Reads extern paths from the manifests published by the build scripts of dependencies.
+    ///
+    /// This reads the manifests passed by Cargo in the `DEP_<links>_PROST_EXTERN_PATHS`
+    /// environment variables, for dependencies which call
+    /// [`publish_extern_paths`](Self::publish_extern_paths) and set the `links` key of their
+    /// manifest. Only direct dependencies are visible to a build script.
+    ///
+    /// # Examples
+    ///
+    /// ```toml
+    /// # Cargo.toml of the dependency.
+    /// [package]
+    /// name = "items"
+    /// links = "items"
+    /// ```
+    ///
+    /// ```rust,no_run
+    /// # fn main() -> std::io::Result<()> {
+    /// // build.rs of the dependent crate, which imports `snazzy/items.proto`.
+    /// prost_build::Config::new()
+    ///     .discover_extern_paths()
+    ///     .compile_protos(&["src/orders.proto"], &["src/", "../items/src/"])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn discover_extern_paths(&mut self) -> &mut Self {
+        self.discover_extern_paths = true;
+        self
+    }
+
     /// When set, the `FileDescriptorSet` generated by `protoc` is written to the provided
     /// filesystem path.
     ///
@@ -619,6 +1404,28 @@ impl Config {
         self
     }
 
+    /// Configures the code generator to parse `.proto` files with a built-in parser, rather than
+    /// running `protoc`.
+    ///
+    /// The built-in parser produces the same file descriptor set as `protoc`, so `protoc` does not
+    /// need to be installed. Imports are resolved from the include paths and the directory in
+    /// `PROTOC_INCLUDE`, falling back to the well-known types which are distributed with
+    /// `protoc`. Arguments configured with `protoc_arg` are ignored.
+    ///
+    /// If `file_descriptor_set_path` is set, the file descriptor set is written to it as well.
+    ///
+    /// In `build.rs`:
+    ///
+    /// ```rust,no_run
+    /// # let mut config = prost_build::Config::new();
+    /// config.use_builtin_parser()
+    ///     .compile_protos(&["src/items.proto"], &["src/"]);
+    /// ```
+    pub fn use_builtin_parser(&mut self) -> &mut Self {
+        self.builtin_parser = true;
+        self
+    }
+
     /// Configures the code generator to not strip the enum name from variant names.
     ///
     /// Protobuf enum definitions commonly include the enum name as a prefix of every variant name.
@@ -630,6 +1437,112 @@ impl Config {
         self
     }
 
+    /// Configures the code generator to name the generated items with the provided naming
+    /// strategy.
+    ///
+    /// See [`NamingStrategy`] for an example.
+    pub fn naming_strategy(&mut self, naming_strategy: Box<dyn NamingStrategy>) -> &mut Self {
+        self.naming_strategy = Some(naming_strategy);
+        self
+    }
+
+    /// Renames the item generated for a Protobuf element.
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - the fully-qualified path of a Protobuf package, message, field, oneof, enum
+    /// or enum value, starting with a `.`. Enum values are named by the path of their enum, like
+    /// `.my_messages.MyEnum.MY_VALUE`.
+    ///
+    /// **`name`** - the name of the generated item, which is used instead of the name given by
+    /// the [naming strategy](Self::naming_strategy).
+    ///
+    /// Renaming a package renames its module, and renaming a oneof renames its enum type. Renaming
+    /// a field of a oneof renames its variant. The modules of messages and the fields of oneofs
+    /// are renamed with [`rename_module`](Self::rename_module) and
+    /// [`rename_oneof_field`](Self::rename_oneof_field). Renames are useful to resolve collisions
+    /// between generated names, which are reported as errors.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // A nested message `Kind` and a oneof `kind` both generate a type named `Kind`.
+    /// config.rename(".my_messages.Shape.kind", "ShapeKind");
+    ///
+    /// // Match an existing hand-written API.
+    /// config.rename(".my_messages.Shape.Color.COLOR_GREY", "Gray");
+    /// ```
+    pub fn rename<P, N>(&mut self, path: P, name: N) -> &mut Self
+    where
+        P: AsRef<str>,
+        N: AsRef<str>,
+    {
+        self.renames
+            .items
+            .insert(path.as_ref().to_string(), name.as_ref().to_string());
+        self
+    }
+
+    /// Renames the module generated for the nested types of a message.
+    ///
+    /// The module is named by the [naming strategy](Self::naming_strategy) from the name of the
+    /// message, and is generated next to the message, so it can collide with the module of a
+    /// package or of another message, like the modules of messages `FooBar` and `foo_bar`.
+    /// Package modules are renamed with [`rename`](Self::rename).
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - the fully-qualified path of a message, starting with a `.`.
+    ///
+    /// **`name`** - the name of the module.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // The nested types of the message `.my_messages.Shape` are in the module `shape_types`.
+    /// config.rename_module(".my_messages.Shape", "shape_types");
+    /// ```
+    pub fn rename_module<P, N>(&mut self, path: P, name: N) -> &mut Self
+    where
+        P: AsRef<str>,
+        N: AsRef<str>,
+    {
+        self.renames
+            .modules
+            .insert(path.as_ref().to_string(), name.as_ref().to_string());
+        self
+    }
+
+    /// Renames the struct field generated for a oneof, which holds its enum.
+    ///
+    /// [`rename`](Self::rename) renames the enum type of a oneof, and this renames the field of
+    /// the message, along with its [accessors](Self::accessors).
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - the fully-qualified path of a oneof, starting with a `.`.
+    ///
+    /// **`name`** - the name of the field.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.rename_oneof_field(".my_messages.Shape.kind", "shape_kind");
+    /// ```
+    pub fn rename_oneof_field<P, N>(&mut self, path: P, name: N) -> &mut Self
+    where
+        P: AsRef<str>,
+        N: AsRef<str>,
+    {
+        self.renames
+            .oneof_fields
+            .insert(path.as_ref().to_string(), name.as_ref().to_string());
+        self
+    }
+
     /// Configures the output directory where generated Rust files will be written.
     ///
     /// If unset, defaults to the `OUT_DIR` environment variable. `OUT_DIR` is set by Cargo when
@@ -652,12 +1565,95 @@ impl Config {
         self
     }
 
-    /// Configures the code generator to include type names.
+    /// Configures the code generator to include type names.
+    ///
+    /// Message types will implement `Name` trait, which provides type and package name.
+    /// This is needed for encoding messages as `Any` type.
+    pub fn enable_type_names(&mut self) -> &mut Self {
+        self.enable_type_names = true;
+        self
+    }
+
+    /// Configures the code generator to generate a type registration function for each package.
+    ///
+    /// Every generated module will contain a function registering its message types with a
+    /// `prost_types::TypeRegistry`, which can then unpack `Any` values containing these messages
+    /// without knowing their type up front:
+    ///
+    /// ```rust,ignore
+    /// pub fn register_types(registry: &mut ::prost_types::TypeRegistry) {
+    ///     registry.register::<Foo>();
+    ///     registry.register::<foo::Bar>();
+    /// }
+    /// ```
+    ///
+    /// The path of `prost_types` can be configured with
+    /// [`prost_types_path`](Self::prost_types_path).
+    ///
+    /// This implies [`enable_type_names`](Self::enable_type_names).
+    pub fn enable_type_registry(&mut self) -> &mut Self {
+        self.enable_type_names = true;
+        self.type_registry = true;
+        self
+    }
+
+    /// Configures the code generator to turn references to Protobuf types in comments into
+    /// intra-doc links.
+    ///
+    /// A reference is a type name in square brackets, like `[Shirt]` or `[snazzy.items.Shirt]`,
+    /// and is resolved with the scoping rules of Protobuf, relative to the commented item. It is
+    /// linked to the generated Rust type. References which don't name a generated or extern type
+    /// are escaped as plain text, like all square brackets are without this option.
+    ///
+    /// Qualified names without brackets, like `snazzy.items.Shirt`, and names in code spans, like
+    /// `` `Shirt` ``, are linked the same way, and left as they are when they don't name a type.
+    /// Other words are never linked, even if they match the name of a type, and neither are
+    /// names in fenced code blocks.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.enable_intra_doc_links();
+    /// ```
+    pub fn enable_intra_doc_links(&mut self) -> &mut Self {
+        self.intra_doc_links = true;
+        self
+    }
+
+    /// Configures the code generator to embed the encoded file descriptors in each package.
+    ///
+    /// Every generated module will contain a `FILE_DESCRIPTOR_SET` constant, holding an encoded
+    /// `google.protobuf.FileDescriptorSet` with the files of the package and all of their
+    /// dependencies. Every message gets an accessor returning its fully-qualified name along with
+    /// that set, which is what gRPC server reflection and other dynamic tooling need:
+    ///
+    /// ```rust,ignore
+    /// pub const FILE_DESCRIPTOR_SET: &[u8] = b"...";
+    ///
+    /// impl Foo {
+    ///     pub fn message_descriptor() -> (&'static str, &'static [u8]) {
+    ///         ("my.package.Foo", FILE_DESCRIPTOR_SET)
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// The files are embedded as they were produced by `protoc`, so custom options are preserved.
+    pub fn embed_file_descriptor_set(&mut self) -> &mut Self {
+        self.embed_file_descriptor_set = true;
+        self
+    }
+
+    /// Removes the source code info, which holds comments and source locations, from the file
+    /// descriptors embedded by [`embed_file_descriptor_set`](Self::embed_file_descriptor_set).
     ///
-    /// Message types will implement `Name` trait, which provides type and package name.
-    /// This is needed for encoding messages as `Any` type.
-    pub fn enable_type_names(&mut self) -> &mut Self {
-        self.enable_type_names = true;
+    /// This substantially reduces the size of the generated code, while still keeping comments
+    /// in the generated documentation.
+    ///
+    /// This implies [`embed_file_descriptor_set`](Self::embed_file_descriptor_set).
+    pub fn strip_embedded_source_info(&mut self) -> &mut Self {
+        self.embed_file_descriptor_set = true;
+        self.strip_embedded_source_info = true;
         self
     }
 
@@ -703,6 +1699,18 @@ impl Config {
         self
     }
 
+    /// Configures the path of the `prost_types` crate used by the type registration functions of
+    /// [`enable_type_registry`](Self::enable_type_registry).
+    /// This is mainly useful for generating crates that wish to re-export prost-types.
+    /// Defaults to `::prost_types` if not specified.
+    pub fn prost_types_path<S>(&mut self, path: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.prost_types_path = Some(path.into());
+        self
+    }
+
     /// Add an argument to the `protoc` protobuf compilation invocation.
     ///
     /// # Example `build.rs`
@@ -793,6 +1801,28 @@ impl Config {
         self
     }
 
+    /// Configures the code generator to check the syntax of the configured attributes and of the
+    /// generated code with `syn`.
+    ///
+    /// Code generation then fails with an error if a string passed to
+    /// [`type_attribute`](Self::type_attribute), [`message_attribute`](Self::message_attribute),
+    /// [`enum_attribute`](Self::enum_attribute) or [`field_attribute`](Self::field_attribute) is
+    /// not a list of outer attributes or doc comments, or if the generated code, including the
+    /// code appended by a [`MessageGenerator`] or a [`ServiceGenerator`], does not parse. Without
+    /// this option, invalid code is written as is when formatting is disabled.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.check_generated_code();
+    /// ```
+    #[cfg(feature = "format")]
+    pub fn check_generated_code(&mut self) -> &mut Self {
+        self.check_generated_code = true;
+        self
+    }
+
     /// Compile a [`FileDescriptorSet`] into Rust files during a Cargo build with
     /// additional code generator configuration options.
     ///
@@ -824,12 +1854,107 @@ impl Config {
                 })
         })?;
 
-        let requests = fds
-            .file
+        for (file_name, content) in self.generate_files(fds.file, !target_is_env)? {
+            let output_path = target.join(file_name);
+            write_file_if_changed(&output_path, content.as_bytes())?;
+        }
+
+        // `OUT_DIR` is only set for build scripts.
+        if self.published_extern_path.is_some() && env::var_os("OUT_DIR").is_some() {
+            println!(
+                "cargo:prost_extern_paths={}",
+                target.join(crate::EXTERN_PATHS_MANIFEST).display()
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Generates code for a `protoc` plugin request, returning a response with one file per
+    /// package, as well as the include file if [`Self::include_file()`] is set.
+    ///
+    /// `request` is an encoded `CodeGeneratorRequest`, as written to the standard input of a
+    /// plugin. Only the files in `file_to_generate` are compiled. The `parameter` of the request
+    /// is not interpreted, so the `Config` must be configured before calling this.
+    ///
+    /// # Example plugin
+    ///
+    /// ```rust,no_run
+    /// # use std::io::{Read, Write};
+    /// # use prost::Message;
+    /// fn main() -> std::io::Result<()> {
+    ///     let mut request = Vec::new();
+    ///     std::io::stdin().read_to_end(&mut request)?;
+    ///
+    ///     let response = prost_build::Config::new().compile_request(&request)?;
+    ///     std::io::stdout().write_all(&response.encode_to_vec())
+    /// }
+    /// ```
+    pub fn compile_request(&mut self, request: &[u8]) -> Result<CodeGeneratorResponse> {
+        let CodeGeneratorRequest {
+            file_to_generate,
+            proto_file,
+            ..
+        } = CodeGeneratorRequest::decode(request).map_err(|error| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid CodeGeneratorRequest: {}", error),
+            )
+        })?;
+
+        if !self.validation.matchers.is_empty() {
+            self.field_constraints = extract_field_constraints(request, 15).map_err(|error| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid buf.validate field constraints: {}", error),
+                )
+            })?;
+        }
+
+        if self.embed_file_descriptor_set {
+            self.encoded_files = EncodedFiles::decode(request, 15).map_err(|error| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid CodeGeneratorRequest: {}", error),
+                )
+            })?;
+        }
+
+        let files = proto_file
+            .into_iter()
+            .filter(|file| file_to_generate.iter().any(|name| name == file.name()))
+            .collect();
+
+        Ok(CodeGeneratorResponse {
+            error: None,
+            supported_features: Some(code_generator_response::Feature::Proto3Optional as u64),
+            file: self
+                .generate_files(files, true)?
+                .into_iter()
+                .map(|(name, content)| code_generator_response::File {
+                    name: Some(name),
+                    content: Some(content),
+                    ..Default::default()
+                })
+                .collect(),
+        })
+    }
+
+    /// Generates code for `files`, returning the contents of each output file by file name,
+    /// followed by the include file if one is configured.
+    ///
+    /// If `relative_includes` is `false`, the include file includes the output files from
+    /// `OUT_DIR`.
+    fn generate_files(
+        &mut self,
+        files: Vec<FileDescriptorProto>,
+        relative_includes: bool,
+    ) -> Result<Vec<(String, String)>> {
+        let requests = files
             .into_iter()
             .map(|descriptor| {
                 (
-                    Module::from_protobuf_package_name(descriptor.package()),
+                    self.naming().package_module(descriptor.package()),
                     descriptor,
                 )
             })
@@ -846,31 +1971,37 @@ impl Config {
             .collect::<HashMap<Module, String>>();
 
         let modules = self.generate(requests)?;
-        for (module, content) in &modules {
-            let file_name = file_names
-                .get(module)
-                .expect("every module should have a filename");
-            let output_path = target.join(file_name);
-
-            write_file_if_changed(&output_path, content.as_bytes())?;
-        }
+        let mut output = modules
+            .iter()
+            .map(|(module, content)| {
+                let file_name = file_names
+                    .get(module)
+                    .expect("every module should have a filename");
+                (file_name.clone(), content.clone())
+            })
+            .collect::<Vec<_>>();
+        output.sort();
 
         if let Some(ref include_file) = self.include_file {
-            let path = target.join(include_file);
-            trace!("Writing include file: {}", path.display());
+            trace!("Writing include file: {}", include_file.display());
             let mut buffer = Vec::new();
             self.write_line(&mut buffer, 0, "// This file is @generated by prost-build.")?;
             self.write_includes(
                 modules.keys().collect(),
                 &mut buffer,
-                if target_is_env { None } else { Some(&target) },
+                relative_includes,
                 &file_names,
             )?;
+            let content = String::from_utf8(buffer).expect("include file should be valid UTF-8");
+            output.push((include_file.to_string_lossy().into_owned(), content));
+        }
 
-            write_file_if_changed(&path, &buffer)?;
+        if let Some(ref rust_path) = self.published_extern_path {
+            let content = extern_paths_manifest(rust_path, &self.published_types);
+            output.push((crate::EXTERN_PATHS_MANIFEST.to_string(), content));
         }
 
-        Ok(())
+        Ok(output)
     }
 
     /// Loads `.proto` files as a [`FileDescriptorSet`]. This allows inspection of the descriptors
@@ -908,6 +2039,11 @@ impl Config {
         protos: &[impl AsRef<Path>],
         includes: &[impl AsRef<Path>],
     ) -> Result<FileDescriptorSet> {
+        if self.builtin_parser && !self.skip_protoc_run {
+            let buf = self.run_builtin_parser(protos, includes)?;
+            return self.decode_fds(&buf);
+        }
+
         let tmp;
         let file_descriptor_set_path = if let Some(path) = &self.file_descriptor_set_path {
             path.clone()
@@ -987,16 +2123,66 @@ impl Config {
                 ),
             )
         })?;
-        let file_descriptor_set = FileDescriptorSet::decode(buf.as_slice()).map_err(|error| {
+        self.decode_fds(&buf)
+    }
+
+    fn decode_fds(&mut self, buf: &[u8]) -> Result<FileDescriptorSet> {
+        let file_descriptor_set = FileDescriptorSet::decode(buf).map_err(|error| {
             Error::new(
                 ErrorKind::InvalidInput,
                 format!("invalid FileDescriptorSet: {}", error),
             )
         })?;
 
+        if !self.validation.matchers.is_empty() {
+            self.field_constraints = extract_field_constraints(buf, 1).map_err(|error| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid buf.validate field constraints: {}", error),
+                )
+            })?;
+        }
+
+        if self.embed_file_descriptor_set {
+            self.encoded_files = EncodedFiles::decode(buf, 1).map_err(|error| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid FileDescriptorSet: {}", error),
+                )
+            })?;
+        }
+
         Ok(file_descriptor_set)
     }
 
+    /// Parses `protos` with the built-in parser, returning the encoded file descriptor set.
+    fn run_builtin_parser(
+        &self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+    ) -> Result<Vec<u8>> {
+        let mut include_paths = Vec::new();
+        for include in includes {
+            if include.as_ref().exists() {
+                include_paths.push(include.as_ref().to_path_buf());
+            } else {
+                debug!(
+                    "ignoring {} since it does not exist.",
+                    include.as_ref().display()
+                )
+            }
+        }
+        if let Some(protoc_include) = protoc_include_from_env() {
+            include_paths.push(protoc_include);
+        }
+
+        let buf = crate::parser::parse(protos, &include_paths, !self.skip_source_info)?;
+        if let Some(path) = &self.file_descriptor_set_path {
+            fs::write(path, &buf)?;
+        }
+        Ok(buf)
+    }
+
     /// Compile `.proto` files into Rust files during a Cargo build with additional code generator
     /// configuration options.
     ///
@@ -1037,7 +2223,7 @@ impl Config {
         &self,
         mut modules: Vec<&Module>,
         outfile: &mut impl Write,
-        basepath: Option<&PathBuf>,
+        relative: bool,
         file_names: &HashMap<Module, String>,
     ) -> Result<()> {
         modules.sort();
@@ -1062,7 +2248,7 @@ impl Config {
                 .get(module)
                 .expect("every module should have a filename");
 
-            if basepath.is_some() {
+            if relative {
                 self.write_line(
                     outfile,
                     stack.len(),
@@ -1088,6 +2274,63 @@ impl Config {
         outfile.write_all(format!("{}{}\n", ("    ").to_owned().repeat(depth), line).as_bytes())
     }
 
+    /// Returns the configured extern paths, followed by those read from manifests which aren't
+    /// configured.
+    fn all_extern_paths(&self) -> Result<Vec<(String, String)>> {
+        let mut manifests = self.extern_path_manifests.clone();
+        if self.discover_extern_paths {
+            let mut discovered = env::vars_os()
+                .filter(|(key, _)| {
+                    key.to_str().is_some_and(|key| {
+                        key.starts_with("DEP_") && key.ends_with("_PROST_EXTERN_PATHS")
+                    })
+                })
+                .collect::<Vec<_>>();
+            discovered.sort();
+            manifests.extend(discovered.into_iter().map(|(_, path)| PathBuf::from(path)));
+        }
+
+        let mut extern_paths = self.extern_paths.clone();
+        for manifest in manifests {
+            let content = fs::read_to_string(&manifest).map_err(|error| {
+                Error::new(
+                    error.kind(),
+                    format!(
+                        "failed to read extern path manifest {}: {}",
+                        manifest.display(),
+                        error
+                    ),
+                )
+            })?;
+            for (proto_path, rust_path) in
+                parse_extern_paths_manifest(&content).map_err(|error| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "invalid extern path manifest {}: {}",
+                            manifest.display(),
+                            error
+                        ),
+                    )
+                })?
+            {
+                if !extern_paths.iter().any(|(path, _)| *path == proto_path) {
+                    extern_paths.push((proto_path, rust_path));
+                }
+            }
+        }
+        Ok(extern_paths)
+    }
+
+    /// Returns the configured naming strategy and renames.
+    fn naming(&self) -> Naming<'_> {
+        Naming::new(
+            self.naming_strategy.as_deref(),
+            &self.renames,
+            self.strip_enum_prefix,
+        )
+    }
+
     /// Processes a set of modules and file descriptors, returning a map of modules to generated
     /// code contents.
     ///
@@ -1100,20 +2343,121 @@ impl Config {
     ) -> Result<HashMap<Module, String>> {
         let mut modules = HashMap::new();
         let mut packages = HashMap::new();
+        let mut message_packages = HashMap::new();
+        let mut registered_types: HashMap<Module, Vec<String>> = HashMap::new();
+        let mut module_files: HashMap<Module, Vec<String>> = HashMap::new();
+        let mut encoded_files = EncodedFiles::default();
+        if self.embed_file_descriptor_set {
+            encoded_files = self.encoded_files.clone();
+            for (module, file) in &requests {
+                encoded_files.insert(file);
+                module_files
+                    .entry(module.clone())
+                    .or_default()
+                    .push(file.name().to_string());
+            }
+        }
 
-        let message_graph = MessageGraph::new(requests.iter().map(|x| &x.1), self.boxed.clone());
-        let extern_paths = ExternPaths::new(&self.extern_paths, self.prost_types)
-            .map_err(|error| Error::new(ErrorKind::InvalidInput, error))?;
+        #[cfg(feature = "format")]
+        if self.check_generated_code {
+            self.check_attributes()?;
+        }
+        check_type_names(requests.iter().map(|x| &x.1))?;
+        self.check_patterns(requests.iter().map(|x| &x.1))?;
+
+        let mut message_graph =
+            MessageGraph::new(requests.iter().map(|x| &x.1), self.boxed.clone());
+        let extern_paths = ExternPaths::new(
+            &self.all_extern_paths()?,
+            self.prost_types,
+            self.wrapper_types,
+        )
+        .map_err(|error| Error::new(ErrorKind::InvalidInput, error))?;
+        message_graph.set_derives(
+            &self.derive_eq_hash,
+            &self.derive_ord,
+            &self.map_type,
+            &extern_paths,
+            &self.extern_eq_hash,
+            &self.extern_ord,
+        );
+        message_graph.set_required_fields(&self.required_field_presence);
+        if let Some(threshold) = self.box_threshold {
+            message_graph.set_large_fields(threshold, &self.bytes_type);
+            for (field, size) in message_graph.large_fields() {
+                cargo_warning(&format!(
+                    "Boxing field `{}`, whose type has an estimated size of {} bytes",
+                    field.trim_start_matches('.'),
+                    size
+                ));
+            }
+        }
+        self.unreachable_types.clear();
+        if !self.roots.matchers.is_empty() {
+            let dropped = message_graph.set_reachable(requests.iter().map(|x| &x.1), &self.roots);
+            if !dropped.is_empty() {
+                cargo_warning(&format!(
+                    "Skipped {} types which are not reachable from the configured roots",
+                    dropped.len()
+                ));
+            }
+            for name in &dropped {
+                info!("skipped unreachable type: {}", name.trim_start_matches('.'));
+            }
+            self.unreachable_types = dropped;
+        }
+        let names = Names::new(
+            requests.iter().map(|x| &x.1),
+            self.naming(),
+            &extern_paths,
+            self,
+        )?;
+        self.published_types.clear();
+        if self.published_extern_path.is_some() {
+            for (module, file) in &requests {
+                let mut scope = TypeScope {
+                    names: &names,
+                    message_graph: &message_graph,
+                    extern_paths: &extern_paths,
+                    types: &mut self.published_types,
+                };
+                let package = match file.package() {
+                    "" => String::new(),
+                    package => format!(".{}", package),
+                };
+                let rust_path = module.to_string();
+                scope.add_messages(&package, &rust_path, &file.message_type)?;
+                scope.add_enums(&package, &rust_path, &file.enum_type)?;
+            }
+        }
 
         for (request_module, request_fd) in requests {
             // Only record packages that have services
-            if !request_fd.service.is_empty() {
+            let fq_package = match request_fd.package() {
+                "" => String::new(),
+                package => format!(".{package}"),
+            };
+            if request_fd.service.iter().any(|service| {
+                message_graph.is_reachable(&format!("{}.{}", fq_package, service.name()))
+            }) {
                 packages.insert(request_module.clone(), request_fd.package().to_string());
             }
+            message_packages.insert(request_module.clone(), request_fd.package().to_string());
             let buf = modules
                 .entry(request_module.clone())
                 .or_insert_with(String::new);
-            CodeGenerator::generate(self, &message_graph, &extern_paths, request_fd, buf);
+            let types = CodeGenerator::generate(
+                self,
+                &message_graph,
+                &extern_paths,
+                &names,
+                request_fd,
+                buf,
+            )?;
+            registered_types
+                .entry(request_module.clone())
+                .or_default()
+                .extend(types);
             if buf.is_empty() {
                 // Did not generate any code, remove from list to avoid inclusion in include file or output file list
                 modules.remove(&request_module);
@@ -1127,12 +2471,41 @@ impl Config {
             }
         }
 
+        if let Some(ref mut message_generator) = self.message_generator {
+            for (module, package) in &message_packages {
+                if let Some(buf) = modules.get_mut(module) {
+                    message_generator.finalize_package(package, buf);
+                }
+            }
+        }
+
+        if self.type_registry {
+            for (module, buf) in modules.iter_mut() {
+                CodeGenerator::append_register_types(self, &registered_types[module], buf);
+            }
+        }
+
+        if self.embed_file_descriptor_set {
+            for (module, buf) in modules.iter_mut() {
+                let file_descriptor_set = encoded_files.encode_set(
+                    module_files[module].iter().map(String::as_str),
+                    self.strip_embedded_source_info,
+                );
+                CodeGenerator::append_file_descriptor_set(&file_descriptor_set, buf);
+            }
+        }
+
         #[cfg(feature = "format")]
-        if self.fmt {
-            for buf in modules.values_mut() {
-                let file = syn::parse_file(buf).unwrap();
-                let formatted = prettyplease::unparse(&file);
-                *buf = formatted;
+        if self.fmt || self.check_generated_code {
+            for (module, buf) in modules.iter_mut() {
+                let file = parse_module(module, buf)?;
+                if let Some(ref mut message_generator) = self.message_generator {
+                    let mut file = crate::GeneratedFile::new(file);
+                    message_generator.modify_file(&message_packages[module], &mut file);
+                    *buf = prettyplease::unparse(&file.into_inner());
+                } else if self.fmt {
+                    *buf = prettyplease::unparse(&file);
+                }
             }
         }
 
@@ -1141,6 +2514,59 @@ impl Config {
         Ok(modules)
     }
 
+    /// Checks the `buf.validate` patterns of the fields of validated messages.
+    fn check_patterns<'a>(
+        &self,
+        files: impl Iterator<Item = &'a FileDescriptorProto> + Clone,
+    ) -> std::result::Result<(), crate::Error> {
+        let mut fields = self
+            .field_constraints
+            .iter()
+            .filter(|(fq_field_name, _)| {
+                let (fq_message_name, _) = fq_field_name.rsplit_once('.').unwrap();
+                self.validation.get(fq_message_name).next().is_some()
+            })
+            .collect::<Vec<_>>();
+        fields.sort_by_key(|(fq_field_name, _)| *fq_field_name);
+        for (fq_field_name, constraints) in fields {
+            constraints
+                .check_patterns(fq_field_name)
+                .map_err(|error| error.locate(files.clone()))?;
+        }
+        Ok(())
+    }
+
+    /// Checks that the configured attributes parse as outer attributes.
+    #[cfg(feature = "format")]
+    fn check_attributes(&self) -> Result<()> {
+        use syn::parse::Parser;
+
+        let attributes = [
+            ("type_attribute", &self.type_attributes),
+            ("message_attribute", &self.message_attributes),
+            ("enum_attribute", &self.enum_attributes),
+            ("field_attribute", &self.field_attributes),
+        ];
+        for (option, attributes) in attributes {
+            for (path, attribute) in &attributes.matchers {
+                syn::Attribute::parse_outer
+                    .parse_str(attribute)
+                    .map_err(|error| {
+                        crate::Error::new(format!(
+                            "invalid {} for `{}`: `{}` is not a list of attributes: {}",
+                            option, path, attribute, error
+                        ))
+                        .with_element(path)
+                        .with_help(format!(
+                            "pass complete outer attributes to `Config::{}`, like `#[derive(Eq)]`",
+                            option
+                        ))
+                    })?;
+            }
+        }
+        Ok(())
+    }
+
     fn add_generated_modules(&mut self, modules: &mut HashMap<Module, String>) {
         for buf in modules.values_mut() {
             let with_generated = "// This file is @generated by prost-build.\n".to_string() + buf;
@@ -1169,11 +2595,207 @@ fn write_file_if_changed(path: &Path, content: &[u8]) -> std::io::Result<()> {
     }
 }
 
+/// Returns the content of an extern path manifest, mapping each package to its module below
+/// `rust_path`.
+fn extern_paths_manifest(rust_path: &str, types: &[(String, String)]) -> String {
+    let mut lines = types
+        .iter()
+        .map(|(proto_path, path)| {
+            let rust_path = rust_path.trim_end_matches("::");
+            match path.as_str() {
+                "" => format!("{} {}", proto_path, rust_path),
+                path => format!("{} {}::{}", proto_path, rust_path, path),
+            }
+        })
+        .collect::<Vec<_>>();
+    lines.sort();
+
+    let mut content = String::from("# Extern paths published by prost-build.\n");
+    for line in lines {
+        content.push_str(&line);
+        content.push('\n');
+    }
+    content
+}
+
+/// Collects the Protobuf paths of the generated messages and enums, with their Rust paths
+/// relative to the module tree.
+struct TypeScope<'a> {
+    names: &'a Names,
+    message_graph: &'a MessageGraph,
+    extern_paths: &'a ExternPaths,
+    types: &'a mut Vec<(String, String)>,
+}
+
+impl TypeScope<'_> {
+    fn is_generated(&self, fq_name: &str) -> bool {
+        self.extern_paths.resolve_ident(fq_name).is_none()
+            && self.message_graph.is_reachable(fq_name)
+    }
+
+    fn add_messages(
+        &mut self,
+        scope: &str,
+        rust_scope: &str,
+        messages: &[DescriptorProto],
+    ) -> std::result::Result<(), crate::Error> {
+        for message in messages {
+            let fq_name = format!("{}.{}", scope, message.name());
+            let map_entry = message
+                .options
+                .as_ref()
+                .is_some_and(|options| options.map_entry());
+            if map_entry || !self.is_generated(&fq_name) {
+                continue;
+            }
+            let name = self.names.type_name(&fq_name)?;
+            self.types
+                .push((fq_name.clone(), join_path(rust_scope, name)));
+            let module = join_path(rust_scope, self.names.module(&fq_name)?);
+            self.add_messages(&fq_name, &module, &message.nested_type)?;
+            self.add_enums(&fq_name, &module, &message.enum_type)?;
+        }
+        Ok(())
+    }
+
+    fn add_enums(
+        &mut self,
+        scope: &str,
+        rust_scope: &str,
+        enums: &[EnumDescriptorProto],
+    ) -> std::result::Result<(), crate::Error> {
+        for enum_type in enums {
+            let fq_name = format!("{}.{}", scope, enum_type.name());
+            if self.is_generated(&fq_name) {
+                let name = self.names.type_name(&fq_name)?;
+                self.types.push((fq_name, join_path(rust_scope, name)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Joins a Rust path and a name, where the path may be empty.
+fn join_path(path: &str, name: &str) -> String {
+    match path {
+        "" => name.to_string(),
+        path => format!("{}::{}", path, name),
+    }
+}
+
+/// Parses an extern path manifest into pairs of Protobuf and Rust paths.
+fn parse_extern_paths_manifest(
+    content: &str,
+) -> std::result::Result<Vec<(String, String)>, String> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(
+            |(idx, line)| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [proto_path, rust_path] => Ok((proto_path.to_string(), rust_path.to_string())),
+                _ => Err(format!(
+                    "line {}: expected a Protobuf path and a Rust path",
+                    idx + 1
+                )),
+            },
+        )
+        .collect()
+}
+
+/// Checks that the types of fields and methods are fully qualified, as resolving them relies on
+/// it.
+fn check_type_names<'a>(
+    files: impl Iterator<Item = &'a FileDescriptorProto> + Clone,
+) -> std::result::Result<(), crate::Error> {
+    fn check(type_name: &str, element: String) -> std::result::Result<(), crate::Error> {
+        if type_name.len() > 1 && type_name.starts_with('.') {
+            return Ok(());
+        }
+        Err(crate::Error::new(format!(
+            "the type `{}` of `{}` is not a fully-qualified name",
+            type_name,
+            &element[1..]
+        ))
+        .with_element(element)
+        .with_help(
+            "type names must start with the package, like `.my_package.MyMessage`, as in the \
+             file descriptor sets produced by `protoc`",
+        ))
+    }
+
+    fn check_message(
+        scope: &str,
+        message: &DescriptorProto,
+    ) -> std::result::Result<(), crate::Error> {
+        let fq_name = format!("{}.{}", scope, message.name());
+        for field in &message.field {
+            if matches!(field.r#type(), Type::Message | Type::Group | Type::Enum) {
+                check(field.type_name(), format!("{}.{}", fq_name, field.name()))?;
+            }
+        }
+        for nested in &message.nested_type {
+            check_message(&fq_name, nested)?;
+        }
+        Ok(())
+    }
+
+    for file in files.clone() {
+        let scope = match file.package() {
+            "" => String::new(),
+            package => format!(".{}", package),
+        };
+        let result = file
+            .message_type
+            .iter()
+            .try_for_each(|message| check_message(&scope, message))
+            .and_then(|()| {
+                file.service.iter().try_for_each(|service| {
+                    service.method.iter().try_for_each(|method| {
+                        let element = format!("{}.{}.{}", scope, service.name(), method.name());
+                        check(method.input_type(), element.clone())?;
+                        check(method.output_type(), element)
+                    })
+                })
+            });
+        result.map_err(|error| error.locate(files.clone()))?;
+    }
+    Ok(())
+}
+
+/// Reports a warning with a `cargo:warning` line when running in a build script, which is when
+/// `OUT_DIR` is set, and otherwise logs it, since standard output may be used for something
+/// else, like the response of a `protoc` plugin.
+fn cargo_warning(message: &str) {
+    if env::var_os("OUT_DIR").is_some() {
+        println!("cargo:warning={}", message);
+    } else {
+        warn!("{}", message);
+    }
+}
+
+/// Parses the code generated for a module.
+#[cfg(feature = "format")]
+fn parse_module(module: &Module, buf: &str) -> Result<syn::File> {
+    syn::parse_file(buf).map_err(|error| {
+        crate::Error::new(format!(
+            "failed to parse the code generated for `{}`: {}",
+            module, error
+        ))
+        .with_kind(ErrorKind::InvalidData)
+        .with_element(module.to_string())
+        .into()
+    })
+}
+
 impl default::Default for Config {
     fn default() -> Config {
         Config {
             file_descriptor_set_path: None,
             service_generator: None,
+            message_generator: None,
+            naming_strategy: None,
+            renames: Renames::default(),
             map_type: PathMap::default(),
             bytes_type: PathMap::default(),
             type_attributes: PathMap::default(),
@@ -1181,23 +2803,55 @@ impl default::Default for Config {
             enum_attributes: PathMap::default(),
             field_attributes: PathMap::default(),
             boxed: PathMap::default(),
+            box_threshold: None,
             prost_types: true,
+            wrapper_types: false,
             strip_enum_prefix: true,
             out_dir: None,
             extern_paths: Vec::new(),
+            published_extern_path: None,
+            extern_path_manifests: Vec::new(),
+            discover_extern_paths: false,
             default_package_filename: "_".to_string(),
             enable_type_names: false,
             type_name_domains: PathMap::default(),
+            type_registry: false,
+            intra_doc_links: false,
+            embed_file_descriptor_set: false,
+            strip_embedded_source_info: false,
+            encoded_files: EncodedFiles::default(),
             protoc_args: Vec::new(),
             protoc_executable: protoc_from_env(),
             disable_comments: PathMap::default(),
             skip_debug: PathMap::default(),
             skip_protoc_run: false,
             skip_source_info: false,
+            builtin_parser: false,
             include_file: None,
             prost_path: None,
+            prost_types_path: None,
+            validation: PathMap::default(),
+            builders: PathMap::default(),
+            accessors: PathMap::default(),
+            oneof_conversions: PathMap::default(),
+            enum_helpers: PathMap::default(),
+            roots: PathMap::default(),
+            derive_eq_hash: PathMap::default(),
+            derive_ord: PathMap::default(),
+            extern_eq_hash: PathMap::default(),
+            extern_ord: PathMap::default(),
+            required_field_presence: PathMap::default(),
+            arbitrary: PathMap::default(),
+            proptest: PathMap::default(),
+            arbitrary_unknown_enum_values: PathMap::default(),
+            arbitrary_max_depth: 4,
+            field_constraints: HashMap::new(),
+            unreachable_types: Vec::new(),
+            published_types: Vec::new(),
             #[cfg(feature = "format")]
             fmt: true,
+            #[cfg(feature = "format")]
+            check_generated_code: false,
         }
     }
 }
@@ -1207,21 +2861,56 @@ impl fmt::Debug for Config {
         fmt.debug_struct("Config")
             .field("file_descriptor_set_path", &self.file_descriptor_set_path)
             .field("service_generator", &self.service_generator.is_some())
+            .field("message_generator", &self.message_generator.is_some())
+            .field("naming_strategy", &self.naming_strategy.is_some())
+            .field("renames", &self.renames)
             .field("map_type", &self.map_type)
             .field("bytes_type", &self.bytes_type)
             .field("type_attributes", &self.type_attributes)
             .field("field_attributes", &self.field_attributes)
             .field("prost_types", &self.prost_types)
+            .field("wrapper_types", &self.wrapper_types)
+            .field("box_threshold", &self.box_threshold)
             .field("strip_enum_prefix", &self.strip_enum_prefix)
             .field("out_dir", &self.out_dir)
             .field("extern_paths", &self.extern_paths)
+            .field("published_extern_path", &self.published_extern_path)
+            .field("extern_path_manifests", &self.extern_path_manifests)
+            .field("discover_extern_paths", &self.discover_extern_paths)
             .field("default_package_filename", &self.default_package_filename)
             .field("enable_type_names", &self.enable_type_names)
             .field("type_name_domains", &self.type_name_domains)
+            .field("type_registry", &self.type_registry)
+            .field("intra_doc_links", &self.intra_doc_links)
+            .field("embed_file_descriptor_set", &self.embed_file_descriptor_set)
+            .field(
+                "strip_embedded_source_info",
+                &self.strip_embedded_source_info,
+            )
             .field("protoc_args", &self.protoc_args)
             .field("disable_comments", &self.disable_comments)
             .field("skip_debug", &self.skip_debug)
+            .field("builtin_parser", &self.builtin_parser)
             .field("prost_path", &self.prost_path)
+            .field("prost_types_path", &self.prost_types_path)
+            .field("validation", &self.validation)
+            .field("builders", &self.builders)
+            .field("accessors", &self.accessors)
+            .field("oneof_conversions", &self.oneof_conversions)
+            .field("enum_helpers", &self.enum_helpers)
+            .field("roots", &self.roots)
+            .field("derive_eq_hash", &self.derive_eq_hash)
+            .field("derive_ord", &self.derive_ord)
+            .field("extern_eq_hash", &self.extern_eq_hash)
+            .field("extern_ord", &self.extern_ord)
+            .field("required_field_presence", &self.required_field_presence)
+            .field("arbitrary", &self.arbitrary)
+            .field("proptest", &self.proptest)
+            .field(
+                "arbitrary_unknown_enum_values",
+                &self.arbitrary_unknown_enum_values,
+            )
+            .field("arbitrary_max_depth", &self.arbitrary_max_depth)
             .finish()
     }
 }
@@ -1291,6 +2980,173 @@ mod tests {
         };
     }
 
+    #[test]
+    fn extern_path_manifest() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let items_dir = tempdir.path().join("items");
+        let orders_dir = tempdir.path().join("orders");
+        fs::create_dir_all(items_dir.join("snazzy")).unwrap();
+        fs::create_dir_all(&orders_dir).unwrap();
+        fs::write(
+            items_dir.join("snazzy/items.proto"),
+            "syntax = \"proto3\";\npackage snazzy.items;\nmessage Shirt {}\n",
+        )
+        .unwrap();
+        fs::write(
+            orders_dir.join("orders.proto"),
+            "syntax = \"proto3\";\npackage orders;\nimport \"snazzy/items.proto\";\n\
+             message Order { snazzy.items.Shirt shirt = 1; }\n",
+        )
+        .unwrap();
+
+        Config::new()
+            .use_builtin_parser()
+            .out_dir(&items_dir)
+            .publish_extern_paths("::items")
+            .compile_protos(&[items_dir.join("snazzy/items.proto")], &[&items_dir])
+            .unwrap();
+        let manifest = items_dir.join(crate::EXTERN_PATHS_MANIFEST);
+        assert_eq!(
+            fs::read_to_string(&manifest).unwrap(),
+            "# Extern paths published by prost-build.\n\
+             .snazzy.items.Shirt ::items::snazzy::items::Shirt\n"
+        );
+
+        Config::new()
+            .use_builtin_parser()
+            .out_dir(&orders_dir)
+            .extern_path_manifest(&manifest)
+            .compile_protos(
+                &[orders_dir.join("orders.proto")],
+                &[&orders_dir, &items_dir],
+            )
+            .unwrap();
+        assert!(!orders_dir.join("snazzy.items.rs").exists());
+        let orders = fs::read_to_string(orders_dir.join("orders.rs")).unwrap();
+        assert!(
+            orders.contains("::items::snazzy::items::Shirt"),
+            "{}",
+            orders
+        );
+    }
+
+    #[test]
+    fn extern_path_manifest_nested_packages() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let a_dir = tempdir.path().join("a");
+        let b_dir = tempdir.path().join("b");
+        fs::create_dir_all(a_dir.join("acme")).unwrap();
+        fs::create_dir_all(b_dir.join("acme")).unwrap();
+        let write = |path: PathBuf, content: &str| fs::write(path, content).unwrap();
+        write(
+            a_dir.join("acme/acme.proto"),
+            "syntax = \"proto3\";\npackage acme;\nmessage Thing { message Part {} }\n",
+        );
+        write(
+            a_dir.join("acme/items.proto"),
+            "syntax = \"proto3\";\npackage acme.items;\nmessage Item {}\n",
+        );
+        write(
+            b_dir.join("acme/notes.proto"),
+            "syntax = \"proto3\";\npackage acme;\nmessage Note {}\n",
+        );
+        write(
+            b_dir.join("acme/orders.proto"),
+            "syntax = \"proto3\";\npackage acme.orders;\n\
+             import \"acme/acme.proto\";\nimport \"acme/items.proto\";\n\
+             message Order { acme.Thing.Part part = 1; acme.items.Item item = 2; }\n",
+        );
+
+        Config::new()
+            .use_builtin_parser()
+            .out_dir(&a_dir)
+            .publish_extern_paths("::a")
+            .compile_protos(
+                &[
+                    a_dir.join("acme/acme.proto"),
+                    a_dir.join("acme/items.proto"),
+                ],
+                &[&a_dir],
+            )
+            .unwrap();
+        let manifest = a_dir.join(crate::EXTERN_PATHS_MANIFEST);
+        assert_eq!(
+            fs::read_to_string(&manifest).unwrap(),
+            "# Extern paths published by prost-build.\n\
+             .acme.Thing ::a::acme::Thing\n\
+             .acme.Thing.Part ::a::acme::thing::Part\n\
+             .acme.items.Item ::a::acme::items::Item\n"
+        );
+
+        // The crate depending on `a` generates types of the same package and of a nested one.
+        Config::new()
+            .use_builtin_parser()
+            .out_dir(&b_dir)
+            .extern_path_manifest(&manifest)
+            .compile_protos(
+                &[
+                    b_dir.join("acme/notes.proto"),
+                    b_dir.join("acme/orders.proto"),
+                ],
+                &[&b_dir, &a_dir],
+            )
+            .unwrap();
+        assert!(!b_dir.join("acme.items.rs").exists());
+        let acme = fs::read_to_string(b_dir.join("acme.rs")).unwrap();
+        assert!(acme.contains("pub struct Note"), "{}", acme);
+        assert!(!acme.contains("pub struct Thing"), "{}", acme);
+        let orders = fs::read_to_string(b_dir.join("acme.orders.rs")).unwrap();
+        assert!(orders.contains("pub struct Order"), "{}", orders);
+        assert!(orders.contains("::a::acme::thing::Part"), "{}", orders);
+        assert!(orders.contains("::a::acme::items::Item"), "{}", orders);
+    }
+
+    #[test]
+    fn extern_derives() {
+        let tempdir = tempfile::tempdir().unwrap();
+        fs::write(
+            tempdir.path().join("orders.proto"),
+            "syntax = \"proto3\";\npackage orders;\n\
+             message Id {}\nmessage Shirt {}\n\
+             message Order { Id id = 1; }\nmessage Line { Id id = 1; Shirt shirt = 2; }\n",
+        )
+        .unwrap();
+
+        Config::new()
+            .use_builtin_parser()
+            .out_dir(tempdir.path())
+            .extern_path(".orders.Id", "::ids::Id")
+            .extern_path(".orders.Shirt", "::items::Shirt")
+            .extern_ord([".orders.Id"])
+            .derive_ord(["."])
+            .compile_protos(&[tempdir.path().join("orders.proto")], &[tempdir.path()])
+            .unwrap();
+        let orders = fs::read_to_string(tempdir.path().join("orders.rs")).unwrap();
+        let derives = |message: &str| {
+            let end = orders.find(&format!("pub struct {} {{", message)).unwrap();
+            let start = orders[..end].rfind("#[derive(").unwrap();
+            orders[start..end].to_string()
+        };
+        assert!(
+            derives("Order").contains("Eq, Hash, PartialOrd, Ord"),
+            "{}",
+            orders
+        );
+        assert!(!derives("Line").contains(" Eq,"), "{}", orders);
+    }
+
+    #[test]
+    fn parse_extern_paths_manifest() {
+        assert_eq!(
+            super::parse_extern_paths_manifest("# comment\n\n.a.b ::a::b\n").unwrap(),
+            [(".a.b".to_string(), "::a::b".to_string())]
+        );
+        assert_eq!(
+            super::parse_extern_paths_manifest(".a.b\n").unwrap_err(),
+            "line 1: expected a Protobuf path and a Rust path"
+        );
+    }
+
     #[test]
     fn test_error_protoc_not_found() {
         let mut config = Config::new();
@@ -1367,4 +3223,97 @@ mod tests {
             .unwrap_err();
         assert_eq!(err.to_string(), "OUT_DIR environment variable is not set")
     }
+
+    #[test]
+    fn test_error_name_collision() {
+        let tempdir = tempfile::tempdir().unwrap();
+        fs::write(
+            tempdir.path().join("shapes.proto"),
+            "syntax = \"proto2\";\n\
+             package shapes;\n\
+             \n\
+             message Shape {\n  \
+               optional string corner_count = 1;\n  \
+               optional string cornerCount = 2;\n\
+             }\n",
+        )
+        .unwrap();
+
+        let err = Config::new()
+            .out_dir(tempdir.path())
+            .compile_protos(&[tempdir.path().join("shapes.proto")], &[tempdir.path()])
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let error = crate::Error::from_io(&err).unwrap();
+        assert_eq!(error.element(), Some("shapes.Shape.cornerCount"));
+        assert_eq!(error.file(), Some("shapes.proto"));
+        assert_eq!((error.line(), error.column()), (Some(6), Some(3)));
+        assert_eq!(
+            error.help(),
+            Some(
+                "use `Config::rename` to rename one of them, e.g. \
+                 `config.rename(\".shapes.Shape.cornerCount\", \"corner_count2\")`"
+            )
+        );
+        assert_starts_with!(
+            err.to_string(),
+            "shapes.proto:6:3: `shapes.Shape.corner_count` and `shapes.Shape.cornerCount` both \
+             generate `corner_count` among the fields of `shapes.Shape`\nhelp: "
+        );
+    }
+
+    #[test]
+    fn test_error_unqualified_type_name() {
+        use prost_types::{FieldDescriptorProto, MethodDescriptorProto, ServiceDescriptorProto};
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let file = FileDescriptorProto {
+            name: Some("shapes.proto".to_string()),
+            package: Some("shapes".to_string()),
+            message_type: vec![DescriptorProto {
+                name: Some("Shape".to_string()),
+                ..Default::default()
+            }],
+            service: vec![ServiceDescriptorProto {
+                name: Some("Shapes".to_string()),
+                method: vec![MethodDescriptorProto {
+                    name: Some("Get".to_string()),
+                    input_type: Some(".shapes.Shape".to_string()),
+                    output_type: Some("Shape".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut message = file.clone();
+        message.message_type[0].field.push(FieldDescriptorProto {
+            name: Some("parent".to_string()),
+            number: Some(1),
+            r#type: Some(Type::Message as i32),
+            type_name: Some(String::new()),
+            ..Default::default()
+        });
+
+        for (file, element, type_name) in [
+            (file, "shapes.Shapes.Get", "Shape"),
+            (message, "shapes.Shape.parent", ""),
+        ] {
+            let err = Config::new()
+                .out_dir(tempdir.path())
+                .compile_fds(FileDescriptorSet { file: vec![file] })
+                .unwrap_err();
+            let error = crate::Error::from_io(&err).unwrap();
+            assert_eq!(error.element(), Some(element));
+            assert_eq!(error.file(), Some("shapes.proto"));
+            assert_eq!(error.line(), None);
+            assert_eq!(
+                error.message(),
+                format!(
+                    "the type `{}` of `{}` is not a fully-qualified name",
+                    type_name, element
+                )
+            );
+        }
+    }
 }
@@ -0,0 +1,435 @@
+//! Configuration from textual options, as used by `protoc-gen-prost-build` and the `prost-build`
+//! command-line tool.
+
+use std::io::{Error, ErrorKind, Result};
+
+use super::Config;
+use crate::{BytesType, MapType, ServiceTraitGenerator};
+
+/// An option of [`Config::apply_options`].
+struct ConfigOption {
+    name: &'static str,
+    /// Whether the option is of the form `<option>=<value>` rather than a flag.
+    takes_value: bool,
+    /// Applies the option with its value, which is empty for flags.
+    apply: fn(&mut Config, &str) -> std::result::Result<(), String>,
+}
+
+/// Defines [`Config::apply_options`] from a table of options grouped under headings, which
+/// documents every option and generates the `OPTIONS` table it looks options up in.
+///
+/// The options are listed in an `options` block, after the beginning of the documentation and
+/// before the function. An option is written as `name = "<value syntax>" => apply` or, for flags,
+/// as `name => apply`, optionally preceded by a doc comment which is appended to its entry in the
+/// documentation.
+macro_rules! options {
+    (
+        $(#[doc = $doc:literal])*
+        options {
+            $(
+                $heading:literal {
+                    $(
+                        $(#[doc = $note:literal])?
+                        $name:ident $(= $syntax:literal)? => $apply:expr,
+                    )*
+                }
+            )*
+        }
+        $apply_options:item
+    ) => {
+        impl Config {
+            $(#[doc = $doc])*
+            $(
+                #[doc = ""]
+                #[doc = $heading]
+                #[doc = ""]
+                $(
+                    #[doc = concat!(
+                        "- `", stringify!($name), $("=", $syntax,)? "`", $(",", $note)?
+                    )]
+                )*
+            )*
+            $apply_options
+        }
+
+        const OPTIONS: &[ConfigOption] = &[
+            $($(
+                ConfigOption {
+                    name: stringify!($name),
+                    takes_value: options!(@takes_value $($syntax)?),
+                    apply: $apply,
+                },
+            )*)*
+        ];
+    };
+    (@takes_value $syntax:literal) => {
+        true
+    };
+    (@takes_value) => {
+        false
+    };
+}
+
+options! {
+    /// Applies configuration options given as text, each of the form `<option>` or
+    /// `<option>=<value>`.
+    ///
+    /// This is used by tools which are configured without a build script, like the
+    /// `protoc-gen-prost-build` plugin. Each option corresponds to a method of `Config`. Unlike the
+    /// methods, options which take paths add to the previously configured paths.
+    options {
+        "Options which take paths, and may be given more than once:" {
+            btree_map = "<path>" => |config, path| {
+                config.map_type.insert(path.to_string(), MapType::BTreeMap);
+                Ok(())
+            },
+            bytes = "<path>" => |config, path| {
+                config.bytes_type.insert(path.to_string(), BytesType::Bytes);
+                Ok(())
+            },
+            boxed = "<path>" => |config, path| {
+                config.boxed.insert(path.to_string(), ());
+                Ok(())
+            },
+            disable_comments = "<path>" => |config, path| {
+                config.disable_comments.insert(path.to_string(), ());
+                Ok(())
+            },
+            skip_debug = "<path>" => |config, path| {
+                config.skip_debug.insert(path.to_string(), ());
+                Ok(())
+            },
+            validation = "<path>" => |config, path| {
+                config.validation.insert(path.to_string(), ());
+                Ok(())
+            },
+            builders = "<path>" => |config, path| {
+                config.builders.insert(path.to_string(), ());
+                Ok(())
+            },
+            accessors = "<path>" => |config, path| {
+                config.accessors.insert(path.to_string(), ());
+                Ok(())
+            },
+            oneof_conversions = "<path>" => |config, path| {
+                config.oneof_conversions.insert(path.to_string(), ());
+                Ok(())
+            },
+            enum_helpers = "<path>" => |config, path| {
+                config.enum_helpers.insert(path.to_string(), ());
+                Ok(())
+            },
+            retain_reachable_types = "<path>" => |config, path| {
+                config.roots.insert(path.to_string(), ());
+                Ok(())
+            },
+            derive_eq_hash = "<path>" => |config, path| {
+                config.derive_eq_hash.insert(path.to_string(), ());
+                Ok(())
+            },
+            derive_ord = "<path>" => |config, path| {
+                config.derive_ord.insert(path.to_string(), ());
+                Ok(())
+            },
+            extern_eq_hash = "<path>" => |config, path| {
+                config.extern_eq_hash.insert(path.to_string(), ());
+                Ok(())
+            },
+            extern_ord = "<path>" => |config, path| {
+                config.extern_ord.insert(path.to_string(), ());
+                Ok(())
+            },
+            required_field_presence = "<path>" => |config, path| {
+                config.required_field_presence.insert(path.to_string(), ());
+                Ok(())
+            },
+            arbitrary = "<path>" => |config, path| {
+                config.arbitrary.insert(path.to_string(), ());
+                Ok(())
+            },
+            proptest = "<path>" => |config, path| {
+                config.proptest.insert(path.to_string(), ());
+                Ok(())
+            },
+            arbitrary_unknown_enum_values = "<path>" => |config, path| {
+                config.arbitrary_unknown_enum_values.insert(path.to_string(), ());
+                Ok(())
+            },
+            type_attribute = "<path>=<attribute>" => |config, value| {
+                let (path, attribute) = pair("type_attribute", value)?;
+                config.type_attribute(path, attribute);
+                Ok(())
+            },
+            message_attribute = "<path>=<attribute>" => |config, value| {
+                let (path, attribute) = pair("message_attribute", value)?;
+                config.message_attribute(path, attribute);
+                Ok(())
+            },
+            enum_attribute = "<path>=<attribute>" => |config, value| {
+                let (path, attribute) = pair("enum_attribute", value)?;
+                config.enum_attribute(path, attribute);
+                Ok(())
+            },
+            field_attribute = "<path>=<attribute>" => |config, value| {
+                let (path, attribute) = pair("field_attribute", value)?;
+                config.field_attribute(path, attribute);
+                Ok(())
+            },
+            extern_path = "<proto path>=<rust path>" => |config, value| {
+                let (proto_path, rust_path) = pair("extern_path", value)?;
+                config.extern_path(proto_path, rust_path);
+                Ok(())
+            },
+            type_name_domain = "<path>=<domain>" => |config, value| {
+                let (path, domain) = pair("type_name_domain", value)?;
+                config
+                    .type_name_domains
+                    .insert(path.to_string(), domain.to_string());
+                Ok(())
+            },
+            rename = "<path>=<name>" => |config, value| {
+                let (path, name) = pair("rename", value)?;
+                config.rename(path, name);
+                Ok(())
+            },
+            rename_module = "<path>=<name>" => |config, value| {
+                let (path, name) = pair("rename_module", value)?;
+                config.rename_module(path, name);
+                Ok(())
+            },
+            rename_oneof_field = "<path>=<name>" => |config, value| {
+                let (path, name) = pair("rename_oneof_field", value)?;
+                config.rename_oneof_field(path, name);
+                Ok(())
+            },
+        }
+        "Options which take a single value:" {
+            arbitrary_max_depth = "<depth>" => |config, value| {
+                config.arbitrary_max_depth(parse("arbitrary_max_depth", value)?);
+                Ok(())
+            },
+            box_large_fields = "<bytes>" => |config, value| {
+                config.box_large_fields(parse("box_large_fields", value)?);
+                Ok(())
+            },
+            default_package_filename = "<name>" => |config, value| {
+                config.default_package_filename(value);
+                Ok(())
+            },
+            include_file = "<name>" => |config, value| {
+                config.include_file(value);
+                Ok(())
+            },
+            prost_path = "<path>" => |config, value| {
+                config.prost_path(value);
+                Ok(())
+            },
+            prost_types_path = "<path>" => |config, value| {
+                config.prost_types_path(value);
+                Ok(())
+            },
+            format = "<true|false>" => |config, value| {
+                let enabled: bool = parse("format", value)?;
+                // Without the `format` feature, the output is never formatted.
+                #[cfg(feature = "format")]
+                config.format(enabled);
+                #[cfg(not(feature = "format"))]
+                let _ = (config, enabled);
+                Ok(())
+            },
+            publish_extern_paths = "<rust path>" => |config, value| {
+                config.publish_extern_paths(value);
+                Ok(())
+            },
+            extern_path_manifest = "<file>" => |config, value| {
+                config.extern_path_manifest(value);
+                Ok(())
+            },
+        }
+        "Flags:" {
+            compile_well_known_types => |config, _| {
+                config.compile_well_known_types();
+                Ok(())
+            },
+            map_wrapper_types => |config, _| {
+                config.map_wrapper_types();
+                Ok(())
+            },
+            retain_enum_prefix => |config, _| {
+                config.retain_enum_prefix();
+                Ok(())
+            },
+            enable_type_names => |config, _| {
+                config.enable_type_names();
+                Ok(())
+            },
+            enable_type_registry => |config, _| {
+                config.enable_type_registry();
+                Ok(())
+            },
+            enable_intra_doc_links => |config, _| {
+                config.enable_intra_doc_links();
+                Ok(())
+            },
+            discover_extern_paths => |config, _| {
+                config.discover_extern_paths();
+                Ok(())
+            },
+            embed_file_descriptor_set => |config, _| {
+                config.embed_file_descriptor_set();
+                Ok(())
+            },
+            strip_embedded_source_info => |config, _| {
+                config.strip_embedded_source_info();
+                Ok(())
+            },
+            use_builtin_parser => |config, _| {
+                config.use_builtin_parser();
+                Ok(())
+            },
+            check_generated_code => |config, _| {
+                // Without the `format` feature, the generated code is never parsed.
+                #[cfg(feature = "format")]
+                config.check_generated_code();
+                #[cfg(not(feature = "format"))]
+                let _ = config;
+                Ok(())
+            },
+            /// which generates services with [`ServiceTraitGenerator`], using `prost_path`
+            service_traits => |_, _| Ok(()),
+        }
+    }
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> {
+    /// # let mut config = prost_build::Config::new();
+    /// config.apply_options([
+    ///     "bytes=.",
+    ///     "type_attribute=.my_messages=#[derive(Eq, Hash)]",
+    ///     "extern_path=.uuid=::uuid",
+    ///     "enable_type_names",
+    /// ])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn apply_options<I, S>(&mut self, options: I) -> Result<&mut Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        // The service generator is created once the other options are applied, to use the
+        // configured `prost_path`.
+        let mut service_traits = false;
+        for option in options {
+            let option = option.as_ref();
+            service_traits |= option == "service_traits";
+            apply_option(self, option).map_err(|error| Error::new(ErrorKind::InvalidInput, error))?;
+        }
+        if service_traits {
+            let mut generator = ServiceTraitGenerator::new();
+            if let Some(prost_path) = &self.prost_path {
+                generator = generator.prost_path(prost_path);
+            }
+            self.service_generator(Box::new(generator));
+        }
+        Ok(self)
+    }
+}
+
+fn apply_option(config: &mut Config, option: &str) -> std::result::Result<(), String> {
+    let (key, value) = match option.split_once('=') {
+        Some((key, value)) => (key, Some(value)),
+        None => (option, None),
+    };
+    let Some(option) = OPTIONS.iter().find(|option| option.name == key) else {
+        return Err(format!("unknown option `{}`", key));
+    };
+    match (option.takes_value, value) {
+        (true, Some(value)) => (option.apply)(config, value),
+        (true, None) => Err(format!("option `{}` requires a value", key)),
+        (false, None) => (option.apply)(config, ""),
+        (false, Some(_)) => Err(format!("option `{}` does not take a value", key)),
+    }
+}
+
+/// Splits a value of the form `<path>=<value>` at the first `=`.
+fn pair<'a>(key: &str, value: &'a str) -> std::result::Result<(&'a str, &'a str), String> {
+    value.split_once('=').ok_or_else(|| {
+        format!(
+            "option `{}` requires a value of the form `<path>=<value>`",
+            key
+        )
+    })
+}
+
+/// Parses the value of an option.
+fn parse<T: std::str::FromStr>(key: &str, value: &str) -> std::result::Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value `{}` for option `{}`", value, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_options() {
+        let error = |option| {
+            Config::new()
+                .apply_options([option])
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(error("unknown"), "unknown option `unknown`");
+        assert_eq!(error("bytes"), "option `bytes` requires a value");
+        assert_eq!(
+            error("enable_type_names=true"),
+            "option `enable_type_names` does not take a value"
+        );
+        assert_eq!(
+            error("service_traits=true"),
+            "option `service_traits` does not take a value"
+        );
+        assert_eq!(
+            error("extern_path=.foo"),
+            "option `extern_path` requires a value of the form `<path>=<value>`"
+        );
+        assert_eq!(
+            error("format=yes"),
+            "invalid value `yes` for option `format`"
+        );
+    }
+
+    #[test]
+    fn service_traits() {
+        let mut config = Config::new();
+        config.apply_options(["service_traits"]).unwrap();
+        assert!(config.service_generator.is_some());
+    }
+
+    #[test]
+    fn paths_are_cumulative() {
+        let mut config = Config::new();
+        config
+            .apply_options([
+                "bytes=.foo",
+                "bytes=.bar",
+                "type_name_domain=.foo=foo.com",
+                "type_name_domain=.bar=bar.com",
+            ])
+            .unwrap();
+        assert!(config.bytes_type.get_first(".foo.Message").is_some());
+        assert!(config.bytes_type.get_first(".bar.Message").is_some());
+        assert_eq!(
+            config.type_name_domains.get_first(".foo.Message"),
+            Some(&"foo.com".to_string())
+        );
+        assert_eq!(
+            config.type_name_domains.get_first(".bar.Message"),
+            Some(&"bar.com".to_string())
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//! Support for embedding encoded file descriptor sets in generated code.
+//!
+//! `prost` discards extensions when decoding descriptors, so the files are kept as they were
+//! encoded by `protoc` where possible. This preserves custom options for consumers like gRPC
+//! server reflection.
+
+use std::collections::{HashMap, HashSet};
+
+use prost::bytes::Buf;
+use prost::encoding::{decode_key, decode_varint, encode_key, encode_varint, skip_field};
+use prost::encoding::{DecodeContext, WireType};
+use prost::{DecodeError, Message};
+use prost_types::FileDescriptorProto;
+
+/// The field number of `source_code_info` in `google.protobuf.FileDescriptorProto`.
+const SOURCE_CODE_INFO: u32 = 9;
+
+/// Encoded files, keyed by name.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct EncodedFiles {
+    files: HashMap<String, EncodedFile>,
+}
+
+#[derive(Clone, Debug)]
+struct EncodedFile {
+    bytes: Vec<u8>,
+    dependencies: Vec<String>,
+}
+
+impl EncodedFiles {
+    /// Extracts the files of an encoded message containing files.
+    ///
+    /// `file_tag` is the field number of the files, which is 1 for a `FileDescriptorSet` and 15
+    /// for a `CodeGeneratorRequest`.
+    pub(crate) fn decode(buf: &[u8], file_tag: u32) -> Result<EncodedFiles, DecodeError> {
+        let mut files = HashMap::new();
+        for (tag, file) in fields(buf)? {
+            if tag != file_tag {
+                continue;
+            }
+            let file = length_delimited(file)?;
+            let mut name = String::new();
+            let mut dependencies = Vec::new();
+            for (tag, value) in fields(file)? {
+                match tag {
+                    1 => name = utf8(length_delimited(value)?)?,
+                    3 => dependencies.push(utf8(length_delimited(value)?)?),
+                    _ => {}
+                }
+            }
+            files.insert(
+                name,
+                EncodedFile {
+                    bytes: file.to_vec(),
+                    dependencies,
+                },
+            );
+        }
+        Ok(EncodedFiles { files })
+    }
+
+    /// Adds a decoded file, unless a file with the same name is already present.
+    pub(crate) fn insert(&mut self, file: &FileDescriptorProto) {
+        self.files
+            .entry(file.name().to_string())
+            .or_insert_with(|| EncodedFile {
+                bytes: file.encode_to_vec(),
+                dependencies: file.dependency.clone(),
+            });
+    }
+
+    /// Returns an encoded `FileDescriptorSet` containing the named files and all of their
+    /// dependencies, with each file following its dependencies.
+    ///
+    /// Dependencies which are not present are left out.
+    pub(crate) fn encode_set<'a>(
+        &self,
+        names: impl IntoIterator<Item = &'a str>,
+        strip_source_info: bool,
+    ) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let mut visited = HashSet::new();
+        for name in names {
+            self.encode_file(name, strip_source_info, &mut visited, &mut buf);
+        }
+        buf
+    }
+
+    fn encode_file<'a>(
+        &'a self,
+        name: &'a str,
+        strip_source_info: bool,
+        visited: &mut HashSet<&'a str>,
+        buf: &mut Vec<u8>,
+    ) {
+        if !visited.insert(name) {
+            return;
+        }
+        let Some(file) = self.files.get(name) else {
+            return;
+        };
+        for dependency in &file.dependencies {
+            self.encode_file(dependency, strip_source_info, visited, buf);
+        }
+
+        let stripped;
+        let bytes = if strip_source_info {
+            stripped = without_source_info(&file.bytes);
+            &stripped
+        } else {
+            &file.bytes
+        };
+        encode_key(1, WireType::LengthDelimited, buf);
+        encode_varint(bytes.len() as u64, buf);
+        buf.extend_from_slice(bytes);
+    }
+}
+
+/// Returns an encoded file without its `source_code_info`.
+fn without_source_info(file: &[u8]) -> Vec<u8> {
+    let mut stripped = Vec::with_capacity(file.len());
+    // The file was already decoded once, so it is well-formed.
+    for (tag, field) in fields(file).expect("encoded file should be valid") {
+        if tag != SOURCE_CODE_INFO {
+            stripped.extend_from_slice(field);
+        }
+    }
+    stripped
+}
+
+/// Splits an encoded message into its fields, returning the tag and the complete encoding of each
+/// field, including its key.
+fn fields(buf: &[u8]) -> Result<Vec<(u32, &[u8])>, DecodeError> {
+    let mut fields = Vec::new();
+    let mut rest = buf;
+    while rest.has_remaining() {
+        let start = buf.len() - rest.len();
+        let (tag, wire_type) = decode_key(&mut rest)?;
+        skip_field(wire_type, tag, &mut rest, DecodeContext::default())?;
+        fields.push((tag, &buf[start..buf.len() - rest.len()]));
+    }
+    Ok(fields)
+}
+
+/// Returns the value of an encoded length-delimited field.
+fn length_delimited(mut field: &[u8]) -> Result<&[u8], DecodeError> {
+    let (_, wire_type) = decode_key(&mut field)?;
+    if wire_type != WireType::LengthDelimited {
+        return Err(DecodeError::new(format!(
+            "invalid wire type: {:?} (expected {:?})",
+            wire_type,
+            WireType::LengthDelimited
+        )));
+    }
+    decode_varint(&mut field)?;
+    Ok(field)
+}
+
+fn utf8(value: &[u8]) -> Result<String, DecodeError> {
+    String::from_utf8(value.to_vec())
+        .map_err(|_| DecodeError::new("invalid string value: data is not UTF-8 encoded"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use prost_types::{FileDescriptorSet, SourceCodeInfo};
+
+    fn file(name: &str, dependencies: &[&str]) -> FileDescriptorProto {
+        FileDescriptorProto {
+            name: Some(name.to_string()),
+            dependency: dependencies.iter().map(|d| d.to_string()).collect(),
+            source_code_info: Some(SourceCodeInfo::default()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn dependencies_come_first() {
+        let set = FileDescriptorSet {
+            file: vec![
+                file("a.proto", &["b.proto", "c.proto"]),
+                file("b.proto", &["c.proto", "missing.proto"]),
+                file("c.proto", &[]),
+                file("unused.proto", &[]),
+            ],
+        };
+        let files = EncodedFiles::decode(&set.encode_to_vec(), 1).unwrap();
+
+        let encoded = FileDescriptorSet::decode(&*files.encode_set(["a.proto"], false)).unwrap();
+        let names = encoded.file.iter().map(|f| f.name()).collect::<Vec<_>>();
+        assert_eq!(names, ["c.proto", "b.proto", "a.proto"]);
+        assert!(encoded.file[0].source_code_info.is_some());
+
+        let stripped = FileDescriptorSet::decode(&*files.encode_set(["b.proto"], true)).unwrap();
+        let names = stripped.file.iter().map(|f| f.name()).collect::<Vec<_>>();
+        assert_eq!(names, ["c.proto", "b.proto"]);
+        assert!(stripped.file.iter().all(|f| f.source_code_info.is_none()));
+    }
+
+    #[test]
+    fn unknown_fields_are_kept() {
+        let mut buf = file("a.proto", &[]).encode_to_vec();
+        // An extension of `FileOptions`, which `prost` would discard.
+        let options = [0xc0, 0x3e, 0x01];
+        encode_key(8, WireType::LengthDelimited, &mut buf);
+        encode_varint(options.len() as u64, &mut buf);
+        buf.extend_from_slice(&options);
+
+        let mut set = Vec::new();
+        encode_key(1, WireType::LengthDelimited, &mut set);
+        encode_varint(buf.len() as u64, &mut set);
+        set.extend_from_slice(&buf);
+
+        let mut files = EncodedFiles::decode(&set, 1).unwrap();
+        files.insert(&file("a.proto", &["ignored.proto"]));
+        assert_eq!(files.encode_set(["a.proto"], false), set);
+    }
+}
@@ -0,0 +1,310 @@
+//! Errors in the Protobuf definitions or the configuration, found while generating code.
+
+use std::fmt;
+use std::io;
+
+use prost_types::{DescriptorProto, EnumDescriptorProto, FileDescriptorProto};
+
+/// An error in the Protobuf definitions or the configuration, found while generating code.
+///
+/// The error names the Protobuf element which caused it and, when the file descriptors include
+/// source information, its location in the `.proto` file. Most errors also suggest a fix, like a
+/// [`Config`](crate::Config) option.
+///
+/// The methods generating code return [`std::io::Error`]s, which wrap an `Error` when the code
+/// could not be generated. Use [`Error::from_io`] to retrieve it:
+///
+/// ```rust,no_run
+/// # fn main() {
+/// if let Err(error) = prost_build::compile_protos(&["src/items.proto"], &["src/"]) {
+///     match prost_build::Error::from_io(&error) {
+///         Some(error) => panic!("invalid definitions in {:?}: {}", error.file(), error),
+///         None => panic!("{}", error),
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    kind: io::ErrorKind,
+    message: String,
+    element: Option<String>,
+    file: Option<String>,
+    line: Option<u32>,
+    column: Option<u32>,
+    help: Option<String>,
+}
+
+impl Error {
+    /// Creates an error about invalid input.
+    pub(crate) fn new(message: impl Into<String>) -> Error {
+        Error {
+            kind: io::ErrorKind::InvalidInput,
+            message: message.into(),
+            element: None,
+            file: None,
+            line: None,
+            column: None,
+            help: None,
+        }
+    }
+
+    /// Sets the kind of the `io::Error` wrapping this error.
+    #[cfg(feature = "format")]
+    pub(crate) fn with_kind(mut self, kind: io::ErrorKind) -> Error {
+        self.kind = kind;
+        self
+    }
+
+    /// Sets the fully-qualified name of the element causing the error, with a leading period for
+    /// Protobuf elements.
+    pub(crate) fn with_element(mut self, element: impl Into<String>) -> Error {
+        self.element = Some(element.into());
+        self
+    }
+
+    /// Sets the suggested fix.
+    pub(crate) fn with_help(mut self, help: impl Into<String>) -> Error {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Sets the location of the element causing the error from the source information of
+    /// `files`, if they define it.
+    pub(crate) fn locate<'a>(
+        mut self,
+        files: impl IntoIterator<Item = &'a FileDescriptorProto>,
+    ) -> Error {
+        let element = match &self.element {
+            Some(element) if element.starts_with('.') => element.clone(),
+            _ => return self,
+        };
+        for file in files {
+            let Some(path) = element_path(file, &element) else {
+                continue;
+            };
+            self.file = Some(file.name().to_string());
+            let span = file.source_code_info.as_ref().and_then(|info| {
+                info.location
+                    .iter()
+                    .find(|location| location.path == path)
+                    .map(|location| &location.span)
+            });
+            if let Some([line, column, ..]) = span.map(Vec::as_slice) {
+                self.line = Some(*line as u32 + 1);
+                self.column = Some(*column as u32 + 1);
+            }
+            break;
+        }
+        self
+    }
+
+    /// Returns the `Error` wrapped by an `io::Error` returned when generating code, if there is
+    /// one.
+    pub fn from_io(error: &io::Error) -> Option<&Error> {
+        error.get_ref()?.downcast_ref()
+    }
+
+    /// Returns the description of the error, without the location and suggested fix.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the fully-qualified name of the Protobuf element causing the error, like
+    /// `my_messages.MyMessage.my_field`, or the configured path or module for errors in the
+    /// configuration or the generated code.
+    pub fn element(&self) -> Option<&str> {
+        self.element
+            .as_deref()
+            .map(|element| element.strip_prefix('.').unwrap_or(element))
+    }
+
+    /// Returns the name of the `.proto` file defining the element causing the error.
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    /// Returns the line of the element in the `.proto` file, starting at 1.
+    pub fn line(&self) -> Option<u32> {
+        self.line
+    }
+
+    /// Returns the column of the element in the `.proto` file, starting at 1.
+    pub fn column(&self) -> Option<u32> {
+        self.column
+    }
+
+    /// Returns the suggested fix.
+    pub fn help(&self) -> Option<&str> {
+        self.help.as_deref()
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file)?;
+            if let (Some(line), Some(column)) = (self.line, self.column) {
+                write!(f, "{}:{}:", line, column)?;
+            }
+            f.write_str(" ")?;
+        }
+        f.write_str(&self.message)?;
+        if let Some(help) = &self.help {
+            write!(f, "\nhelp: {}", help)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for io::Error {
+    fn from(error: Error) -> io::Error {
+        io::Error::new(error.kind, error)
+    }
+}
+
+/// Returns the path of an element in the source information of `file`, if the file defines it.
+fn element_path(file: &FileDescriptorProto, element: &str) -> Option<Vec<i32>> {
+    let name = match file.package() {
+        "" => element,
+        package => element.strip_prefix('.')?.strip_prefix(package)?,
+    };
+    let name = name.strip_prefix('.')?;
+
+    for (idx, message) in file.message_type.iter().enumerate() {
+        if let Some(path) = message_path(message, name) {
+            return Some([vec![4, idx as i32], path].concat());
+        }
+    }
+    for (idx, enum_type) in file.enum_type.iter().enumerate() {
+        if let Some(path) = enum_path(enum_type, name) {
+            return Some([vec![5, idx as i32], path].concat());
+        }
+    }
+    for (idx, service) in file.service.iter().enumerate() {
+        if name == service.name() {
+            return Some(vec![6, idx as i32]);
+        }
+        let method = name
+            .strip_prefix(service.name())
+            .and_then(|name| name.strip_prefix('.'));
+        if let Some(method) = method {
+            let idx_method = service.method.iter().position(|m| m.name() == method)?;
+            return Some(vec![6, idx as i32, 2, idx_method as i32]);
+        }
+    }
+    None
+}
+
+/// Returns the path of an element relative to a message, given its name relative to the scope
+/// of the message.
+fn message_path(message: &DescriptorProto, name: &str) -> Option<Vec<i32>> {
+    if name == message.name() {
+        return Some(Vec::new());
+    }
+    let name = name.strip_prefix(message.name())?.strip_prefix('.')?;
+    if let Some(idx) = message.field.iter().position(|field| field.name() == name) {
+        return Some(vec![2, idx as i32]);
+    }
+    if let Some(idx) = message
+        .oneof_decl
+        .iter()
+        .position(|oneof| oneof.name() == name)
+    {
+        return Some(vec![8, idx as i32]);
+    }
+    for (idx, nested) in message.nested_type.iter().enumerate() {
+        if let Some(path) = message_path(nested, name) {
+            return Some([vec![3, idx as i32], path].concat());
+        }
+    }
+    for (idx, enum_type) in message.enum_type.iter().enumerate() {
+        if let Some(path) = enum_path(enum_type, name) {
+            return Some([vec![4, idx as i32], path].concat());
+        }
+    }
+    None
+}
+
+/// Returns the path of an enum or one of its values relative to the enum, given its name relative
+/// to the scope of the enum.
+fn enum_path(enum_type: &EnumDescriptorProto, name: &str) -> Option<Vec<i32>> {
+    if name == enum_type.name() {
+        return Some(Vec::new());
+    }
+    let name = name.strip_prefix(enum_type.name())?.strip_prefix('.')?;
+    let idx = enum_type
+        .value
+        .iter()
+        .position(|value| value.name() == name)?;
+    Some(vec![2, idx as i32])
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_types::source_code_info::Location;
+    use prost_types::{FieldDescriptorProto, SourceCodeInfo};
+
+    use super::*;
+
+    fn file() -> FileDescriptorProto {
+        FileDescriptorProto {
+            name: Some("shapes.proto".to_string()),
+            package: Some("shapes".to_string()),
+            message_type: vec![DescriptorProto {
+                name: Some("Shape".to_string()),
+                field: vec![FieldDescriptorProto {
+                    name: Some("name".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            source_code_info: Some(SourceCodeInfo {
+                location: vec![Location {
+                    path: vec![4, 0, 2, 0],
+                    span: vec![4, 2, 20],
+                    ..Default::default()
+                }],
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn locate() {
+        let error = Error::new("invalid field")
+            .with_element(".shapes.Shape.name")
+            .with_help("rename it")
+            .locate(&[file()]);
+        assert_eq!(error.element(), Some("shapes.Shape.name"));
+        assert_eq!(error.file(), Some("shapes.proto"));
+        assert_eq!((error.line(), error.column()), (Some(5), Some(3)));
+        assert_eq!(
+            error.to_string(),
+            "shapes.proto:5:3: invalid field\nhelp: rename it"
+        );
+
+        let error = Error::new("invalid message")
+            .with_element(".shapes.Shape")
+            .locate(&[file()]);
+        assert_eq!(error.to_string(), "shapes.proto: invalid message");
+
+        let error = Error::new("unknown")
+            .with_element(".shapes.Circle")
+            .locate(&[file()]);
+        assert_eq!(error.to_string(), "unknown");
+    }
+
+    #[test]
+    fn from_io() {
+        let error = io::Error::from(Error::new("invalid field"));
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            Error::from_io(&error).map(Error::message),
+            Some("invalid field")
+        );
+        assert!(Error::from_io(&io::Error::new(io::ErrorKind::Other, "other")).is_none());
+    }
+}
@@ -17,15 +17,60 @@ fn validate_proto_path(path: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// The wrapper well-known types, and the Rust types they are mapped to. `prost` implements
+/// `Message` for these Rust types, with the same encoding as the wrapper messages.
+const WRAPPER_TYPES: &[(&str, &str)] = &[
+    (".google.protobuf.BoolValue", "bool"),
+    (
+        ".google.protobuf.BytesValue",
+        "::prost::alloc::vec::Vec<u8>",
+    ),
+    (".google.protobuf.DoubleValue", "f64"),
+    (".google.protobuf.FloatValue", "f32"),
+    (".google.protobuf.Int32Value", "i32"),
+    (".google.protobuf.Int64Value", "i64"),
+    (
+        ".google.protobuf.StringValue",
+        "::prost::alloc::string::String",
+    ),
+    (".google.protobuf.UInt32Value", "u32"),
+    (".google.protobuf.UInt64Value", "u64"),
+];
+
+/// The well-known types whose Rust types implement `Eq` and `Hash`, and whether they also
+/// implement `PartialOrd` and `Ord`.
+const WELL_KNOWN_DERIVES: &[(&str, bool)] = &[
+    (".google.protobuf.BoolValue", true),
+    (".google.protobuf.BytesValue", true),
+    (".google.protobuf.Empty", true),
+    (".google.protobuf.Int32Value", true),
+    (".google.protobuf.Int64Value", true),
+    (".google.protobuf.StringValue", true),
+    (".google.protobuf.Timestamp", false),
+    (".google.protobuf.UInt32Value", true),
+    (".google.protobuf.UInt64Value", true),
+];
+
 #[derive(Debug)]
 pub struct ExternPaths {
     extern_paths: HashMap<String, String>,
+    /// The well-known types mapped by `prost-build` itself, and whether they implement
+    /// `PartialOrd` and `Ord` besides `Eq` and `Hash`.
+    known_derives: HashMap<String, bool>,
 }
 
 impl ExternPaths {
-    pub fn new(paths: &[(String, String)], prost_types: bool) -> Result<ExternPaths, String> {
+    /// Creates the extern paths, adding the well-known types of `prost_types` if `prost_types` is
+    /// set, and mapping the wrapper well-known types to Rust primitives if either `prost_types` or
+    /// `wrapper_types` is set.
+    pub fn new(
+        paths: &[(String, String)],
+        prost_types: bool,
+        wrapper_types: bool,
+    ) -> Result<ExternPaths, String> {
         let mut extern_paths = ExternPaths {
             extern_paths: HashMap::new(),
+            known_derives: HashMap::new(),
         };
 
         for (proto_path, rust_path) in paths {
@@ -34,36 +79,42 @@ impl ExternPaths {
 
         if prost_types {
             extern_paths.insert(".google.protobuf".to_string(), "::prost_types".to_string())?;
-            extern_paths.insert(".google.protobuf.BoolValue".to_string(), "bool".to_string())?;
-            extern_paths.insert(
-                ".google.protobuf.BytesValue".to_string(),
-                "::prost::alloc::vec::Vec<u8>".to_string(),
-            )?;
-            extern_paths.insert(
-                ".google.protobuf.DoubleValue".to_string(),
-                "f64".to_string(),
-            )?;
             extern_paths.insert(".google.protobuf.Empty".to_string(), "()".to_string())?;
-            extern_paths.insert(".google.protobuf.FloatValue".to_string(), "f32".to_string())?;
-            extern_paths.insert(".google.protobuf.Int32Value".to_string(), "i32".to_string())?;
-            extern_paths.insert(".google.protobuf.Int64Value".to_string(), "i64".to_string())?;
-            extern_paths.insert(
-                ".google.protobuf.StringValue".to_string(),
-                "::prost::alloc::string::String".to_string(),
-            )?;
-            extern_paths.insert(
-                ".google.protobuf.UInt32Value".to_string(),
-                "u32".to_string(),
-            )?;
-            extern_paths.insert(
-                ".google.protobuf.UInt64Value".to_string(),
-                "u64".to_string(),
-            )?;
+        }
+
+        if prost_types || wrapper_types {
+            for (proto_path, rust_path) in WRAPPER_TYPES {
+                extern_paths.insert(proto_path.to_string(), rust_path.to_string())?;
+            }
+        }
+
+        for &(proto_path, ord) in WELL_KNOWN_DERIVES {
+            let mapped = match proto_path {
+                // An extern path of the type itself takes precedence over `::prost_types`.
+                ".google.protobuf.Timestamp" => {
+                    prost_types && !extern_paths.extern_paths.contains_key(proto_path)
+                }
+                ".google.protobuf.Empty" => prost_types,
+                _ => prost_types || wrapper_types,
+            };
+            if mapped {
+                extern_paths
+                    .known_derives
+                    .insert(proto_path.to_string(), ord);
+            }
         }
 
         Ok(extern_paths)
     }
 
+    /// Returns whether the Rust type of a well-known type mapped by `prost-build` implements
+    /// `Eq` and `Hash`, and also `PartialOrd` and `Ord` if `ord` is set.
+    pub fn known_derives(&self, pb_ident: &str, ord: bool) -> bool {
+        self.known_derives
+            .get(pb_ident)
+            .is_some_and(|&known_ord| known_ord || !ord)
+    }
+
     fn insert(&mut self, proto_path: String, rust_path: String) -> Result<(), String> {
         validate_proto_path(&proto_path)?;
         match self.extern_paths.entry(proto_path) {
@@ -132,6 +183,7 @@ mod tests {
                 (".a.b.c.d.e.f".to_string(), "::abc::def".to_string()),
             ],
             false,
+            false,
         )
         .unwrap();
 
@@ -157,7 +209,7 @@ mod tests {
 
     #[test]
     fn test_well_known_types() {
-        let paths = ExternPaths::new(&[], true).unwrap();
+        let paths = ExternPaths::new(&[], true, false).unwrap();
 
         let case = |proto_ident: &str, resolved_ident: &str| {
             assert_eq!(paths.resolve_ident(proto_ident).unwrap(), resolved_ident);
@@ -166,12 +218,50 @@ mod tests {
         case(".google.protobuf.Value", "::prost_types::Value");
         case(".google.protobuf.Duration", "::prost_types::Duration");
         case(".google.protobuf.Empty", "()");
+        case(".google.protobuf.Int32Value", "i32");
+    }
+
+    #[test]
+    fn test_wrapper_types() {
+        let paths = ExternPaths::new(&[], false, true).unwrap();
+
+        assert_eq!(
+            paths.resolve_ident(".google.protobuf.StringValue").unwrap(),
+            "::prost::alloc::string::String"
+        );
+        assert!(paths.resolve_ident(".google.protobuf.Duration").is_none());
+        assert!(paths.resolve_ident(".google.protobuf.Empty").is_none());
+    }
+
+    #[test]
+    fn test_known_derives() {
+        let paths = ExternPaths::new(
+            &[(
+                ".google.protobuf.Timestamp".to_string(),
+                "::my_types::Timestamp".to_string(),
+            )],
+            true,
+            false,
+        )
+        .unwrap();
+        assert!(paths.known_derives(".google.protobuf.Int32Value", true));
+        assert!(!paths.known_derives(".google.protobuf.FloatValue", false));
+        assert!(!paths.known_derives(".google.protobuf.Duration", false));
+        assert!(!paths.known_derives(".google.protobuf.Timestamp", false));
+
+        let paths = ExternPaths::new(&[], true, false).unwrap();
+        assert!(paths.known_derives(".google.protobuf.Timestamp", false));
+        assert!(!paths.known_derives(".google.protobuf.Timestamp", true));
+
+        let paths = ExternPaths::new(&[], false, true).unwrap();
+        assert!(paths.known_derives(".google.protobuf.StringValue", true));
+        assert!(!paths.known_derives(".google.protobuf.Empty", false));
     }
 
     #[test]
     fn test_error_fully_qualified() {
         let paths = [("foo".to_string(), "bar".to_string())];
-        let err = ExternPaths::new(&paths, false).unwrap_err();
+        let err = ExternPaths::new(&paths, false, false).unwrap_err();
         assert_eq!(
             err.to_string(),
             "Protobuf paths must be fully qualified (begin with a leading '.'): foo"
@@ -181,7 +271,7 @@ mod tests {
     #[test]
     fn test_error_invalid_path() {
         let paths = [(".foo.".to_string(), "bar".to_string())];
-        let err = ExternPaths::new(&paths, false).unwrap_err();
+        let err = ExternPaths::new(&paths, false, false).unwrap_err();
         assert_eq!(
             err.to_string(),
             "invalid fully-qualified Protobuf path: .foo."
@@ -194,7 +284,7 @@ mod tests {
             (".foo".to_string(), "bar".to_string()),
             (".foo".to_string(), "bar".to_string()),
         ];
-        let err = ExternPaths::new(&paths, false).unwrap_err();
+        let err = ExternPaths::new(&paths, false, false).unwrap_err();
         assert_eq!(err.to_string(), "duplicate extern Protobuf path: .foo")
     }
 }
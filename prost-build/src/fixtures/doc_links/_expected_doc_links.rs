@@ -0,0 +1,65 @@
+// This file is @generated by prost-build.
+/// An order, placed at a [google.protobuf.Timestamp](::prost_types::Timestamp).
+///
+/// Each item is an [Order.Item](order::Item), and the order is in a [Status]. Orders are not a \[Cart\], and
+/// \[0, 9\] is not a reference.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Order {
+    /// See [Item](order::Item).
+    #[prost(message, repeated, tag="1")]
+    pub items: ::prost::alloc::vec::Vec<order::Item>,
+    #[prost(oneof="order::Payment", tags="2")]
+    pub payment: ::core::option::Option<order::Payment>,
+}
+/// Nested message and enum types in `Order`.
+pub mod order {
+    /// An item of an [Order](super::Order).
+    #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+    pub struct Item {
+        /// The [Status](super::Status) of the item, like the [.doc_links.Status](super::Status) of its order.
+        #[prost(enumeration="super::Status", tag="1")]
+        pub status: i32,
+    }
+    #[derive(Clone, Copy, PartialEq, ::prost::Oneof)]
+    pub enum Payment {
+        /// Paid with a [Card](super::Card).
+        #[prost(message, tag="2")]
+        Card(super::Card),
+    }
+}
+/// A payment card, used in an [Order].
+///
+/// Qualified names, like [doc_links.Status](Status) or [google.protobuf.Timestamp](::prost_types::Timestamp), and names in code, like
+/// [`Order`], are linked too, but not plain words like Order, or `Cart`.
+///
+/// ```text
+/// Card { order: Order }
+/// ```
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct Card {
+}
+/// The status of an [Order] or an [Order.Item](order::Item).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum Status {
+    /// Not yet in an [Order].
+    Unspecified = 0,
+}
+impl Status {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "STATUS_UNSPECIFIED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "STATUS_UNSPECIFIED" => Some(Self::Unspecified),
+            _ => None,
+        }
+    }
+}
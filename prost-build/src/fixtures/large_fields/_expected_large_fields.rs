@@ -0,0 +1,48 @@
+// This file is @generated by prost-build.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct Large {
+    #[prost(fixed64, tag="1")]
+    pub a: u64,
+    #[prost(fixed64, tag="2")]
+    pub b: u64,
+    #[prost(fixed64, tag="3")]
+    pub c: u64,
+    #[prost(fixed64, tag="4")]
+    pub d: u64,
+    #[prost(fixed64, tag="5")]
+    pub e: u64,
+    #[prost(fixed64, tag="6")]
+    pub f: u64,
+    #[prost(fixed64, tag="7")]
+    pub g: u64,
+    #[prost(fixed64, tag="8")]
+    pub h: u64,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct Small {
+    #[prost(int32, tag="1")]
+    pub value: i32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Event {
+    #[prost(message, optional, boxed, tag="4")]
+    pub details: ::core::option::Option<::prost::alloc::boxed::Box<Large>>,
+    #[prost(message, optional, tag="5")]
+    pub summary: ::core::option::Option<Small>,
+    #[prost(message, repeated, tag="6")]
+    pub history: ::prost::alloc::vec::Vec<Large>,
+    #[prost(oneof="event::Kind", tags="1, 2, 3")]
+    pub kind: ::core::option::Option<event::Kind>,
+}
+/// Nested message and enum types in `Event`.
+pub mod event {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Kind {
+        #[prost(message, tag="1")]
+        Small(super::Small),
+        #[prost(message, tag="2")]
+        Large(::prost::alloc::boxed::Box<super::Large>),
+        #[prost(string, tag="3")]
+        Name(::prost::alloc::string::String),
+    }
+}
@@ -0,0 +1,87 @@
+use crate::Error;
+
+/// The parsed code of a protobuf package, which [`MessageGenerator::modify_file`] can change
+/// before it is printed.
+///
+/// Only the top-level items of the package module are exposed, by name. The parser is an
+/// implementation detail, so items are added as source code.
+///
+/// [`MessageGenerator::modify_file`]: crate::MessageGenerator::modify_file
+pub struct GeneratedFile {
+    file: syn::File,
+}
+
+impl GeneratedFile {
+    pub(crate) fn new(file: syn::File) -> Self {
+        Self { file }
+    }
+
+    pub(crate) fn into_inner(self) -> syn::File {
+        self.file
+    }
+
+    /// Returns the names of the named top-level items, like types, modules, functions and
+    /// constants, in order. Impl blocks have no name.
+    pub fn item_names(&self) -> Vec<String> {
+        self.file
+            .items
+            .iter()
+            .filter_map(item_name)
+            .map(|ident| ident.to_string())
+            .collect()
+    }
+
+    /// Removes the top-level items named `name`, along with the impl blocks for `name`, and
+    /// returns whether an item was removed.
+    pub fn remove_item(&mut self, name: &str) -> bool {
+        let len = self.file.items.len();
+        self.file
+            .items
+            .retain(|item| item_name(item).map_or(true, |ident| ident != name));
+        let removed = self.file.items.len() != len;
+        if removed {
+            self.file
+                .items
+                .retain(|item| !matches!(item, syn::Item::Impl(item) if impl_of(item, name)));
+        }
+        removed
+    }
+
+    /// Parses `code` as a list of items, and appends them to the file.
+    ///
+    /// Returns an error if `code` does not parse, leaving the file as it is.
+    pub fn push_items(&mut self, code: &str) -> Result<(), Error> {
+        let file = syn::parse_file(code).map_err(|error| {
+            Error::new(format!("failed to parse the items `{}`: {}", code, error))
+        })?;
+        self.file.items.extend(file.items);
+        Ok(())
+    }
+}
+
+/// Returns the name of an item, if it has one.
+fn item_name(item: &syn::Item) -> Option<&syn::Ident> {
+    match item {
+        syn::Item::Const(item) => Some(&item.ident),
+        syn::Item::Enum(item) => Some(&item.ident),
+        syn::Item::ExternCrate(item) => Some(&item.ident),
+        syn::Item::Fn(item) => Some(&item.sig.ident),
+        syn::Item::Macro(item) => item.ident.as_ref(),
+        syn::Item::Mod(item) => Some(&item.ident),
+        syn::Item::Static(item) => Some(&item.ident),
+        syn::Item::Struct(item) => Some(&item.ident),
+        syn::Item::Trait(item) => Some(&item.ident),
+        syn::Item::TraitAlias(item) => Some(&item.ident),
+        syn::Item::Type(item) => Some(&item.ident),
+        syn::Item::Union(item) => Some(&item.ident),
+        _ => None,
+    }
+}
+
+/// Returns whether an impl block is for the item named `name`.
+fn impl_of(item: &syn::ItemImpl, name: &str) -> bool {
+    match *item.self_ty {
+        syn::Type::Path(ref ty) => ty.qself.is_none() && ty.path.is_ident(name),
+        _ => false,
+    }
+}
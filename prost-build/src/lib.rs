@@ -134,6 +134,23 @@
 //! ```
 //!
 //! [`protobuf-src`]: https://docs.rs/protobuf-src
+//!
+//! ### Using the built-in parser
+//!
+//! `prost-build` also includes a parser for `.proto` files, which produces the same output as
+//! `protoc`. It can be enabled with [`Config::use_builtin_parser()`], in which case `protoc` is
+//! not needed at all.
+//!
+//! ## Generating code without a build script
+//!
+//! Projects which check generated code into their source tree can use the `prost-build`
+//! command-line tool instead of a build script. It is configured with the options accepted by
+//! [`Config::apply_options()`], and `--check` verifies that the checked-in code is up to date:
+//!
+//! ```bash
+//! $ prost-build -I proto --option bytes=. --out-dir src/generated proto/*.proto
+//! $ prost-build -I proto --option bytes=. --out-dir src/generated --check proto/*.proto
+//! ```
 
 use std::io::Result;
 use std::path::Path;
@@ -141,25 +158,47 @@ use std::path::Path;
 use prost_types::FileDescriptorSet;
 
 mod ast;
-pub use crate::ast::{Comments, Method, Service};
+pub use crate::ast::{Comments, Enum, EnumVariant, Message, MessageField, Method, Oneof, Service};
+
+mod compatibility;
+pub use compatibility::{Change, CompatibilityChecker, Rule};
 
 mod collections;
 pub(crate) use collections::{BytesType, MapType};
 
 mod code_generator;
+mod descriptor_set;
+mod error;
+pub use error::Error;
 mod extern_paths;
 mod ident;
 mod message_graph;
+mod parser;
 mod path;
+mod validation;
 
 mod config;
 pub use config::{
     error_message_protoc_not_found, protoc_from_env, protoc_include_from_env, Config,
 };
 
+/// The name of the extern path manifest written by [`Config::publish_extern_paths`].
+pub const EXTERN_PATHS_MANIFEST: &str = "prost_extern_paths.txt";
+
 mod module;
 pub use module::Module;
 
+mod naming;
+pub use naming::NamingStrategy;
+
+mod service_generator;
+pub use service_generator::ServiceTraitGenerator;
+
+#[cfg(feature = "format")]
+mod generated_file;
+#[cfg(feature = "format")]
+pub use generated_file::GeneratedFile;
+
 /// A service generator takes a service descriptor and generates Rust code.
 ///
 /// `ServiceGenerator` can be used to generate application-specific interfaces
@@ -173,7 +212,8 @@ pub use module::Module;
 /// details like how errors are handled or if it is asynchronous. Then the user provides an
 /// implementation of the generated trait in the application code and plugs it into the framework.
 ///
-/// Such framework isn't part of Prost at present.
+/// A framework-independent implementation is provided by [`ServiceTraitGenerator`], which
+/// generates a trait, a client and a server for each service.
 pub trait ServiceGenerator {
     /// Generates a Rust interface or implementation for a service, writing the
     /// result to `buf`.
@@ -205,6 +245,55 @@ pub trait ServiceGenerator {
     fn finalize_package(&mut self, _package: &str, _buf: &mut String) {}
 }
 
+/// A message generator takes message and enum descriptors and generates additional Rust code for
+/// them.
+///
+/// Where [`ServiceGenerator`] generates code for services, `MessageGenerator` lets applications
+/// generate code alongside the messages and enums prost generates, such as conversions to domain
+/// types, trait implementations or registries.
+///
+/// Message generators are registered with a code generator using the
+/// `Config::message_generator` method.
+///
+/// The code appended by [`generate_message`](Self::generate_message) and
+/// [`generate_enum`](Self::generate_enum) is placed directly after the generated type, in the
+/// same module, so the type can be referred to by its name. Oneof enums are nested in the module
+/// of their message, and are reached through [`Oneof::rust_path`].
+pub trait MessageGenerator {
+    /// Generates additional Rust code for a message, writing the result to `buf`.
+    ///
+    /// The default implementation is empty and does nothing.
+    fn generate_message(&mut self, _message: &Message, _buf: &mut String) {}
+
+    /// Generates additional Rust code for an enum, writing the result to `buf`.
+    ///
+    /// The default implementation is empty and does nothing.
+    fn generate_enum(&mut self, _enum: &Enum, _buf: &mut String) {}
+
+    /// Finalizes the generation process for an entire protobuf package.
+    ///
+    /// This is called once per protobuf package, after the code for all of its `.proto` files
+    /// has been generated, with `buf` holding the package module. Paths relative to the package
+    /// module, like [`Message::rust_path`], can be used here.
+    ///
+    /// The default implementation is empty and does nothing.
+    fn finalize_package(&mut self, _package: &str, _buf: &mut String) {}
+
+    /// Modifies the parsed code of an entire protobuf package before it is written.
+    ///
+    /// This is called once per protobuf package, after
+    /// [`finalize_package`](Self::finalize_package), with the generated code parsed as a
+    /// [`GeneratedFile`], whose top-level items can be listed, removed or added. The generated
+    /// code is only parsed when it is formatted, which is the default, or checked with
+    /// [`Config::check_generated_code`], so this is not called with `format(false)` otherwise.
+    /// Since the file is printed again afterwards, the output of a package is always formatted
+    /// with `prettyplease` when this is called.
+    ///
+    /// The default implementation is empty and does nothing.
+    #[cfg(feature = "format")]
+    fn modify_file(&mut self, _package: &str, _file: &mut GeneratedFile) {}
+}
+
 /// Compile `.proto` files into Rust files during a Cargo build.
 ///
 /// The generated `.rs` files are written to the Cargo `OUT_DIR` directory, suitable for use with
@@ -417,6 +506,216 @@ mod tests {
         assert_eq!(state.finalized, 3);
     }
 
+    /// Implements `MessageGenerator`, recording the generated types and appending a constant to
+    /// each message.
+    #[derive(Default)]
+    struct MockMessageGenerator {
+        state: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl MessageGenerator for MockMessageGenerator {
+        fn generate_message(&mut self, message: &Message, buf: &mut String) {
+            let mut state = self.state.borrow_mut();
+            state.push(format!(
+                "message {} {}",
+                message.full_name, message.rust_path
+            ));
+            for oneof in &message.oneofs {
+                let fields = oneof.fields.iter().map(|field| field.name.as_str());
+                state.push(format!(
+                    "oneof {} {} {}",
+                    oneof.name,
+                    oneof.rust_path,
+                    fields.collect::<Vec<_>>().join(",")
+                ));
+            }
+            buf.push_str(&format!(
+                "impl {} {{ pub const TYPE: &'static str = \"{}\"; }}\n",
+                message.name, message.full_name
+            ));
+        }
+
+        fn finalize_package(&mut self, package: &str, _buf: &mut String) {
+            self.state.borrow_mut().push(format!("package {}", package));
+        }
+    }
+
+    #[test]
+    fn message_generator() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let state = Rc::new(RefCell::new(Vec::new()));
+        let gen = MockMessageGenerator {
+            state: Rc::clone(&state),
+        };
+
+        Config::new()
+            .message_generator(Box::new(gen))
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &["src/fixtures/field_attributes/field_attributes.proto"],
+                &["src/fixtures/field_attributes"],
+            )
+            .unwrap();
+
+        assert_eq!(
+            *state.borrow(),
+            [
+                "message field_attributes.Container Container",
+                "oneof data container::Data Foo,Bar",
+                "message field_attributes.Foo Foo",
+                "message field_attributes.Bar Bar",
+                "message field_attributes.Qux Qux",
+                "package field_attributes",
+            ]
+        );
+        let generated =
+            std::fs::read_to_string(tempdir.path().join("field_attributes.rs")).unwrap();
+        assert!(generated.contains("\"field_attributes.Qux\""));
+    }
+
+    #[test]
+    fn prost_types_path() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .enable_type_registry()
+            .prost_types_path("::my_prost::types")
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &["src/fixtures/field_attributes/field_attributes.proto"],
+                &["src/fixtures/field_attributes"],
+            )
+            .unwrap();
+
+        let generated =
+            std::fs::read_to_string(tempdir.path().join("field_attributes.rs")).unwrap();
+        assert!(generated.contains("registry: &mut ::my_prost::types::TypeRegistry"));
+    }
+
+    #[test]
+    #[cfg(feature = "format")]
+    fn invalid_attribute() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        // Attributes are not checked by default.
+        Config::new()
+            .out_dir(tempdir.path())
+            .format(false)
+            .type_attribute(".field_attributes.Foo", "// Not an attribute.")
+            .compile_protos(
+                &["src/fixtures/field_attributes/field_attributes.proto"],
+                &["src/fixtures/field_attributes"],
+            )
+            .unwrap();
+
+        let error = Config::new()
+            .out_dir(tempdir.path())
+            .check_generated_code()
+            .type_attribute(".field_attributes.Foo", "#[derive(Eq)")
+            .compile_protos(
+                &["src/fixtures/field_attributes/field_attributes.proto"],
+                &["src/fixtures/field_attributes"],
+            )
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(error.to_string().starts_with(
+            "invalid type_attribute for `.field_attributes.Foo`: `#[derive(Eq)` is not a list of attributes"
+        ));
+    }
+
+    /// Appends invalid code to messages, and replaces a message in the parsed file.
+    #[cfg(feature = "format")]
+    struct SyntaxGenerator {
+        valid: bool,
+    }
+
+    #[cfg(feature = "format")]
+    impl MessageGenerator for SyntaxGenerator {
+        fn generate_message(&mut self, message: &Message, buf: &mut String) {
+            if !self.valid && message.name == "Bar" {
+                buf.push_str("impl Bar {\n    fn broken() -> {}\n}\n");
+            }
+        }
+
+        fn modify_file(&mut self, _package: &str, file: &mut GeneratedFile) {
+            assert!(file.item_names().iter().any(|name| name == "Qux"));
+            assert!(file.remove_item("Qux"));
+            assert!(!file.remove_item("Qux"));
+            assert!(file.push_items("pub type Qux = (").is_err());
+            file.push_items("pub type Qux = ();").unwrap();
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "format")]
+    fn invalid_generated_code() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let error = Config::new()
+            .message_generator(Box::new(SyntaxGenerator { valid: false }))
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &["src/fixtures/field_attributes/field_attributes.proto"],
+                &["src/fixtures/field_attributes"],
+            )
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        let error = error.to_string();
+        assert!(
+            error.starts_with("failed to parse the code generated for `field_attributes`: "),
+            "{}",
+            error
+        );
+
+        // Without formatting, the generated code is only parsed when it is checked.
+        Config::new()
+            .message_generator(Box::new(SyntaxGenerator { valid: false }))
+            .format(false)
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &["src/fixtures/field_attributes/field_attributes.proto"],
+                &["src/fixtures/field_attributes"],
+            )
+            .unwrap();
+        let error = Config::new()
+            .message_generator(Box::new(SyntaxGenerator { valid: false }))
+            .format(false)
+            .check_generated_code()
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &["src/fixtures/field_attributes/field_attributes.proto"],
+                &["src/fixtures/field_attributes"],
+            )
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    #[cfg(feature = "format")]
+    fn modify_file() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .message_generator(Box::new(SyntaxGenerator { valid: true }))
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &["src/fixtures/field_attributes/field_attributes.proto"],
+                &["src/fixtures/field_attributes"],
+            )
+            .unwrap();
+
+        let generated =
+            std::fs::read_to_string(tempdir.path().join("field_attributes.rs")).unwrap();
+        assert!(!generated.contains("pub struct Qux"));
+        assert!(generated.contains("pub type Qux = ();"));
+    }
+
     #[test]
     fn test_generate_message_attributes() {
         let _ = env_logger::try_init();
@@ -522,6 +821,124 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_generate_intra_doc_links() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .out_dir(tempdir.path())
+            .enable_intra_doc_links()
+            .compile_protos(
+                &["src/fixtures/doc_links/doc_links.proto"],
+                &["src/fixtures/doc_links"],
+            )
+            .unwrap();
+
+        assert_eq_fixture_file!(
+            if cfg!(feature = "format") {
+                "src/fixtures/doc_links/_expected_doc_links_formatted.rs"
+            } else {
+                "src/fixtures/doc_links/_expected_doc_links.rs"
+            },
+            tempdir.path().join("doc_links.rs")
+        );
+    }
+
+    #[test]
+    fn test_generate_large_fields() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .out_dir(tempdir.path())
+            .box_large_fields(32)
+            .compile_protos(
+                &["src/fixtures/large_fields/large_fields.proto"],
+                &["src/fixtures/large_fields"],
+            )
+            .unwrap();
+
+        assert_eq_fixture_file!(
+            if cfg!(feature = "format") {
+                "src/fixtures/large_fields/_expected_large_fields_formatted.rs"
+            } else {
+                "src/fixtures/large_fields/_expected_large_fields.rs"
+            },
+            tempdir.path().join("large_fields.rs")
+        );
+    }
+
+    #[test]
+    fn retain_reachable_types() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let mut config = Config::new();
+        config
+            .out_dir(tempdir.path())
+            .service_generator(Box::new(ServiceTraitGenerator))
+            .retain_reachable_types([".tree_shaking.Library"])
+            .enable_intra_doc_links()
+            .compile_protos(
+                &["src/fixtures/tree_shaking/tree_shaking.proto"],
+                &["src/fixtures/tree_shaking"],
+            )
+            .unwrap();
+        assert_eq!(
+            config.unreachable_types(),
+            [
+                ".tree_shaking.Admin",
+                ".tree_shaking.PurgeRequest",
+                ".tree_shaking.PurgeResponse",
+                ".tree_shaking.Unused",
+                ".tree_shaking.UnusedKind",
+            ]
+        );
+
+        let docs = std::fs::read_to_string(tempdir.path().join("tree_shaking.rs")).unwrap();
+        let generated = docs
+            .lines()
+            .filter(|line| !line.trim_start().starts_with("///"))
+            .collect::<String>()
+            .replace(char::is_whitespace, "");
+        for kept in [
+            "traitLibrary",
+            "pubstructGetBookRequest",
+            "pubstructBook",
+            "pubstructChapter",
+            "pubstructAuthor",
+            "pubenumGenre",
+            "pubstructScan",
+            // `Shelf` is kept for its nested `Position` enum, and keeps the type of its fields.
+            "pubstructShelf",
+            "pubenumPosition",
+            "pubstructLabel",
+        ] {
+            assert!(generated.contains(kept), "missing {kept}");
+        }
+        for dropped in [
+            "traitAdmin",
+            "PurgeRequest",
+            "PurgeResponse",
+            "Unused",
+            "UnusedKind",
+        ] {
+            assert!(!generated.contains(dropped), "unexpected {dropped}");
+        }
+        // Only generated types are linked.
+        assert!(
+            docs.contains(
+                "/// A book, returned by \\[Library\\]. Its chapters are [Book.Chapter](book::Chapter) messages, but it is not a\n"
+            ),
+            "{}",
+            docs
+        );
+        assert!(docs.contains(
+            "/// \\[PurgeResponse\\] or a tree_shaking.Unused, which are not generated.\n"
+        ));
+    }
+
     #[test]
     fn deterministic_include_file() {
         let _ = env_logger::try_init();
@@ -576,7 +993,7 @@ mod tests {
         let mut buf = Vec::new();
         Config::new()
             .default_package_filename("_.default")
-            .write_includes(modules.iter().collect(), &mut buf, None, &file_names)
+            .write_includes(modules.iter().collect(), &mut buf, false, &file_names)
             .unwrap();
         let actual = String::from_utf8(buf).unwrap();
         assert_eq_fixture_contents!("src/fixtures/write_includes/_.includes.rs", actual);
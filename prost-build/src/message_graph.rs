@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use petgraph::algo::has_path_connecting;
 use petgraph::graph::NodeIndex;
@@ -6,10 +6,12 @@ use petgraph::Graph;
 
 use prost_types::{
     field_descriptor_proto::{Label, Type},
-    DescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
 };
 
+use crate::extern_paths::ExternPaths;
 use crate::path::PathMap;
+use crate::{BytesType, MapType};
 
 /// `MessageGraph` builds a graph of messages whose edges correspond to nesting.
 /// The goal is to recognize when message types are recursively nested, so
@@ -18,7 +20,25 @@ pub struct MessageGraph {
     index: HashMap<String, NodeIndex>,
     graph: Graph<String, ()>,
     messages: HashMap<String, DescriptorProto>,
+    enums: HashMap<String, EnumDescriptorProto>,
     boxed: PathMap<()>,
+    eq_hash: HashSet<String>,
+    ord: HashSet<String>,
+    large_fields: BTreeMap<String, usize>,
+    proto3: HashSet<String>,
+    reachable: Option<HashSet<String>>,
+    required_fields: HashSet<String>,
+}
+
+/// The configuration deciding which types implement the derived traits, and whether
+/// `PartialOrd` and `Ord` are derived besides `Eq` and `Hash`.
+#[derive(Clone, Copy)]
+struct Derives<'a> {
+    map_type: &'a PathMap<MapType>,
+    extern_paths: &'a ExternPaths,
+    extern_eq_hash: &'a PathMap<()>,
+    extern_ord: &'a PathMap<()>,
+    ord: bool,
 }
 
 impl MessageGraph {
@@ -30,7 +50,14 @@ impl MessageGraph {
             index: HashMap::new(),
             graph: Graph::new(),
             messages: HashMap::new(),
+            enums: HashMap::new(),
             boxed,
+            eq_hash: HashSet::new(),
+            ord: HashSet::new(),
+            large_fields: BTreeMap::new(),
+            proto3: HashSet::new(),
+            reachable: None,
+            required_fields: HashSet::new(),
         };
 
         for file in files {
@@ -39,8 +66,12 @@ impl MessageGraph {
                 if file.package.is_some() { "." } else { "" },
                 file.package.as_ref().map(String::as_str).unwrap_or("")
             );
+            let proto3 = file.syntax() == "proto3";
             for msg in &file.message_type {
-                msg_graph.add_message(&package, msg);
+                msg_graph.add_message(&package, msg, proto3);
+            }
+            for enum_type in &file.enum_type {
+                msg_graph.add_enum(&package, enum_type);
             }
         }
 
@@ -64,7 +95,7 @@ impl MessageGraph {
     /// Because prost does not box message fields, recursively nested messages would not compile in Rust.
     /// To allow recursive messages, the message graph is used to detect recursion and automatically box the recursive field.
     /// Since repeated messages are already put in a Vec, boxing them isn’t necessary even if the reference is recursive.
-    fn add_message(&mut self, package: &str, msg: &DescriptorProto) {
+    fn add_message(&mut self, package: &str, msg: &DescriptorProto, proto3: bool) {
         let msg_name = format!("{}.{}", package, msg.name.as_ref().unwrap());
         let msg_index = self.get_or_insert_index(msg_name.clone());
 
@@ -75,17 +106,33 @@ impl MessageGraph {
             }
         }
         self.messages.insert(msg_name.clone(), msg.clone());
+        if proto3 {
+            self.proto3.insert(msg_name.clone());
+        }
 
         for msg in &msg.nested_type {
-            self.add_message(&msg_name, msg);
+            self.add_message(&msg_name, msg, proto3);
+        }
+        for enum_type in &msg.enum_type {
+            self.add_enum(&msg_name, enum_type);
         }
     }
 
+    fn add_enum(&mut self, scope: &str, enum_type: &EnumDescriptorProto) {
+        let enum_name = format!("{}.{}", scope, enum_type.name());
+        self.enums.insert(enum_name, enum_type.clone());
+    }
+
     /// Try get a message descriptor from current message graph
     pub fn get_message(&self, message: &str) -> Option<&DescriptorProto> {
         self.messages.get(message)
     }
 
+    /// Returns the descriptor of an enum defined by the files of the graph.
+    pub(crate) fn get_enum(&self, enum_name: &str) -> Option<&EnumDescriptorProto> {
+        self.enums.get(enum_name)
+    }
+
     /// Returns true if message type `inner` is nested in message type `outer`.
     pub fn is_nested(&self, outer: &str, inner: &str) -> bool {
         let outer = match self.index.get(outer) {
@@ -101,13 +148,17 @@ impl MessageGraph {
     }
 
     /// Returns `true` if this message can automatically derive Copy trait.
+    ///
+    /// Messages which are not part of the graph, like those of files which are not compiled, are
+    /// assumed not to derive Copy.
     pub fn can_message_derive_copy(&self, fq_message_name: &str) -> bool {
         assert_eq!(".", &fq_message_name[..1]);
-        self.get_message(fq_message_name)
-            .unwrap()
-            .field
-            .iter()
-            .all(|field| self.can_field_derive_copy(fq_message_name, field))
+        self.get_message(fq_message_name).is_some_and(|message| {
+            message
+                .field
+                .iter()
+                .all(|field| self.can_field_derive_copy(fq_message_name, field))
+        })
     }
 
     /// Returns `true` if the type of this field allows deriving the Copy trait.
@@ -128,6 +179,7 @@ impl MessageGraph {
                     .boxed
                     .get_first_field(fq_message_name, field.name())
                     .is_some()
+                || self.is_large_field(fq_message_name, field.name())
             {
                 false
             } else {
@@ -153,4 +205,479 @@ impl MessageGraph {
             )
         }
     }
+
+    /// Decides which messages derive `Eq` and `Hash`, and which also derive `PartialOrd` and
+    /// `Ord`, among the messages matched by `eq_hash` and `ord`.
+    ///
+    /// A message can derive these traits if the types of all of its fields implement them.
+    /// Floating point fields, `HashMap` fields and fields of messages which don't derive them
+    /// prevent the derives. Extern types implement the traits if they are matched by
+    /// `extern_eq_hash` or `extern_ord`, or if they are well-known types known to implement them.
+    pub(crate) fn set_derives(
+        &mut self,
+        eq_hash: &PathMap<()>,
+        ord: &PathMap<()>,
+        map_type: &PathMap<MapType>,
+        extern_paths: &ExternPaths,
+        extern_eq_hash: &PathMap<()>,
+        extern_ord: &PathMap<()>,
+    ) {
+        let generated = |name: &String| {
+            extern_paths.resolve_ident(name).is_none() && !is_map_entry(&self.messages[name])
+        };
+        let ord_candidates: HashSet<String> = self
+            .messages
+            .keys()
+            .filter(|name| generated(name) && ord.get(name).next().is_some())
+            .cloned()
+            .collect();
+        let eq_hash_candidates = self
+            .messages
+            .keys()
+            .filter(|name| generated(name) && eq_hash.get(name).next().is_some())
+            .cloned()
+            .chain(ord_candidates.iter().cloned())
+            .collect();
+        let derives = Derives {
+            map_type,
+            extern_paths,
+            extern_eq_hash,
+            extern_ord,
+            ord: true,
+        };
+        self.ord = self.derivable(ord_candidates, &derives);
+        self.eq_hash = self.derivable(
+            eq_hash_candidates,
+            &Derives {
+                ord: false,
+                ..derives
+            },
+        );
+    }
+
+    /// Returns the largest subset of `candidates` whose fields only have types implementing
+    /// the traits, given that the messages in the subset implement them.
+    fn derivable(&self, mut candidates: HashSet<String>, derives: &Derives) -> HashSet<String> {
+        // Recursive messages can derive the traits, so candidates are only removed once one of
+        // their fields is known not to allow it.
+        loop {
+            let excluded = candidates
+                .iter()
+                .filter(|name| {
+                    !self.messages[*name].field.iter().all(|field| {
+                        self.can_field_derive_traits(name, field, &candidates, derives)
+                    })
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            if excluded.is_empty() {
+                return candidates;
+            }
+            for name in excluded {
+                candidates.remove(&name);
+            }
+        }
+    }
+
+    fn can_field_derive_traits(
+        &self,
+        fq_message_name: &str,
+        field: &FieldDescriptorProto,
+        derivable: &HashSet<String>,
+        derives: &Derives,
+    ) -> bool {
+        match field.r#type() {
+            Type::Float | Type::Double => false,
+            Type::Message | Type::Group => {
+                let type_name = field.type_name();
+                if derives.extern_paths.resolve_ident(type_name).is_some() {
+                    return derives.extern_ord.get_first(type_name).is_some()
+                        || (!derives.ord && derives.extern_eq_hash.get_first(type_name).is_some())
+                        || derives.extern_paths.known_derives(type_name, derives.ord);
+                }
+                match self.messages.get(type_name) {
+                    Some(entry) if is_map_entry(entry) => {
+                        derives
+                            .map_type
+                            .get_first_field(fq_message_name, field.name())
+                            == Some(&MapType::BTreeMap)
+                            && entry.field.iter().all(|field| {
+                                self.can_field_derive_traits(
+                                    fq_message_name,
+                                    field,
+                                    derivable,
+                                    derives,
+                                )
+                            })
+                    }
+                    _ => derivable.contains(type_name),
+                }
+            }
+            _ => true,
+        }
+    }
+
+    /// Returns `true` if this message derives `Eq` and `Hash`.
+    pub fn can_message_derive_eq_hash(&self, fq_message_name: &str) -> bool {
+        self.eq_hash.contains(fq_message_name)
+    }
+
+    /// Returns `true` if this message derives `PartialOrd` and `Ord`.
+    pub fn can_message_derive_ord(&self, fq_message_name: &str) -> bool {
+        self.ord.contains(fq_message_name)
+    }
+
+    /// Decides which messages may be missing required fields, in themselves or in their
+    /// submessages, given the required fields matched by `required_field_presence`.
+    ///
+    /// Message types which are not part of the graph, like those of extern paths, may have been
+    /// generated with required fields too, so messages containing them are included.
+    pub(crate) fn set_required_fields(&mut self, required_field_presence: &PathMap<()>) {
+        if required_field_presence.matchers.is_empty() {
+            return;
+        }
+        let mut required_fields = self
+            .messages
+            .iter()
+            .filter(|(name, message)| {
+                message.field.iter().any(|field| {
+                    field.label() == Label::Required
+                        && required_field_presence
+                            .get_first_field(name, field.name())
+                            .is_some()
+                })
+            })
+            .map(|(name, _)| name.clone())
+            .collect::<HashSet<_>>();
+        // Propagate to the messages containing them, until no message is added.
+        loop {
+            let added = self
+                .messages
+                .iter()
+                .filter(|(name, message)| {
+                    !required_fields.contains(*name)
+                        && message.field.iter().any(|field| {
+                            matches!(field.r#type(), Type::Message | Type::Group)
+                                && (required_fields.contains(field.type_name())
+                                    || !self.messages.contains_key(field.type_name()))
+                        })
+                })
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>();
+            if added.is_empty() {
+                break;
+            }
+            required_fields.extend(added);
+        }
+        self.required_fields = required_fields;
+    }
+
+    /// Returns `true` if this message or its submessages may be missing required fields.
+    pub fn has_required_fields(&self, fq_message_name: &str) -> bool {
+        self.required_fields.contains(fq_message_name)
+    }
+
+    /// Restricts the generated types to the messages, enums and services matched by `roots`, and
+    /// the types which are reachable from them, and returns the fully-qualified names of the
+    /// other types, sorted.
+    ///
+    /// A message reaches the types of its fields, including the values of map fields and the
+    /// members of oneofs, and a service reaches the input and output types of its methods.
+    /// Nested types are generated in the module of their parent message, so they also reach
+    /// their parent.
+    pub(crate) fn set_reachable<'a>(
+        &mut self,
+        files: impl Iterator<Item = &'a FileDescriptorProto>,
+        roots: &PathMap<()>,
+    ) -> Vec<String> {
+        let mut edges = HashMap::new();
+        for file in files {
+            let package = match file.package() {
+                "" => String::new(),
+                package => format!(".{package}"),
+            };
+            for message in &file.message_type {
+                add_message_edges(&package, None, message, &mut edges);
+            }
+            for enum_type in &file.enum_type {
+                add_enum_edges(&package, None, enum_type, &mut edges);
+            }
+            for service in &file.service {
+                edges.insert(
+                    format!("{}.{}", package, service.name()),
+                    service
+                        .method
+                        .iter()
+                        .flat_map(|method| [method.input_type(), method.output_type()])
+                        .map(str::to_string)
+                        .collect(),
+                );
+            }
+        }
+
+        let mut reachable = HashSet::new();
+        let mut pending = edges
+            .keys()
+            .filter(|name| roots.get(name).next().is_some())
+            .cloned()
+            .collect::<Vec<_>>();
+        while let Some(name) = pending.pop() {
+            if let Some(targets) = edges.get(&name) {
+                if reachable.insert(name) {
+                    pending.extend(targets.iter().cloned());
+                }
+            }
+        }
+
+        let mut dropped = edges
+            .into_keys()
+            .filter(|name| {
+                !reachable.contains(name) && !self.messages.get(name).is_some_and(is_map_entry)
+            })
+            .collect::<Vec<_>>();
+        dropped.sort();
+        self.reachable = Some(reachable);
+        dropped
+    }
+
+    /// Returns `true` if code is generated for this message, enum or service.
+    pub fn is_reachable(&self, fq_name: &str) -> bool {
+        self.reachable
+            .as_ref()
+            .map_or(true, |reachable| reachable.contains(fq_name))
+    }
+
+    /// Estimates the size of the generated structs, and records the non-repeated message fields
+    /// and oneof members whose message type is estimated to be larger than `threshold` bytes.
+    ///
+    /// The estimate assumes a 64-bit target. Fields which are already boxed, either because of
+    /// recursion or because they are configured with `Config::boxed`, count as a pointer, as do
+    /// the fields which are boxed for their size.
+    pub(crate) fn set_large_fields(&mut self, threshold: usize, bytes_type: &PathMap<BytesType>) {
+        let mut sizes = HashMap::new();
+        let mut large_fields = BTreeMap::new();
+        let mut names = self.messages.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        for name in names {
+            self.message_size(&name, threshold, bytes_type, &mut sizes, &mut large_fields);
+        }
+        self.large_fields = large_fields;
+    }
+
+    /// Returns the fields which are boxed because of their size, as fully-qualified field names
+    /// mapped to the estimated size of their message type.
+    pub(crate) fn large_fields(&self) -> &BTreeMap<String, usize> {
+        &self.large_fields
+    }
+
+    /// Returns `true` if this field is boxed because of its size.
+    pub fn is_large_field(&self, fq_message_name: &str, field_name: &str) -> bool {
+        self.large_fields
+            .contains_key(&format!("{fq_message_name}.{field_name}"))
+    }
+
+    fn message_size(
+        &self,
+        fq_message_name: &str,
+        threshold: usize,
+        bytes_type: &PathMap<BytesType>,
+        sizes: &mut HashMap<String, Option<usize>>,
+        large_fields: &mut BTreeMap<String, usize>,
+    ) -> usize {
+        match sizes.get(fq_message_name) {
+            Some(Some(size)) => return *size,
+            // A message which contains itself is boxed somewhere along the way.
+            Some(None) => return POINTER_SIZE,
+            None => {}
+        }
+        let message = match self.messages.get(fq_message_name) {
+            Some(message) => message,
+            // External types are assumed to be about as large as a `Vec`.
+            None => return VEC_SIZE,
+        };
+        sizes.insert(fq_message_name.to_string(), None);
+
+        let mut size = 0;
+        let mut oneofs = vec![0; message.oneof_decl.len()];
+        for field in &message.field {
+            match field.oneof_index {
+                Some(index) if !field.proto3_optional() => {
+                    let oneof = &message.oneof_decl[index as usize];
+                    let config_path = format!("{}.{}", fq_message_name, oneof.name());
+                    let field_size = self.field_size(
+                        fq_message_name,
+                        &config_path,
+                        field,
+                        false,
+                        threshold,
+                        bytes_type,
+                        sizes,
+                        large_fields,
+                    );
+                    oneofs[index as usize] = oneofs[index as usize].max(field_size);
+                }
+                _ => {
+                    size += self.field_size(
+                        fq_message_name,
+                        fq_message_name,
+                        field,
+                        true,
+                        threshold,
+                        bytes_type,
+                        sizes,
+                        large_fields,
+                    );
+                }
+            }
+        }
+        // Every oneof enum holds a discriminant next to its largest variant.
+        size += oneofs
+            .iter()
+            .filter(|&&variant| variant > 0)
+            .map(|variant| align(variant + 1))
+            .sum::<usize>();
+
+        let size = align(size);
+        sizes.insert(fq_message_name.to_string(), Some(size));
+        size
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn field_size(
+        &self,
+        fq_message_name: &str,
+        config_path: &str,
+        field: &FieldDescriptorProto,
+        in_struct: bool,
+        threshold: usize,
+        bytes_type: &PathMap<BytesType>,
+        sizes: &mut HashMap<String, Option<usize>>,
+        large_fields: &mut BTreeMap<String, usize>,
+    ) -> usize {
+        if field.label() == Label::Repeated {
+            return match self.messages.get(field.type_name()) {
+                Some(entry) if is_map_entry(entry) => MAP_SIZE,
+                _ => VEC_SIZE,
+            };
+        }
+        let size = match field.r#type() {
+            Type::Bool => 1,
+            Type::Int32
+            | Type::Uint32
+            | Type::Sint32
+            | Type::Fixed32
+            | Type::Sfixed32
+            | Type::Float
+            | Type::Enum => 4,
+            Type::Int64
+            | Type::Uint64
+            | Type::Sint64
+            | Type::Fixed64
+            | Type::Sfixed64
+            | Type::Double => 8,
+            // `String`, `Vec<u8>` and `Bytes` have a niche for the `None` variant.
+            Type::String => return VEC_SIZE,
+            Type::Bytes => {
+                return match bytes_type.get_first_field(fq_message_name, field.name()) {
+                    Some(BytesType::Bytes) => BYTES_SIZE,
+                    _ => VEC_SIZE,
+                }
+            }
+            Type::Message | Type::Group => {
+                if self.is_nested(field.type_name(), fq_message_name)
+                    || self
+                        .boxed
+                        .get_first_field(config_path, field.name())
+                        .is_some()
+                {
+                    return POINTER_SIZE;
+                }
+                let size = self.message_size(
+                    field.type_name(),
+                    threshold,
+                    bytes_type,
+                    sizes,
+                    large_fields,
+                );
+                if size > threshold {
+                    large_fields.insert(format!("{}.{}", fq_message_name, field.name()), size);
+                    return POINTER_SIZE;
+                }
+                // Message fields outside of oneofs are always wrapped in an `Option`.
+                return if in_struct { align(size + 1) } else { size };
+            }
+        };
+        let optional = field.proto3_optional()
+            || (in_struct
+                && field.label() == Label::Optional
+                && !self.proto3.contains(fq_message_name));
+        if optional {
+            // The discriminant of an `Option` takes as much space as the alignment of the value.
+            size * 2
+        } else {
+            size
+        }
+    }
+}
+
+/// Adds the types reachable from a message and its nested types to `edges`.
+fn add_message_edges(
+    scope: &str,
+    parent: Option<&str>,
+    message: &DescriptorProto,
+    edges: &mut HashMap<String, Vec<String>>,
+) {
+    let name = format!("{}.{}", scope, message.name());
+    let targets = message
+        .field
+        .iter()
+        .filter(|field| matches!(field.r#type(), Type::Message | Type::Group | Type::Enum))
+        .map(|field| field.type_name().to_string())
+        .chain(parent.map(str::to_string))
+        .collect();
+    edges.insert(name.clone(), targets);
+    for nested in &message.nested_type {
+        add_message_edges(&name, Some(&name), nested, edges);
+    }
+    for enum_type in &message.enum_type {
+        add_enum_edges(&name, Some(&name), enum_type, edges);
+    }
+}
+
+/// Adds the types reachable from an enum to `edges`.
+fn add_enum_edges(
+    scope: &str,
+    parent: Option<&str>,
+    enum_type: &EnumDescriptorProto,
+    edges: &mut HashMap<String, Vec<String>>,
+) {
+    edges.insert(
+        format!("{}.{}", scope, enum_type.name()),
+        parent.map(str::to_string).into_iter().collect(),
+    );
+}
+
+// The sizes below are those of 64-bit targets, whatever the target of the build, so that the
+// fields which are boxed, and so the generated API, don't depend on the target.
+
+/// The size of a pointer on 64-bit targets.
+const POINTER_SIZE: usize = 8;
+/// The size of `Vec` and `String`.
+const VEC_SIZE: usize = 24;
+/// The size of `bytes::Bytes`.
+const BYTES_SIZE: usize = 32;
+/// The size of `HashMap`, which is larger than `BTreeMap`.
+const MAP_SIZE: usize = 48;
+
+/// Rounds a size up to the alignment of a pointer.
+fn align(size: usize) -> usize {
+    (size + POINTER_SIZE - 1) / POINTER_SIZE * POINTER_SIZE
+}
+
+fn is_map_entry(message: &DescriptorProto) -> bool {
+    message
+        .options
+        .as_ref()
+        .and_then(|options| options.map_entry)
+        .unwrap_or(false)
 }
@@ -0,0 +1,971 @@
+//! Naming of the Rust items generated for Protobuf definitions.
+
+use std::collections::HashMap;
+
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+};
+
+use crate::code_generator::is_optional;
+use crate::extern_paths::ExternPaths;
+use crate::ident::{sanitize_identifier, strip_enum_prefix, to_snake, to_upper_camel};
+use crate::Config;
+use crate::Error;
+use crate::Module;
+
+/// A naming strategy decides the names of the Rust items generated for Protobuf definitions.
+///
+/// Each method takes the name of a Protobuf element, as written in the `.proto` file, and
+/// returns the name of the generated Rust item. The default implementations return the names
+/// `prost-build` uses by default, so an implementation only overrides the names it changes.
+/// Names which are Rust keywords are escaped, like `r#type`, after the strategy is applied.
+///
+/// A naming strategy is registered with [`Config::naming_strategy`](crate::Config::naming_strategy).
+/// Individual items are renamed with [`Config::rename`](crate::Config::rename), which takes
+/// precedence over the naming strategy.
+///
+/// # Example
+///
+/// ```rust
+/// use prost_build::NamingStrategy;
+///
+/// /// Names enum variants like the Protobuf enum values.
+/// struct ScreamingVariants;
+///
+/// impl NamingStrategy for ScreamingVariants {
+///     fn enum_variant_name(&self, name: &str) -> String {
+///         name.to_string()
+///     }
+/// }
+///
+/// # let mut config = prost_build::Config::new();
+/// config.naming_strategy(Box::new(ScreamingVariants)).retain_enum_prefix();
+/// ```
+pub trait NamingStrategy {
+    /// Returns the name of the struct generated for a message.
+    fn message_name(&self, name: &str) -> String {
+        to_upper_camel(name)
+    }
+
+    /// Returns the name of the struct field generated for a message field or a oneof.
+    fn field_name(&self, name: &str) -> String {
+        to_snake(name)
+    }
+
+    /// Returns the name of the enum generated for an enumeration.
+    fn enum_name(&self, name: &str) -> String {
+        to_upper_camel(name)
+    }
+
+    /// Returns the name of the variant generated for an enumeration value.
+    ///
+    /// Unless [`Config::retain_enum_prefix`](crate::Config::retain_enum_prefix) is set, the name
+    /// of the enum is then stripped from the start of the variant name.
+    fn enum_variant_name(&self, name: &str) -> String {
+        to_upper_camel(name)
+    }
+
+    /// Returns the name of the enum generated for a oneof.
+    fn oneof_name(&self, name: &str) -> String {
+        to_upper_camel(name)
+    }
+
+    /// Returns the name of the variant generated for a field of a oneof.
+    fn oneof_variant_name(&self, name: &str) -> String {
+        to_upper_camel(name)
+    }
+
+    /// Returns the name of the module generated for a package component, or for the nested
+    /// types of a message.
+    fn module_name(&self, name: &str) -> String {
+        to_snake(name)
+    }
+}
+
+/// The default naming strategy.
+struct DefaultNaming;
+
+impl NamingStrategy for DefaultNaming {}
+
+/// The names configured with `Config::rename`, `Config::rename_module` and
+/// `Config::rename_oneof_field`, by fully-qualified Protobuf path.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Renames {
+    /// Packages, messages, fields, oneofs, enums and enum values.
+    pub(crate) items: HashMap<String, String>,
+    /// The modules of the nested types of messages.
+    pub(crate) modules: HashMap<String, String>,
+    /// The struct fields of oneofs.
+    pub(crate) oneof_fields: HashMap<String, String>,
+}
+
+/// A naming strategy with the configured renames.
+#[derive(Clone, Copy)]
+pub(crate) struct Naming<'a> {
+    strategy: &'a dyn NamingStrategy,
+    renames: &'a Renames,
+    strip_enum_prefix: bool,
+}
+
+impl<'a> Naming<'a> {
+    pub(crate) fn new(
+        strategy: Option<&'a dyn NamingStrategy>,
+        renames: &'a Renames,
+        strip_enum_prefix: bool,
+    ) -> Naming<'a> {
+        Naming {
+            strategy: strategy.unwrap_or(&DefaultNaming),
+            renames,
+            strip_enum_prefix,
+        }
+    }
+
+    /// Returns the module for a Protobuf package.
+    pub(crate) fn package_module(&self, package: &str) -> Module {
+        let mut path = String::new();
+        Module::from_parts(
+            package
+                .split('.')
+                .filter(|s| !s.is_empty())
+                .map(|component| {
+                    path.push('.');
+                    path.push_str(component);
+                    self.name(&path, || self.strategy.module_name(component))
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Returns the rename of the item at `path`, or else the name given by the strategy.
+    fn name(&self, path: &str, strategy: impl FnOnce() -> String) -> String {
+        renamed(&self.renames.items, path, strategy)
+    }
+}
+
+/// Returns the name of the item at `path` in `renames`, or else the name given by the strategy.
+fn renamed(
+    renames: &HashMap<String, String>,
+    path: &str,
+    strategy: impl FnOnce() -> String,
+) -> String {
+    match renames.get(path) {
+        Some(name) => sanitize_identifier(name),
+        None => sanitize_identifier(strategy()),
+    }
+}
+
+/// The names of the items generated for a set of files, by fully-qualified Protobuf name.
+#[derive(Debug, Default)]
+pub(crate) struct Names {
+    /// Messages, enums and oneofs.
+    types: HashMap<String, String>,
+    /// Package components, and the nested types of messages.
+    modules: HashMap<String, String>,
+    /// Message fields and oneofs.
+    fields: HashMap<String, String>,
+    /// Enum values and oneof fields.
+    variants: HashMap<String, String>,
+}
+
+impl Names {
+    /// Names the items generated for `files`, including the builders and the accessor methods of
+    /// the messages for which `config` generates them.
+    ///
+    /// Returns an error if two items in the same namespace would have the same name.
+    pub(crate) fn new<'a>(
+        files: impl IntoIterator<Item = &'a FileDescriptorProto>,
+        naming: Naming<'_>,
+        extern_paths: &ExternPaths,
+        config: &Config,
+    ) -> Result<Names, Error> {
+        let files = files.into_iter().collect::<Vec<_>>();
+        Names::build(&files, naming, extern_paths, config).map_err(|error| error.locate(files))
+    }
+
+    fn build(
+        files: &[&FileDescriptorProto],
+        naming: Naming<'_>,
+        extern_paths: &ExternPaths,
+        config: &Config,
+    ) -> Result<Names, Error> {
+        let mut builder = Builder {
+            naming,
+            extern_paths,
+            config,
+            proto2: false,
+            names: Names::default(),
+            namespaces: HashMap::new(),
+        };
+        for file in files {
+            builder.proto2 = file.syntax() != "proto3";
+            let package = match file.package() {
+                "" => String::new(),
+                package => format!(".{}", package),
+            };
+            let module = naming.package_module(&package);
+            let mut path = String::new();
+            for (component, name) in package.split('.').skip(1).zip(module.parts()) {
+                builder.claim(
+                    &types_namespace(&path),
+                    name,
+                    &format!("{}.{}", path, component),
+                )?;
+                path.push('.');
+                path.push_str(component);
+                builder.names.modules.insert(path.clone(), name.to_string());
+            }
+            for message in &file.message_type {
+                builder.add_message(&package, message)?;
+            }
+            for enum_type in &file.enum_type {
+                builder.add_enum(&package, enum_type)?;
+            }
+        }
+        Ok(builder.names)
+    }
+
+    /// Returns the name of a message, enum or oneof, if it is generated.
+    pub(crate) fn find_type(&self, fq_name: &str) -> Option<&str> {
+        self.types.get(fq_name).map(String::as_str)
+    }
+
+    /// Returns the name of the module of a package component or message, if it is generated.
+    pub(crate) fn find_module(&self, fq_name: &str) -> Option<&str> {
+        self.modules.get(fq_name).map(String::as_str)
+    }
+
+    /// Returns the name of an enum value, if it is generated.
+    pub(crate) fn find_variant(&self, fq_name: &str) -> Option<&str> {
+        self.variants.get(fq_name).map(String::as_str)
+    }
+
+    /// Returns the name of a generated message, enum or oneof.
+    pub(crate) fn type_name(&self, fq_name: &str) -> Result<&str, Error> {
+        lookup(&self.types, fq_name)
+    }
+
+    /// Returns the name of the module of a generated message.
+    pub(crate) fn module(&self, fq_name: &str) -> Result<&str, Error> {
+        lookup(&self.modules, fq_name)
+    }
+
+    /// Returns the name of a generated message field or oneof.
+    pub(crate) fn field(&self, fq_name: &str) -> Result<&str, Error> {
+        lookup(&self.fields, fq_name)
+    }
+
+    /// Returns the name of a generated enum value or oneof field.
+    pub(crate) fn variant(&self, fq_name: &str) -> Result<&str, Error> {
+        lookup(&self.variants, fq_name)
+    }
+}
+
+fn lookup<'a>(names: &'a HashMap<String, String>, fq_name: &str) -> Result<&'a str, Error> {
+    names.get(fq_name).map(String::as_str).ok_or_else(|| {
+        Error::new(format!(
+            "no name was generated for `{}`",
+            fq_name.trim_start_matches('.')
+        ))
+        .with_element(fq_name)
+    })
+}
+
+struct Builder<'a> {
+    naming: Naming<'a>,
+    extern_paths: &'a ExternPaths,
+    config: &'a Config,
+    /// Whether the file being named uses the `proto2` syntax.
+    proto2: bool,
+    names: Names,
+    /// The names used in each namespace, with the Protobuf element using them.
+    namespaces: HashMap<String, HashMap<String, String>>,
+}
+
+impl Builder<'_> {
+    fn add_message(&mut self, scope: &str, message: &DescriptorProto) -> Result<(), Error> {
+        let fq_name = format!("{}.{}", scope, message.name());
+        if self.is_map_entry(message) || self.extern_paths.resolve_ident(&fq_name).is_some() {
+            return Ok(());
+        }
+
+        let naming = self.naming;
+        let strategy = naming.strategy;
+        let name = naming.name(&fq_name, || strategy.message_name(message.name()));
+        self.claim(&types_namespace(scope), &name, &fq_name)?;
+        if self.config.builders.get(&fq_name).next().is_some() {
+            // The builder of a message is generated next to it.
+            self.claim(
+                &types_namespace(scope),
+                &format!("{}Builder", name),
+                &fq_name,
+            )?;
+        }
+        self.names.types.insert(fq_name.clone(), name);
+        let module = renamed(&naming.renames.modules, &fq_name, || {
+            strategy.module_name(message.name())
+        });
+        if self.has_module(message) {
+            // The module is generated next to the message, in the same namespace as the types.
+            self.claim_as(&types_namespace(scope), &module, &fq_name, "rename_module")?;
+        }
+        self.names.modules.insert(fq_name.clone(), module);
+
+        let fields_namespace = format!("fields of `{}`", &fq_name[1..]);
+        for field in &message.field {
+            let fq_field = format!("{}.{}", fq_name, field.name());
+            let is_oneof_field =
+                field.oneof_index.is_some() && !field.proto3_optional.unwrap_or(false);
+            if is_oneof_field {
+                let oneof = &message.oneof_decl[field.oneof_index() as usize];
+                let variant = naming.name(&fq_field, || strategy.oneof_variant_name(field.name()));
+                self.claim(
+                    &format!("variants of `{}.{}`", &fq_name[1..], oneof.name()),
+                    &variant,
+                    &fq_field,
+                )?;
+                self.names.variants.insert(fq_field.clone(), variant);
+                // The field name of a oneof field names its builder methods.
+                let field_name = sanitize_identifier(strategy.field_name(field.name()));
+                self.names.fields.insert(fq_field, field_name);
+            } else {
+                let field_name = naming.name(&fq_field, || strategy.field_name(field.name()));
+                self.claim(&fields_namespace, &field_name, &fq_field)?;
+                self.names.fields.insert(fq_field, field_name);
+            }
+        }
+
+        for (idx, oneof) in message.oneof_decl.iter().enumerate() {
+            let synthetic = message
+                .field
+                .iter()
+                .filter(|field| field.oneof_index == Some(idx as i32))
+                .all(|field| field.proto3_optional.unwrap_or(false));
+            if synthetic {
+                continue;
+            }
+            let fq_oneof = format!("{}.{}", fq_name, oneof.name());
+            let field_name = renamed(&naming.renames.oneof_fields, &fq_oneof, || {
+                strategy.field_name(oneof.name())
+            });
+            self.claim_as(
+                &fields_namespace,
+                &field_name,
+                &fq_oneof,
+                "rename_oneof_field",
+            )?;
+            self.names.fields.insert(fq_oneof.clone(), field_name);
+            let type_name = naming.name(&fq_oneof, || strategy.oneof_name(oneof.name()));
+            self.claim(&types_namespace(&fq_name), &type_name, &fq_oneof)?;
+            self.names.types.insert(fq_oneof, type_name);
+        }
+
+        if self.config.accessors.get(&fq_name).next().is_some() {
+            self.add_accessors(&fq_name, message)?;
+        }
+
+        for nested in &message.nested_type {
+            self.add_message(&fq_name, nested)?;
+        }
+        for enum_type in &message.enum_type {
+            self.add_enum(&fq_name, enum_type)?;
+        }
+        Ok(())
+    }
+
+    fn add_enum(&mut self, scope: &str, enum_type: &EnumDescriptorProto) -> Result<(), Error> {
+        let fq_name = format!("{}.{}", scope, enum_type.name());
+        if self.extern_paths.resolve_ident(&fq_name).is_some() {
+            return Ok(());
+        }
+
+        let naming = self.naming;
+        let strategy = naming.strategy;
+        let name = naming.name(&fq_name, || strategy.enum_name(enum_type.name()));
+        self.claim(&types_namespace(scope), &name, &fq_name)?;
+
+        let variants_namespace = format!("variants of `{}`", &fq_name[1..]);
+        let mut numbers = Vec::new();
+        for value in &enum_type.value {
+            // Aliases of a value, which are allowed with the `allow_alias` option, don't have a
+            // variant.
+            if numbers.contains(&value.number()) {
+                continue;
+            }
+            numbers.push(value.number());
+
+            let fq_value = format!("{}.{}", fq_name, value.name());
+            let variant = match naming.renames.items.get(&fq_value) {
+                Some(variant) => sanitize_identifier(variant),
+                None if naming.strip_enum_prefix => {
+                    strip_enum_prefix(&name, &strategy.enum_variant_name(value.name()))
+                }
+                None => sanitize_identifier(strategy.enum_variant_name(value.name())),
+            };
+            self.claim(&variants_namespace, &variant, &fq_value)?;
+            self.names.variants.insert(fq_value, variant);
+        }
+
+        self.names.types.insert(fq_name, name);
+        Ok(())
+    }
+
+    /// Claims the names of the accessor methods of a message, along with the getters which
+    /// `prost-derive` generates in the same namespace.
+    fn add_accessors(&mut self, fq_name: &str, message: &DescriptorProto) -> Result<(), Error> {
+        let namespace = format!("methods of `{}`", &fq_name[1..]);
+        for field in &message.field {
+            let fq_field = format!("{}.{}", fq_name, field.name());
+            let name = self.names.fields[&fq_field].clone();
+            // `prost-derive` names the accessors of a member of a oneof after its variant.
+            let member = field.oneof_index.is_some() && !field.proto3_optional.unwrap_or(false);
+            let (base, help) = if member {
+                let variant = &self.names.variants[&fq_field];
+                (
+                    variant_snake(variant),
+                    rename_help("rename", &fq_field, variant),
+                )
+            } else {
+                let base = name.trim_start_matches("r#").to_string();
+                let help = rename_help("rename", &fq_field, &base);
+                (base, help)
+            };
+            let prefixed = |prefixes: &[&str]| {
+                prefixes
+                    .iter()
+                    .map(|prefix| format!("{}{}", prefix, base))
+                    .collect::<Vec<_>>()
+            };
+            let mut methods = Vec::new();
+            if member {
+                methods.extend(prefixed(&["is_", "as_", "set_", "take_"]));
+                methods.push(format!("as_{}_mut", base));
+            } else if let Some(value) = self.map_value(fq_name, message, field) {
+                if value.r#type() == Type::Enum {
+                    methods.extend(prefixed(&["get_", "insert_"]));
+                }
+            } else {
+                let optional = is_optional(self.config, field, fq_name, self.proto2);
+                if field.r#type() == Type::Enum {
+                    methods.push(name.clone());
+                    if field.label() == Label::Repeated {
+                        methods.extend(prefixed(&["push_"]));
+                    } else {
+                        methods.extend(prefixed(&["set_"]));
+                    }
+                } else if optional && !matches!(field.r#type(), Type::Message | Type::Group) {
+                    methods.push(name.clone());
+                }
+                if optional {
+                    methods.extend(prefixed(&["has_", "clear_", "take_"]));
+                }
+            }
+            for method in methods {
+                self.claim_with_help(&namespace, &method, &fq_field, &help)?;
+            }
+        }
+
+        for (idx, oneof) in message.oneof_decl.iter().enumerate() {
+            let synthetic = message
+                .field
+                .iter()
+                .filter(|field| field.oneof_index == Some(idx as i32))
+                .all(|field| field.proto3_optional.unwrap_or(false));
+            if synthetic {
+                continue;
+            }
+            let fq_oneof = format!("{}.{}", fq_name, oneof.name());
+            let name = self.names.fields[&fq_oneof].clone();
+            let base = name.trim_start_matches("r#");
+            let help = rename_help("rename_oneof_field", &fq_oneof, base);
+            for prefix in ["which_", "has_", "clear_", "take_"] {
+                let method = format!("{}{}", prefix, base);
+                self.claim_with_help(&namespace, &method, &fq_oneof, &help)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the value field of a map field.
+    fn map_value<'m>(
+        &self,
+        fq_name: &str,
+        message: &'m DescriptorProto,
+        field: &FieldDescriptorProto,
+    ) -> Option<&'m FieldDescriptorProto> {
+        if field.label() != Label::Repeated || field.r#type() != Type::Message {
+            return None;
+        }
+        message
+            .nested_type
+            .iter()
+            .find(|nested| {
+                self.is_map_entry(nested)
+                    && field.type_name() == format!("{}.{}", fq_name, nested.name())
+            })
+            .and_then(|entry| entry.field.get(1))
+    }
+
+    fn is_map_entry(&self, message: &DescriptorProto) -> bool {
+        message
+            .options
+            .as_ref()
+            .and_then(|options| options.map_entry)
+            .unwrap_or(false)
+    }
+
+    /// Returns whether a module is generated for the nested types of a message.
+    fn has_module(&self, message: &DescriptorProto) -> bool {
+        !message.enum_type.is_empty()
+            || message
+                .nested_type
+                .iter()
+                .any(|nested| !self.is_map_entry(nested))
+            || message.oneof_decl.iter().enumerate().any(|(idx, _)| {
+                message.field.iter().any(|field| {
+                    field.oneof_index == Some(idx as i32) && !field.proto3_optional.unwrap_or(false)
+                })
+            })
+    }
+
+    /// Records that the Protobuf element at `fq_name` uses `name` in `namespace`.
+    fn claim(&mut self, namespace: &str, name: &str, fq_name: &str) -> Result<(), Error> {
+        self.claim_as(namespace, name, fq_name, "rename")
+    }
+
+    /// Records that the Protobuf element at `fq_name` uses `name` in `namespace`, suggesting to
+    /// rename it with the `Config` method `method` on a collision.
+    fn claim_as(
+        &mut self,
+        namespace: &str,
+        name: &str,
+        fq_name: &str,
+        method: &str,
+    ) -> Result<(), Error> {
+        let help = rename_help(method, fq_name, name.trim_start_matches("r#"));
+        self.claim_with_help(namespace, name, fq_name, &help)
+    }
+
+    /// Records that the Protobuf element at `fq_name` uses `name` in `namespace`, suggesting
+    /// `help` on a collision. An element may claim the same name again, like a package declared
+    /// by several files.
+    fn claim_with_help(
+        &mut self,
+        namespace: &str,
+        name: &str,
+        fq_name: &str,
+        help: &str,
+    ) -> Result<(), Error> {
+        let names = self.namespaces.entry(namespace.to_string()).or_default();
+        match names.get(name) {
+            Some(other) if other == fq_name => Ok(()),
+            Some(other) => Err(Error::new(format!(
+                "`{}` and `{}` both generate `{}` among the {}",
+                &other[1..],
+                &fq_name[1..],
+                name,
+                namespace
+            ))
+            .with_element(fq_name)
+            .with_help(help)),
+            None => {
+                names.insert(name.to_string(), fq_name.to_string());
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Returns the help for a collision, suggesting to rename the element at `fq_name` from `name`
+/// with the `Config` method `method`.
+fn rename_help(method: &str, fq_name: &str, name: &str) -> String {
+    format!(
+        "use `Config::{method}` to rename one of them, e.g. `config.{method}(\"{}\", \"{}2\")`",
+        fq_name, name
+    )
+}
+
+/// Returns the snake case name which `prost-derive` gives the accessors of a oneof variant, like
+/// `foo_bar` for `FooBar`.
+fn variant_snake(variant: &str) -> String {
+    let mut name = String::new();
+    for (i, ch) in variant.trim_start_matches("r#").char_indices() {
+        if ch.is_uppercase() {
+            if i > 0 {
+                name.push('_');
+            }
+            name.extend(ch.to_lowercase());
+        } else {
+            name.push(ch);
+        }
+    }
+    name
+}
+
+/// Returns the namespace of the types in a package or message.
+fn types_namespace(scope: &str) -> String {
+    match scope {
+        "" => "types of the root module".to_string(),
+        scope => format!("types in `{}`", &scope[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use prost_types::{EnumValueDescriptorProto, OneofDescriptorProto};
+
+    fn field(name: &str, oneof_index: Option<i32>) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            oneof_index,
+            ..Default::default()
+        }
+    }
+
+    fn file() -> FileDescriptorProto {
+        FileDescriptorProto {
+            name: Some("shapes.proto".to_string()),
+            package: Some("shapes.v1".to_string()),
+            message_type: vec![DescriptorProto {
+                name: Some("Shape".to_string()),
+                field: vec![field("name", None), field("radius", Some(0))],
+                nested_type: vec![DescriptorProto {
+                    name: Some("Kind".to_string()),
+                    ..Default::default()
+                }],
+                oneof_decl: vec![OneofDescriptorProto {
+                    name: Some("kind".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            enum_type: vec![EnumDescriptorProto {
+                name: Some("Color".to_string()),
+                value: ["COLOR_UNSPECIFIED", "COLOR_GREY", "GREY"]
+                    .iter()
+                    .zip(0..)
+                    .map(|(name, number)| EnumValueDescriptorProto {
+                        name: Some(name.to_string()),
+                        number: Some(number),
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn names(renames: &[(&str, &str)]) -> Result<Names, Error> {
+        let renames = Renames {
+            items: renames
+                .iter()
+                .map(|(path, name)| (path.to_string(), name.to_string()))
+                .collect(),
+            ..Default::default()
+        };
+        let extern_paths = ExternPaths::new(&[], true, false).unwrap();
+        Names::new(
+            &[file()],
+            Naming::new(None, &renames, true),
+            &extern_paths,
+            &Config::new(),
+        )
+    }
+
+    #[test]
+    fn collisions_are_errors() {
+        assert_eq!(
+            names(&[]).unwrap_err().to_string(),
+            "shapes.proto: `shapes.v1.Shape.kind` and `shapes.v1.Shape.Kind` both generate `Kind` among the \
+             types in `shapes.v1.Shape`\nhelp: use `Config::rename` to rename one of them, e.g. \
+             `config.rename(\".shapes.v1.Shape.Kind\", \"Kind2\")`"
+        );
+        let error = names(&[(".shapes.v1.Shape.kind", "ShapeKind")]).unwrap_err();
+        assert_eq!(
+            error.message(),
+            "`shapes.v1.Color.COLOR_GREY` and `shapes.v1.Color.GREY` both generate `Grey` among \
+             the variants of `shapes.v1.Color`"
+        );
+        assert_eq!(error.element(), Some("shapes.v1.Color.GREY"));
+        assert_eq!(error.file(), Some("shapes.proto"));
+    }
+
+    #[test]
+    fn builder_collisions() {
+        let file = FileDescriptorProto {
+            name: Some("points.proto".to_string()),
+            package: Some("points".to_string()),
+            message_type: ["Point", "PointBuilder"]
+                .iter()
+                .map(|name| DescriptorProto {
+                    name: Some(name.to_string()),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let renames = Renames::default();
+        let extern_paths = ExternPaths::new(&[], true, false).unwrap();
+        let names = |builders: &[&str]| {
+            Names::new(
+                [&file],
+                Naming::new(None, &renames, true),
+                &extern_paths,
+                Config::new().builders(builders),
+            )
+        };
+
+        assert!(names(&[".points.PointBuilder"]).is_ok());
+        let error = names(&["."]).unwrap_err();
+        assert_eq!(
+            error.message(),
+            "`points.Point` and `points.PointBuilder` both generate `PointBuilder` among the \
+             types in `points`"
+        );
+        assert_eq!(error.element(), Some("points.PointBuilder"));
+    }
+
+    #[test]
+    fn accessor_collisions() {
+        let file = FileDescriptorProto {
+            name: Some("shapes.proto".to_string()),
+            package: Some("shapes".to_string()),
+            message_type: vec![
+                DescriptorProto {
+                    name: Some("Shape".to_string()),
+                    field: vec![field("corner", Some(0)), field("corner_mut", Some(0))],
+                    oneof_decl: vec![OneofDescriptorProto {
+                        name: Some("kind".to_string()),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+                DescriptorProto {
+                    name: Some("Text".to_string()),
+                    field: vec![
+                        FieldDescriptorProto {
+                            label: Some(Label::Optional as i32),
+                            r#type: Some(Type::String as i32),
+                            ..field("label", None)
+                        },
+                        FieldDescriptorProto {
+                            label: Some(Label::Optional as i32),
+                            r#type: Some(Type::Enum as i32),
+                            type_name: Some(".shapes.Color".to_string()),
+                            ..field("has_label", None)
+                        },
+                    ],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let renames = Renames::default();
+        let extern_paths = ExternPaths::new(&[], true, false).unwrap();
+        let names = |renames: &Renames, accessors: &[&str]| {
+            Names::new(
+                [&file],
+                Naming::new(None, renames, true),
+                &extern_paths,
+                Config::new().accessors(accessors),
+            )
+        };
+
+        assert!(names(&renames, &[]).is_ok());
+        let error = names(&renames, &["."]).unwrap_err();
+        assert_eq!(
+            error.message(),
+            "`shapes.Shape.corner` and `shapes.Shape.corner_mut` both generate `as_corner_mut` \
+             among the methods of `shapes.Shape`"
+        );
+        assert_eq!(error.element(), Some("shapes.Shape.corner_mut"));
+        assert_eq!(
+            error.help(),
+            Some(
+                "use `Config::rename` to rename one of them, e.g. \
+                 `config.rename(\".shapes.Shape.corner_mut\", \"CornerMut2\")`"
+            )
+        );
+        // The accessors of the members of a oneof are named after their variants.
+        let renamed = Renames {
+            items: [(".shapes.Shape.corner_mut".to_string(), "Edge".to_string())]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        assert!(names(&renamed, &[".shapes.Shape"]).is_ok());
+
+        // The getter of an enumeration field is also a method of the message.
+        let error = names(&renames, &[".shapes.Text"]).unwrap_err();
+        assert_eq!(
+            error.message(),
+            "`shapes.Text.label` and `shapes.Text.has_label` both generate `has_label` among \
+             the methods of `shapes.Text`"
+        );
+        assert_eq!(
+            error.help(),
+            Some(
+                "use `Config::rename` to rename one of them, e.g. \
+                 `config.rename(\".shapes.Text.has_label\", \"has_label2\")`"
+            )
+        );
+        let renames = Renames {
+            items: [(".shapes.Text.label".to_string(), "text".to_string())]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        assert!(names(&renames, &[".shapes.Text"]).is_ok());
+    }
+
+    #[test]
+    fn renames() {
+        let names = names(&[
+            (".shapes.v1.Shape.kind", "ShapeKind"),
+            (".shapes.v1.Color.GREY", "Gray"),
+            (".shapes.v1.Shape.name", "type"),
+            (".shapes", "geometry"),
+        ])
+        .unwrap();
+        assert_eq!(names.type_name(".shapes.v1.Shape"), Ok("Shape"));
+        assert_eq!(names.type_name(".shapes.v1.Shape.kind"), Ok("ShapeKind"));
+        assert_eq!(names.type_name(".shapes.v1.Shape.Kind"), Ok("Kind"));
+        assert_eq!(names.module(".shapes.v1.Shape"), Ok("shape"));
+        assert_eq!(names.find_module(".shapes"), Some("geometry"));
+        assert_eq!(names.find_module(".shapes.v1"), Some("v1"));
+        assert_eq!(names.field(".shapes.v1.Shape.name"), Ok("r#type"));
+        assert_eq!(names.field(".shapes.v1.Shape.kind"), Ok("kind"));
+        assert_eq!(names.variant(".shapes.v1.Shape.radius"), Ok("Radius"));
+        assert_eq!(names.variant(".shapes.v1.Color.COLOR_GREY"), Ok("Grey"));
+        assert_eq!(names.variant(".shapes.v1.Color.GREY"), Ok("Gray"));
+    }
+
+    #[test]
+    fn module_collisions() {
+        let message = |name: &str, nested_type| DescriptorProto {
+            name: Some(name.to_string()),
+            nested_type,
+            ..Default::default()
+        };
+        let file = |name: &str, package: &str, message_type| FileDescriptorProto {
+            name: Some(name.to_string()),
+            package: Some(package.to_string()),
+            message_type,
+            ..Default::default()
+        };
+        let files = [
+            file("foo.proto", "a.foo", vec![message("Baz", Vec::new())]),
+            file(
+                "a.proto",
+                "a",
+                vec![message("Foo", vec![message("Bar", Vec::new())])],
+            ),
+        ];
+        let extern_paths = ExternPaths::new(&[], true, false).unwrap();
+        let names = |renames: &Renames| {
+            Names::new(
+                &files,
+                Naming::new(None, renames, true),
+                &extern_paths,
+                &Config::new(),
+            )
+        };
+
+        let error = names(&Renames::default()).unwrap_err();
+        assert_eq!(
+            error.message(),
+            "`a.foo` and `a.Foo` both generate `foo` among the types in `a`"
+        );
+        assert_eq!(
+            error.help(),
+            Some(
+                "use `Config::rename_module` to rename one of them, e.g. \
+                 `config.rename_module(\".a.Foo\", \"foo2\")`"
+            )
+        );
+
+        let renames = Renames {
+            modules: [(".a.Foo".to_string(), "foo_types".to_string())]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        let names = names(&renames).unwrap();
+        assert_eq!(names.module(".a.Foo"), Ok("foo_types"));
+        assert_eq!(names.find_module(".a.foo"), Some("foo"));
+        assert_eq!(
+            names.module(".a.Baz").unwrap_err().message(),
+            "no name was generated for `a.Baz`"
+        );
+    }
+
+    #[test]
+    fn oneof_field_renames() {
+        let renames = Renames {
+            oneof_fields: [(
+                ".shapes.v1.Shape.kind".to_string(),
+                "shape_kind".to_string(),
+            )]
+            .into_iter()
+            .collect(),
+            items: [
+                (".shapes.v1.Shape.kind", "ShapeKind"),
+                (".shapes.v1.Color.GREY", "Gray"),
+            ]
+            .iter()
+            .map(|(path, name)| (path.to_string(), name.to_string()))
+            .collect(),
+            ..Default::default()
+        };
+        let extern_paths = ExternPaths::new(&[], true, false).unwrap();
+        let names = Names::new(
+            &[file()],
+            Naming::new(None, &renames, true),
+            &extern_paths,
+            &Config::new(),
+        )
+        .unwrap();
+        assert_eq!(names.field(".shapes.v1.Shape.kind"), Ok("shape_kind"));
+        assert_eq!(names.type_name(".shapes.v1.Shape.kind"), Ok("ShapeKind"));
+    }
+
+    #[test]
+    fn naming_strategy() {
+        struct Verbatim;
+
+        impl NamingStrategy for Verbatim {
+            fn message_name(&self, name: &str) -> String {
+                format!("{}Message", name)
+            }
+
+            fn enum_variant_name(&self, name: &str) -> String {
+                name.to_string()
+            }
+
+            fn module_name(&self, name: &str) -> String {
+                name.to_string()
+            }
+        }
+
+        let renames = Renames::default();
+        let extern_paths = ExternPaths::new(&[], true, false).unwrap();
+        let names = Names::new(
+            &[file()],
+            Naming::new(Some(&Verbatim), &renames, false),
+            &extern_paths,
+            &Config::new(),
+        )
+        .unwrap();
+        assert_eq!(names.type_name(".shapes.v1.Shape"), Ok("ShapeMessage"));
+        assert_eq!(names.type_name(".shapes.v1.Shape.kind"), Ok("Kind"));
+        assert_eq!(names.module(".shapes.v1.Shape"), Ok("Shape"));
+        assert_eq!(
+            names.variant(".shapes.v1.Color.COLOR_GREY"),
+            Ok("COLOR_GREY")
+        );
+        assert_eq!(names.field(".shapes.v1.Shape.name"), Ok("name"));
+    }
+}
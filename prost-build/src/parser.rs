@@ -0,0 +1,547 @@
+//! A built-in alternative to `protoc` for producing file descriptor sets.
+//!
+//! The parser and linker follow the behavior of `protoc` closely, so that the encoded
+//! `FileDescriptorSet` is the same as the output of
+//! `protoc --include_imports [--include_source_info] -o <path>`.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use prost::encoding::{encode_key, encode_varint, WireType};
+
+mod linker;
+mod options;
+mod syntax;
+mod tokenizer;
+
+use linker::Linker;
+
+/// The well-known types which are distributed with `protoc`, used when an import can't be found
+/// in the include paths.
+const BUILTIN_INCLUDES: &[(&str, &str)] = &[
+    (
+        "google/protobuf/any.proto",
+        include_str!("parser/include/google/protobuf/any.proto"),
+    ),
+    (
+        "google/protobuf/api.proto",
+        include_str!("parser/include/google/protobuf/api.proto"),
+    ),
+    (
+        "google/protobuf/compiler/plugin.proto",
+        include_str!("parser/include/google/protobuf/compiler/plugin.proto"),
+    ),
+    (
+        "google/protobuf/descriptor.proto",
+        include_str!("parser/include/google/protobuf/descriptor.proto"),
+    ),
+    (
+        "google/protobuf/duration.proto",
+        include_str!("parser/include/google/protobuf/duration.proto"),
+    ),
+    (
+        "google/protobuf/empty.proto",
+        include_str!("parser/include/google/protobuf/empty.proto"),
+    ),
+    (
+        "google/protobuf/field_mask.proto",
+        include_str!("parser/include/google/protobuf/field_mask.proto"),
+    ),
+    (
+        "google/protobuf/source_context.proto",
+        include_str!("parser/include/google/protobuf/source_context.proto"),
+    ),
+    (
+        "google/protobuf/struct.proto",
+        include_str!("parser/include/google/protobuf/struct.proto"),
+    ),
+    (
+        "google/protobuf/timestamp.proto",
+        include_str!("parser/include/google/protobuf/timestamp.proto"),
+    ),
+    (
+        "google/protobuf/type.proto",
+        include_str!("parser/include/google/protobuf/type.proto"),
+    ),
+    (
+        "google/protobuf/wrappers.proto",
+        include_str!("parser/include/google/protobuf/wrappers.proto"),
+    ),
+];
+
+/// The file which defines the options messages, which is needed to interpret options.
+const DESCRIPTOR_PROTO: &str = "google/protobuf/descriptor.proto";
+
+/// Field numbers of the descriptor messages, used for source code info paths.
+mod tags {
+    pub(super) const UNINTERPRETED_OPTION: i32 = 999;
+
+    pub(super) mod file {
+        pub(crate) const PACKAGE: i32 = 2;
+        pub(crate) const DEPENDENCY: i32 = 3;
+        pub(crate) const MESSAGE_TYPE: i32 = 4;
+        pub(crate) const ENUM_TYPE: i32 = 5;
+        pub(crate) const SERVICE: i32 = 6;
+        pub(crate) const EXTENSION: i32 = 7;
+        pub(crate) const OPTIONS: i32 = 8;
+        pub(crate) const PUBLIC_DEPENDENCY: i32 = 10;
+        pub(crate) const WEAK_DEPENDENCY: i32 = 11;
+        pub(crate) const SYNTAX: i32 = 12;
+    }
+
+    pub(super) mod message {
+        pub(crate) const NAME: i32 = 1;
+        pub(crate) const FIELD: i32 = 2;
+        pub(crate) const NESTED_TYPE: i32 = 3;
+        pub(crate) const ENUM_TYPE: i32 = 4;
+        pub(crate) const EXTENSION_RANGE: i32 = 5;
+        pub(crate) const EXTENSION: i32 = 6;
+        pub(crate) const OPTIONS: i32 = 7;
+        pub(crate) const ONEOF_DECL: i32 = 8;
+        pub(crate) const RESERVED_RANGE: i32 = 9;
+        pub(crate) const RESERVED_NAME: i32 = 10;
+    }
+
+    pub(super) mod extension_range {
+        pub(crate) const OPTIONS: i32 = 3;
+    }
+
+    pub(super) mod range {
+        pub(crate) const START: i32 = 1;
+        pub(crate) const END: i32 = 2;
+    }
+
+    pub(super) mod field {
+        pub(crate) const NAME: i32 = 1;
+        pub(crate) const EXTENDEE: i32 = 2;
+        pub(crate) const NUMBER: i32 = 3;
+        pub(crate) const LABEL: i32 = 4;
+        pub(crate) const TYPE: i32 = 5;
+        pub(crate) const TYPE_NAME: i32 = 6;
+        pub(crate) const DEFAULT_VALUE: i32 = 7;
+        pub(crate) const OPTIONS: i32 = 8;
+        pub(crate) const JSON_NAME: i32 = 10;
+    }
+
+    pub(super) mod oneof {
+        pub(crate) const NAME: i32 = 1;
+        pub(crate) const OPTIONS: i32 = 2;
+    }
+
+    pub(super) mod enum_type {
+        pub(crate) const NAME: i32 = 1;
+        pub(crate) const VALUE: i32 = 2;
+        pub(crate) const OPTIONS: i32 = 3;
+        pub(crate) const RESERVED_RANGE: i32 = 4;
+        pub(crate) const RESERVED_NAME: i32 = 5;
+    }
+
+    pub(super) mod enum_value {
+        pub(crate) const NAME: i32 = 1;
+        pub(crate) const NUMBER: i32 = 2;
+        pub(crate) const OPTIONS: i32 = 3;
+    }
+
+    pub(super) mod service {
+        pub(crate) const NAME: i32 = 1;
+        pub(crate) const METHOD: i32 = 2;
+        pub(crate) const OPTIONS: i32 = 3;
+    }
+
+    pub(super) mod method {
+        pub(crate) const NAME: i32 = 1;
+        pub(crate) const INPUT_TYPE: i32 = 2;
+        pub(crate) const OUTPUT_TYPE: i32 = 3;
+        pub(crate) const OPTIONS: i32 = 4;
+        pub(crate) const CLIENT_STREAMING: i32 = 5;
+        pub(crate) const SERVER_STREAMING: i32 = 6;
+    }
+
+    pub(super) mod uninterpreted_option {
+        pub(crate) const NAME: i32 = 2;
+        pub(crate) const IDENTIFIER_VALUE: i32 = 3;
+        pub(crate) const POSITIVE_INT_VALUE: i32 = 4;
+        pub(crate) const NEGATIVE_INT_VALUE: i32 = 5;
+        pub(crate) const DOUBLE_VALUE: i32 = 6;
+        pub(crate) const STRING_VALUE: i32 = 7;
+        pub(crate) const AGGREGATE_VALUE: i32 = 8;
+    }
+
+    pub(super) mod name_part {
+        pub(crate) const NAME_PART: i32 = 1;
+    }
+}
+
+/// A syntax error in a `.proto` file, with a zero-based line and column.
+#[derive(Debug)]
+struct ParseError {
+    line: i32,
+    column: i32,
+    message: String,
+}
+
+impl ParseError {
+    fn new(line: i32, column: i32, message: impl Into<String>) -> ParseError {
+        ParseError {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line + 1, self.column + 1, self.message)
+    }
+}
+
+/// Parses and links `protos` and all of their imports, and returns the encoded
+/// `FileDescriptorSet`.
+pub(crate) fn parse(
+    protos: &[impl AsRef<Path>],
+    includes: &[PathBuf],
+    include_source_info: bool,
+) -> Result<Vec<u8>> {
+    let mut loader = Loader {
+        includes,
+        linker: Linker::default(),
+        loaded: HashMap::new(),
+        stack: Vec::new(),
+    };
+
+    // The options messages are needed to interpret options, even if the descriptor.proto is not
+    // imported.
+    loader.load(DESCRIPTOR_PROTO)?;
+
+    let mut files = Vec::new();
+    let mut seen = HashSet::new();
+    for proto in protos {
+        let name = virtual_name(proto.as_ref(), includes)?;
+        let file = loader.load(&name)?;
+        loader
+            .linker
+            .transitive_dependencies(file, &mut seen, &mut files);
+    }
+
+    let mut buf = Vec::new();
+    for file in files {
+        let file = loader.linker.encode(file, include_source_info);
+        encode_key(1, WireType::LengthDelimited, &mut buf);
+        encode_varint(file.len() as u64, &mut buf);
+        buf.extend_from_slice(&file);
+    }
+    Ok(buf)
+}
+
+/// Maps the path of a `.proto` file to its name relative to the first include which contains it.
+fn virtual_name(proto: &Path, includes: &[PathBuf]) -> Result<String> {
+    let canonical_proto = fs::canonicalize(proto).ok();
+    for include in includes {
+        let relative = proto.strip_prefix(include).ok().or_else(|| {
+            let include = fs::canonicalize(include).ok()?;
+            canonical_proto.as_deref()?.strip_prefix(include).ok()
+        });
+        if let Some(relative) = relative {
+            let components = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>();
+            return Ok(components.join("/"));
+        }
+    }
+
+    Err(Error::new(
+        ErrorKind::NotFound,
+        format!(
+            "{}: File does not reside within any path specified using --proto_path (or -I).  \
+             You must specify a --proto_path which encompasses this file.",
+            proto.display()
+        ),
+    ))
+}
+
+struct Loader<'a> {
+    includes: &'a [PathBuf],
+    linker: Linker,
+    /// The index in the linker of each loaded file.
+    loaded: HashMap<String, usize>,
+    /// The files which are currently being loaded, to detect import cycles.
+    stack: Vec<String>,
+}
+
+impl Loader<'_> {
+    fn load(&mut self, name: &str) -> Result<usize> {
+        if let Some(&index) = self.loaded.get(name) {
+            return Ok(index);
+        }
+
+        if let Some(position) = self.stack.iter().position(|file| file == name) {
+            let mut cycle = self.stack[position..].to_vec();
+            cycle.push(name.to_string());
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "{}: File recursively imports itself: {}",
+                    name,
+                    cycle.join(" -> ")
+                ),
+            ));
+        }
+
+        let source = self.read(name)?;
+        let mut file = syntax::parse(&source)
+            .map_err(|error| Error::new(ErrorKind::InvalidInput, format!("{}:{}", name, error)))?;
+        file.name = Some(name.to_string());
+
+        self.stack.push(name.to_string());
+        for dependency in &file.dependency {
+            self.load(dependency).map_err(|error| {
+                Error::new(
+                    error.kind(),
+                    format!(
+                        "{}\n{}: Import \"{}\" was not found or had errors.",
+                        error, name, dependency
+                    ),
+                )
+            })?;
+        }
+        self.stack.pop();
+
+        let index = self
+            .linker
+            .add_file(file)
+            .map_err(|error| Error::new(ErrorKind::InvalidInput, format!("{}: {}", name, error)))?;
+        self.loaded.insert(name.to_string(), index);
+        Ok(index)
+    }
+
+    fn read(&self, name: &str) -> Result<String> {
+        for include in self.includes {
+            let path = include.join(name);
+            if path.is_file() {
+                debug!("reading {}", path.display());
+                let source = fs::read(&path)?;
+                return String::from_utf8(source).map_err(|_| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("{}: File is not valid UTF-8.", path.display()),
+                    )
+                });
+            }
+        }
+
+        BUILTIN_INCLUDES
+            .iter()
+            .find(|(builtin, _)| *builtin == name)
+            .map(|(_, source)| source.to_string())
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("{}: File not found.", name)))
+    }
+}
+
+/// Formats a double like `printf("%.<precision>g")`.
+fn format_g(value: f64, precision: usize) -> String {
+    let sign = if value.is_sign_negative() { "-" } else { "" };
+    let value = value.abs();
+    if value == 0.0 {
+        return format!("{}0", sign);
+    }
+
+    // Round to the precision first, since rounding can change the exponent.
+    let scientific = format!("{:.*e}", precision - 1, value);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+
+    let strip_zeros = |digits: &str| -> String {
+        if digits.contains('.') {
+            digits
+                .trim_end_matches('0')
+                .trim_end_matches('.')
+                .to_string()
+        } else {
+            digits.to_string()
+        }
+    };
+
+    if exponent < -4 || exponent >= precision as i32 {
+        format!(
+            "{}{}e{}{:02}",
+            sign,
+            strip_zeros(mantissa),
+            if exponent < 0 { '-' } else { '+' },
+            exponent.abs()
+        )
+    } else {
+        let decimals = (precision as i32 - 1 - exponent) as usize;
+        format!(
+            "{}{}",
+            sign,
+            strip_zeros(&format!("{:.*}", decimals, value))
+        )
+    }
+}
+
+/// Formats a double with the shortest of 15 or 17 significant digits which round-trips, like
+/// `SimpleDtoa` in `protoc`.
+fn simple_dtoa(value: f64) -> String {
+    if value.is_nan() {
+        return "nan".to_string();
+    } else if value.is_infinite() {
+        return if value > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+
+    let formatted = format_g(value, 15);
+    if formatted.parse::<f64>() == Ok(value) {
+        formatted
+    } else {
+        format_g(value, 17)
+    }
+}
+
+/// Formats a float with the shortest of 6 or 9 significant digits which round-trips, like
+/// `SimpleFtoa` in `protoc`.
+fn simple_ftoa(value: f32) -> String {
+    if value.is_nan() {
+        return "nan".to_string();
+    } else if value.is_infinite() {
+        return if value > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+
+    let formatted = format_g(value.into(), 6);
+    if formatted.parse::<f32>() == Ok(value) {
+        formatted
+    } else {
+        format_g(value.into(), 9)
+    }
+}
+
+/// Escapes bytes like `CEscape` in `protoc`.
+fn c_escape(bytes: &[u8]) -> String {
+    let mut escaped = String::with_capacity(bytes.len());
+    for &byte in bytes {
+        match byte {
+            b'\n' => escaped.push_str("\\n"),
+            b'\r' => escaped.push_str("\\r"),
+            b'\t' => escaped.push_str("\\t"),
+            b'"' => escaped.push_str("\\\""),
+            b'\'' => escaped.push_str("\\'"),
+            b'\\' => escaped.push_str("\\\\"),
+            0x20..=0x7e => escaped.push(byte as char),
+            _ => escaped.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use prost::Message;
+
+    fn find_protos(dir: &Path, protos: &mut Vec<PathBuf>) {
+        let mut entries = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        entries.sort();
+        for path in entries {
+            if path.is_dir() {
+                find_protos(&path, protos);
+            } else if path
+                .extension()
+                .is_some_and(|extension| extension == "proto")
+            {
+                protos.push(path);
+            }
+        }
+    }
+
+    fn load(proto: &Path, includes: &[PathBuf], builtin: bool) -> Result<Vec<u8>> {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("file_descriptor_set.bin");
+        let mut config = Config::new();
+        config.file_descriptor_set_path(&path);
+        if builtin {
+            config.use_builtin_parser();
+        }
+        config.load_fds(&[proto], includes)?;
+        Ok(fs::read(&path).unwrap())
+    }
+
+    /// Checks that the built-in parser produces the same file descriptor set as `protoc` for the
+    /// `.proto` files in the repository.
+    #[test]
+    fn matches_protoc() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+
+        let mut cases = Vec::new();
+        let tests = root.join("tests/src");
+        let mut protos = Vec::new();
+        find_protos(&tests, &mut protos);
+        for proto in protos {
+            let includes = if proto.starts_with(tests.join("include")) {
+                vec![tests.join("include")]
+            } else {
+                vec![proto.parent().unwrap().to_path_buf(), tests.clone()]
+            };
+            cases.push((proto, includes));
+        }
+
+        let protobuf = root.join("third_party/protobuf/src");
+        if protobuf.is_dir() {
+            let mut protos = Vec::new();
+            find_protos(&protobuf, &mut protos);
+            for proto in protos {
+                cases.push((proto, vec![protobuf.clone()]));
+            }
+        }
+
+        for (proto, includes) in cases {
+            let expected = load(&proto, &includes, false);
+            let actual = load(&proto, &includes, true);
+            match (expected, actual) {
+                (Ok(expected), Ok(actual)) => {
+                    if expected != actual {
+                        assert_eq!(
+                            prost_types::FileDescriptorSet::decode(expected.as_slice()).unwrap(),
+                            prost_types::FileDescriptorSet::decode(actual.as_slice()).unwrap(),
+                            "{}",
+                            proto.display()
+                        );
+                        panic!("{}: encoded file descriptor sets differ", proto.display());
+                    }
+                }
+                (Err(_), Err(_)) => {}
+                (expected, actual) => panic!(
+                    "{}: protoc returned {:?}, but the built-in parser returned {:?}",
+                    proto.display(),
+                    expected.map(|_| ()),
+                    actual.map(|_| ())
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn dtoa() {
+        assert_eq!(simple_dtoa(1.0), "1");
+        assert_eq!(simple_dtoa(-0.0), "-0");
+        assert_eq!(simple_dtoa(0.1), "0.1");
+        assert_eq!(simple_dtoa(1e100), "1e+100");
+        assert_eq!(simple_dtoa(1.5e-7), "1.5e-07");
+        assert_eq!(simple_dtoa(123456789012345680.0), "1.2345678901234568e+17");
+        assert_eq!(simple_dtoa(f64::NEG_INFINITY), "-inf");
+        assert_eq!(simple_ftoa(1.1), "1.1");
+        assert_eq!(simple_ftoa(3.4028235e38), "3.40282347e+38");
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!(c_escape(b"a\"b\0\x7f\xff\n"), "a\\\"b\\000\\177\\377\\n");
+    }
+}
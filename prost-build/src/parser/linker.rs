@@ -0,0 +1,1505 @@
+//! Resolves the names in parsed files, validates them and interprets their options.
+//!
+//! This mirrors `DescriptorBuilder` in `protoc`, which also determines the encoding of the
+//! resulting file descriptors.
+
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+use prost::bytes::Buf;
+use prost::encoding::{decode_key, decode_varint, encode_key, encode_varint, WireType};
+use prost::Message;
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, EnumOptions, EnumValueOptions, ExtensionRangeOptions,
+    FieldDescriptorProto, FieldOptions, FileDescriptorProto, FileOptions, MessageOptions,
+    MethodOptions, OneofOptions, ServiceOptions, SourceCodeInfo, UninterpretedOption,
+};
+
+use super::options::{self, PendingOptions};
+use super::{simple_dtoa, simple_ftoa, tags};
+
+type Result<T> = std::result::Result<T, String>;
+
+/// The largest valid field number.
+const MAX_FIELD_NUMBER: i32 = 536_870_911;
+
+/// Field numbers reserved for the protobuf implementation.
+const RESERVED_FIELD_NUMBERS: std::ops::RangeInclusive<i32> = 19_000..=19_999;
+
+#[derive(Clone, Debug)]
+pub(super) struct Symbol {
+    pub(super) file: usize,
+    pub(super) kind: SymbolKind,
+}
+
+#[derive(Clone, Debug)]
+pub(super) enum SymbolKind {
+    Package,
+    /// A message, with the index of its top-level message followed by nested message indices.
+    Message(Vec<usize>),
+    Enum {
+        parent: Vec<usize>,
+        index: usize,
+    },
+    EnumValue {
+        enum_name: String,
+        number: i32,
+    },
+    Field {
+        parent: Vec<usize>,
+        index: usize,
+        is_extension: bool,
+    },
+    Oneof,
+    Service,
+    Method,
+}
+
+impl Symbol {
+    fn is_type(&self) -> bool {
+        matches!(self.kind, SymbolKind::Message(_) | SymbolKind::Enum { .. })
+    }
+
+    fn is_aggregate(&self) -> bool {
+        matches!(
+            self.kind,
+            SymbolKind::Package
+                | SymbolKind::Message(_)
+                | SymbolKind::Enum { .. }
+                | SymbolKind::Service
+        )
+    }
+}
+
+/// Joins a scope and a name into a full name.
+pub(super) fn join(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+/// Converts a field name to its JSON name, like `ToJsonName` in `protoc`.
+fn to_json_name(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut capitalize_next = false;
+    for c in name.chars() {
+        if c == '_' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            result.push(c.to_ascii_uppercase());
+            capitalize_next = false;
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// A pool of linked files.
+#[derive(Default)]
+pub(super) struct Linker {
+    files: Vec<FileDescriptorProto>,
+    file_indices: HashMap<String, usize>,
+    /// The files whose symbols are visible from each file: its imports and their public imports.
+    dependencies: Vec<HashSet<usize>>,
+    packages: Vec<String>,
+    symbols: HashMap<String, Symbol>,
+    /// The name of each extension, by extendee and field number.
+    extension_numbers: HashMap<(String, i32), String>,
+    /// The encoded extensions set in each options message, by file and options path.
+    extension_options: Vec<HashMap<Vec<i32>, Vec<u8>>>,
+}
+
+impl Linker {
+    /// Adds a parsed file to the pool. Its imports must already have been added.
+    pub(super) fn add_file(&mut self, mut file: FileDescriptorProto) -> Result<usize> {
+        let index = self.files.len();
+
+        let mut dependencies = HashSet::new();
+        for (i, dependency) in file.dependency.iter().enumerate() {
+            if file.dependency[..i].contains(dependency) {
+                return Err(format!("Import \"{}\" was listed twice.", dependency));
+            }
+            let dependency = *self
+                .file_indices
+                .get(dependency)
+                .ok_or_else(|| format!("Import \"{}\" has not been loaded.", dependency))?;
+            dependencies.insert(dependency);
+            self.add_public_dependencies(dependency, &mut dependencies);
+        }
+        self.dependencies.push(dependencies);
+        self.packages.push(file.package().to_string());
+
+        self.add_file_symbols(index, &file)?;
+        self.resolve_file(index, &mut file)?;
+
+        // Options are interpreted once the file is in the pool, since they may refer to the
+        // extensions it defines.
+        let mut pending = Vec::new();
+        visit_options(&mut file, &mut |options_path, scope, mut options| {
+            let uninterpreted = mem::take(options.uninterpreted_option());
+            if !uninterpreted.is_empty() {
+                pending.push(PendingOptions {
+                    options_path,
+                    scope: scope.to_string(),
+                    options_type: options.type_name(),
+                    uninterpreted,
+                });
+            }
+            Ok(())
+        })?;
+
+        self.file_indices.insert(file.name().to_string(), index);
+        self.files.push(file);
+        self.extension_options.push(HashMap::new());
+
+        self.validate_file(index)?;
+        self.add_extension_numbers(index)?;
+        self.interpret_options(index, pending)?;
+        self.validate_enums(index)?;
+
+        Ok(index)
+    }
+
+    fn add_public_dependencies(&self, file: usize, dependencies: &mut HashSet<usize>) {
+        for &public in &self.files[file].public_dependency {
+            let name = &self.files[file].dependency[public as usize];
+            let public = self.file_indices[name];
+            if dependencies.insert(public) {
+                self.add_public_dependencies(public, dependencies);
+            }
+        }
+    }
+
+    /// Adds `file` and its dependencies to `files` in topological order, if not `seen` yet.
+    pub(super) fn transitive_dependencies(
+        &self,
+        file: usize,
+        seen: &mut HashSet<usize>,
+        files: &mut Vec<usize>,
+    ) {
+        if !seen.insert(file) {
+            return;
+        }
+        for dependency in &self.files[file].dependency {
+            self.transitive_dependencies(self.file_indices[dependency], seen, files);
+        }
+        files.push(file);
+    }
+
+    /// Encodes a linked file, including the extensions set in its options.
+    pub(super) fn encode(&self, file: usize, include_source_info: bool) -> Vec<u8> {
+        let mut proto = self.files[file].clone();
+        if !include_source_info {
+            proto.source_code_info = None;
+        }
+        // Like `protoc`, only record the syntax if it isn't the default.
+        if proto.syntax() == "proto2" {
+            proto.syntax = None;
+        }
+        let buf = proto.encode_to_vec();
+
+        let extension_options = &self.extension_options[file];
+        if extension_options.is_empty() {
+            buf
+        } else {
+            splice_options(&buf, Element::File, &mut Vec::new(), extension_options)
+        }
+    }
+
+    // Symbols.
+
+    fn add_file_symbols(&mut self, file: usize, proto: &FileDescriptorProto) -> Result<()> {
+        let package = proto.package();
+        if !package.is_empty() {
+            self.add_package(file, package)?;
+        }
+        for (i, message) in proto.message_type.iter().enumerate() {
+            self.add_message_symbols(file, package, message, vec![i])?;
+        }
+        for (i, enum_type) in proto.enum_type.iter().enumerate() {
+            self.add_enum_symbols(file, package, enum_type, Vec::new(), i)?;
+        }
+        for (i, extension) in proto.extension.iter().enumerate() {
+            self.add_symbol(
+                file,
+                join(package, extension.name()),
+                SymbolKind::Field {
+                    parent: Vec::new(),
+                    index: i,
+                    is_extension: true,
+                },
+            )?;
+        }
+        for service in &proto.service {
+            let service_name = join(package, service.name());
+            self.add_symbol(file, service_name.clone(), SymbolKind::Service)?;
+            for method in &service.method {
+                self.add_symbol(file, join(&service_name, method.name()), SymbolKind::Method)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn add_package(&mut self, file: usize, package: &str) -> Result<()> {
+        let mut end = 0;
+        for part in package.split('.') {
+            end += part.len();
+            let name = &package[..end];
+            end += 1;
+            match self.symbols.get(name) {
+                Some(Symbol {
+                    kind: SymbolKind::Package,
+                    ..
+                }) => {}
+                Some(other) => {
+                    return Err(format!(
+                        "\"{}\" is already defined (as something other than a package) in file \"{}\".",
+                        name,
+                        self.file_name(other.file)
+                    ));
+                }
+                None => {
+                    self.symbols.insert(
+                        name.to_string(),
+                        Symbol {
+                            file,
+                            kind: SymbolKind::Package,
+                        },
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn add_message_symbols(
+        &mut self,
+        file: usize,
+        scope: &str,
+        message: &DescriptorProto,
+        path: Vec<usize>,
+    ) -> Result<()> {
+        let full_name = join(scope, message.name());
+        self.add_symbol(file, full_name.clone(), SymbolKind::Message(path.clone()))?;
+
+        for (i, field) in message.field.iter().enumerate() {
+            self.add_symbol(
+                file,
+                join(&full_name, field.name()),
+                SymbolKind::Field {
+                    parent: path.clone(),
+                    index: i,
+                    is_extension: false,
+                },
+            )?;
+        }
+        for oneof in &message.oneof_decl {
+            self.add_symbol(file, join(&full_name, oneof.name()), SymbolKind::Oneof)?;
+        }
+        for (i, nested) in message.nested_type.iter().enumerate() {
+            let mut nested_path = path.clone();
+            nested_path.push(i);
+            self.add_message_symbols(file, &full_name, nested, nested_path)?;
+        }
+        for (i, enum_type) in message.enum_type.iter().enumerate() {
+            self.add_enum_symbols(file, &full_name, enum_type, path.clone(), i)?;
+        }
+        for (i, extension) in message.extension.iter().enumerate() {
+            self.add_symbol(
+                file,
+                join(&full_name, extension.name()),
+                SymbolKind::Field {
+                    parent: path.clone(),
+                    index: i,
+                    is_extension: true,
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    fn add_enum_symbols(
+        &mut self,
+        file: usize,
+        scope: &str,
+        enum_type: &EnumDescriptorProto,
+        parent: Vec<usize>,
+        index: usize,
+    ) -> Result<()> {
+        let enum_name = join(scope, enum_type.name());
+        self.add_symbol(file, enum_name.clone(), SymbolKind::Enum { parent, index })?;
+
+        // Enum values are siblings of their enum, following C++ scoping rules.
+        for value in &enum_type.value {
+            let value_name = join(scope, value.name());
+            let existing = self.symbols.get(&value_name).cloned();
+            self.add_symbol(
+                file,
+                value_name,
+                SymbolKind::EnumValue {
+                    enum_name: enum_name.clone(),
+                    number: value.number(),
+                },
+            )
+            .map_err(|error| match existing {
+                Some(Symbol {
+                    kind: SymbolKind::EnumValue { .. },
+                    file: existing_file,
+                }) if existing_file == file => format!(
+                    "{}  Note that enum values use C++ scoping rules, meaning that enum values \
+                     are siblings of their type, not children of it.  Therefore, \"{}\" must be \
+                     unique within \"{}\", not just within \"{}\".",
+                    error,
+                    value.name(),
+                    scope,
+                    enum_type.name()
+                ),
+                _ => error,
+            })?;
+        }
+        Ok(())
+    }
+
+    fn add_symbol(&mut self, file: usize, full_name: String, kind: SymbolKind) -> Result<()> {
+        if let Some(other) = self.symbols.get(&full_name) {
+            return Err(if other.file != file {
+                format!(
+                    "\"{}\" is already defined in file \"{}\".",
+                    full_name,
+                    self.file_name(other.file)
+                )
+            } else if let Some((scope, name)) = full_name.rsplit_once('.') {
+                format!("\"{}\" is already defined in \"{}\".", name, scope)
+            } else {
+                format!("\"{}\" is already defined.", full_name)
+            });
+        }
+        self.symbols.insert(full_name, Symbol { file, kind });
+        Ok(())
+    }
+
+    fn file_name(&self, file: usize) -> &str {
+        self.files.get(file).map_or("", |file| file.name())
+    }
+
+    /// Finds a symbol by its full name, if it is visible from `file`.
+    fn find_symbol(&self, file: usize, name: &str) -> Option<&Symbol> {
+        let symbol = self.symbols.get(name)?;
+        if symbol.file == file || self.dependencies[file].contains(&symbol.file) {
+            return Some(symbol);
+        }
+
+        // A package may be defined by several files, some of which are visible.
+        if let SymbolKind::Package = symbol.kind {
+            let in_package = |package: &str| {
+                package.starts_with(name)
+                    && (package.len() == name.len() || package.as_bytes()[name.len()] == b'.')
+            };
+            if in_package(&self.packages[file])
+                || self.dependencies[file]
+                    .iter()
+                    .any(|&dependency| in_package(&self.packages[dependency]))
+            {
+                return Some(symbol);
+            }
+        }
+        None
+    }
+
+    /// Finds a symbol by its full name, regardless of whether it is visible.
+    pub(super) fn find_symbol_not_enforcing_deps(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.get(name)
+    }
+
+    /// Looks up a name relative to a scope, like `DescriptorBuilder::LookupSymbol`, returning
+    /// the full name of the symbol.
+    pub(super) fn lookup(
+        &self,
+        file: usize,
+        name: &str,
+        relative_to: &str,
+        types_only: bool,
+    ) -> Result<(String, &Symbol)> {
+        if let Some(full_name) = name.strip_prefix('.') {
+            return self
+                .find_symbol(file, full_name)
+                .map(|symbol| (full_name.to_string(), symbol))
+                .ok_or_else(|| self.not_defined(file, name, full_name));
+        }
+
+        // If the name is compound, like `Foo.Bar`, look up the first part in the innermost scope
+        // and then the rest of the name within it.
+        let first_part = name.split('.').next().unwrap();
+        let mut scope = relative_to.to_string();
+        loop {
+            let Some(dot) = scope.rfind('.') else {
+                return self
+                    .find_symbol(file, name)
+                    .map(|symbol| (name.to_string(), symbol))
+                    .ok_or_else(|| self.not_defined(file, name, name));
+            };
+            scope.truncate(dot);
+
+            let candidate = format!("{}.{}", scope, first_part);
+            if let Some(symbol) = self.find_symbol(file, &candidate) {
+                if first_part.len() < name.len() {
+                    if symbol.is_aggregate() {
+                        let full_name = format!("{}.{}", scope, name);
+                        return match self.find_symbol(file, &full_name) {
+                            Some(symbol) => Ok((full_name, symbol)),
+                            None => Err(format!(
+                                "\"{}\" is resolved to \"{}\", which is not defined. The innermost \
+                                 scope is searched first in name resolution. Consider using a \
+                                 leading '.'(i.e., \".{}\") to start from the outermost scope.",
+                                name, full_name, name
+                            )),
+                        };
+                    }
+                } else if !types_only || symbol.is_type() {
+                    return Ok((candidate, symbol));
+                }
+            }
+        }
+    }
+
+    fn not_defined(&self, file: usize, name: &str, full_name: &str) -> String {
+        match self.symbols.get(full_name) {
+            Some(symbol) if symbol.file != file => format!(
+                "\"{}\" seems to be defined in \"{}\", which is not imported by \"{}\".  To use it \
+                 here, please add the necessary import.",
+                name,
+                self.file_name(symbol.file),
+                self.file_name(file)
+            ),
+            _ => format!("\"{}\" is not defined.", name),
+        }
+    }
+
+    // Accessors.
+
+    pub(super) fn is_proto3(&self, file: usize) -> bool {
+        self.files[file].syntax() == "proto3"
+    }
+
+    fn message_at(&self, file: usize, path: &[usize]) -> &DescriptorProto {
+        let mut message = &self.files[file].message_type[path[0]];
+        for &index in &path[1..] {
+            message = &message.nested_type[index];
+        }
+        message
+    }
+
+    /// Returns a message by its full name, with a leading `.`.
+    pub(super) fn message(&self, type_name: &str) -> Option<(usize, &DescriptorProto)> {
+        let name = type_name.strip_prefix('.').unwrap_or(type_name);
+        match self.symbols.get(name)? {
+            Symbol {
+                file,
+                kind: SymbolKind::Message(path),
+            } => Some((*file, self.message_at(*file, path))),
+            _ => None,
+        }
+    }
+
+    /// Returns an enum by its full name, with a leading `.`.
+    pub(super) fn enum_type(&self, type_name: &str) -> Option<(usize, &EnumDescriptorProto)> {
+        let name = type_name.strip_prefix('.').unwrap_or(type_name);
+        match self.symbols.get(name)? {
+            Symbol {
+                file,
+                kind: SymbolKind::Enum { parent, index },
+            } => {
+                let enum_type = if parent.is_empty() {
+                    &self.files[*file].enum_type[*index]
+                } else {
+                    &self.message_at(*file, parent).enum_type[*index]
+                };
+                Some((*file, enum_type))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the field or extension which a symbol refers to.
+    pub(super) fn field(&self, symbol: &Symbol) -> Option<&FieldDescriptorProto> {
+        match &symbol.kind {
+            SymbolKind::Field {
+                parent,
+                index,
+                is_extension,
+            } => Some(match (parent.is_empty(), is_extension) {
+                (true, _) => &self.files[symbol.file].extension[*index],
+                (false, true) => &self.message_at(symbol.file, parent).extension[*index],
+                (false, false) => &self.message_at(symbol.file, parent).field[*index],
+            }),
+            _ => None,
+        }
+    }
+
+    /// Looks up an enum value by name, within the scope of its enum.
+    fn enum_value(&self, enum_name: &str, value_name: &str) -> Option<i32> {
+        let enum_name = enum_name.strip_prefix('.').unwrap_or(enum_name);
+        let scope = enum_name.rsplit_once('.').map_or("", |(scope, _)| scope);
+        match self.symbols.get(&join(scope, value_name))? {
+            Symbol {
+                kind:
+                    SymbolKind::EnumValue {
+                        enum_name: e,
+                        number,
+                    },
+                ..
+            } if e == enum_name => Some(*number),
+            _ => None,
+        }
+    }
+
+    // Resolution.
+
+    fn resolve_file(&self, file: usize, proto: &mut FileDescriptorProto) -> Result<()> {
+        let package = proto.package().to_string();
+        for message in &mut proto.message_type {
+            self.resolve_message(file, &package, message)?;
+        }
+        for extension in &mut proto.extension {
+            self.resolve_field(file, &package, extension)?;
+        }
+        for service in &mut proto.service {
+            let service_name = join(&package, service.name());
+            for method in &mut service.method {
+                let method_name = join(&service_name, method.name());
+                for type_name in [&mut method.input_type, &mut method.output_type] {
+                    let name = type_name.take().unwrap_or_default();
+                    let (full_name, symbol) = self.lookup(file, &name, &method_name, false)?;
+                    if !matches!(symbol.kind, SymbolKind::Message(_)) {
+                        return Err(format!("\"{}\" is not a message type.", name));
+                    }
+                    *type_name = Some(format!(".{}", full_name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve_message(
+        &self,
+        file: usize,
+        scope: &str,
+        message: &mut DescriptorProto,
+    ) -> Result<()> {
+        let full_name = join(scope, message.name());
+        for field in message.field.iter_mut().chain(&mut message.extension) {
+            self.resolve_field(file, &full_name, field)?;
+        }
+        for nested in &mut message.nested_type {
+            self.resolve_message(file, &full_name, nested)?;
+        }
+        Ok(())
+    }
+
+    fn resolve_field(
+        &self,
+        file: usize,
+        scope: &str,
+        field: &mut FieldDescriptorProto,
+    ) -> Result<()> {
+        let full_name = join(scope, field.name());
+
+        if let Some(extendee) = field.extendee.take() {
+            let (extendee_name, symbol) = self.lookup(file, &extendee, &full_name, false)?;
+            if !matches!(symbol.kind, SymbolKind::Message(_)) {
+                return Err(format!("\"{}\" is not a message type.", extendee));
+            }
+            field.extendee = Some(format!(".{}", extendee_name));
+        }
+
+        if let Some(type_name) = field.type_name.take() {
+            let (resolved_name, symbol) = self.lookup(file, &type_name, &full_name, true)?;
+            match (
+                &symbol.kind,
+                field.r#type.and_then(|ty| Type::try_from(ty).ok()),
+            ) {
+                (SymbolKind::Message(_), None) => field.set_type(Type::Message),
+                (SymbolKind::Message(_), Some(Type::Message | Type::Group)) => {}
+                (SymbolKind::Message(_), _) => {
+                    return Err(format!("\"{}\" is not an enum type.", type_name));
+                }
+                (SymbolKind::Enum { .. }, None) => field.set_type(Type::Enum),
+                (SymbolKind::Enum { .. }, Some(Type::Enum)) => {}
+                (SymbolKind::Enum { .. }, _) => {
+                    return Err(format!("\"{}\" is not a message type.", type_name));
+                }
+                _ => return Err(format!("\"{}\" is not a type.", type_name)),
+            }
+            field.type_name = Some(format!(".{}", resolved_name));
+        }
+
+        if let Some(default_value) = field.default_value.take() {
+            field.default_value = Some(self.canonical_default_value(field, &default_value)?);
+        }
+
+        if field.json_name.is_none() {
+            field.json_name = Some(to_json_name(field.name()));
+        }
+        Ok(())
+    }
+
+    /// Returns the default value of a field as `protoc` formats it.
+    fn canonical_default_value(&self, field: &FieldDescriptorProto, value: &str) -> Result<String> {
+        if field.label() == Label::Repeated {
+            return Err("Repeated fields can't have default values.".to_string());
+        }
+
+        let invalid = || format!("Couldn't parse default value \"{}\".", value);
+        Ok(match field.r#type() {
+            Type::Int32
+            | Type::Int64
+            | Type::Sint32
+            | Type::Sint64
+            | Type::Sfixed32
+            | Type::Sfixed64 => value.parse::<i64>().map_err(|_| invalid())?.to_string(),
+            Type::Uint32 | Type::Uint64 | Type::Fixed32 | Type::Fixed64 => {
+                value.parse::<u64>().map_err(|_| invalid())?.to_string()
+            }
+            Type::Float => simple_ftoa(value.parse::<f64>().map_err(|_| invalid())? as f32),
+            Type::Double => simple_dtoa(value.parse().map_err(|_| invalid())?),
+            Type::Bool => match value {
+                "true" | "false" => value.to_string(),
+                _ => return Err("Boolean default must be true or false.".to_string()),
+            },
+            Type::String | Type::Bytes => value.to_string(),
+            Type::Enum => {
+                if self.enum_value(field.type_name(), value).is_none() {
+                    return Err(format!(
+                        "Enum type \"{}\" has no value named \"{}\".",
+                        &field.type_name()[1..],
+                        value
+                    ));
+                }
+                value.to_string()
+            }
+            Type::Message | Type::Group => {
+                return Err("Messages can't have default values.".to_string());
+            }
+        })
+    }
+
+    // Validation.
+
+    fn validate_file(&self, file: usize) -> Result<()> {
+        let proto = &self.files[file];
+        let proto3 = self.is_proto3(file);
+        let package = proto.package();
+
+        for message in &proto.message_type {
+            self.validate_message(file, proto3, package, message)?;
+        }
+        for extension in &proto.extension {
+            self.validate_extension(file, proto3, package, extension)?;
+        }
+        for enum_type in &proto.enum_type {
+            validate_enum_ranges(proto3, enum_type)?;
+        }
+        Ok(())
+    }
+
+    fn validate_message(
+        &self,
+        file: usize,
+        proto3: bool,
+        scope: &str,
+        message: &DescriptorProto,
+    ) -> Result<()> {
+        let full_name = join(scope, message.name());
+
+        let mut numbers = HashMap::new();
+        for field in &message.field {
+            validate_field_number(field)?;
+            if let Some(other) = numbers.insert(field.number(), field.name()) {
+                return Err(format!(
+                    "Field number {} has already been used in \"{}\" by field \"{}\".",
+                    field.number(),
+                    full_name,
+                    other
+                ));
+            }
+            self.validate_field(file, proto3, field)?;
+
+            for range in &message.reserved_range {
+                if range.start() <= field.number() && field.number() < range.end() {
+                    return Err(format!(
+                        "Field \"{}\" uses reserved number {}.",
+                        field.name(),
+                        field.number()
+                    ));
+                }
+            }
+            if message
+                .reserved_name
+                .iter()
+                .any(|name| name == field.name())
+            {
+                return Err(format!("Field name \"{}\" is reserved.", field.name()));
+            }
+            for range in &message.extension_range {
+                if range.start() <= field.number() && field.number() < range.end() {
+                    return Err(format!(
+                        "Extension range {} to {} includes field \"{}\" ({}).",
+                        range.start(),
+                        range.end() - 1,
+                        field.name(),
+                        field.number()
+                    ));
+                }
+            }
+        }
+
+        if proto3 {
+            if !message.extension_range.is_empty() {
+                return Err("Extension ranges are not allowed in proto3.".to_string());
+            }
+
+            let mut json_names = HashMap::new();
+            for field in &message.field {
+                let json_name = to_json_name(field.name()).to_ascii_lowercase();
+                if let Some(other) = json_names.insert(json_name, field.name()) {
+                    return Err(format!(
+                        "The JSON camel-case name of field \"{}\" conflicts with field \"{}\". \
+                         This is not allowed in proto3.",
+                        field.name(),
+                        other
+                    ));
+                }
+            }
+        }
+
+        let ranges = message
+            .extension_range
+            .iter()
+            .map(|range| (range.start(), range.end(), "Extension"))
+            .chain(
+                message
+                    .reserved_range
+                    .iter()
+                    .map(|range| (range.start(), range.end(), "Reserved")),
+            )
+            .collect::<Vec<_>>();
+        for (i, &(start, end, kind)) in ranges.iter().enumerate() {
+            if start <= 0 {
+                return Err(format!("{} numbers must be positive integers.", kind));
+            }
+            if end <= start {
+                return Err(format!(
+                    "{} range end number must be greater than start number.",
+                    kind
+                ));
+            }
+            for &(other_start, other_end, other_kind) in &ranges[..i] {
+                if start < other_end && other_start < end {
+                    return Err(format!(
+                        "{} range {} to {} overlaps with {} range {} to {}.",
+                        kind,
+                        start,
+                        end - 1,
+                        other_kind.to_ascii_lowercase(),
+                        other_start,
+                        other_end - 1
+                    ));
+                }
+            }
+        }
+
+        for extension in &message.extension {
+            self.validate_extension(file, proto3, &full_name, extension)?;
+        }
+        for nested in &message.nested_type {
+            self.validate_message(file, proto3, &full_name, nested)?;
+        }
+        for enum_type in &message.enum_type {
+            validate_enum_ranges(proto3, enum_type)?;
+        }
+
+        if message.options.as_ref().and_then(|o| o.map_entry) == Some(true) {
+            let key = &message.field[0];
+            if matches!(
+                key.r#type(),
+                Type::Float | Type::Double | Type::Bytes | Type::Message | Type::Group | Type::Enum
+            ) {
+                return Err(if key.r#type() == Type::Enum {
+                    "Key in map fields cannot be enum types.".to_string()
+                } else {
+                    "Key in map fields cannot be float/double, bytes or message types.".to_string()
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_field(
+        &self,
+        file: usize,
+        proto3: bool,
+        field: &FieldDescriptorProto,
+    ) -> Result<()> {
+        if !proto3 {
+            return Ok(());
+        }
+        if field.label() == Label::Required {
+            return Err("Required fields are not allowed in proto3.".to_string());
+        }
+        if field.default_value.is_some() {
+            return Err("Explicit default values are not allowed in proto3.".to_string());
+        }
+        if field.r#type() == Type::Group {
+            return Err("Groups are not supported in proto3 syntax.".to_string());
+        }
+        if field.r#type() == Type::Enum {
+            if let Some((enum_file, _)) = self.enum_type(field.type_name()) {
+                if enum_file != file && !self.is_proto3(enum_file) {
+                    return Err(format!(
+                        "Enum type \"{}\" is not a proto3 enum, but is used in \"{}\" which is a \
+                         proto3 message type.",
+                        &field.type_name()[1..],
+                        field.name()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_extension(
+        &self,
+        file: usize,
+        proto3: bool,
+        scope: &str,
+        extension: &FieldDescriptorProto,
+    ) -> Result<()> {
+        validate_field_number(extension)?;
+        self.validate_field(file, proto3, extension)?;
+
+        let full_name = join(scope, extension.name());
+        if extension.json_name.as_deref() != Some(&to_json_name(extension.name())) {
+            return Err("option json_name is not allowed on extension fields.".to_string());
+        }
+        if extension.label() == Label::Required {
+            return Err(format!("The extension {} cannot be required.", full_name));
+        }
+        if proto3 && !extension.extendee().starts_with(".google.protobuf.") {
+            return Err("Extensions in proto3 are only allowed for defining options.".to_string());
+        }
+
+        let (_, extendee) = self.message(extension.extendee()).unwrap();
+        if !extendee
+            .extension_range
+            .iter()
+            .any(|range| range.start() <= extension.number() && extension.number() < range.end())
+        {
+            return Err(format!(
+                "\"{}\" does not declare {} as an extension number.",
+                &extension.extendee()[1..],
+                extension.number()
+            ));
+        }
+        Ok(())
+    }
+
+    fn add_extension_numbers(&mut self, file: usize) -> Result<()> {
+        let mut extensions = Vec::new();
+        let proto = &self.files[file];
+        collect_extensions(
+            proto.package(),
+            &proto.extension,
+            &proto.message_type,
+            &mut extensions,
+        );
+
+        for (full_name, extendee, number) in extensions {
+            if let Some(other) = self
+                .extension_numbers
+                .insert((extendee.clone(), number), full_name)
+            {
+                return Err(format!(
+                    "Extension number {} has already been used in \"{}\" by extension \"{}\".",
+                    number,
+                    &extendee[1..],
+                    other
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_enums(&self, file: usize) -> Result<()> {
+        fn validate(enum_type: &EnumDescriptorProto, scope: &str) -> Result<()> {
+            if enum_type.options.as_ref().and_then(|o| o.allow_alias) == Some(true) {
+                return Ok(());
+            }
+            let mut numbers = HashMap::new();
+            for value in &enum_type.value {
+                if let Some(other) = numbers.insert(value.number(), value.name()) {
+                    return Err(format!(
+                        "\"{}\" uses the same enum value as \"{}\". If this is intended, set \
+                         'option allow_alias = true;' to the enum definition.",
+                        join(scope, value.name()),
+                        join(scope, other)
+                    ));
+                }
+            }
+            Ok(())
+        }
+
+        fn validate_message(message: &DescriptorProto, scope: &str) -> Result<()> {
+            let full_name = join(scope, message.name());
+            for enum_type in &message.enum_type {
+                validate(enum_type, &full_name)?;
+            }
+            for nested in &message.nested_type {
+                validate_message(nested, &full_name)?;
+            }
+            Ok(())
+        }
+
+        let proto = &self.files[file];
+        for enum_type in &proto.enum_type {
+            validate(enum_type, proto.package())?;
+        }
+        for message in &proto.message_type {
+            validate_message(message, proto.package())?;
+        }
+        Ok(())
+    }
+
+    // Options.
+
+    fn interpret_options(&mut self, file: usize, pending: Vec<PendingOptions>) -> Result<()> {
+        let mut repeated_counts = HashMap::new();
+        let mut interpreted_paths = HashMap::new();
+        for options in &pending {
+            let interpreted = options::interpret(self, file, options, &mut repeated_counts)?;
+            interpreted_paths.extend(interpreted.paths);
+            let mut unknown =
+                self.merge_options(file, &options.options_path, &interpreted.known)?;
+            // Fields which can't be represented are encoded after the known fields, like
+            // extensions.
+            unknown.extend_from_slice(&interpreted.extensions);
+            if !unknown.is_empty() {
+                self.extension_options[file].insert(options.options_path.clone(), unknown);
+            }
+        }
+
+        if let Some(source_code_info) = &mut self.files[file].source_code_info {
+            update_source_code_info(source_code_info, &interpreted_paths);
+        }
+        Ok(())
+    }
+
+    /// Merges the encoded known fields of an options message into the file as soon as they are
+    /// interpreted, since later options may depend on them, and returns the fields which are
+    /// unknown to `prost-types`.
+    fn merge_options(
+        &mut self,
+        file: usize,
+        options_path: &[i32],
+        known: &[u8],
+    ) -> Result<Vec<u8>> {
+        let mut unknown = Vec::new();
+        visit_options(&mut self.files[file], &mut |path, _, mut options| {
+            if path == options_path {
+                unknown = options
+                    .merge(known)
+                    .map_err(|error| format!("Failed to interpret options: {}", error))?;
+            }
+            Ok(())
+        })?;
+        Ok(unknown)
+    }
+}
+
+fn validate_field_number(field: &FieldDescriptorProto) -> Result<()> {
+    let number = field.number();
+    if number <= 0 {
+        Err("Field numbers must be positive integers.".to_string())
+    } else if number > MAX_FIELD_NUMBER && field.extendee.is_none() {
+        Err(format!(
+            "Field numbers cannot be greater than {}.",
+            MAX_FIELD_NUMBER
+        ))
+    } else if RESERVED_FIELD_NUMBERS.contains(&number) {
+        Err(format!(
+            "Field numbers {} through {} are reserved for the protocol buffer library \
+             implementation.",
+            RESERVED_FIELD_NUMBERS.start(),
+            RESERVED_FIELD_NUMBERS.end()
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_enum_ranges(proto3: bool, enum_type: &EnumDescriptorProto) -> Result<()> {
+    if enum_type.value.is_empty() {
+        return Err("Enums must contain at least one value.".to_string());
+    }
+    if proto3 && enum_type.value[0].number() != 0 {
+        return Err("The first enum value must be zero in proto3.".to_string());
+    }
+    for value in &enum_type.value {
+        for range in &enum_type.reserved_range {
+            if range.start() <= value.number() && value.number() <= range.end() {
+                return Err(format!(
+                    "Enum value \"{}\" uses reserved number {}.",
+                    value.name(),
+                    value.number()
+                ));
+            }
+        }
+        if enum_type
+            .reserved_name
+            .iter()
+            .any(|name| name == value.name())
+        {
+            return Err(format!("Enum value \"{}\" is reserved.", value.name()));
+        }
+    }
+    for (i, range) in enum_type.reserved_range.iter().enumerate() {
+        if range.end() < range.start() {
+            return Err("Reserved range end number must be greater than start number.".to_string());
+        }
+        for other in &enum_type.reserved_range[..i] {
+            if range.start() <= other.end() && other.start() <= range.end() {
+                return Err(format!(
+                    "Reserved range {} to {} overlaps with already-defined range {} to {}.",
+                    range.start(),
+                    range.end(),
+                    other.start(),
+                    other.end()
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn collect_extensions(
+    scope: &str,
+    extensions: &[FieldDescriptorProto],
+    messages: &[DescriptorProto],
+    output: &mut Vec<(String, String, i32)>,
+) {
+    for extension in extensions {
+        output.push((
+            join(scope, extension.name()),
+            extension.extendee().to_string(),
+            extension.number(),
+        ));
+    }
+    for message in messages {
+        collect_extensions(
+            &join(scope, message.name()),
+            &message.extension,
+            &message.nested_type,
+            output,
+        );
+    }
+}
+
+/// Rewrites the paths of the interpreted options in the source code info, and removes the
+/// locations within them, like `OptionInterpreter::UpdateSourceCodeInfo`.
+fn update_source_code_info(
+    info: &mut SourceCodeInfo,
+    interpreted_paths: &HashMap<Vec<i32>, Vec<i32>>,
+) {
+    if interpreted_paths.is_empty() {
+        return;
+    }
+
+    let mut locations = Vec::with_capacity(info.location.len());
+    let mut matched: Option<Vec<i32>> = None;
+    for mut location in mem::take(&mut info.location) {
+        if let Some(path) = &matched {
+            if location.path.starts_with(path) {
+                continue;
+            }
+            matched = None;
+        }
+
+        if let Some(dest_path) = interpreted_paths.get(&location.path) {
+            matched = Some(mem::replace(&mut location.path, dest_path.clone()));
+        }
+        locations.push(location);
+    }
+    info.location = locations;
+}
+
+/// A mutable reference to an options message.
+pub(super) enum OptionsMut<'a> {
+    File(&'a mut FileOptions),
+    Message(&'a mut MessageOptions),
+    Field(&'a mut FieldOptions),
+    Oneof(&'a mut OneofOptions),
+    ExtensionRange(&'a mut ExtensionRangeOptions),
+    Enum(&'a mut EnumOptions),
+    EnumValue(&'a mut EnumValueOptions),
+    Service(&'a mut ServiceOptions),
+    Method(&'a mut MethodOptions),
+}
+
+impl OptionsMut<'_> {
+    fn type_name(&self) -> &'static str {
+        match self {
+            OptionsMut::File(_) => "google.protobuf.FileOptions",
+            OptionsMut::Message(_) => "google.protobuf.MessageOptions",
+            OptionsMut::Field(_) => "google.protobuf.FieldOptions",
+            OptionsMut::Oneof(_) => "google.protobuf.OneofOptions",
+            OptionsMut::ExtensionRange(_) => "google.protobuf.ExtensionRangeOptions",
+            OptionsMut::Enum(_) => "google.protobuf.EnumOptions",
+            OptionsMut::EnumValue(_) => "google.protobuf.EnumValueOptions",
+            OptionsMut::Service(_) => "google.protobuf.ServiceOptions",
+            OptionsMut::Method(_) => "google.protobuf.MethodOptions",
+        }
+    }
+
+    fn uninterpreted_option(&mut self) -> &mut Vec<UninterpretedOption> {
+        match self {
+            OptionsMut::File(options) => &mut options.uninterpreted_option,
+            OptionsMut::Message(options) => &mut options.uninterpreted_option,
+            OptionsMut::Field(options) => &mut options.uninterpreted_option,
+            OptionsMut::Oneof(options) => &mut options.uninterpreted_option,
+            OptionsMut::ExtensionRange(options) => &mut options.uninterpreted_option,
+            OptionsMut::Enum(options) => &mut options.uninterpreted_option,
+            OptionsMut::EnumValue(options) => &mut options.uninterpreted_option,
+            OptionsMut::Service(options) => &mut options.uninterpreted_option,
+            OptionsMut::Method(options) => &mut options.uninterpreted_option,
+        }
+    }
+
+    /// Merges encoded fields into the options, and returns the fields which aren't known to
+    /// `prost-types`, since the `descriptor.proto` being compiled may be newer.
+    fn merge(&mut self, buf: &[u8]) -> std::result::Result<Vec<u8>, prost::DecodeError> {
+        match self {
+            OptionsMut::File(options) => merge_known(*options, buf),
+            OptionsMut::Message(options) => merge_known(*options, buf),
+            OptionsMut::Field(options) => merge_known(*options, buf),
+            OptionsMut::Oneof(options) => merge_known(*options, buf),
+            OptionsMut::ExtensionRange(options) => merge_known(*options, buf),
+            OptionsMut::Enum(options) => merge_known(*options, buf),
+            OptionsMut::EnumValue(options) => merge_known(*options, buf),
+            OptionsMut::Service(options) => merge_known(*options, buf),
+            OptionsMut::Method(options) => merge_known(*options, buf),
+        }
+    }
+}
+
+fn merge_known<M: Message + Default>(
+    options: &mut M,
+    mut buf: &[u8],
+) -> std::result::Result<Vec<u8>, prost::DecodeError> {
+    let mut unknown = Vec::new();
+    while buf.has_remaining() {
+        let field_start = buf;
+        let (tag, wire_type) = decode_key(&mut buf)?;
+        prost::encoding::skip_field(wire_type, tag, &mut buf, Default::default())?;
+        let field = &field_start[..field_start.len() - buf.len()];
+        // Fields unknown to the message are skipped when decoding.
+        if M::decode(field)?.encoded_len() == 0 {
+            unknown.extend_from_slice(field);
+        } else {
+            options.merge(field)?;
+        }
+    }
+    Ok(unknown)
+}
+
+type OptionsVisitor<'v> = dyn FnMut(Vec<i32>, &str, OptionsMut<'_>) -> Result<()> + 'v;
+
+/// Calls `visitor` with each options message in a file, along with its source code info path and
+/// the scope in which extension names are looked up.
+///
+/// Options are visited in the order in which `protoc` interprets them: the options of an element
+/// come after the options of its children.
+fn visit_options(file: &mut FileDescriptorProto, visitor: &mut OptionsVisitor<'_>) -> Result<()> {
+    let package = file.package().to_string();
+
+    for (i, message) in file.message_type.iter_mut().enumerate() {
+        visit_message_options(
+            message,
+            &package,
+            vec![tags::file::MESSAGE_TYPE, i as i32],
+            visitor,
+        )?;
+    }
+    for (i, enum_type) in file.enum_type.iter_mut().enumerate() {
+        visit_enum_options(
+            enum_type,
+            &package,
+            vec![tags::file::ENUM_TYPE, i as i32],
+            visitor,
+        )?;
+    }
+    for (i, service) in file.service.iter_mut().enumerate() {
+        let service_name = join(&package, service.name.as_deref().unwrap_or_default());
+        let path = [tags::file::SERVICE, i as i32];
+        for (j, method) in service.method.iter_mut().enumerate() {
+            if let Some(options) = &mut method.options {
+                visitor(
+                    [
+                        &path[..],
+                        &[tags::service::METHOD, j as i32, tags::method::OPTIONS],
+                    ]
+                    .concat(),
+                    &join(&service_name, method.name.as_deref().unwrap_or_default()),
+                    OptionsMut::Method(options),
+                )?;
+            }
+        }
+        if let Some(options) = &mut service.options {
+            visitor(
+                [&path[..], &[tags::service::OPTIONS]].concat(),
+                &service_name,
+                OptionsMut::Service(options),
+            )?;
+        }
+    }
+    for (i, extension) in file.extension.iter_mut().enumerate() {
+        if let Some(options) = &mut extension.options {
+            visitor(
+                vec![tags::file::EXTENSION, i as i32, tags::field::OPTIONS],
+                &join(&package, extension.name.as_deref().unwrap_or_default()),
+                OptionsMut::Field(options),
+            )?;
+        }
+    }
+    if let Some(options) = &mut file.options {
+        visitor(
+            vec![tags::file::OPTIONS],
+            &format!("{}.dummy", package),
+            OptionsMut::File(options),
+        )?;
+    }
+    Ok(())
+}
+
+fn visit_message_options(
+    message: &mut DescriptorProto,
+    scope: &str,
+    path: Vec<i32>,
+    visitor: &mut OptionsVisitor<'_>,
+) -> Result<()> {
+    let full_name = join(scope, message.name.as_deref().unwrap_or_default());
+
+    for (i, oneof) in message.oneof_decl.iter_mut().enumerate() {
+        if let Some(options) = &mut oneof.options {
+            visitor(
+                [
+                    &path[..],
+                    &[tags::message::ONEOF_DECL, i as i32, tags::oneof::OPTIONS],
+                ]
+                .concat(),
+                &join(&full_name, oneof.name.as_deref().unwrap_or_default()),
+                OptionsMut::Oneof(options),
+            )?;
+        }
+    }
+    for (i, field) in message.field.iter_mut().enumerate() {
+        if let Some(options) = &mut field.options {
+            visitor(
+                [
+                    &path[..],
+                    &[tags::message::FIELD, i as i32, tags::field::OPTIONS],
+                ]
+                .concat(),
+                &join(&full_name, field.name.as_deref().unwrap_or_default()),
+                OptionsMut::Field(options),
+            )?;
+        }
+    }
+    for (i, enum_type) in message.enum_type.iter_mut().enumerate() {
+        visit_enum_options(
+            enum_type,
+            &full_name,
+            [&path[..], &[tags::message::ENUM_TYPE, i as i32]].concat(),
+            visitor,
+        )?;
+    }
+    for (i, range) in message.extension_range.iter_mut().enumerate() {
+        if let Some(options) = &mut range.options {
+            visitor(
+                [
+                    &path[..],
+                    &[
+                        tags::message::EXTENSION_RANGE,
+                        i as i32,
+                        tags::extension_range::OPTIONS,
+                    ],
+                ]
+                .concat(),
+                &full_name,
+                OptionsMut::ExtensionRange(options),
+            )?;
+        }
+    }
+    for (i, extension) in message.extension.iter_mut().enumerate() {
+        if let Some(options) = &mut extension.options {
+            visitor(
+                [
+                    &path[..],
+                    &[tags::message::EXTENSION, i as i32, tags::field::OPTIONS],
+                ]
+                .concat(),
+                &join(&full_name, extension.name.as_deref().unwrap_or_default()),
+                OptionsMut::Field(options),
+            )?;
+        }
+    }
+    for (i, nested) in message.nested_type.iter_mut().enumerate() {
+        visit_message_options(
+            nested,
+            &full_name,
+            [&path[..], &[tags::message::NESTED_TYPE, i as i32]].concat(),
+            visitor,
+        )?;
+    }
+    if let Some(options) = &mut message.options {
+        visitor(
+            [&path[..], &[tags::message::OPTIONS]].concat(),
+            &full_name,
+            OptionsMut::Message(options),
+        )?;
+    }
+    Ok(())
+}
+
+fn visit_enum_options(
+    enum_type: &mut EnumDescriptorProto,
+    scope: &str,
+    path: Vec<i32>,
+    visitor: &mut OptionsVisitor<'_>,
+) -> Result<()> {
+    for (i, value) in enum_type.value.iter_mut().enumerate() {
+        if let Some(options) = &mut value.options {
+            visitor(
+                [
+                    &path[..],
+                    &[tags::enum_type::VALUE, i as i32, tags::enum_value::OPTIONS],
+                ]
+                .concat(),
+                &join(scope, value.name.as_deref().unwrap_or_default()),
+                OptionsMut::EnumValue(options),
+            )?;
+        }
+    }
+    if let Some(options) = &mut enum_type.options {
+        visitor(
+            [&path[..], &[tags::enum_type::OPTIONS]].concat(),
+            &join(scope, enum_type.name.as_deref().unwrap_or_default()),
+            OptionsMut::Enum(options),
+        )?;
+    }
+    Ok(())
+}
+
+/// The kinds of elements in a file descriptor which contain options.
+#[derive(Clone, Copy)]
+enum Element {
+    File,
+    Message,
+    Field,
+    Oneof,
+    ExtensionRange,
+    Enum,
+    EnumValue,
+    Service,
+    Method,
+    Options,
+}
+
+impl Element {
+    /// Returns the kind of a field of this element, and whether it is repeated.
+    fn child(self, tag: u32) -> Option<(Element, bool)> {
+        let tag = tag as i32;
+        Some(match (self, tag) {
+            (Element::File, tags::file::MESSAGE_TYPE) => (Element::Message, true),
+            (Element::File, tags::file::ENUM_TYPE) => (Element::Enum, true),
+            (Element::File, tags::file::SERVICE) => (Element::Service, true),
+            (Element::File, tags::file::EXTENSION) => (Element::Field, true),
+            (Element::File, tags::file::OPTIONS) => (Element::Options, false),
+            (Element::Message, tags::message::FIELD | tags::message::EXTENSION) => {
+                (Element::Field, true)
+            }
+            (Element::Message, tags::message::NESTED_TYPE) => (Element::Message, true),
+            (Element::Message, tags::message::ENUM_TYPE) => (Element::Enum, true),
+            (Element::Message, tags::message::EXTENSION_RANGE) => (Element::ExtensionRange, true),
+            (Element::Message, tags::message::ONEOF_DECL) => (Element::Oneof, true),
+            (Element::Message, tags::message::OPTIONS) => (Element::Options, false),
+            (Element::Field, tags::field::OPTIONS) => (Element::Options, false),
+            (Element::Oneof, tags::oneof::OPTIONS) => (Element::Options, false),
+            (Element::ExtensionRange, tags::extension_range::OPTIONS) => (Element::Options, false),
+            (Element::Enum, tags::enum_type::VALUE) => (Element::EnumValue, true),
+            (Element::Enum, tags::enum_type::OPTIONS) => (Element::Options, false),
+            (Element::EnumValue, tags::enum_value::OPTIONS) => (Element::Options, false),
+            (Element::Service, tags::service::METHOD) => (Element::Method, true),
+            (Element::Service, tags::service::OPTIONS) => (Element::Options, false),
+            (Element::Method, tags::method::OPTIONS) => (Element::Options, false),
+            _ => return None,
+        })
+    }
+}
+
+/// Appends the encoded extensions to the options messages within an encoded element.
+///
+/// `protoc` keeps extensions which are not known to it as unknown fields, which are encoded after
+/// the known fields of the options messages.
+fn splice_options(
+    mut buf: &[u8],
+    element: Element,
+    path: &mut Vec<i32>,
+    extension_options: &HashMap<Vec<i32>, Vec<u8>>,
+) -> Vec<u8> {
+    let mut output = Vec::with_capacity(buf.len());
+    let mut counts = HashMap::new();
+    while buf.has_remaining() {
+        let field_start = buf;
+        let (tag, wire_type) = decode_key(&mut buf).expect("invalid encoded descriptor");
+        let child = element
+            .child(tag)
+            .filter(|_| wire_type == WireType::LengthDelimited);
+        let Some((child, repeated)) = child else {
+            prost::encoding::skip_field(wire_type, tag, &mut buf, Default::default())
+                .expect("invalid encoded descriptor");
+            output.extend_from_slice(&field_start[..field_start.len() - buf.len()]);
+            continue;
+        };
+
+        let len = decode_varint(&mut buf).expect("invalid encoded descriptor") as usize;
+        let (value, rest) = buf.split_at(len);
+        buf = rest;
+
+        let path_len = path.len();
+        path.push(tag as i32);
+        if repeated {
+            let count = counts.entry(tag).or_insert(0);
+            path.push(*count);
+            *count += 1;
+        }
+        let value = match child {
+            Element::Options => {
+                let mut value = value.to_vec();
+                if let Some(extensions) = extension_options.get(path.as_slice()) {
+                    value.extend_from_slice(extensions);
+                }
+                value
+            }
+            _ => splice_options(value, child, path, extension_options),
+        };
+        path.truncate(path_len);
+
+        encode_key(tag, WireType::LengthDelimited, &mut output);
+        encode_varint(value.len() as u64, &mut output);
+        output.extend_from_slice(&value);
+    }
+    output
+}
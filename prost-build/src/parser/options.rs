@@ -0,0 +1,1034 @@
+//! Interprets options, like `DescriptorBuilder::OptionInterpreter` in `protoc`.
+//!
+//! Each option is encoded separately as it would appear in its options message. Aggregate values
+//! of message options are parsed from the protobuf text format.
+
+use std::collections::{BTreeMap, HashMap};
+
+use prost::bytes::Buf;
+use prost::encoding::{decode_key, encode_key, encode_varint, skip_field, WireType};
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{DescriptorProto, FieldDescriptorProto, UninterpretedOption};
+
+use super::linker::{join, Linker, Symbol};
+use super::tags;
+use super::tokenizer::{self, TokenType, Tokenizer};
+
+type Result<T> = std::result::Result<T, String>;
+
+/// An options message whose options have not been interpreted yet.
+pub(super) struct PendingOptions {
+    /// The source code info path of the options message.
+    pub(super) options_path: Vec<i32>,
+    /// The scope in which extension names are looked up.
+    pub(super) scope: String,
+    /// The full name of the options message type.
+    pub(super) options_type: &'static str,
+    pub(super) uninterpreted: Vec<UninterpretedOption>,
+}
+
+#[derive(Default)]
+pub(super) struct InterpretedOptions {
+    /// The encoded fields of the options message.
+    pub(super) known: Vec<u8>,
+    /// The encoded extensions of the options message.
+    pub(super) extensions: Vec<u8>,
+    /// The source code info path of each uninterpreted option, mapped to the path of the
+    /// interpreted option.
+    pub(super) paths: Vec<(Vec<i32>, Vec<i32>)>,
+}
+
+/// A field or extension, along with the syntax of the file which defines it.
+#[derive(Clone, Copy)]
+struct FieldRef<'a> {
+    proto: &'a FieldDescriptorProto,
+    proto3: bool,
+}
+
+impl FieldRef<'_> {
+    fn is_repeated(&self) -> bool {
+        self.proto.label() == Label::Repeated
+    }
+
+    fn is_message(&self) -> bool {
+        matches!(self.proto.r#type(), Type::Message | Type::Group)
+    }
+
+    fn has_presence(&self) -> bool {
+        !self.proto3
+            || self.is_message()
+            || self.proto.oneof_index.is_some()
+            || self.proto.extendee.is_some()
+    }
+
+    fn is_packed(&self) -> bool {
+        if matches!(
+            self.proto.r#type(),
+            Type::String | Type::Bytes | Type::Message | Type::Group
+        ) {
+            return false;
+        }
+        let packed = self
+            .proto
+            .options
+            .as_ref()
+            .and_then(|options| options.packed);
+        if self.proto3 {
+            packed != Some(false)
+        } else {
+            packed == Some(true)
+        }
+    }
+}
+
+/// Interprets the options of an options message.
+pub(super) fn interpret(
+    linker: &Linker,
+    file: usize,
+    options: &PendingOptions,
+    repeated_counts: &mut HashMap<Vec<i32>, i32>,
+) -> Result<InterpretedOptions> {
+    let mut interpreted = InterpretedOptions::default();
+    // All of the options which have been set so far, to detect options which are set twice.
+    let mut all = Vec::new();
+
+    for (i, option) in options.uninterpreted.iter().enumerate() {
+        let mut src_path = options.options_path.clone();
+        src_path.extend([tags::UNINTERPRETED_OPTION, i as i32]);
+
+        let interpreter = Interpreter {
+            linker,
+            file,
+            options,
+            option,
+        };
+        let (is_extension, encoded, dest_path) = interpreter.interpret(&all, repeated_counts)?;
+
+        all.extend_from_slice(&encoded);
+        if is_extension {
+            interpreted.extensions.extend_from_slice(&encoded);
+        } else {
+            interpreted.known.extend_from_slice(&encoded);
+        }
+        interpreted.paths.push((src_path, dest_path));
+    }
+
+    Ok(interpreted)
+}
+
+struct Interpreter<'a> {
+    linker: &'a Linker,
+    file: usize,
+    options: &'a PendingOptions,
+    option: &'a UninterpretedOption,
+}
+
+impl<'a> Interpreter<'a> {
+    /// Interprets a single option, returning whether it is an extension, its encoding and its
+    /// source code info path.
+    fn interpret(
+        &self,
+        all: &[u8],
+        repeated_counts: &mut HashMap<Vec<i32>, i32>,
+    ) -> Result<(bool, Vec<u8>, Vec<i32>)> {
+        let name = &self.option.name;
+        if name.is_empty() {
+            return Err("Option must have a name.".to_string());
+        }
+        if name[0].name_part == "uninterpreted_option" {
+            return Err("Option must not use reserved name \"uninterpreted_option\".".to_string());
+        }
+
+        let (_, mut message) = self
+            .linker
+            .message(self.options.options_type)
+            .ok_or_else(|| format!("{} is not defined.", self.options.options_type))?;
+        let mut message_name = self.options.options_type.to_string();
+
+        let mut debug_name = String::new();
+        let mut dest_path = self.options.options_path.clone();
+        let mut intermediate_fields = Vec::new();
+        let mut leaf = None;
+        for (i, part) in name.iter().enumerate() {
+            if !debug_name.is_empty() {
+                debug_name.push('.');
+            }
+            let (field, containing_type, full_name) = if part.is_extension {
+                debug_name.push_str(&format!("({})", part.name_part));
+                match self
+                    .linker
+                    .lookup(self.file, &part.name_part, &self.options.scope, false)
+                {
+                    Ok((full_name, symbol)) => match self.field(symbol) {
+                        Some(field) => {
+                            let containing_type = match &field.proto.extendee {
+                                Some(extendee) => extendee[1..].to_string(),
+                                None => full_name.rsplit_once('.').unwrap().0.to_string(),
+                            };
+                            (Some(field), containing_type, full_name)
+                        }
+                        None => (None, String::new(), full_name),
+                    },
+                    Err(_) => (None, String::new(), String::new()),
+                }
+            } else {
+                debug_name.push_str(&part.name_part);
+                let field = message
+                    .field
+                    .iter()
+                    .find(|field| field.name() == part.name_part)
+                    .map(|proto| FieldRef {
+                        proto,
+                        proto3: self.linker.is_proto3(self.message_file(&message_name)),
+                    });
+                let full_name = join(&message_name, &part.name_part);
+                (field, message_name.clone(), full_name)
+            };
+
+            let Some(field) = field else {
+                return Err(format!(
+                    "Option \"{}\" unknown. Ensure that your proto definition file imports the \
+                     proto which defines the option.",
+                    debug_name
+                ));
+            };
+            if containing_type != message_name {
+                return Err(format!(
+                    "Option field \"{}\" is not a field or extension of message \"{}\".",
+                    debug_name,
+                    message.name()
+                ));
+            }
+
+            dest_path.push(field.proto.number());
+            if i < name.len() - 1 {
+                if !field.is_message() {
+                    return Err(format!(
+                        "Option \"{}\" is an atomic type, not a message.",
+                        debug_name
+                    ));
+                } else if field.is_repeated() {
+                    return Err(format!(
+                        "Option field \"{}\" is a repeated message. Repeated message options \
+                         must be initialized using an aggregate value.",
+                        debug_name
+                    ));
+                }
+                intermediate_fields.push(field);
+                message_name = field.proto.type_name()[1..].to_string();
+                message = self.linker.message(field.proto.type_name()).unwrap().1;
+            } else {
+                leaf = Some((field, full_name));
+            }
+        }
+        let (leaf, leaf_name) = leaf.unwrap();
+
+        if !leaf.is_repeated() && is_option_set(all, &intermediate_fields, leaf.proto.number()) {
+            return Err(format!("Option \"{}\" was already set.", debug_name));
+        }
+
+        let value = self.option_value(leaf, &leaf_name, &debug_name)?;
+        let mut encoded = Vec::new();
+        encode_value(
+            leaf.proto.number(),
+            leaf.proto.r#type(),
+            &value,
+            &mut encoded,
+        );
+
+        // Wrap the value in the intermediate messages.
+        for field in intermediate_fields.iter().rev() {
+            let mut wrapped = Vec::new();
+            let number = field.proto.number() as u32;
+            if field.proto.r#type() == Type::Group {
+                encode_key(number, WireType::StartGroup, &mut wrapped);
+                wrapped.extend_from_slice(&encoded);
+                encode_key(number, WireType::EndGroup, &mut wrapped);
+            } else {
+                encode_key(number, WireType::LengthDelimited, &mut wrapped);
+                encode_varint(encoded.len() as u64, &mut wrapped);
+                wrapped.extend_from_slice(&encoded);
+            }
+            encoded = wrapped;
+        }
+
+        if leaf.is_repeated() {
+            let count = repeated_counts.entry(dest_path.clone()).or_insert(0);
+            dest_path.push(*count);
+            *count += 1;
+        }
+
+        let is_extension = name[0].is_extension;
+        Ok((is_extension, encoded, dest_path))
+    }
+
+    fn field(&self, symbol: &Symbol) -> Option<FieldRef<'a>> {
+        Some(FieldRef {
+            proto: self.linker.field(symbol)?,
+            proto3: self.linker.is_proto3(symbol.file),
+        })
+    }
+
+    fn message_file(&self, message_name: &str) -> usize {
+        self.linker.message(message_name).unwrap().0
+    }
+
+    fn option_value(
+        &self,
+        field: FieldRef<'_>,
+        full_name: &str,
+        debug_name: &str,
+    ) -> Result<Value<'a>> {
+        let option = self.option;
+        let ty = field.proto.r#type();
+
+        match ty {
+            Type::Int32
+            | Type::Sint32
+            | Type::Sfixed32
+            | Type::Int64
+            | Type::Sint64
+            | Type::Sfixed64 => {
+                let (type_name, min, max) = match ty {
+                    Type::Int32 | Type::Sint32 | Type::Sfixed32 => {
+                        ("int32", i64::from(i32::MIN), i64::from(i32::MAX))
+                    }
+                    _ => ("int64", i64::MIN, i64::MAX),
+                };
+                let value = if let Some(value) = option.positive_int_value {
+                    if value > max as u64 {
+                        return Err(format!(
+                            "Value out of range for {} option \"{}\".",
+                            type_name, full_name
+                        ));
+                    }
+                    value as i64
+                } else if let Some(value) = option.negative_int_value {
+                    if value < min {
+                        return Err(format!(
+                            "Value out of range for {} option \"{}\".",
+                            type_name, full_name
+                        ));
+                    }
+                    value
+                } else {
+                    return Err(format!(
+                        "Value must be integer for {} option \"{}\".",
+                        type_name, full_name
+                    ));
+                };
+                Ok(signed_value(ty, value))
+            }
+            Type::Uint32 | Type::Fixed32 | Type::Uint64 | Type::Fixed64 => {
+                let (type_name, max) = match ty {
+                    Type::Uint32 | Type::Fixed32 => ("uint32", u64::from(u32::MAX)),
+                    _ => ("uint64", u64::MAX),
+                };
+                match option.positive_int_value {
+                    Some(value) if value > max => Err(format!(
+                        "Value out of range for {} option \"{}\".",
+                        type_name, full_name
+                    )),
+                    Some(value) => Ok(unsigned_value(ty, value)),
+                    None => Err(format!(
+                        "Value must be non-negative integer for {} option \"{}\".",
+                        type_name, full_name
+                    )),
+                }
+            }
+            Type::Float | Type::Double => {
+                let value = if let Some(value) = option.double_value {
+                    value
+                } else if let Some(value) = option.positive_int_value {
+                    value as f64
+                } else if let Some(value) = option.negative_int_value {
+                    value as f64
+                } else if option.identifier_value.as_deref() == Some("inf") {
+                    f64::INFINITY
+                } else if option.identifier_value.as_deref() == Some("nan") {
+                    f64::NAN
+                } else {
+                    return Err(format!(
+                        "Value must be number for {} option \"{}\".",
+                        if ty == Type::Float { "float" } else { "double" },
+                        full_name
+                    ));
+                };
+                Ok(float_value(ty, value))
+            }
+            Type::Bool => match option.identifier_value.as_deref() {
+                Some("true") => Ok(Value::Varint(1)),
+                Some("false") => Ok(Value::Varint(0)),
+                _ => Err(format!(
+                    "Value must be \"true\" or \"false\" for boolean option \"{}\".",
+                    full_name
+                )),
+            },
+            Type::Enum => {
+                let Some(value_name) = &option.identifier_value else {
+                    return Err(format!(
+                        "Value must be identifier for enum-valued option \"{}\".",
+                        full_name
+                    ));
+                };
+                let enum_name = &field.proto.type_name()[1..];
+                // Enum values are siblings of their enum.
+                let scope = enum_name.rsplit_once('.').map_or("", |(scope, _)| scope);
+                match self
+                    .linker
+                    .find_symbol_not_enforcing_deps(&join(scope, value_name))
+                    .map(|symbol| &symbol.kind)
+                {
+                    Some(super::linker::SymbolKind::EnumValue {
+                        enum_name: value_enum,
+                        number,
+                    }) => {
+                        if value_enum != enum_name {
+                            return Err(format!(
+                                "Enum type \"{}\" has no value named \"{}\" for option \"{}\". \
+                                 This appears to be a value from a sibling type.",
+                                enum_name, value_name, full_name
+                            ));
+                        }
+                        Ok(Value::Varint(i64::from(*number) as u64))
+                    }
+                    _ => Err(format!(
+                        "Enum type \"{}\" has no value named \"{}\" for option \"{}\".",
+                        enum_name, value_name, full_name
+                    )),
+                }
+            }
+            Type::String | Type::Bytes => match &option.string_value {
+                Some(value) => Ok(Value::Bytes(value.clone())),
+                None => Err(format!(
+                    "Value must be quoted string for string option \"{}\".",
+                    full_name
+                )),
+            },
+            Type::Message | Type::Group => {
+                let Some(aggregate) = &option.aggregate_value else {
+                    return Err(format!(
+                        "Option \"{}\" is a message. To set the entire message, use syntax like \
+                         \"{} = {{ <proto text format> }}\". To set fields within it, use syntax \
+                         like \"{}.foo = value\".",
+                        debug_name, debug_name, debug_name
+                    ));
+                };
+                let mut parser = TextFormatParser {
+                    linker: self.linker,
+                    file: self.file,
+                    input: Tokenizer::new(aggregate),
+                };
+                let message = parser.parse(field.proto.type_name()).map_err(|error| {
+                    format!(
+                        "Error while parsing option value for \"{}\": {}",
+                        field.proto.name(),
+                        error
+                    )
+                })?;
+                Ok(Value::Message(message))
+            }
+        }
+    }
+}
+
+/// Returns whether a non-repeated option was already set in the encoded options, like
+/// `OptionInterpreter::ExamineIfOptionIsSet`.
+fn is_option_set(mut buf: &[u8], intermediate_fields: &[FieldRef<'_>], number: i32) -> bool {
+    while buf.has_remaining() {
+        let Ok((tag, wire_type)) = decode_key(&mut buf) else {
+            return false;
+        };
+        let start = buf;
+        if skip_field(wire_type, tag, &mut buf, Default::default()).is_err() {
+            return false;
+        }
+        let mut value = &start[..start.len() - buf.len()];
+
+        match intermediate_fields.split_first() {
+            None if tag as i32 == number => return true,
+            Some((field, rest)) if tag as i32 == field.proto.number() => {
+                let nested = match wire_type {
+                    WireType::LengthDelimited => {
+                        prost::encoding::decode_varint(&mut value).ok();
+                        value
+                    }
+                    WireType::StartGroup => {
+                        // Strip the end group key.
+                        let key_len = prost::encoding::encoded_len_varint(u64::from(tag << 3 | 4));
+                        &value[..value.len() - key_len]
+                    }
+                    _ => continue,
+                };
+                if is_option_set(nested, rest, number) {
+                    return true;
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// A field value, in its wire format.
+enum Value<'a> {
+    Varint(u64),
+    Fixed32(u32),
+    Fixed64(u64),
+    Bytes(Vec<u8>),
+    Message(DynamicMessage<'a>),
+}
+
+impl Value<'_> {
+    fn is_default(&self) -> bool {
+        match self {
+            Value::Varint(value) | Value::Fixed64(value) => *value == 0,
+            Value::Fixed32(value) => *value == 0,
+            Value::Bytes(value) => value.is_empty(),
+            Value::Message(_) => false,
+        }
+    }
+}
+
+fn signed_value<'a>(ty: Type, value: i64) -> Value<'a> {
+    match ty {
+        Type::Sint32 => Value::Varint(u64::from(((value << 1) ^ (value >> 31)) as u32)),
+        Type::Sint64 => Value::Varint(((value << 1) ^ (value >> 63)) as u64),
+        Type::Sfixed32 => Value::Fixed32(value as u32),
+        Type::Sfixed64 => Value::Fixed64(value as u64),
+        _ => Value::Varint(value as u64),
+    }
+}
+
+fn unsigned_value<'a>(ty: Type, value: u64) -> Value<'a> {
+    match ty {
+        Type::Fixed32 => Value::Fixed32(value as u32),
+        Type::Fixed64 => Value::Fixed64(value),
+        _ => Value::Varint(value),
+    }
+}
+
+fn float_value<'a>(ty: Type, value: f64) -> Value<'a> {
+    if ty == Type::Float {
+        Value::Fixed32((value as f32).to_bits())
+    } else {
+        Value::Fixed64(value.to_bits())
+    }
+}
+
+fn encode_value(number: i32, ty: Type, value: &Value<'_>, buf: &mut Vec<u8>) {
+    let number = number as u32;
+    match value {
+        Value::Varint(value) => {
+            encode_key(number, WireType::Varint, buf);
+            encode_varint(*value, buf);
+        }
+        Value::Fixed32(value) => {
+            encode_key(number, WireType::ThirtyTwoBit, buf);
+            buf.extend_from_slice(&value.to_le_bytes());
+        }
+        Value::Fixed64(value) => {
+            encode_key(number, WireType::SixtyFourBit, buf);
+            buf.extend_from_slice(&value.to_le_bytes());
+        }
+        Value::Bytes(value) => {
+            encode_key(number, WireType::LengthDelimited, buf);
+            encode_varint(value.len() as u64, buf);
+            buf.extend_from_slice(value);
+        }
+        Value::Message(message) if ty == Type::Group => {
+            encode_key(number, WireType::StartGroup, buf);
+            message.encode(buf);
+            encode_key(number, WireType::EndGroup, buf);
+        }
+        Value::Message(message) => {
+            let mut encoded = Vec::new();
+            message.encode(&mut encoded);
+            encode_key(number, WireType::LengthDelimited, buf);
+            encode_varint(encoded.len() as u64, buf);
+            buf.extend_from_slice(&encoded);
+        }
+    }
+}
+
+/// A message parsed from the text format.
+#[derive(Default)]
+struct DynamicMessage<'a> {
+    fields: BTreeMap<i32, (FieldRef<'a>, Vec<Value<'a>>)>,
+    /// Whether the message uses `message_set_wire_format`.
+    message_set: bool,
+}
+
+impl DynamicMessage<'_> {
+    /// Encodes the message like `protoc`, with fields in field number order.
+    fn encode(&self, buf: &mut Vec<u8>) {
+        for (&number, (field, values)) in &self.fields {
+            let ty = field.proto.r#type();
+            if self.message_set && field.proto.extendee.is_some() {
+                // Each extension of a message set is encoded as an item group.
+                for value in values {
+                    encode_key(1, WireType::StartGroup, buf);
+                    encode_key(2, WireType::Varint, buf);
+                    encode_varint(number as u64, buf);
+                    encode_value(3, ty, value, buf);
+                    encode_key(1, WireType::EndGroup, buf);
+                }
+            } else if field.is_repeated() {
+                if field.is_packed() && !values.is_empty() {
+                    let mut packed = Vec::new();
+                    for value in values {
+                        match value {
+                            Value::Varint(value) => encode_varint(*value, &mut packed),
+                            Value::Fixed32(value) => packed.extend_from_slice(&value.to_le_bytes()),
+                            Value::Fixed64(value) => packed.extend_from_slice(&value.to_le_bytes()),
+                            Value::Bytes(_) | Value::Message(_) => unreachable!(),
+                        }
+                    }
+                    encode_key(number as u32, WireType::LengthDelimited, buf);
+                    encode_varint(packed.len() as u64, buf);
+                    buf.extend_from_slice(&packed);
+                } else {
+                    for value in values {
+                        encode_value(number, ty, value, buf);
+                    }
+                }
+            } else if let Some(value) = values.last() {
+                if field.has_presence() || !value.is_default() {
+                    encode_value(number, ty, value, buf);
+                }
+            }
+        }
+    }
+
+    /// Adds the paths of missing required fields to `missing`.
+    fn find_missing_fields(
+        &self,
+        linker: &Linker,
+        type_name: &str,
+        prefix: &str,
+        missing: &mut Vec<String>,
+    ) {
+        let Some((_, descriptor)) = linker.message(type_name) else {
+            return;
+        };
+        for field in &descriptor.field {
+            if field.label() == Label::Required && !self.fields.contains_key(&field.number()) {
+                missing.push(format!("{}{}", prefix, field.name()));
+            }
+        }
+        for (field, values) in self.fields.values() {
+            let repeated = field.is_repeated();
+            for (i, value) in values.iter().enumerate() {
+                if let Value::Message(message) = value {
+                    let name = if field.proto.extendee.is_some() {
+                        format!("({})", &field.proto.name())
+                    } else {
+                        field.proto.name().to_string()
+                    };
+                    let prefix = if repeated {
+                        format!("{}{}[{}].", prefix, name, i)
+                    } else {
+                        format!("{}{}.", prefix, name)
+                    };
+                    message.find_missing_fields(linker, field.proto.type_name(), &prefix, missing);
+                }
+            }
+        }
+    }
+}
+
+/// A parser for aggregate option values, like `TextFormat::Parser` in `protoc`.
+struct TextFormatParser<'a> {
+    linker: &'a Linker,
+    file: usize,
+    input: Tokenizer<'a>,
+}
+
+impl<'a> TextFormatParser<'a> {
+    fn parse(&mut self, type_name: &str) -> Result<DynamicMessage<'a>> {
+        self.next()?;
+        let mut message = DynamicMessage::default();
+        while !self.looking_at_type(TokenType::End) {
+            self.consume_field(type_name, &mut message)?;
+        }
+
+        let mut missing = Vec::new();
+        message.find_missing_fields(self.linker, type_name, "", &mut missing);
+        if !missing.is_empty() {
+            return Err(format!(
+                "Message missing required fields: {}",
+                missing.join(", ")
+            ));
+        }
+        Ok(message)
+    }
+
+    fn next(&mut self) -> Result<()> {
+        self.input.next().map(|_| ()).map_err(|error| error.message)
+    }
+
+    fn text(&self) -> &str {
+        &self.input.current().text
+    }
+
+    fn looking_at(&self, text: &str) -> bool {
+        self.text() == text
+    }
+
+    fn looking_at_type(&self, ty: TokenType) -> bool {
+        self.input.current().ty == ty
+    }
+
+    fn try_consume(&mut self, text: &str) -> Result<bool> {
+        if self.looking_at(text) {
+            self.next()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn consume(&mut self, text: &str) -> Result<()> {
+        if self.try_consume(text)? {
+            Ok(())
+        } else {
+            Err(format!("Expected \"{}\", found \"{}\".", text, self.text()))
+        }
+    }
+
+    fn consume_identifier(&mut self) -> Result<String> {
+        if !self.looking_at_type(TokenType::Identifier) {
+            return Err(format!("Expected identifier, got: {}", self.text()));
+        }
+        let identifier = self.text().to_string();
+        self.next()?;
+        Ok(identifier)
+    }
+
+    fn consume_full_type_name(&mut self) -> Result<String> {
+        let mut name = self.consume_identifier()?;
+        while self.try_consume(".")? {
+            name.push('.');
+            name.push_str(&self.consume_identifier()?);
+        }
+        Ok(name)
+    }
+
+    fn consume_field(&mut self, type_name: &str, message: &mut DynamicMessage<'a>) -> Result<()> {
+        let linker = self.linker;
+        let (message_file, descriptor) = linker
+            .message(type_name)
+            .ok_or_else(|| format!("\"{}\" is not defined.", type_name))?;
+        let message_name = &type_name[1..];
+        let proto3 = linker.is_proto3(message_file);
+
+        let (field, field_name) = if self.try_consume("[")? {
+            let field_name = self.consume_full_type_name()?;
+            if message_name == "google.protobuf.Any" && self.looking_at("/") {
+                return self.consume_any_value(field_name, descriptor, proto3, message);
+            }
+            self.consume("]")?;
+
+            let field = linker
+                .lookup(self.file, &field_name, message_name, false)
+                .ok()
+                .and_then(|(_, symbol)| {
+                    let proto = linker.field(symbol)?;
+                    (proto.extendee() == type_name).then_some(FieldRef {
+                        proto,
+                        proto3: linker.is_proto3(symbol.file),
+                    })
+                })
+                .ok_or_else(|| {
+                    format!(
+                        "Extension \"{}\" is not defined or is not an extension of \"{}\".",
+                        field_name, message_name
+                    )
+                })?;
+            (field, field_name)
+        } else {
+            let field_name = self.consume_identifier()?;
+            let find = |name: &str| descriptor.field.iter().find(|field| field.name() == name);
+            let mut field = find(&field_name);
+            // Groups are named by their type name.
+            if field.is_none() {
+                field = find(&field_name.to_ascii_lowercase())
+                    .filter(|field| field.r#type() == Type::Group);
+            }
+            if let Some(group) = field.filter(|field| field.r#type() == Type::Group) {
+                if !group.type_name().ends_with(&format!(".{}", field_name)) {
+                    field = None;
+                }
+            }
+            let proto = field.ok_or_else(|| {
+                format!(
+                    "Message type \"{}\" has no field named \"{}\".",
+                    message_name, field_name
+                )
+            })?;
+            (FieldRef { proto, proto3 }, field_name)
+        };
+
+        if let Some(oneof_index) = field.proto.oneof_index {
+            let other = message.fields.values().find(|(other, _)| {
+                other.proto.extendee.is_none() && other.proto.oneof_index == Some(oneof_index)
+            });
+            if let Some((other, _)) = other {
+                return Err(format!(
+                    "Field \"{}\" is specified along with field \"{}\", another member of oneof \
+                     \"{}\".",
+                    field_name,
+                    other.proto.name(),
+                    descriptor.oneof_decl[oneof_index as usize].name()
+                ));
+            }
+        }
+
+        if field.is_message() {
+            // The ':' is optional for messages.
+            self.try_consume(":")?;
+        } else {
+            self.consume(":")?;
+        }
+
+        if field.is_repeated() && self.try_consume("[")? {
+            // A list of values, like `foo: [1, 2, 3]`.
+            if !self.try_consume("]")? {
+                loop {
+                    self.consume_field_value(field, message)?;
+                    if self.try_consume("]")? {
+                        break;
+                    }
+                    self.consume(",")?;
+                }
+            }
+        } else {
+            self.consume_field_value(field, message)?;
+        }
+
+        // Fields may be separated by commas or semicolons.
+        if !self.try_consume(";")? {
+            self.try_consume(",")?;
+        }
+        Ok(())
+    }
+
+    fn consume_field_value(
+        &mut self,
+        field: FieldRef<'a>,
+        message: &mut DynamicMessage<'a>,
+    ) -> Result<()> {
+        let (_, values) = message
+            .fields
+            .entry(field.proto.number())
+            .or_insert_with(|| (field, Vec::new()));
+
+        if field.is_message() {
+            // Singular messages are merged.
+            if field.is_repeated() || values.is_empty() {
+                values.push(Value::Message(DynamicMessage::default()));
+            }
+            let Some(Value::Message(sub_message)) = values.last_mut() else {
+                unreachable!()
+            };
+            return self.consume_message(field.proto.type_name(), sub_message);
+        }
+
+        let value = self.consume_scalar(field)?;
+        if !field.is_repeated() {
+            values.clear();
+        }
+        values.push(value);
+        Ok(())
+    }
+
+    fn consume_message(&mut self, type_name: &str, message: &mut DynamicMessage<'a>) -> Result<()> {
+        let delimiter = if self.try_consume("<")? {
+            ">"
+        } else {
+            self.consume("{")?;
+            "}"
+        };
+        message.message_set = self
+            .linker
+            .message(type_name)
+            .and_then(|(_, descriptor)| descriptor.options.as_ref())
+            .is_some_and(|options| options.message_set_wire_format());
+        while !self.try_consume(delimiter)? {
+            if self.looking_at_type(TokenType::End) {
+                return Err(format!("Expected \"{}\".", delimiter));
+            }
+            self.consume_field(type_name, message)?;
+        }
+        Ok(())
+    }
+
+    fn consume_any_value(
+        &mut self,
+        prefix: String,
+        descriptor: &'a DescriptorProto,
+        proto3: bool,
+        message: &mut DynamicMessage<'a>,
+    ) -> Result<()> {
+        self.consume("/")?;
+        let full_type_name = self.consume_full_type_name()?;
+        self.consume("]")?;
+        self.try_consume(":")?;
+
+        let type_url = format!("{}/{}", prefix, full_type_name);
+        let value_type = format!(".{}", full_type_name);
+        let known_prefix = prefix == "type.googleapis.com" || prefix == "type.googleprod.com";
+        if !known_prefix || self.linker.message(&value_type).is_none() {
+            return Err(format!(
+                "Could not find type \"{}\" stored in google.protobuf.Any.",
+                type_url
+            ));
+        }
+
+        let mut value = DynamicMessage::default();
+        self.consume_message(&value_type, &mut value)?;
+        let mut encoded = Vec::new();
+        value.encode(&mut encoded);
+
+        for (number, value) in [(1, type_url.into_bytes()), (2, encoded)] {
+            let proto = descriptor
+                .field
+                .iter()
+                .find(|field| field.number() == number)
+                .unwrap();
+            message.fields.insert(
+                number,
+                (FieldRef { proto, proto3 }, vec![Value::Bytes(value)]),
+            );
+        }
+        Ok(())
+    }
+
+    fn consume_scalar(&mut self, field: FieldRef<'_>) -> Result<Value<'a>> {
+        let ty = field.proto.r#type();
+        Ok(match ty {
+            Type::Int32 | Type::Sint32 | Type::Sfixed32 => {
+                signed_value(ty, self.consume_signed_integer(i32::MAX as u64)?)
+            }
+            Type::Int64 | Type::Sint64 | Type::Sfixed64 => {
+                signed_value(ty, self.consume_signed_integer(i64::MAX as u64)?)
+            }
+            Type::Uint32 | Type::Fixed32 => {
+                unsigned_value(ty, self.consume_unsigned_integer(u32::MAX.into())?)
+            }
+            Type::Uint64 | Type::Fixed64 => {
+                unsigned_value(ty, self.consume_unsigned_integer(u64::MAX)?)
+            }
+            Type::Float | Type::Double => float_value(ty, self.consume_double()?),
+            Type::Bool => {
+                if self.looking_at_type(TokenType::Integer) {
+                    Value::Varint(self.consume_unsigned_integer(1)?)
+                } else {
+                    let value = self.consume_identifier()?;
+                    match value.as_str() {
+                        "true" | "True" | "t" => Value::Varint(1),
+                        "false" | "False" | "f" => Value::Varint(0),
+                        _ => {
+                            return Err(format!(
+                                "Invalid value for boolean field \"{}\". Value: \"{}\".",
+                                field.proto.name(),
+                                value
+                            ));
+                        }
+                    }
+                }
+            }
+            Type::String | Type::Bytes => {
+                if !self.looking_at_type(TokenType::String) {
+                    return Err(format!("Expected string, got: {}", self.text()));
+                }
+                let mut value = Vec::new();
+                while self.looking_at_type(TokenType::String) {
+                    tokenizer::parse_string_append(self.text(), &mut value);
+                    self.next()?;
+                }
+                Value::Bytes(value)
+            }
+            Type::Enum => {
+                let (enum_file, enum_type) =
+                    self.linker.enum_type(field.proto.type_name()).unwrap();
+                let unknown = |value: &str| {
+                    format!(
+                        "Unknown enumeration value of \"{}\" for field \"{}\".",
+                        value,
+                        field.proto.name()
+                    )
+                };
+                if self.looking_at_type(TokenType::Identifier) {
+                    let name = self.consume_identifier()?;
+                    let value = enum_type.value.iter().find(|value| value.name() == name);
+                    Value::Varint(i64::from(value.ok_or_else(|| unknown(&name))?.number()) as u64)
+                } else if self.looking_at("-") || self.looking_at_type(TokenType::Integer) {
+                    let text = format!(
+                        "{}{}",
+                        if self.looking_at("-") { "-" } else { "" },
+                        self.text()
+                    );
+                    let number = self.consume_signed_integer(i32::MAX as u64)?;
+                    let known = enum_type
+                        .value
+                        .iter()
+                        .any(|value| i64::from(value.number()) == number);
+                    // Unknown values are only allowed for open enums.
+                    if !known && !self.linker.is_proto3(enum_file) {
+                        return Err(unknown(&text));
+                    }
+                    Value::Varint(number as u64)
+                } else {
+                    return Err(format!(
+                        "Expected integer or identifier, got: {}",
+                        self.text()
+                    ));
+                }
+            }
+            Type::Message | Type::Group => unreachable!(),
+        })
+    }
+
+    fn consume_unsigned_integer(&mut self, max_value: u64) -> Result<u64> {
+        if !self.looking_at_type(TokenType::Integer) {
+            return Err(format!("Expected integer, got: {}", self.text()));
+        }
+        let value = tokenizer::parse_integer(self.text(), max_value)
+            .ok_or_else(|| format!("Integer out of range ({})", self.text()))?;
+        self.next()?;
+        Ok(value)
+    }
+
+    fn consume_signed_integer(&mut self, max_value: u64) -> Result<i64> {
+        let negative = self.try_consume("-")?;
+        let value = self.consume_unsigned_integer(max_value + u64::from(negative))?;
+        Ok(if negative {
+            0i64.wrapping_sub(value as i64)
+        } else {
+            value as i64
+        })
+    }
+
+    fn consume_double(&mut self) -> Result<f64> {
+        let negative = self.try_consume("-")?;
+        let value = match self.input.current().ty {
+            TokenType::Integer => {
+                let text = self.text();
+                if text.len() > 1 && text.starts_with('0') {
+                    return Err(format!("Expect a decimal number, got: {}", text));
+                }
+                text.parse::<f64>().unwrap_or(f64::INFINITY)
+            }
+            TokenType::Float => tokenizer::parse_float(self.text()),
+            TokenType::Identifier => match self.text().to_ascii_lowercase().as_str() {
+                "inf" | "infinity" => f64::INFINITY,
+                "nan" => f64::NAN,
+                _ => return Err(format!("Expected double, got: {}", self.text())),
+            },
+            _ => return Err(format!("Expected double, got: {}", self.text())),
+        };
+        self.next()?;
+        Ok(if negative { -value } else { value })
+    }
+}
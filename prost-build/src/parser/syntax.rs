@@ -0,0 +1,1845 @@
+//! A recursive descent parser for `.proto` files.
+//!
+//! The parser mirrors `google::protobuf::compiler::Parser`: it produces a `FileDescriptorProto`
+//! with unresolved type names and uninterpreted options, along with the same source code info
+//! that `protoc` records.
+
+use std::collections::HashSet;
+use std::mem;
+
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::source_code_info::Location;
+use prost_types::uninterpreted_option::NamePart;
+use prost_types::{
+    descriptor_proto, enum_descriptor_proto, DescriptorProto, EnumDescriptorProto,
+    EnumValueDescriptorProto, FieldDescriptorProto, FileDescriptorProto, MessageOptions,
+    MethodDescriptorProto, OneofDescriptorProto, ServiceDescriptorProto, SourceCodeInfo,
+    UninterpretedOption,
+};
+
+use super::tokenizer::{self, TokenType, Tokenizer};
+use super::{c_escape, simple_dtoa, tags, ParseError};
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+/// The end of a range which was specified as `max`, before it is known whether the message uses
+/// the message set wire format.
+const MAX_RANGE_SENTINEL: i32 = -1;
+
+/// One past the largest valid field number.
+const MAX_FIELD_NUMBER_END: i32 = 536_870_912;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OptionStyle {
+    /// An option statement, like `option foo = 1;`.
+    Statement,
+    /// An option in brackets, like `[foo = 1]`.
+    Assignment,
+}
+
+struct MapField {
+    key_type: Option<Type>,
+    key_type_name: String,
+    value_type: Option<Type>,
+    value_type_name: String,
+}
+
+/// Parses the contents of a `.proto` file.
+pub(super) fn parse(input: &str) -> Result<FileDescriptorProto> {
+    let mut parser = Parser {
+        input: Tokenizer::new(input),
+        syntax: String::new(),
+        locations: Vec::new(),
+        upcoming_doc_comments: String::new(),
+        upcoming_detached_comments: Vec::new(),
+    };
+    parser.parse_file()
+}
+
+fn scalar_type(name: &str) -> Option<Type> {
+    Some(match name {
+        "double" => Type::Double,
+        "float" => Type::Float,
+        "uint64" => Type::Uint64,
+        "fixed64" => Type::Fixed64,
+        "fixed32" => Type::Fixed32,
+        "bool" => Type::Bool,
+        "string" => Type::String,
+        "group" => Type::Group,
+        "bytes" => Type::Bytes,
+        "uint32" => Type::Uint32,
+        "sfixed32" => Type::Sfixed32,
+        "sfixed64" => Type::Sfixed64,
+        "int32" => Type::Int32,
+        "int64" => Type::Int64,
+        "sint32" => Type::Sint32,
+        "sint64" => Type::Sint64,
+        _ => return None,
+    })
+}
+
+/// Returns the name of the generated entry message of a map field, e.g. `FooMapEntry` for
+/// `foo_map`.
+fn map_entry_name(field_name: &str) -> String {
+    let mut result = String::with_capacity(field_name.len() + 5);
+    let mut cap_next = true;
+    for c in field_name.chars() {
+        if c == '_' {
+            cap_next = true;
+        } else if cap_next {
+            result.push(c.to_ascii_uppercase());
+            cap_next = false;
+        } else {
+            result.push(c);
+        }
+    }
+    result.push_str("Entry");
+    result
+}
+
+fn is_message_set_wire_format(message: &DescriptorProto) -> bool {
+    message.options.iter().any(|options| {
+        options.uninterpreted_option.iter().any(|option| {
+            option.name.len() == 1
+                && option.name[0].name_part == "message_set_wire_format"
+                && option.identifier_value.as_deref() == Some("true")
+        })
+    })
+}
+
+struct Parser<'a> {
+    input: Tokenizer<'a>,
+    syntax: String,
+    locations: Vec<Location>,
+    upcoming_doc_comments: String,
+    upcoming_detached_comments: Vec<String>,
+}
+
+/// An index into the locations recorded by the parser, see `LocationRecorder` in `parser.cc`.
+///
+/// A location starts at the current token when it is created, and unless its end is set
+/// explicitly, ends at the previous token when it is closed.
+#[derive(Clone, Copy)]
+struct Loc(usize);
+
+impl Parser<'_> {
+    // Token helpers.
+
+    fn looking_at(&self, text: &str) -> bool {
+        self.input.current().text == text
+    }
+
+    fn looking_at_type(&self, ty: TokenType) -> bool {
+        self.input.current().ty == ty
+    }
+
+    fn at_end(&self) -> bool {
+        self.looking_at_type(TokenType::End)
+    }
+
+    fn next(&mut self) -> Result<()> {
+        self.input.next()?;
+        Ok(())
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        let token = self.input.current();
+        ParseError::new(token.line, token.column, message)
+    }
+
+    fn try_consume(&mut self, text: &str) -> Result<bool> {
+        if self.looking_at(text) {
+            self.next()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn consume(&mut self, text: &str) -> Result<()> {
+        self.consume_with(text, &format!("Expected \"{}\".", text))
+    }
+
+    fn consume_with(&mut self, text: &str, error: &str) -> Result<()> {
+        if self.try_consume(text)? {
+            Ok(())
+        } else {
+            Err(self.error(error))
+        }
+    }
+
+    fn consume_identifier(&mut self, error: &str) -> Result<String> {
+        if self.looking_at_type(TokenType::Identifier) {
+            let identifier = self.input.current().text.clone();
+            self.next()?;
+            Ok(identifier)
+        } else {
+            Err(self.error(error))
+        }
+    }
+
+    fn consume_integer(&mut self, error: &str) -> Result<i32> {
+        Ok(self.consume_integer64(i32::MAX as u64, error)? as i32)
+    }
+
+    fn consume_signed_integer(&mut self, error: &str) -> Result<i32> {
+        let is_negative = self.try_consume("-")?;
+        let max_value = i32::MAX as u64 + u64::from(is_negative);
+        let value = self.consume_integer64(max_value, error)?;
+        Ok(if is_negative {
+            (value as i64).wrapping_neg() as i32
+        } else {
+            value as i32
+        })
+    }
+
+    fn consume_integer64(&mut self, max_value: u64, error: &str) -> Result<u64> {
+        if !self.looking_at_type(TokenType::Integer) {
+            return Err(self.error(error));
+        }
+        match tokenizer::parse_integer(&self.input.current().text, max_value) {
+            Some(value) => {
+                self.next()?;
+                Ok(value)
+            }
+            None => Err(self.error("Integer out of range.")),
+        }
+    }
+
+    fn consume_number(&mut self, error: &str) -> Result<f64> {
+        let value = if self.looking_at_type(TokenType::Float) {
+            tokenizer::parse_float(&self.input.current().text)
+        } else if self.looking_at_type(TokenType::Integer) {
+            // Also accept integers.
+            match tokenizer::parse_integer(&self.input.current().text, u64::MAX) {
+                Some(value) => value as f64,
+                None => return Err(self.error("Integer out of range.")),
+            }
+        } else if self.looking_at("inf") {
+            f64::INFINITY
+        } else if self.looking_at("nan") {
+            f64::NAN
+        } else {
+            return Err(self.error(error));
+        };
+        self.next()?;
+        Ok(value)
+    }
+
+    fn consume_string(&mut self, error: &str) -> Result<Vec<u8>> {
+        if !self.looking_at_type(TokenType::String) {
+            return Err(self.error(error));
+        }
+        let mut value = Vec::new();
+        // Adjacent string literals are concatenated.
+        while self.looking_at_type(TokenType::String) {
+            tokenizer::parse_string_append(&self.input.current().text, &mut value);
+            self.next()?;
+        }
+        Ok(value)
+    }
+
+    fn consume_utf8_string(&mut self, error: &str) -> Result<String> {
+        let token = self.input.current().clone();
+        String::from_utf8(self.consume_string(error)?)
+            .map_err(|_| ParseError::new(token.line, token.column, "String must be valid UTF-8."))
+    }
+
+    fn try_consume_end_of_declaration(
+        &mut self,
+        text: &str,
+        location: Option<Loc>,
+    ) -> Result<bool> {
+        if !self.looking_at(text) {
+            return Ok(false);
+        }
+
+        let comments = self.input.next_with_comments()?;
+
+        // Save the leading comments for next time, and recall the leading comments from last time.
+        let leading = mem::replace(&mut self.upcoming_doc_comments, comments.leading);
+
+        if let Some(location) = location {
+            let detached = mem::replace(&mut self.upcoming_detached_comments, comments.detached);
+            let location = &mut self.locations[location.0];
+            if !leading.is_empty() {
+                location.leading_comments = Some(leading);
+            }
+            if !comments.trailing.is_empty() {
+                location.trailing_comments = Some(comments.trailing);
+            }
+            location.leading_detached_comments = detached;
+        } else if text == "}" {
+            // Drop pending detached comments at the end of a scope.
+            self.upcoming_detached_comments = comments.detached;
+        } else {
+            self.upcoming_detached_comments.extend(comments.detached);
+        }
+
+        Ok(true)
+    }
+
+    fn consume_end_of_declaration(&mut self, text: &str, location: Option<Loc>) -> Result<()> {
+        if self.try_consume_end_of_declaration(text, location)? {
+            Ok(())
+        } else {
+            Err(self.error(format!("Expected \"{}\".", text)))
+        }
+    }
+
+    // Location helpers.
+
+    fn root_location(&mut self) -> Loc {
+        self.push_location(Vec::new())
+    }
+
+    fn location(&mut self, parent: Loc, path: &[i32]) -> Loc {
+        let mut parent_path = self.locations[parent.0].path.clone();
+        parent_path.extend_from_slice(path);
+        self.push_location(parent_path)
+    }
+
+    fn push_location(&mut self, path: Vec<i32>) -> Loc {
+        let token = self.input.current();
+        self.locations.push(Location {
+            path,
+            span: vec![token.line, token.column],
+            ..Location::default()
+        });
+        Loc(self.locations.len() - 1)
+    }
+
+    fn add_path(&mut self, location: Loc, component: i32) {
+        self.locations[location.0].path.push(component);
+    }
+
+    fn start_at(&mut self, location: Loc, line: i32, column: i32) {
+        let span = &mut self.locations[location.0].span;
+        span[0] = line;
+        span[1] = column;
+    }
+
+    fn end_at(&mut self, location: Loc, line: i32, end_column: i32) {
+        let span = &mut self.locations[location.0].span;
+        if line != span[0] {
+            span.push(line);
+        }
+        span.push(end_column);
+    }
+
+    fn end_at_token(&mut self, location: Loc, token: &tokenizer::Token) {
+        self.end_at(location, token.line, token.end_column);
+    }
+
+    /// Ends the location at the previous token, unless its end was already set.
+    fn close(&mut self, location: Loc) {
+        if self.locations[location.0].span.len() <= 2 {
+            let previous = self.input.previous();
+            let (line, end_column) = (previous.line, previous.end_column);
+            self.end_at(location, line, end_column);
+        }
+    }
+
+    fn path_len(&self, location: Loc) -> usize {
+        self.locations[location.0].path.len()
+    }
+
+    // Files.
+
+    fn parse_file(&mut self) -> Result<FileDescriptorProto> {
+        let mut file = FileDescriptorProto::default();
+
+        if self.looking_at_type(TokenType::Start) {
+            // Advance to the first token.
+            let comments = self.input.next_with_comments()?;
+            self.upcoming_detached_comments = comments.detached;
+            self.upcoming_doc_comments = comments.leading;
+        }
+
+        let root = self.root_location();
+
+        if self.looking_at("syntax") {
+            self.parse_syntax_identifier(root)?;
+            file.syntax = Some(self.syntax.clone());
+        } else {
+            self.syntax = "proto2".to_string();
+        }
+
+        while !self.at_end() {
+            self.parse_top_level_statement(&mut file, root)?;
+        }
+        self.close(root);
+
+        file.source_code_info = Some(SourceCodeInfo {
+            location: mem::take(&mut self.locations),
+        });
+        Ok(file)
+    }
+
+    fn parse_syntax_identifier(&mut self, parent: Loc) -> Result<()> {
+        let location = self.location(parent, &[tags::file::SYNTAX]);
+        self.consume_with(
+            "syntax",
+            "File must begin with a syntax statement, e.g. 'syntax = \"proto2\";'.",
+        )?;
+        self.consume("=")?;
+        let syntax_token = self.input.current().clone();
+        let syntax = self.consume_utf8_string("Expected syntax identifier.")?;
+        self.consume_end_of_declaration(";", Some(location))?;
+
+        if syntax != "proto2" && syntax != "proto3" {
+            return Err(ParseError::new(
+                syntax_token.line,
+                syntax_token.column,
+                format!(
+                    "Unrecognized syntax identifier \"{}\".  This parser only recognizes \"proto2\" and \"proto3\".",
+                    syntax
+                ),
+            ));
+        }
+        self.syntax = syntax;
+        self.close(location);
+        Ok(())
+    }
+
+    fn parse_top_level_statement(
+        &mut self,
+        file: &mut FileDescriptorProto,
+        root: Loc,
+    ) -> Result<()> {
+        if self.try_consume_end_of_declaration(";", None)? {
+            // Empty statement; ignore.
+        } else if self.looking_at("message") {
+            let location = self.location(
+                root,
+                &[tags::file::MESSAGE_TYPE, file.message_type.len() as i32],
+            );
+            file.message_type.push(DescriptorProto::default());
+            self.parse_message_definition(file.message_type.last_mut().unwrap(), location)?;
+            self.close(location);
+        } else if self.looking_at("enum") {
+            let location =
+                self.location(root, &[tags::file::ENUM_TYPE, file.enum_type.len() as i32]);
+            file.enum_type.push(EnumDescriptorProto::default());
+            self.parse_enum_definition(file.enum_type.last_mut().unwrap(), location)?;
+            self.close(location);
+        } else if self.looking_at("service") {
+            let location = self.location(root, &[tags::file::SERVICE, file.service.len() as i32]);
+            file.service.push(ServiceDescriptorProto::default());
+            self.parse_service_definition(file.service.last_mut().unwrap(), location)?;
+            self.close(location);
+        } else if self.looking_at("extend") {
+            let location = self.location(root, &[tags::file::EXTENSION]);
+            self.parse_extend(
+                &mut file.extension,
+                &mut file.message_type,
+                root,
+                tags::file::MESSAGE_TYPE,
+                location,
+            )?;
+            self.close(location);
+        } else if self.looking_at("import") {
+            self.parse_import(file, root)?;
+        } else if self.looking_at("package") {
+            self.parse_package(file, root)?;
+        } else if self.looking_at("option") {
+            let location = self.location(root, &[tags::file::OPTIONS]);
+            let options = file.options.get_or_insert_with(Default::default);
+            self.parse_option(
+                &mut options.uninterpreted_option,
+                location,
+                OptionStyle::Statement,
+            )?;
+            self.close(location);
+        } else {
+            return Err(self.error("Expected top-level statement (e.g. \"message\")."));
+        }
+        Ok(())
+    }
+
+    fn parse_package(&mut self, file: &mut FileDescriptorProto, root: Loc) -> Result<()> {
+        if file.package.is_some() {
+            return Err(self.error("Multiple package definitions."));
+        }
+
+        let location = self.location(root, &[tags::file::PACKAGE]);
+        self.consume("package")?;
+
+        let mut package = String::new();
+        loop {
+            package.push_str(&self.consume_identifier("Expected identifier.")?);
+            if !self.try_consume(".")? {
+                break;
+            }
+            package.push('.');
+        }
+        file.package = Some(package);
+
+        self.consume_end_of_declaration(";", Some(location))?;
+        self.close(location);
+        Ok(())
+    }
+
+    fn parse_import(&mut self, file: &mut FileDescriptorProto, root: Loc) -> Result<()> {
+        let location = self.location(
+            root,
+            &[tags::file::DEPENDENCY, file.dependency.len() as i32],
+        );
+        self.consume("import")?;
+
+        if self.looking_at("public") {
+            let public_location = self.location(
+                root,
+                &[
+                    tags::file::PUBLIC_DEPENDENCY,
+                    file.public_dependency.len() as i32,
+                ],
+            );
+            self.consume("public")?;
+            self.close(public_location);
+            file.public_dependency.push(file.dependency.len() as i32);
+        } else if self.looking_at("weak") {
+            let weak_location = self.location(
+                root,
+                &[
+                    tags::file::WEAK_DEPENDENCY,
+                    file.weak_dependency.len() as i32,
+                ],
+            );
+            self.consume("weak")?;
+            self.close(weak_location);
+            file.weak_dependency.push(file.dependency.len() as i32);
+        }
+
+        let import = self.consume_utf8_string("Expected a string naming the file to import.")?;
+        file.dependency.push(import);
+
+        self.consume_end_of_declaration(";", Some(location))?;
+        self.close(location);
+        Ok(())
+    }
+
+    // Messages.
+
+    fn parse_message_definition(
+        &mut self,
+        message: &mut DescriptorProto,
+        location: Loc,
+    ) -> Result<()> {
+        self.consume("message")?;
+        let name_location = self.location(location, &[tags::message::NAME]);
+        message.name = Some(self.consume_identifier("Expected message name.")?);
+        self.close(name_location);
+
+        self.parse_message_block(message, location)?;
+
+        if self.syntax == "proto3" {
+            // Add synthetic oneofs for optional fields, with names which don't conflict with any
+            // other field or oneof.
+            let mut names: HashSet<String> = message
+                .field
+                .iter()
+                .map(|field| field.name().to_string())
+                .chain(
+                    message
+                        .oneof_decl
+                        .iter()
+                        .map(|oneof| oneof.name().to_string()),
+                )
+                .collect();
+
+            for field in &mut message.field {
+                if !field.proto3_optional() {
+                    continue;
+                }
+                let mut oneof_name = field.name().to_string();
+                if !oneof_name.starts_with('_') {
+                    oneof_name.insert(0, '_');
+                }
+                while names.contains(&oneof_name) {
+                    oneof_name.insert(0, 'X');
+                }
+                names.insert(oneof_name.clone());
+                field.oneof_index = Some(message.oneof_decl.len() as i32);
+                message.oneof_decl.push(OneofDescriptorProto {
+                    name: Some(oneof_name),
+                    options: None,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_message_block(&mut self, message: &mut DescriptorProto, location: Loc) -> Result<()> {
+        self.consume_end_of_declaration("{", Some(location))?;
+
+        while !self.try_consume_end_of_declaration("}", None)? {
+            if self.at_end() {
+                return Err(self.error("Reached end of input in message definition (missing '}')."));
+            }
+            self.parse_message_statement(message, location)?;
+        }
+
+        // The end of ranges specified as `max` depends on the message options.
+        let max_end = if is_message_set_wire_format(message) {
+            i32::MAX
+        } else {
+            MAX_FIELD_NUMBER_END
+        };
+        for range in &mut message.extension_range {
+            if range.end == Some(MAX_RANGE_SENTINEL) {
+                range.end = Some(max_end);
+            }
+        }
+        for range in &mut message.reserved_range {
+            if range.end == Some(MAX_RANGE_SENTINEL) {
+                range.end = Some(max_end);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_message_statement(
+        &mut self,
+        message: &mut DescriptorProto,
+        message_location: Loc,
+    ) -> Result<()> {
+        if self.try_consume_end_of_declaration(";", None)? {
+            // Empty statement; ignore.
+        } else if self.looking_at("message") {
+            let location = self.location(
+                message_location,
+                &[tags::message::NESTED_TYPE, message.nested_type.len() as i32],
+            );
+            message.nested_type.push(DescriptorProto::default());
+            self.parse_message_definition(message.nested_type.last_mut().unwrap(), location)?;
+            self.close(location);
+        } else if self.looking_at("enum") {
+            let location = self.location(
+                message_location,
+                &[tags::message::ENUM_TYPE, message.enum_type.len() as i32],
+            );
+            message.enum_type.push(EnumDescriptorProto::default());
+            self.parse_enum_definition(message.enum_type.last_mut().unwrap(), location)?;
+            self.close(location);
+        } else if self.looking_at("extensions") {
+            let location = self.location(message_location, &[tags::message::EXTENSION_RANGE]);
+            self.parse_extensions(message, location)?;
+            self.close(location);
+        } else if self.looking_at("reserved") {
+            self.parse_message_reserved(message, message_location)?;
+        } else if self.looking_at("extend") {
+            let location = self.location(message_location, &[tags::message::EXTENSION]);
+            self.parse_extend(
+                &mut message.extension,
+                &mut message.nested_type,
+                message_location,
+                tags::message::NESTED_TYPE,
+                location,
+            )?;
+            self.close(location);
+        } else if self.looking_at("option") {
+            let location = self.location(message_location, &[tags::message::OPTIONS]);
+            let options = message.options.get_or_insert_with(MessageOptions::default);
+            self.parse_option(
+                &mut options.uninterpreted_option,
+                location,
+                OptionStyle::Statement,
+            )?;
+            self.close(location);
+        } else if self.looking_at("oneof") {
+            let oneof_index = message.oneof_decl.len();
+            let location = self.location(
+                message_location,
+                &[tags::message::ONEOF_DECL, oneof_index as i32],
+            );
+            message.oneof_decl.push(OneofDescriptorProto::default());
+            self.parse_oneof(message, oneof_index, location, message_location)?;
+            self.close(location);
+        } else {
+            let location = self.location(
+                message_location,
+                &[tags::message::FIELD, message.field.len() as i32],
+            );
+            message.field.push(FieldDescriptorProto::default());
+            self.parse_message_field(
+                message.field.last_mut().unwrap(),
+                &mut message.nested_type,
+                message_location,
+                tags::message::NESTED_TYPE,
+                location,
+            )?;
+            self.close(location);
+        }
+        Ok(())
+    }
+
+    fn parse_message_field(
+        &mut self,
+        field: &mut FieldDescriptorProto,
+        messages: &mut Vec<DescriptorProto>,
+        parent_location: Loc,
+        nested_type_number: i32,
+        field_location: Loc,
+    ) -> Result<()> {
+        if let Some(label) = self.parse_label(field_location)? {
+            field.set_label(label);
+            if label == Label::Optional && self.syntax == "proto3" {
+                field.proto3_optional = Some(true);
+            }
+        }
+
+        self.parse_message_field_no_label(
+            field,
+            messages,
+            parent_location,
+            nested_type_number,
+            field_location,
+        )
+    }
+
+    fn parse_message_field_no_label(
+        &mut self,
+        field: &mut FieldDescriptorProto,
+        messages: &mut Vec<DescriptorProto>,
+        parent_location: Loc,
+        nested_type_number: i32,
+        field_location: Loc,
+    ) -> Result<()> {
+        let mut map_field = None;
+
+        // Parse the type. Its path is only known once the type is parsed.
+        let location = self.location(field_location, &[]);
+        let mut type_parsed = false;
+        let mut ty = None;
+        let mut type_name = String::new();
+
+        // A field is only treated as a map field if the type name `map` is followed by `<`.
+        if self.try_consume("map")? {
+            if self.looking_at("<") {
+                map_field = Some(self.parse_map_type(field, location)?);
+            } else {
+                type_parsed = true;
+                type_name = "map".to_string();
+            }
+        }
+        if map_field.is_none() {
+            if field.label.is_none() && self.syntax == "proto3" {
+                field.set_label(Label::Optional);
+            }
+            if field.label.is_none() {
+                return Err(self.error("Expected \"required\", \"optional\", or \"repeated\"."));
+            }
+
+            if !type_parsed {
+                (ty, type_name) = self.parse_type()?;
+            }
+            if type_name.is_empty() {
+                self.add_path(location, tags::field::TYPE);
+                field.set_type(ty.unwrap());
+            } else {
+                self.add_path(location, tags::field::TYPE_NAME);
+                field.type_name = Some(type_name);
+            }
+        }
+        self.close(location);
+
+        // Parse the name and number.
+        let name_token = self.input.current().clone();
+        let location = self.location(field_location, &[tags::field::NAME]);
+        field.name = Some(self.consume_identifier("Expected field name.")?);
+        self.close(location);
+
+        self.consume_with("=", "Missing field number.")?;
+
+        let location = self.location(field_location, &[tags::field::NUMBER]);
+        field.number = Some(self.consume_integer("Expected field number.")?);
+        self.close(location);
+
+        self.parse_field_options(field, field_location)?;
+
+        if field.r#type == Some(Type::Group as i32) {
+            // A group declares both a message type and a field, so their locations overlap.
+            let group_location = self.location(parent_location, &[]);
+            let span = self.locations[field_location.0].span.clone();
+            self.start_at(group_location, span[0], span[1]);
+            self.add_path(group_location, nested_type_number);
+            self.add_path(group_location, messages.len() as i32);
+
+            let group_name = field.name().to_string();
+
+            // The group name and the field's type name both come from the field name.
+            let location = self.location(group_location, &[tags::message::NAME]);
+            self.start_at(location, name_token.line, name_token.column);
+            self.end_at_token(location, &name_token);
+            let location = self.location(field_location, &[tags::field::TYPE_NAME]);
+            self.start_at(location, name_token.line, name_token.column);
+            self.end_at_token(location, &name_token);
+
+            if !group_name.starts_with(|c: char| c.is_ascii_uppercase()) {
+                return Err(ParseError::new(
+                    name_token.line,
+                    name_token.column,
+                    "Group names must start with a capital letter.",
+                ));
+            }
+            field.name = Some(group_name.to_ascii_lowercase());
+            field.type_name = Some(group_name.clone());
+
+            messages.push(DescriptorProto {
+                name: Some(group_name),
+                ..DescriptorProto::default()
+            });
+            if !self.looking_at("{") {
+                return Err(self.error("Missing group body."));
+            }
+            self.parse_message_block(messages.last_mut().unwrap(), group_location)?;
+            self.close(group_location);
+        } else {
+            self.consume_end_of_declaration(";", Some(field_location))?;
+        }
+
+        if let Some(map_field) = map_field {
+            generate_map_entry(map_field, field, messages);
+        }
+
+        Ok(())
+    }
+
+    fn parse_map_type(
+        &mut self,
+        field: &mut FieldDescriptorProto,
+        type_name_location: Loc,
+    ) -> Result<MapField> {
+        if field.oneof_index.is_some() {
+            return Err(self.error("Map fields are not allowed in oneofs."));
+        }
+        if field.label.is_some() {
+            return Err(self.error(
+                "Field labels (required/optional/repeated) are not allowed on map fields.",
+            ));
+        }
+        if field.extendee.is_some() {
+            return Err(self.error("Map fields are not allowed to be extensions."));
+        }
+        field.set_label(Label::Repeated);
+
+        self.consume("<")?;
+        let (key_type, key_type_name) = self.parse_type()?;
+        self.consume(",")?;
+        let (value_type, value_type_name) = self.parse_type()?;
+        self.consume(">")?;
+
+        // The type name of the field is set once its name is known.
+        self.add_path(type_name_location, tags::field::TYPE_NAME);
+
+        Ok(MapField {
+            key_type,
+            key_type_name,
+            value_type,
+            value_type_name,
+        })
+    }
+
+    fn parse_field_options(
+        &mut self,
+        field: &mut FieldDescriptorProto,
+        field_location: Loc,
+    ) -> Result<()> {
+        if !self.looking_at("[") {
+            return Ok(());
+        }
+
+        let location = self.location(field_location, &[tags::field::OPTIONS]);
+        self.consume("[")?;
+
+        loop {
+            if self.looking_at("default") {
+                // The default value is not actually an option, so it is recorded relative to the
+                // field.
+                self.parse_default_assignment(field, field_location)?;
+            } else if self.looking_at("json_name") {
+                self.parse_json_name(field, field_location)?;
+            } else {
+                let options = field.options.get_or_insert_with(Default::default);
+                self.parse_option(
+                    &mut options.uninterpreted_option,
+                    location,
+                    OptionStyle::Assignment,
+                )?;
+            }
+            if !self.try_consume(",")? {
+                break;
+            }
+        }
+
+        self.consume("]")?;
+        self.close(location);
+        Ok(())
+    }
+
+    fn parse_default_assignment(
+        &mut self,
+        field: &mut FieldDescriptorProto,
+        field_location: Loc,
+    ) -> Result<()> {
+        if field.default_value.is_some() {
+            return Err(self.error("Already set option \"default\"."));
+        }
+
+        self.consume("default")?;
+        self.consume("=")?;
+
+        let location = self.location(field_location, &[tags::field::DEFAULT_VALUE]);
+        let mut default_value = String::new();
+
+        match field.r#type.and_then(|ty| Type::try_from(ty).ok()) {
+            None => {
+                // The type is a message or an enum, which is not known yet. Take the current
+                // token, which is checked once the type is resolved.
+                default_value = self.input.current().text.clone();
+                self.next()?;
+            }
+            Some(
+                ty @ (Type::Int32
+                | Type::Int64
+                | Type::Sint32
+                | Type::Sint64
+                | Type::Sfixed32
+                | Type::Sfixed64),
+            ) => {
+                let mut max_value = match ty {
+                    Type::Int32 | Type::Sint32 | Type::Sfixed32 => i32::MAX as u64,
+                    _ => i64::MAX as u64,
+                };
+                if self.try_consume("-")? {
+                    default_value.push('-');
+                    max_value += 1;
+                }
+                let value =
+                    self.consume_integer64(max_value, "Expected integer for field default value.")?;
+                default_value.push_str(&value.to_string());
+            }
+            Some(ty @ (Type::Uint32 | Type::Uint64 | Type::Fixed32 | Type::Fixed64)) => {
+                let max_value = match ty {
+                    Type::Uint32 | Type::Fixed32 => u32::MAX as u64,
+                    _ => u64::MAX,
+                };
+                if self.looking_at("-") {
+                    return Err(self.error("Unsigned field can't have negative default value."));
+                }
+                let value =
+                    self.consume_integer64(max_value, "Expected integer for field default value.")?;
+                default_value.push_str(&value.to_string());
+            }
+            Some(Type::Float | Type::Double) => {
+                if self.try_consume("-")? {
+                    default_value.push('-');
+                }
+                let value = self.consume_number("Expected number.")?;
+                default_value.push_str(&simple_dtoa(value));
+            }
+            Some(Type::Bool) => {
+                if self.try_consume("true")? {
+                    default_value = "true".to_string();
+                } else if self.try_consume("false")? {
+                    default_value = "false".to_string();
+                } else {
+                    return Err(self.error("Expected \"true\" or \"false\"."));
+                }
+            }
+            Some(Type::String) => {
+                default_value =
+                    self.consume_utf8_string("Expected string for field default value.")?;
+            }
+            Some(Type::Bytes) => {
+                default_value = c_escape(&self.consume_string("Expected string.")?);
+            }
+            Some(Type::Enum) => {
+                default_value =
+                    self.consume_identifier("Expected enum identifier for field default value.")?;
+            }
+            Some(Type::Message | Type::Group) => {
+                return Err(self.error("Messages can't have default values."));
+            }
+        }
+
+        field.default_value = Some(default_value);
+        self.close(location);
+        Ok(())
+    }
+
+    fn parse_json_name(
+        &mut self,
+        field: &mut FieldDescriptorProto,
+        field_location: Loc,
+    ) -> Result<()> {
+        if field.json_name.is_some() {
+            return Err(self.error("Already set option \"json_name\"."));
+        }
+
+        let location = self.location(field_location, &[tags::field::JSON_NAME]);
+        self.consume("json_name")?;
+        self.consume("=")?;
+
+        let value_location = self.location(location, &[]);
+        field.json_name = Some(self.consume_utf8_string("Expected string for JSON name.")?);
+        self.close(value_location);
+        self.close(location);
+        Ok(())
+    }
+
+    fn parse_extensions(
+        &mut self,
+        message: &mut DescriptorProto,
+        extensions_location: Loc,
+    ) -> Result<()> {
+        self.consume("extensions")?;
+
+        let old_range_size = message.extension_range.len();
+
+        loop {
+            let location =
+                self.location(extensions_location, &[message.extension_range.len() as i32]);
+            let (start, end) = self.parse_range(location, false, "Expected field number range.")?;
+            message
+                .extension_range
+                .push(descriptor_proto::ExtensionRange {
+                    start: Some(start),
+                    end: Some(end),
+                    options: None,
+                });
+            self.close(location);
+            if !self.try_consume(",")? {
+                break;
+            }
+        }
+
+        if self.looking_at("[") {
+            // Parse the options of the first range, recording their locations separately.
+            let range_number_index = self.path_len(extensions_location);
+            let mark = self.locations.len();
+
+            let index_location = self.location(extensions_location, &[0]);
+            let location = self.location(index_location, &[tags::extension_range::OPTIONS]);
+            self.consume("[")?;
+            let options = message.extension_range[old_range_size]
+                .options
+                .get_or_insert_with(Default::default);
+            loop {
+                self.parse_option(
+                    &mut options.uninterpreted_option,
+                    location,
+                    OptionStyle::Assignment,
+                )?;
+                if !self.try_consume(",")? {
+                    break;
+                }
+            }
+            self.consume("]")?;
+            self.close(location);
+            self.close(index_location);
+
+            let info = self.locations.split_off(mark);
+
+            // Then copy the options and their locations to all of the ranges.
+            let options = message.extension_range[old_range_size].options.clone();
+            for range in &mut message.extension_range[old_range_size + 1..] {
+                range.options.clone_from(&options);
+            }
+            for i in old_range_size..message.extension_range.len() {
+                for location in &info {
+                    // The location of the range index itself is redundant.
+                    if location.path.len() == range_number_index + 1 {
+                        continue;
+                    }
+                    let mut location = location.clone();
+                    location.path[range_number_index] = i as i32;
+                    self.locations.push(location);
+                }
+            }
+        }
+
+        self.consume_end_of_declaration(";", Some(extensions_location))
+    }
+
+    /// Parses a range of field numbers, like `1`, `1 to 10` or `1 to max`.
+    ///
+    /// Message ranges are returned with an exclusive end, enum ranges with an inclusive end.
+    fn parse_range(&mut self, location: Loc, is_enum: bool, error: &str) -> Result<(i32, i32)> {
+        let start_token = self.input.current().clone();
+        let start_location = self.location(location, &[tags::range::START]);
+        let start = if is_enum {
+            self.consume_signed_integer(error)?
+        } else {
+            self.consume_integer(error)?
+        };
+        self.close(start_location);
+
+        let end = if self.try_consume("to")? {
+            let end_location = self.location(location, &[tags::range::END]);
+            let end = if self.try_consume("max")? {
+                if is_enum {
+                    i32::MAX
+                } else {
+                    // The actual end is set once the message options are known.
+                    MAX_RANGE_SENTINEL - 1
+                }
+            } else if is_enum {
+                self.consume_signed_integer("Expected integer.")?
+            } else {
+                self.consume_integer("Expected integer.")?
+            };
+            self.close(end_location);
+            end
+        } else {
+            let end_location = self.location(location, &[tags::range::END]);
+            self.start_at(end_location, start_token.line, start_token.column);
+            self.end_at_token(end_location, &start_token);
+            self.close(end_location);
+            start
+        };
+
+        // Message ranges are inclusive in the source, but exclusive in descriptors.
+        Ok((start, if is_enum { end } else { end + 1 }))
+    }
+
+    fn parse_message_reserved(
+        &mut self,
+        message: &mut DescriptorProto,
+        message_location: Loc,
+    ) -> Result<()> {
+        let start_token = self.input.current().clone();
+        self.consume("reserved")?;
+        if self.looking_at_type(TokenType::String) {
+            let location = self.location(message_location, &[tags::message::RESERVED_NAME]);
+            self.start_at(location, start_token.line, start_token.column);
+            message.reserved_name = self.parse_reserved_names(location, "Expected field name.")?;
+            self.close(location);
+        } else {
+            let location = self.location(message_location, &[tags::message::RESERVED_RANGE]);
+            self.start_at(location, start_token.line, start_token.column);
+            let mut first = true;
+            loop {
+                let range_location =
+                    self.location(location, &[message.reserved_range.len() as i32]);
+                let error = if first {
+                    "Expected field name or number range."
+                } else {
+                    "Expected field number range."
+                };
+                let (start, end) = self.parse_range(range_location, false, error)?;
+                message
+                    .reserved_range
+                    .push(descriptor_proto::ReservedRange {
+                        start: Some(start),
+                        end: Some(end),
+                    });
+                self.close(range_location);
+                first = false;
+                if !self.try_consume(",")? {
+                    break;
+                }
+            }
+            self.consume_end_of_declaration(";", Some(location))?;
+            self.close(location);
+        }
+        Ok(())
+    }
+
+    fn parse_reserved_names(&mut self, location: Loc, error: &str) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        loop {
+            let name_location = self.location(location, &[names.len() as i32]);
+            names.push(self.consume_utf8_string(error)?);
+            self.close(name_location);
+            if !self.try_consume(",")? {
+                break;
+            }
+        }
+        self.consume_end_of_declaration(";", Some(location))?;
+        Ok(names)
+    }
+
+    fn parse_extend(
+        &mut self,
+        extensions: &mut Vec<FieldDescriptorProto>,
+        messages: &mut Vec<DescriptorProto>,
+        parent_location: Loc,
+        nested_type_number: i32,
+        extend_location: Loc,
+    ) -> Result<()> {
+        self.consume("extend")?;
+
+        let extendee_start = self.input.current().clone();
+        let extendee = self.parse_user_defined_type()?;
+        let extendee_end = self.input.previous().clone();
+
+        self.consume_end_of_declaration("{", Some(extend_location))?;
+
+        loop {
+            if self.at_end() {
+                return Err(self.error("Reached end of input in extend definition (missing '}')."));
+            }
+
+            let location = self.location(extend_location, &[extensions.len() as i32]);
+            let extendee_location = self.location(location, &[tags::field::EXTENDEE]);
+            self.start_at(
+                extendee_location,
+                extendee_start.line,
+                extendee_start.column,
+            );
+            self.end_at_token(extendee_location, &extendee_end);
+
+            extensions.push(FieldDescriptorProto {
+                extendee: Some(extendee.clone()),
+                ..FieldDescriptorProto::default()
+            });
+            self.parse_message_field(
+                extensions.last_mut().unwrap(),
+                messages,
+                parent_location,
+                nested_type_number,
+                location,
+            )?;
+            self.close(location);
+
+            if self.try_consume_end_of_declaration("}", None)? {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_oneof(
+        &mut self,
+        message: &mut DescriptorProto,
+        oneof_index: usize,
+        oneof_location: Loc,
+        message_location: Loc,
+    ) -> Result<()> {
+        self.consume("oneof")?;
+
+        let name_location = self.location(oneof_location, &[tags::oneof::NAME]);
+        message.oneof_decl[oneof_index].name =
+            Some(self.consume_identifier("Expected oneof name.")?);
+        self.close(name_location);
+
+        self.consume_end_of_declaration("{", Some(oneof_location))?;
+
+        loop {
+            if self.at_end() {
+                return Err(self.error("Reached end of input in oneof definition (missing '}')."));
+            }
+
+            if self.looking_at("option") {
+                let location = self.location(oneof_location, &[tags::oneof::OPTIONS]);
+                let options = message.oneof_decl[oneof_index]
+                    .options
+                    .get_or_insert_with(Default::default);
+                self.parse_option(
+                    &mut options.uninterpreted_option,
+                    location,
+                    OptionStyle::Statement,
+                )?;
+                self.close(location);
+            } else {
+                if self.looking_at("required")
+                    || self.looking_at("optional")
+                    || self.looking_at("repeated")
+                {
+                    return Err(self.error(
+                        "Fields in oneofs must not have labels (required / optional / repeated).",
+                    ));
+                }
+
+                let location = self.location(
+                    message_location,
+                    &[tags::message::FIELD, message.field.len() as i32],
+                );
+                let mut field = FieldDescriptorProto {
+                    oneof_index: Some(oneof_index as i32),
+                    ..FieldDescriptorProto::default()
+                };
+                field.set_label(Label::Optional);
+                message.field.push(field);
+                self.parse_message_field_no_label(
+                    message.field.last_mut().unwrap(),
+                    &mut message.nested_type,
+                    message_location,
+                    tags::message::NESTED_TYPE,
+                    location,
+                )?;
+                self.close(location);
+            }
+
+            if self.try_consume_end_of_declaration("}", None)? {
+                return Ok(());
+            }
+        }
+    }
+
+    // Enums.
+
+    fn parse_enum_definition(
+        &mut self,
+        enum_type: &mut EnumDescriptorProto,
+        enum_location: Loc,
+    ) -> Result<()> {
+        self.consume("enum")?;
+
+        let location = self.location(enum_location, &[tags::enum_type::NAME]);
+        enum_type.name = Some(self.consume_identifier("Expected enum name.")?);
+        self.close(location);
+
+        self.consume_end_of_declaration("{", Some(enum_location))?;
+        while !self.try_consume_end_of_declaration("}", None)? {
+            if self.at_end() {
+                return Err(self.error("Reached end of input in enum definition (missing '}')."));
+            }
+            self.parse_enum_statement(enum_type, enum_location)?;
+        }
+
+        self.validate_enum(enum_type)
+    }
+
+    fn parse_enum_statement(
+        &mut self,
+        enum_type: &mut EnumDescriptorProto,
+        enum_location: Loc,
+    ) -> Result<()> {
+        if self.try_consume_end_of_declaration(";", None)? {
+            // Empty statement; ignore.
+        } else if self.looking_at("option") {
+            let location = self.location(enum_location, &[tags::enum_type::OPTIONS]);
+            let options = enum_type.options.get_or_insert_with(Default::default);
+            self.parse_option(
+                &mut options.uninterpreted_option,
+                location,
+                OptionStyle::Statement,
+            )?;
+            self.close(location);
+        } else if self.looking_at("reserved") {
+            self.parse_enum_reserved(enum_type, enum_location)?;
+        } else {
+            let location = self.location(
+                enum_location,
+                &[tags::enum_type::VALUE, enum_type.value.len() as i32],
+            );
+            enum_type.value.push(EnumValueDescriptorProto::default());
+            self.parse_enum_constant(enum_type.value.last_mut().unwrap(), location)?;
+            self.close(location);
+        }
+        Ok(())
+    }
+
+    fn parse_enum_constant(
+        &mut self,
+        value: &mut EnumValueDescriptorProto,
+        value_location: Loc,
+    ) -> Result<()> {
+        let location = self.location(value_location, &[tags::enum_value::NAME]);
+        value.name = Some(self.consume_identifier("Expected enum constant name.")?);
+        self.close(location);
+
+        self.consume_with("=", "Missing numeric value for enum constant.")?;
+
+        let location = self.location(value_location, &[tags::enum_value::NUMBER]);
+        value.number = Some(self.consume_signed_integer("Expected integer.")?);
+        self.close(location);
+
+        if self.looking_at("[") {
+            let location = self.location(value_location, &[tags::enum_value::OPTIONS]);
+            self.consume("[")?;
+            let options = value.options.get_or_insert_with(Default::default);
+            loop {
+                self.parse_option(
+                    &mut options.uninterpreted_option,
+                    location,
+                    OptionStyle::Assignment,
+                )?;
+                if !self.try_consume(",")? {
+                    break;
+                }
+            }
+            self.consume("]")?;
+            self.close(location);
+        }
+
+        self.consume_end_of_declaration(";", Some(value_location))
+    }
+
+    fn parse_enum_reserved(
+        &mut self,
+        enum_type: &mut EnumDescriptorProto,
+        enum_location: Loc,
+    ) -> Result<()> {
+        let start_token = self.input.current().clone();
+        self.consume("reserved")?;
+        if self.looking_at_type(TokenType::String) {
+            let location = self.location(enum_location, &[tags::enum_type::RESERVED_NAME]);
+            self.start_at(location, start_token.line, start_token.column);
+            enum_type.reserved_name =
+                self.parse_reserved_names(location, "Expected enum value.")?;
+            self.close(location);
+        } else {
+            let location = self.location(enum_location, &[tags::enum_type::RESERVED_RANGE]);
+            self.start_at(location, start_token.line, start_token.column);
+            let mut first = true;
+            loop {
+                let range_location =
+                    self.location(location, &[enum_type.reserved_range.len() as i32]);
+                let error = if first {
+                    "Expected enum value or number range."
+                } else {
+                    "Expected enum number range."
+                };
+                let (start, end) = self.parse_range(range_location, true, error)?;
+                enum_type
+                    .reserved_range
+                    .push(enum_descriptor_proto::EnumReservedRange {
+                        start: Some(start),
+                        end: Some(end),
+                    });
+                self.close(range_location);
+                first = false;
+                if !self.try_consume(",")? {
+                    break;
+                }
+            }
+            self.consume_end_of_declaration(";", Some(location))?;
+            self.close(location);
+        }
+        Ok(())
+    }
+
+    fn validate_enum(&self, enum_type: &EnumDescriptorProto) -> Result<()> {
+        let mut has_allow_alias = false;
+        let mut allow_alias = false;
+        for option in enum_type
+            .options
+            .iter()
+            .flat_map(|o| &o.uninterpreted_option)
+        {
+            if option.name.len() > 1 {
+                continue;
+            }
+            if !option.name[0].is_extension && option.name[0].name_part == "allow_alias" {
+                has_allow_alias = true;
+                allow_alias = option.identifier_value.as_deref() == Some("true");
+                break;
+            }
+        }
+
+        if has_allow_alias && !allow_alias {
+            return Err(self.error(format!(
+                "\"{}\" declares 'option allow_alias = false;' which has no effect. Please remove the declaration.",
+                enum_type.name()
+            )));
+        }
+
+        let mut numbers = HashSet::new();
+        let has_duplicates = !enum_type
+            .value
+            .iter()
+            .all(|value| numbers.insert(value.number()));
+        if allow_alias && !has_duplicates {
+            return Err(self.error(format!(
+                "\"{}\" declares support for enum aliases but no enum values share field numbers. Please remove the unnecessary 'option allow_alias = true;' declaration.",
+                enum_type.name()
+            )));
+        }
+
+        Ok(())
+    }
+
+    // Services.
+
+    fn parse_service_definition(
+        &mut self,
+        service: &mut ServiceDescriptorProto,
+        service_location: Loc,
+    ) -> Result<()> {
+        self.consume("service")?;
+
+        let location = self.location(service_location, &[tags::service::NAME]);
+        service.name = Some(self.consume_identifier("Expected service name.")?);
+        self.close(location);
+
+        self.consume_end_of_declaration("{", Some(service_location))?;
+        while !self.try_consume_end_of_declaration("}", None)? {
+            if self.at_end() {
+                return Err(self.error("Reached end of input in service definition (missing '}')."));
+            }
+
+            if self.try_consume_end_of_declaration(";", None)? {
+                // Empty statement; ignore.
+            } else if self.looking_at("option") {
+                let location = self.location(service_location, &[tags::service::OPTIONS]);
+                let options = service.options.get_or_insert_with(Default::default);
+                self.parse_option(
+                    &mut options.uninterpreted_option,
+                    location,
+                    OptionStyle::Statement,
+                )?;
+                self.close(location);
+            } else {
+                let location = self.location(
+                    service_location,
+                    &[tags::service::METHOD, service.method.len() as i32],
+                );
+                service.method.push(MethodDescriptorProto::default());
+                self.parse_service_method(service.method.last_mut().unwrap(), location)?;
+                self.close(location);
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_service_method(
+        &mut self,
+        method: &mut MethodDescriptorProto,
+        method_location: Loc,
+    ) -> Result<()> {
+        self.consume("rpc")?;
+
+        let location = self.location(method_location, &[tags::method::NAME]);
+        method.name = Some(self.consume_identifier("Expected method name.")?);
+        self.close(location);
+
+        // Parse the input type.
+        self.consume("(")?;
+        if self.looking_at("stream") {
+            let location = self.location(method_location, &[tags::method::CLIENT_STREAMING]);
+            method.client_streaming = Some(true);
+            self.consume("stream")?;
+            self.close(location);
+        }
+        let location = self.location(method_location, &[tags::method::INPUT_TYPE]);
+        method.input_type = Some(self.parse_user_defined_type()?);
+        self.close(location);
+        self.consume(")")?;
+
+        // Parse the output type.
+        self.consume("returns")?;
+        self.consume("(")?;
+        if self.looking_at("stream") {
+            let location = self.location(method_location, &[tags::method::SERVER_STREAMING]);
+            self.consume("stream")?;
+            method.server_streaming = Some(true);
+            self.close(location);
+        }
+        let location = self.location(method_location, &[tags::method::OUTPUT_TYPE]);
+        method.output_type = Some(self.parse_user_defined_type()?);
+        self.close(location);
+        self.consume(")")?;
+
+        if !self.looking_at("{") {
+            return self.consume_end_of_declaration(";", Some(method_location));
+        }
+
+        // Options.
+        self.consume_end_of_declaration("{", Some(method_location))?;
+        while !self.try_consume_end_of_declaration("}", None)? {
+            if self.at_end() {
+                return Err(self.error("Reached end of input in method options (missing '}')."));
+            }
+
+            if self.try_consume_end_of_declaration(";", None)? {
+                // Empty statement; ignore.
+            } else {
+                let location = self.location(method_location, &[tags::method::OPTIONS]);
+                let options = method.options.get_or_insert_with(Default::default);
+                self.parse_option(
+                    &mut options.uninterpreted_option,
+                    location,
+                    OptionStyle::Statement,
+                )?;
+                self.close(location);
+            }
+        }
+        Ok(())
+    }
+
+    // Options.
+
+    fn parse_option(
+        &mut self,
+        options: &mut Vec<UninterpretedOption>,
+        options_location: Loc,
+        style: OptionStyle,
+    ) -> Result<()> {
+        let location = self.location(
+            options_location,
+            &[tags::UNINTERPRETED_OPTION, options.len() as i32],
+        );
+
+        if style == OptionStyle::Statement {
+            self.consume("option")?;
+        }
+
+        options.push(UninterpretedOption::default());
+        let option = options.last_mut().unwrap();
+
+        // Parse the dot-separated name.
+        let name_location = self.location(location, &[tags::uninterpreted_option::NAME]);
+        loop {
+            let part_location = self.location(name_location, &[option.name.len() as i32]);
+            self.parse_option_name_part(option, part_location)?;
+            self.close(part_location);
+            if !self.looking_at(".") {
+                break;
+            }
+            self.consume(".")?;
+        }
+        self.close(name_location);
+
+        self.consume("=")?;
+
+        let value_location = self.location(location, &[]);
+
+        // All values are a single token, except for negative numbers.
+        let is_negative = self.try_consume("-")?;
+
+        match self.input.current().ty {
+            TokenType::Start => unreachable!("no tokens have been read"),
+            TokenType::End => {
+                return Err(self.error("Unexpected end of stream while parsing option value."));
+            }
+            TokenType::Identifier => {
+                self.add_path(value_location, tags::uninterpreted_option::IDENTIFIER_VALUE);
+                if is_negative {
+                    return Err(self.error("Invalid '-' symbol before identifier."));
+                }
+                option.identifier_value = Some(self.consume_identifier("Expected identifier.")?);
+            }
+            TokenType::Integer => {
+                let max_value = if is_negative {
+                    i64::MAX as u64 + 1
+                } else {
+                    u64::MAX
+                };
+                let value = self.consume_integer64(max_value, "Expected integer.")?;
+                if is_negative {
+                    self.add_path(
+                        value_location,
+                        tags::uninterpreted_option::NEGATIVE_INT_VALUE,
+                    );
+                    option.negative_int_value = Some(0u64.wrapping_sub(value) as i64);
+                } else {
+                    self.add_path(
+                        value_location,
+                        tags::uninterpreted_option::POSITIVE_INT_VALUE,
+                    );
+                    option.positive_int_value = Some(value);
+                }
+            }
+            TokenType::Float => {
+                self.add_path(value_location, tags::uninterpreted_option::DOUBLE_VALUE);
+                let value = self.consume_number("Expected number.")?;
+                option.double_value = Some(if is_negative { -value } else { value });
+            }
+            TokenType::String => {
+                self.add_path(value_location, tags::uninterpreted_option::STRING_VALUE);
+                if is_negative {
+                    return Err(self.error("Invalid '-' symbol before string."));
+                }
+                option.string_value = Some(self.consume_string("Expected string.")?);
+            }
+            TokenType::Symbol => {
+                if !self.looking_at("{") {
+                    return Err(self.error("Expected option value."));
+                }
+                self.add_path(value_location, tags::uninterpreted_option::AGGREGATE_VALUE);
+                option.aggregate_value = Some(self.parse_uninterpreted_block()?);
+            }
+        }
+        self.close(value_location);
+
+        if style == OptionStyle::Statement {
+            self.consume_end_of_declaration(";", Some(location))?;
+        }
+        self.close(location);
+        Ok(())
+    }
+
+    fn parse_option_name_part(
+        &mut self,
+        option: &mut UninterpretedOption,
+        part_location: Loc,
+    ) -> Result<()> {
+        let mut name_part = String::new();
+        let is_extension = self.looking_at("(");
+        if is_extension {
+            self.consume("(")?;
+
+            // An extension name consists of dot-separated identifiers, and may begin with a dot.
+            let location = self.location(part_location, &[tags::name_part::NAME_PART]);
+            if self.looking_at_type(TokenType::Identifier) {
+                name_part.push_str(&self.consume_identifier("Expected identifier.")?);
+            }
+            while self.looking_at(".") {
+                self.consume(".")?;
+                name_part.push('.');
+                name_part.push_str(&self.consume_identifier("Expected identifier.")?);
+            }
+            self.close(location);
+
+            self.consume(")")?;
+        } else {
+            let location = self.location(part_location, &[tags::name_part::NAME_PART]);
+            name_part.push_str(&self.consume_identifier("Expected identifier.")?);
+            self.close(location);
+        }
+        option.name.push(NamePart {
+            name_part,
+            is_extension,
+        });
+        Ok(())
+    }
+
+    /// Parses an aggregate option value, returning its tokens separated by spaces.
+    fn parse_uninterpreted_block(&mut self) -> Result<String> {
+        self.consume("{")?;
+        let mut value = String::new();
+        let mut brace_depth = 1;
+        while !self.at_end() {
+            if self.looking_at("{") {
+                brace_depth += 1;
+            } else if self.looking_at("}") {
+                brace_depth -= 1;
+                if brace_depth == 0 {
+                    self.next()?;
+                    return Ok(value);
+                }
+            }
+            if !value.is_empty() {
+                value.push(' ');
+            }
+            value.push_str(&self.input.current().text);
+            self.next()?;
+        }
+        Err(self.error("Unexpected end of stream while parsing aggregate value."))
+    }
+
+    // Types.
+
+    fn parse_label(&mut self, field_location: Loc) -> Result<Option<Label>> {
+        let label = match self.input.current().text.as_str() {
+            "optional" => Label::Optional,
+            "repeated" => Label::Repeated,
+            "required" => Label::Required,
+            _ => return Ok(None),
+        };
+        let location = self.location(field_location, &[tags::field::LABEL]);
+        self.next()?;
+        self.close(location);
+        Ok(Some(label))
+    }
+
+    /// Parses a scalar type or a type name.
+    fn parse_type(&mut self) -> Result<(Option<Type>, String)> {
+        match scalar_type(&self.input.current().text) {
+            Some(ty) => {
+                self.next()?;
+                Ok((Some(ty), String::new()))
+            }
+            None => Ok((None, self.parse_user_defined_type()?)),
+        }
+    }
+
+    fn parse_user_defined_type(&mut self) -> Result<String> {
+        if scalar_type(&self.input.current().text).is_some() {
+            return Err(self.error("Expected message type."));
+        }
+
+        let mut type_name = String::new();
+        // A leading "." means the name is fully-qualified.
+        if self.try_consume(".")? {
+            type_name.push('.');
+        }
+        type_name.push_str(&self.consume_identifier("Expected type name.")?);
+        while self.try_consume(".")? {
+            type_name.push('.');
+            type_name.push_str(&self.consume_identifier("Expected identifier.")?);
+        }
+        Ok(type_name)
+    }
+}
+
+fn generate_map_entry(
+    map_field: MapField,
+    field: &mut FieldDescriptorProto,
+    messages: &mut Vec<DescriptorProto>,
+) {
+    let entry_name = map_entry_name(field.name());
+    field.type_name = Some(entry_name.clone());
+
+    let entry_field = |name: &str, number, ty: Option<Type>, type_name: String| {
+        let mut entry_field = FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            r#type: ty.map(|ty| ty as i32),
+            type_name: Some(type_name).filter(|name| !name.is_empty()),
+            ..FieldDescriptorProto::default()
+        };
+        entry_field.set_label(Label::Optional);
+        // The `enforce_utf8` option of the map field is propagated to string keys and values.
+        if ty == Some(Type::String) {
+            for option in field.options.iter().flat_map(|o| &o.uninterpreted_option) {
+                if option.name.len() == 1
+                    && option.name[0].name_part == "enforce_utf8"
+                    && !option.name[0].is_extension
+                {
+                    entry_field
+                        .options
+                        .get_or_insert_with(Default::default)
+                        .uninterpreted_option
+                        .push(option.clone());
+                }
+            }
+        }
+        entry_field
+    };
+    let key = entry_field("key", 1, map_field.key_type, map_field.key_type_name);
+    let value = entry_field("value", 2, map_field.value_type, map_field.value_type_name);
+
+    messages.push(DescriptorProto {
+        name: Some(entry_name),
+        field: vec![key, value],
+        options: Some(MessageOptions {
+            map_entry: Some(true),
+            ..MessageOptions::default()
+        }),
+        ..DescriptorProto::default()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_entry_names() {
+        assert_eq!(map_entry_name("foo_map"), "FooMapEntry");
+        assert_eq!(map_entry_name("foo"), "FooEntry");
+        assert_eq!(map_entry_name("_foo__bar1"), "FooBar1Entry");
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = parse("syntax = \"proto4\";").unwrap_err();
+        assert_eq!((err.line, err.column), (0, 9));
+
+        let err = parse("syntax = \"proto3\";\nmessage Foo {\n  int32 bar = 1\n}").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 0));
+        assert_eq!(err.message, "Expected \";\".");
+
+        let err = parse("message Foo { int32 bar = 1; }").unwrap_err();
+        assert_eq!(
+            err.message,
+            "Expected \"required\", \"optional\", or \"repeated\"."
+        );
+    }
+}
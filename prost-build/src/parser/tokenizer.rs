@@ -0,0 +1,792 @@
+//! A tokenizer for `.proto` source files.
+//!
+//! The tokenizer mirrors `google::protobuf::io::Tokenizer`, including how line and column numbers
+//! are counted and how comments are attributed to neighbouring tokens, so that source code info
+//! matches the output of `protoc`.
+
+use std::mem;
+
+use super::ParseError;
+
+const TAB_WIDTH: i32 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum TokenType {
+    Start,
+    End,
+    Identifier,
+    Integer,
+    Float,
+    String,
+    Symbol,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct Token {
+    pub(super) ty: TokenType,
+    pub(super) text: String,
+    pub(super) line: i32,
+    pub(super) column: i32,
+    pub(super) end_column: i32,
+}
+
+/// Comments collected by [`Tokenizer::next_with_comments`].
+#[derive(Debug, Default)]
+pub(super) struct Comments {
+    /// The comment trailing the previous token.
+    pub(super) trailing: String,
+    /// Comments which are not attached to any token.
+    pub(super) detached: Vec<String>,
+    /// The comment leading the next token.
+    pub(super) leading: String,
+}
+
+enum CommentStart {
+    Line,
+    Block,
+    SlashNotComment,
+    None,
+}
+
+pub(super) struct Tokenizer<'a> {
+    input: &'a [u8],
+    pos: usize,
+    line: i32,
+    column: i32,
+    current: Token,
+    previous: Token,
+}
+
+fn is_whitespace_no_newline(c: u8) -> bool {
+    matches!(c, b' ' | b'\t' | b'\r' | 0x0b | 0x0c)
+}
+
+fn is_whitespace(c: u8) -> bool {
+    c == b'\n' || is_whitespace_no_newline(c)
+}
+
+fn is_letter(c: u8) -> bool {
+    c.is_ascii_alphabetic() || c == b'_'
+}
+
+fn is_alphanumeric(c: u8) -> bool {
+    is_letter(c) || c.is_ascii_digit()
+}
+
+fn is_octal_digit(c: u8) -> bool {
+    matches!(c, b'0'..=b'7')
+}
+
+fn is_escape(c: u8) -> bool {
+    matches!(
+        c,
+        b'a' | b'b' | b'f' | b'n' | b'r' | b't' | b'v' | b'\\' | b'?' | b'\'' | b'"'
+    )
+}
+
+impl<'a> Tokenizer<'a> {
+    pub(super) fn new(input: &'a str) -> Tokenizer<'a> {
+        let start = Token {
+            ty: TokenType::Start,
+            text: String::new(),
+            line: 0,
+            column: 0,
+            end_column: 0,
+        };
+        Tokenizer {
+            input: input.as_bytes(),
+            pos: 0,
+            line: 0,
+            column: 0,
+            current: start.clone(),
+            previous: start,
+        }
+    }
+
+    pub(super) fn current(&self) -> &Token {
+        &self.current
+    }
+
+    pub(super) fn previous(&self) -> &Token {
+        &self.previous
+    }
+
+    /// Returns the current character, or `0` at the end of the input.
+    fn current_char(&self) -> u8 {
+        self.input.get(self.pos).copied().unwrap_or(0)
+    }
+
+    fn at_eof(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+
+    fn next_char(&mut self) {
+        match self.current_char() {
+            b'\n' => {
+                self.line += 1;
+                self.column = 0;
+            }
+            b'\t' => self.column += TAB_WIDTH - self.column % TAB_WIDTH,
+            _ => self.column += 1,
+        }
+        self.pos += 1;
+    }
+
+    fn looking_at(&self, class: fn(u8) -> bool) -> bool {
+        !self.at_eof() && class(self.current_char())
+    }
+
+    fn try_consume(&mut self, c: u8) -> bool {
+        if !self.at_eof() && self.current_char() == c {
+            self.next_char();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn try_consume_one(&mut self, class: fn(u8) -> bool) -> bool {
+        if self.looking_at(class) {
+            self.next_char();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn consume_zero_or_more(&mut self, class: fn(u8) -> bool) {
+        while self.looking_at(class) {
+            self.next_char();
+        }
+    }
+
+    fn consume_one_or_more(
+        &mut self,
+        class: fn(u8) -> bool,
+        error: &str,
+    ) -> Result<(), ParseError> {
+        if !self.looking_at(class) {
+            return Err(self.error(error));
+        }
+        self.consume_zero_or_more(class);
+        Ok(())
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.line, self.column, message)
+    }
+
+    fn text(&self, start: usize) -> String {
+        String::from_utf8_lossy(&self.input[start..self.pos]).into_owned()
+    }
+
+    /// Advances to the next token, discarding any comments in between.
+    ///
+    /// Returns `false` once the end of the input is reached.
+    pub(super) fn next(&mut self) -> Result<bool, ParseError> {
+        self.previous = self.current.clone();
+
+        loop {
+            self.consume_zero_or_more(is_whitespace);
+
+            match self.try_consume_comment_start() {
+                CommentStart::Line => {
+                    self.consume_line_comment();
+                    continue;
+                }
+                CommentStart::Block => {
+                    self.consume_block_comment()?;
+                    continue;
+                }
+                CommentStart::SlashNotComment => return Ok(true),
+                CommentStart::None => {}
+            }
+
+            if self.at_eof() {
+                break;
+            }
+
+            let c = self.current_char();
+            if c < b' ' {
+                return Err(self.error("Invalid control characters encountered in text."));
+            }
+
+            let start = self.pos;
+            let line = self.line;
+            let column = self.column;
+            let ty = if self.try_consume_one(is_letter) {
+                self.consume_zero_or_more(is_alphanumeric);
+                TokenType::Identifier
+            } else if self.try_consume(b'0') {
+                self.consume_number(true, false)?
+            } else if self.try_consume(b'.') {
+                // This could be the beginning of a floating-point number, or just a '.' symbol.
+                if self.try_consume_one(|c| c.is_ascii_digit()) {
+                    if self.previous.ty == TokenType::Identifier
+                        && line == self.previous.line
+                        && column == self.previous.end_column
+                    {
+                        return Err(ParseError::new(
+                            self.line,
+                            self.column - 2,
+                            "Need space between identifier and decimal point.",
+                        ));
+                    }
+                    self.consume_number(false, true)?
+                } else {
+                    TokenType::Symbol
+                }
+            } else if self.try_consume_one(|c| c.is_ascii_digit()) {
+                self.consume_number(false, false)?
+            } else if self.try_consume(b'"') {
+                self.consume_string(b'"')?;
+                TokenType::String
+            } else if self.try_consume(b'\'') {
+                self.consume_string(b'\'')?;
+                TokenType::String
+            } else {
+                if c & 0x80 != 0 {
+                    return Err(self.error(format!("Interpreting non ascii codepoint {}.", c)));
+                }
+                self.next_char();
+                TokenType::Symbol
+            };
+
+            self.current = Token {
+                ty,
+                text: self.text(start),
+                line,
+                column,
+                end_column: self.column,
+            };
+            return Ok(true);
+        }
+
+        self.current = Token {
+            ty: TokenType::End,
+            text: String::new(),
+            line: self.line,
+            column: self.column,
+            end_column: self.column,
+        };
+        Ok(false)
+    }
+
+    /// Advances to the next token like [`Tokenizer::next`], and collects the comments in
+    /// between.
+    pub(super) fn next_with_comments(&mut self) -> Result<Comments, ParseError> {
+        let mut collector = CommentCollector::default();
+
+        if self.current.ty == TokenType::Start {
+            // Ignore the UTF-8 byte order mark at the beginning of the file.
+            if self.input.starts_with(b"\xEF\xBB\xBF") {
+                self.pos = 3;
+                self.column = 3;
+            } else if self.current_char() == 0xEF {
+                return Err(self.error(
+                    "Proto file starts with 0xEF but not UTF-8 BOM. Only UTF-8 is accepted for proto file.",
+                ));
+            }
+            collector.detach_from_prev();
+        } else {
+            // A comment appearing on the same line must be attached to the previous declaration.
+            self.consume_zero_or_more(is_whitespace_no_newline);
+            match self.try_consume_comment_start() {
+                CommentStart::Line => {
+                    let comment = self.consume_line_comment();
+                    collector.add_line_comment(comment);
+                    // Don't allow comments on subsequent lines to be attached to a trailing
+                    // comment.
+                    collector.flush();
+                }
+                CommentStart::Block => {
+                    let comment = self.consume_block_comment()?;
+                    collector.add_block_comment(comment);
+                    self.consume_zero_or_more(is_whitespace_no_newline);
+                    if !self.try_consume(b'\n') {
+                        // The next token is on the same line, so there is no telling which token
+                        // the comment belongs to.
+                        collector.clear_buffer();
+                        self.next()?;
+                        return Ok(collector.finish());
+                    }
+                    collector.flush();
+                }
+                CommentStart::SlashNotComment => return Ok(collector.finish()),
+                CommentStart::None => {
+                    if !self.try_consume(b'\n') {
+                        // The next token is on the same line. There are no comments.
+                        self.next()?;
+                        return Ok(collector.finish());
+                    }
+                }
+            }
+        }
+
+        // We are now on the line after the previous token.
+        loop {
+            self.consume_zero_or_more(is_whitespace_no_newline);
+
+            match self.try_consume_comment_start() {
+                CommentStart::Line => {
+                    let comment = self.consume_line_comment();
+                    collector.add_line_comment(comment);
+                }
+                CommentStart::Block => {
+                    let comment = self.consume_block_comment()?;
+                    collector.add_block_comment(comment);
+                    // Consume the rest of the line so that it isn't interpreted as a blank line.
+                    self.consume_zero_or_more(is_whitespace_no_newline);
+                    self.try_consume(b'\n');
+                }
+                CommentStart::SlashNotComment => return Ok(collector.finish()),
+                CommentStart::None => {
+                    if self.try_consume(b'\n') {
+                        // A completely blank line.
+                        collector.flush();
+                        collector.detach_from_prev();
+                    } else {
+                        let result = self.next()?;
+                        if !result || matches!(self.current.text.as_str(), "}" | "]" | ")") {
+                            // At the end of a scope it makes no sense to attach a comment to the
+                            // following token.
+                            collector.flush();
+                        }
+                        return Ok(collector.finish());
+                    }
+                }
+            }
+        }
+    }
+
+    fn try_consume_comment_start(&mut self) -> CommentStart {
+        if self.try_consume(b'/') {
+            if self.try_consume(b'/') {
+                CommentStart::Line
+            } else if self.try_consume(b'*') {
+                CommentStart::Block
+            } else {
+                // It was just a slash.
+                self.current = Token {
+                    ty: TokenType::Symbol,
+                    text: "/".to_string(),
+                    line: self.line,
+                    column: self.column - 1,
+                    end_column: self.column,
+                };
+                CommentStart::SlashNotComment
+            }
+        } else {
+            CommentStart::None
+        }
+    }
+
+    fn consume_line_comment(&mut self) -> String {
+        let start = self.pos;
+        while !self.at_eof() && self.current_char() != b'\n' {
+            self.next_char();
+        }
+        self.try_consume(b'\n');
+        self.text(start)
+    }
+
+    fn consume_block_comment(&mut self) -> Result<String, ParseError> {
+        let mut content = String::new();
+        let mut start = self.pos;
+
+        loop {
+            while !self.at_eof() && !matches!(self.current_char(), b'*' | b'/' | b'\n') {
+                self.next_char();
+            }
+
+            if self.try_consume(b'\n') {
+                content.push_str(&self.text(start));
+
+                // Consume leading whitespace and asterisk.
+                self.consume_zero_or_more(is_whitespace_no_newline);
+                if self.try_consume(b'*') && self.try_consume(b'/') {
+                    // End of comment.
+                    return Ok(content);
+                }
+
+                start = self.pos;
+            } else if self.try_consume(b'*') && self.try_consume(b'/') {
+                // End of comment, without the trailing "*/".
+                content.push_str(&self.text(start));
+                content.truncate(content.len() - 2);
+                return Ok(content);
+            } else if self.try_consume(b'/') && self.current_char() == b'*' {
+                return Err(
+                    self.error("\"/*\" inside block comment.  Block comments cannot be nested.")
+                );
+            } else if self.at_eof() {
+                return Err(self.error("End-of-file inside block comment."));
+            }
+        }
+    }
+
+    fn consume_number(
+        &mut self,
+        started_with_zero: bool,
+        started_with_dot: bool,
+    ) -> Result<TokenType, ParseError> {
+        let mut is_float = false;
+
+        if started_with_zero && (self.try_consume(b'x') || self.try_consume(b'X')) {
+            self.consume_one_or_more(
+                |c| c.is_ascii_hexdigit(),
+                "\"0x\" must be followed by hex digits.",
+            )?;
+        } else if started_with_zero && self.looking_at(|c| c.is_ascii_digit()) {
+            self.consume_zero_or_more(is_octal_digit);
+            if self.looking_at(|c| c.is_ascii_digit()) {
+                return Err(self.error("Numbers starting with leading zero must be in octal."));
+            }
+        } else {
+            if started_with_dot {
+                is_float = true;
+                self.consume_zero_or_more(|c| c.is_ascii_digit());
+            } else {
+                self.consume_zero_or_more(|c| c.is_ascii_digit());
+                if self.try_consume(b'.') {
+                    is_float = true;
+                    self.consume_zero_or_more(|c| c.is_ascii_digit());
+                }
+            }
+
+            if self.try_consume(b'e') || self.try_consume(b'E') {
+                is_float = true;
+                let _ = self.try_consume(b'-') || self.try_consume(b'+');
+                self.consume_one_or_more(
+                    |c| c.is_ascii_digit(),
+                    "\"e\" must be followed by exponent.",
+                )?;
+            }
+        }
+
+        if self.looking_at(is_letter) {
+            return Err(self.error("Need space between number and identifier."));
+        } else if self.current_char() == b'.' {
+            return Err(self.error(if is_float {
+                "Already saw decimal point or exponent; can't have another one."
+            } else {
+                "Hex and octal numbers must be integers."
+            }));
+        }
+
+        Ok(if is_float {
+            TokenType::Float
+        } else {
+            TokenType::Integer
+        })
+    }
+
+    fn consume_string(&mut self, delimiter: u8) -> Result<(), ParseError> {
+        loop {
+            if self.at_eof() {
+                return Err(self.error("Unexpected end of string."));
+            }
+            match self.current_char() {
+                b'\n' => return Err(self.error("String literals cannot cross line boundaries.")),
+                b'\\' => {
+                    self.next_char();
+                    let valid = if self.try_consume_one(is_escape)
+                        || self.try_consume_one(is_octal_digit)
+                    {
+                        true
+                    } else if self.try_consume(b'x') {
+                        self.try_consume_one(|c| c.is_ascii_hexdigit())
+                    } else if self.try_consume(b'u') {
+                        (0..4).all(|_| self.try_consume_one(|c| c.is_ascii_hexdigit()))
+                    } else if self.try_consume(b'U') {
+                        // Only code points up to 0x10ffff are valid.
+                        self.try_consume(b'0')
+                            && self.try_consume(b'0')
+                            && (self.try_consume(b'0') || self.try_consume(b'1'))
+                            && (0..5).all(|_| self.try_consume_one(|c| c.is_ascii_hexdigit()))
+                    } else {
+                        false
+                    };
+                    if !valid {
+                        return Err(self.error("Invalid escape sequence in string literal."));
+                    }
+                }
+                c => {
+                    self.next_char();
+                    if c == delimiter {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Buffers the most recent comment until it is known where it belongs.
+///
+/// See `CommentCollector` in `tokenizer.cc`.
+struct CommentCollector {
+    comments: Comments,
+    buffer: String,
+    has_comment: bool,
+    is_line_comment: bool,
+    can_attach_to_prev: bool,
+}
+
+impl Default for CommentCollector {
+    fn default() -> Self {
+        CommentCollector {
+            comments: Comments::default(),
+            buffer: String::new(),
+            has_comment: false,
+            is_line_comment: false,
+            can_attach_to_prev: true,
+        }
+    }
+}
+
+impl CommentCollector {
+    fn add_line_comment(&mut self, comment: String) {
+        // Consecutive line comments are combined, but not with block comments.
+        if self.has_comment && !self.is_line_comment {
+            self.flush();
+        }
+        self.has_comment = true;
+        self.is_line_comment = true;
+        self.buffer.push_str(&comment);
+    }
+
+    fn add_block_comment(&mut self, comment: String) {
+        if self.has_comment {
+            self.flush();
+        }
+        self.has_comment = true;
+        self.is_line_comment = false;
+        self.buffer.push_str(&comment);
+    }
+
+    fn clear_buffer(&mut self) {
+        self.buffer.clear();
+        self.has_comment = false;
+    }
+
+    /// Called once the buffered comment is known to not belong to the next token.
+    fn flush(&mut self) {
+        if self.has_comment {
+            if self.can_attach_to_prev {
+                self.comments.trailing.push_str(&self.buffer);
+                self.can_attach_to_prev = false;
+            } else {
+                self.comments.detached.push(mem::take(&mut self.buffer));
+            }
+            self.clear_buffer();
+        }
+    }
+
+    fn detach_from_prev(&mut self) {
+        self.can_attach_to_prev = false;
+    }
+
+    /// Whatever remains in the buffer is the leading comment of the next token.
+    fn finish(mut self) -> Comments {
+        if self.has_comment {
+            self.comments.leading = self.buffer;
+        }
+        self.comments
+    }
+}
+
+/// Parses the text of an integer token, failing if it exceeds `max_value`.
+pub(super) fn parse_integer(text: &str, max_value: u64) -> Option<u64> {
+    let (digits, radix) =
+        if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+            (hex, 16)
+        } else if text.len() > 1 && text.starts_with('0') {
+            (&text[1..], 8)
+        } else {
+            (text, 10)
+        };
+    u64::from_str_radix(digits, radix)
+        .ok()
+        .filter(|&value| value <= max_value)
+}
+
+/// Parses the text of a float token.
+pub(super) fn parse_float(text: &str) -> f64 {
+    // The tokenizer only produces valid floats, although `1e` style exponents without digits are
+    // rejected earlier.
+    text.parse().unwrap_or(0.0)
+}
+
+/// Parses the text of a string token, including its quotes, and appends the value to `output`.
+pub(super) fn parse_string_append(text: &str, output: &mut Vec<u8>) {
+    let bytes = text.as_bytes();
+    // Strip the quotes.
+    let bytes = &bytes[1..bytes.len() - 1];
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        i += 1;
+        if c != b'\\' || i == bytes.len() {
+            output.push(c);
+            continue;
+        }
+
+        let c = bytes[i];
+        i += 1;
+        match c {
+            b'0'..=b'7' => {
+                // An octal escape of up to three digits.
+                let mut code = u32::from(c - b'0');
+                for _ in 0..2 {
+                    match bytes.get(i) {
+                        Some(&d) if is_octal_digit(d) => {
+                            code = code * 8 + u32::from(d - b'0');
+                            i += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                output.push(code as u8);
+            }
+            b'x' => {
+                // A hex escape of up to two digits.
+                let mut code = 0;
+                for _ in 0..2 {
+                    match bytes.get(i).and_then(|&d| char::from(d).to_digit(16)) {
+                        Some(digit) => {
+                            code = code * 16 + digit;
+                            i += 1;
+                        }
+                        None => break,
+                    }
+                }
+                output.push(code as u8);
+            }
+            b'u' | b'U' => {
+                let len = if c == b'u' { 4 } else { 8 };
+                let mut code = parse_hex(&bytes[i..i + len]);
+                i += len;
+                // Combine surrogate pairs.
+                if (0xd800..0xdc00).contains(&code)
+                    && bytes[i..].starts_with(b"\\u")
+                    && bytes.len() >= i + 6
+                {
+                    let trail = parse_hex(&bytes[i + 2..i + 6]);
+                    if (0xdc00..0xe000).contains(&trail) {
+                        code = 0x10000 + ((code - 0xd800) << 10) + (trail - 0xdc00);
+                        i += 6;
+                    }
+                }
+                append_utf8(code, output);
+            }
+            b'a' => output.push(0x07),
+            b'b' => output.push(0x08),
+            b'f' => output.push(0x0c),
+            b'n' => output.push(b'\n'),
+            b'r' => output.push(b'\r'),
+            b't' => output.push(b'\t'),
+            b'v' => output.push(0x0b),
+            c => output.push(c),
+        }
+    }
+}
+
+fn parse_hex(digits: &[u8]) -> u32 {
+    digits.iter().fold(0, |code, &d| {
+        code * 16 + char::from(d).to_digit(16).unwrap_or(0)
+    })
+}
+
+fn append_utf8(code: u32, output: &mut Vec<u8>) {
+    match char::from_u32(code) {
+        Some(c) => output.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+        // Unpaired surrogates are encoded as-is, like protoc does.
+        None => output.extend_from_slice(&[
+            0xe0 | (code >> 12) as u8,
+            0x80 | ((code >> 6) & 0x3f) as u8,
+            0x80 | (code & 0x3f) as u8,
+        ]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(input: &str) -> Vec<(TokenType, String)> {
+        let mut tokenizer = Tokenizer::new(input);
+        let mut tokens = Vec::new();
+        while tokenizer.next().unwrap() {
+            let token = tokenizer.current();
+            tokens.push((token.ty, token.text.clone()));
+        }
+        tokens
+    }
+
+    #[test]
+    fn token_types() {
+        assert_eq!(
+            tokens("foo_1 = 0x1F .5 1e3 'a\\'b' -; // comment\n/* block */ 017"),
+            vec![
+                (TokenType::Identifier, "foo_1".to_string()),
+                (TokenType::Symbol, "=".to_string()),
+                (TokenType::Integer, "0x1F".to_string()),
+                (TokenType::Float, ".5".to_string()),
+                (TokenType::Float, "1e3".to_string()),
+                (TokenType::String, "'a\\'b'".to_string()),
+                (TokenType::Symbol, "-".to_string()),
+                (TokenType::Symbol, ";".to_string()),
+                (TokenType::Integer, "017".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let mut tokenizer = Tokenizer::new("\n  123abc");
+        let err = tokenizer.next().unwrap_err();
+        assert_eq!((err.line, err.column), (1, 5));
+        assert_eq!(err.message, "Need space between number and identifier.");
+
+        let mut tokenizer = Tokenizer::new("/* unterminated");
+        assert!(tokenizer.next().is_err());
+    }
+
+    #[test]
+    fn comments() {
+        let mut tokenizer = Tokenizer::new(
+            "// detached\n\n// leading\n/* more\n * leading */\nfoo; // trailing\n\n// next\nbar",
+        );
+        let comments = tokenizer.next_with_comments().unwrap();
+        assert_eq!(
+            comments.detached,
+            vec![" detached\n".to_string(), " leading\n".to_string()]
+        );
+        assert_eq!(comments.leading, " more\n leading ");
+        assert_eq!(tokenizer.current().text, "foo");
+
+        tokenizer.next().unwrap();
+        assert_eq!(tokenizer.current().text, ";");
+        let comments = tokenizer.next_with_comments().unwrap();
+        assert_eq!(comments.trailing, " trailing\n");
+        assert_eq!(comments.leading, " next\n");
+        assert_eq!(tokenizer.current().text, "bar");
+    }
+
+    #[test]
+    fn strings() {
+        let mut value = Vec::new();
+        parse_string_append(r#""a\n\x41\101é\U0001F600😀""#, &mut value);
+        assert_eq!(value, "a\nAAé😀😀".as_bytes());
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(parse_integer("0x10", u64::MAX), Some(16));
+        assert_eq!(parse_integer("010", u64::MAX), Some(8));
+        assert_eq!(parse_integer("0", u64::MAX), Some(0));
+        assert_eq!(parse_integer("2147483648", i32::MAX as u64), None);
+        assert_eq!(parse_integer("18446744073709551616", u64::MAX), None);
+    }
+}
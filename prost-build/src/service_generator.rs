@@ -0,0 +1,337 @@
+use crate::{Method, Service, ServiceGenerator};
+
+/// A [`ServiceGenerator`] which generates a trait, a client and a server for each service.
+///
+/// The generated code uses the runtime support in `prost::service`, which requires the `service`
+/// feature of `prost`. For a service `Greeter` in the package `helloworld`, it generates:
+///
+/// - A `Greeter` trait with a method for each RPC, to be implemented by the application. The
+///   methods return boxed futures, so that implementations work with any executor.
+/// - A `GreeterClient<T>`, which encodes requests and decodes responses, and sends them through
+///   any `prost::service::Transport` `T`.
+/// - A `GreeterServer<S>`, which implements `Transport` by decoding requests, calling the methods
+///   of an `S: Greeter`, and encoding the responses. Calls are routed by their path, which is
+///   `/helloworld.Greeter/<method>`. A `prost::service::Router` dispatches to several servers.
+///
+/// Since a server is a transport, a client can call a server directly, which makes for an
+/// in-process call without any networking:
+///
+/// ```rust,ignore
+/// let client = GreeterClient::new(GreeterServer::new(MyGreeter));
+/// let reply = client.say_hello(HelloRequest { name: "world".into() }).await?;
+/// ```
+///
+/// Client-streaming and bidirectional methods take streams of requests, and server-streaming
+/// and bidirectional methods return a `prost::service::Streaming` of responses.
+///
+/// The generated code uses `async`, so it requires the 2018 edition or later.
+///
+/// # Example `build.rs`
+///
+/// ```rust,no_run
+/// # use std::io::Result;
+/// fn main() -> Result<()> {
+///     prost_build::Config::new()
+///         .service_generator(Box::new(prost_build::ServiceTraitGenerator::new()))
+///         .compile_protos(&["src/helloworld.proto"], &["src/"])?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct ServiceTraitGenerator {
+    prost_path: Option<String>,
+}
+
+impl ServiceTraitGenerator {
+    /// Creates a service generator.
+    pub fn new() -> ServiceTraitGenerator {
+        ServiceTraitGenerator::default()
+    }
+
+    /// Configures the path of the `prost` crate in the generated code, like
+    /// [`Config::prost_path`](crate::Config::prost_path). Defaults to `::prost`.
+    pub fn prost_path<S>(mut self, path: S) -> ServiceTraitGenerator
+    where
+        S: Into<String>,
+    {
+        self.prost_path = Some(path.into());
+        self
+    }
+
+    /// Returns the path of the `prost::service` module.
+    fn service_path(&self) -> String {
+        format!("{}::service", self.crate_path())
+    }
+
+    fn append_trait(&self, service: &Service, buf: &mut String) {
+        let s = self.service_path();
+        service.comments.append_with_indent(0, buf);
+        buf.push_str(&format!(
+            "pub trait {}: ::core::marker::Send + ::core::marker::Sync {{\n",
+            service.name
+        ));
+        for method in &service.methods {
+            method.comments.append_with_indent(1, buf);
+            let (argument, input_type) = if method.client_streaming {
+                (
+                    "requests",
+                    format!("{}::Streaming<{}>", s, method.input_type),
+                )
+            } else {
+                ("request", method.input_type.clone())
+            };
+            buf.push_str(&format!(
+                "    fn {}(&self, {}: {}) -> {}::BoxFuture<'_, ::core::result::Result<{}, {}::Status>>;\n",
+                method.name,
+                argument,
+                input_type,
+                s,
+                self.output_type(method),
+                s
+            ));
+        }
+        buf.push_str("}\n");
+    }
+
+    fn append_client(&self, service: &Service, buf: &mut String) {
+        let s = self.service_path();
+        let client = format!("{}Client", service.name);
+        buf.push_str(&format!(
+            "/// A client for the `{}` service.\n",
+            full_name(service)
+        ));
+        buf.push_str("#[derive(Clone, Debug)]\n");
+        buf.push_str(&format!("pub struct {}<T> {{\n", client));
+        buf.push_str("    transport: T,\n");
+        buf.push_str("}\n");
+        buf.push_str(&format!("impl<T: {}::Transport> {}<T> {{\n", s, client));
+        buf.push_str("    /// Creates a client which makes calls through `transport`.\n");
+        buf.push_str("    pub fn new(transport: T) -> Self {\n");
+        buf.push_str("        Self { transport }\n");
+        buf.push_str("    }\n");
+        buf.push_str("    /// Returns the transport of the client.\n");
+        buf.push_str("    pub fn into_inner(self) -> T {\n");
+        buf.push_str("        self.transport\n");
+        buf.push_str("    }\n");
+
+        for method in &service.methods {
+            let path = path(service, method);
+            method.comments.append_with_indent(1, buf);
+            let argument = if method.client_streaming {
+                format!(
+                    "requests: impl {}::Stream<Item = {}> + ::core::marker::Send + 'static",
+                    s, method.input_type
+                )
+            } else {
+                format!("request: {}", method.input_type)
+            };
+            buf.push_str(&format!(
+                "    pub async fn {}(&self, {}) -> ::core::result::Result<{}, {}::Status> {{\n",
+                method.name,
+                argument,
+                self.output_type(method),
+                s
+            ));
+            if !method.client_streaming && !method.server_streaming {
+                buf.push_str(&format!(
+                    "        let response = self.transport.unary(\"{}\", {}::encode(&request)).await?;\n",
+                    path, s
+                ));
+                buf.push_str(&format!("        {}::decode(response)\n", s));
+            } else {
+                let requests = if method.client_streaming {
+                    format!("{}::encode_requests(requests)", s)
+                } else {
+                    format!("{}::once({}::encode(&request))", s, s)
+                };
+                buf.push_str(&format!(
+                    "        let responses = self.transport.streaming(\"{}\", {}).await?;\n",
+                    path, requests
+                ));
+                if method.server_streaming {
+                    buf.push_str(&format!(
+                        "        ::core::result::Result::Ok({}::decode_responses(responses))\n",
+                        s
+                    ));
+                } else {
+                    buf.push_str(&format!(
+                        "        {}::decode({}::single(responses).await??)\n",
+                        s, s
+                    ));
+                }
+            }
+            buf.push_str("    }\n");
+        }
+        buf.push_str("}\n");
+    }
+
+    fn append_server(&self, service: &Service, buf: &mut String) {
+        let s = self.service_path();
+        let server = format!("{}Server", service.name);
+        buf.push_str(&format!(
+            "/// Dispatches calls to an implementation of the `{}` service.\n",
+            full_name(service)
+        ));
+        buf.push_str("#[derive(Clone, Debug)]\n");
+        buf.push_str(&format!("pub struct {}<S> {{\n", server));
+        buf.push_str("    inner: S,\n");
+        buf.push_str("}\n");
+        buf.push_str(&format!("impl<S: {}> {}<S> {{\n", service.name, server));
+        buf.push_str("    /// Creates a server which dispatches calls to `inner`.\n");
+        buf.push_str("    pub fn new(inner: S) -> Self {\n");
+        buf.push_str("        Self { inner }\n");
+        buf.push_str("    }\n");
+        buf.push_str("    /// Returns a reference to the service implementation.\n");
+        buf.push_str("    pub fn get_ref(&self) -> &S {\n");
+        buf.push_str("        &self.inner\n");
+        buf.push_str("    }\n");
+        buf.push_str("    /// Returns the service implementation.\n");
+        buf.push_str("    pub fn into_inner(self) -> S {\n");
+        buf.push_str("        self.inner\n");
+        buf.push_str("    }\n");
+        buf.push_str("}\n");
+
+        buf.push_str(&format!(
+            "impl<S> {}::NamedService for {}<S> {{\n",
+            s, server
+        ));
+        buf.push_str(&format!(
+            "    const NAME: &'static str = \"{}\";\n",
+            full_name(service)
+        ));
+        buf.push_str("}\n");
+
+        let (unary, streaming): (Vec<&Method>, Vec<&Method>) = service
+            .methods
+            .iter()
+            .partition(|method| !method.client_streaming && !method.server_streaming);
+
+        buf.push_str(&format!(
+            "impl<S: {}> {}::Transport for {}<S> {{\n",
+            service.name, s, server
+        ));
+        let p = self.crate_path();
+        buf.push_str(&format!(
+            "    fn unary(&self, path: &str, {}: {}::bytes::Bytes) -> {}::BoxFuture<'_, ::core::result::Result<{}::bytes::Bytes, {}::Status>> {{\n",
+            if unary.is_empty() { "_request" } else { "request" },
+            p,
+            s,
+            p,
+            s
+        ));
+        self.append_dispatch(&unary, service, buf, |method| {
+            format!(
+                "let response = self.inner.{}({}::decode(request)?).await?;\n\
+                 ::core::result::Result::Ok({}::encode(&response))",
+                method.name, s, s
+            )
+        });
+        buf.push_str("    }\n");
+
+        buf.push_str(&format!(
+            "    fn streaming(&self, path: &str, {}: {}::BoxStream<'static, {}::bytes::Bytes>) -> {}::BoxFuture<'_, ::core::result::Result<{}::Streaming<{}::bytes::Bytes>, {}::Status>> {{\n",
+            if streaming.is_empty() { "_requests" } else { "requests" },
+            s,
+            p,
+            s,
+            s,
+            p,
+            s
+        ));
+        self.append_dispatch(&streaming, service, buf, |method| {
+            let call = if method.client_streaming {
+                format!(
+                    "let response = self.inner.{}({}::decode_requests(requests)).await?;\n",
+                    method.name, s
+                )
+            } else {
+                format!(
+                    "let request = {}::decode({}::single(requests).await?)?;\n\
+                     let response = self.inner.{}(request).await?;\n",
+                    s, s, method.name
+                )
+            };
+            let responses = if method.server_streaming {
+                format!("{}::encode_responses(response)", s)
+            } else {
+                format!(
+                    "{}::once(::core::result::Result::Ok({}::encode(&response)))",
+                    s, s
+                )
+            };
+            format!("{}::core::result::Result::Ok({})", call, responses)
+        });
+        buf.push_str("    }\n");
+        buf.push_str("}\n");
+    }
+
+    /// Appends a match on the call path, with an arm calling each method.
+    fn append_dispatch(
+        &self,
+        methods: &[&Method],
+        service: &Service,
+        buf: &mut String,
+        call: impl Fn(&Method) -> String,
+    ) {
+        let s = self.service_path();
+        let unimplemented = format!(
+            "let status = {}::Status::unimplemented(path);\n\
+             {}::alloc::boxed::Box::pin(async move {{ ::core::result::Result::Err(status) }})",
+            s,
+            self.crate_path()
+        );
+        if methods.is_empty() {
+            buf.push_str(&unimplemented);
+            buf.push('\n');
+            return;
+        }
+
+        buf.push_str("match path {\n");
+        for method in methods {
+            buf.push_str(&format!(
+                "\"{}\" => {}::alloc::boxed::Box::pin(async move {{\n{}\n}}),\n",
+                path(service, method),
+                self.crate_path(),
+                call(method)
+            ));
+        }
+        buf.push_str(&format!("_ => {{\n{}\n}}\n", unimplemented));
+        buf.push_str("}\n");
+    }
+
+    /// Returns the path of the `prost` crate.
+    fn crate_path(&self) -> &str {
+        self.prost_path.as_deref().unwrap_or("::prost")
+    }
+
+    /// Returns the type of a successful response to a method.
+    fn output_type(&self, method: &Method) -> String {
+        if method.server_streaming {
+            format!("{}::Streaming<{}>", self.service_path(), method.output_type)
+        } else {
+            method.output_type.clone()
+        }
+    }
+}
+
+impl ServiceGenerator for ServiceTraitGenerator {
+    fn generate(&mut self, service: Service, buf: &mut String) {
+        self.append_trait(&service, buf);
+        self.append_client(&service, buf);
+        self.append_server(&service, buf);
+    }
+}
+
+/// Returns the fully-qualified name of a service.
+fn full_name(service: &Service) -> String {
+    if service.package.is_empty() {
+        service.proto_name.clone()
+    } else {
+        format!("{}.{}", service.package, service.proto_name)
+    }
+}
+
+/// Returns the path of calls to a method.
+fn path(service: &Service, method: &Method) -> String {
+    format!("/{}/{}", full_name(service), method.proto_name)
+}
@@ -0,0 +1,1016 @@
+//! Support for generating `Validate` implementations from [protovalidate] field constraints.
+//!
+//! `prost` discards extensions when decoding descriptors, so the `buf.validate.field` option is
+//! read directly from the encoded `FileDescriptorSet`. Only the subset of the constraint messages
+//! which `prost-build` knows how to check is decoded; any other rules are ignored.
+//!
+//! [protovalidate]: https://github.com/bufbuild/protovalidate
+
+use std::ascii;
+use std::collections::HashMap;
+
+use prost::bytes::Buf;
+use prost::encoding::{decode_key, decode_varint, skip_field, DecodeContext, WireType};
+use prost::{DecodeError, Message};
+use regex::Regex;
+
+/// The field number of the `buf.validate.field` extension of `google.protobuf.FieldOptions`.
+const FIELD_EXTENSION: u32 = 1159;
+
+/// Extracts the `buf.validate.field` constraints of every field in an encoded message containing
+/// files, keyed by the fully-qualified field name.
+///
+/// `file_tag` is the field number of the files, which is 1 for a `FileDescriptorSet` and 15 for a
+/// `CodeGeneratorRequest`.
+pub(crate) fn extract_field_constraints(
+    buf: &[u8],
+    file_tag: u32,
+) -> Result<HashMap<String, FieldConstraints>, DecodeError> {
+    let mut constraints = HashMap::new();
+    for file in nested(buf, file_tag)? {
+        let mut package = String::new();
+        let mut messages = Vec::new();
+        for (tag, value) in length_delimited(file)? {
+            match tag {
+                2 => package = utf8(value)?,
+                4 => messages.push(value),
+                _ => {}
+            }
+        }
+        let scope = if package.is_empty() {
+            String::new()
+        } else {
+            format!(".{}", package)
+        };
+        for message in messages {
+            extract_message(message, &scope, &mut constraints)?;
+        }
+    }
+    Ok(constraints)
+}
+
+fn extract_message(
+    message: &[u8],
+    scope: &str,
+    constraints: &mut HashMap<String, FieldConstraints>,
+) -> Result<(), DecodeError> {
+    let mut name = String::new();
+    let mut fields = Vec::new();
+    let mut nested_types = Vec::new();
+    for (tag, value) in length_delimited(message)? {
+        match tag {
+            1 => name = utf8(value)?,
+            2 => fields.push(value),
+            3 => nested_types.push(value),
+            _ => {}
+        }
+    }
+    let fq_message_name = format!("{}.{}", scope, name);
+
+    for field in fields {
+        let mut field_name = String::new();
+        let mut field_constraints = None;
+        for (tag, value) in length_delimited(field)? {
+            match tag {
+                1 => field_name = utf8(value)?,
+                8 => {
+                    for extension in nested(value, FIELD_EXTENSION)? {
+                        field_constraints
+                            .get_or_insert_with(FieldConstraints::default)
+                            .merge(extension)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(field_constraints) = field_constraints {
+            constraints.insert(
+                format!("{}.{}", fq_message_name, field_name),
+                field_constraints,
+            );
+        }
+    }
+
+    for nested_type in nested_types {
+        extract_message(nested_type, &fq_message_name, constraints)?;
+    }
+    Ok(())
+}
+
+/// Returns the length-delimited fields of an encoded message, skipping all other fields.
+fn length_delimited(mut buf: &[u8]) -> Result<Vec<(u32, &[u8])>, DecodeError> {
+    let mut fields = Vec::new();
+    while buf.has_remaining() {
+        let (tag, wire_type) = decode_key(&mut buf)?;
+        if wire_type == WireType::LengthDelimited {
+            let len = decode_varint(&mut buf)?;
+            if len > buf.len() as u64 {
+                return Err(DecodeError::new("buffer underflow"));
+            }
+            let (value, rest) = buf.split_at(len as usize);
+            fields.push((tag, value));
+            buf = rest;
+        } else {
+            skip_field(wire_type, tag, &mut buf, DecodeContext::default())?;
+        }
+    }
+    Ok(fields)
+}
+
+/// Returns the values of the length-delimited field `tag` of an encoded message.
+fn nested(buf: &[u8], tag: u32) -> Result<Vec<&[u8]>, DecodeError> {
+    Ok(length_delimited(buf)?
+        .into_iter()
+        .filter(|&(field_tag, _)| field_tag == tag)
+        .map(|(_, value)| value)
+        .collect())
+}
+
+fn utf8(value: &[u8]) -> Result<String, DecodeError> {
+    String::from_utf8(value.to_vec())
+        .map_err(|_| DecodeError::new("invalid string value: data is not UTF-8 encoded"))
+}
+
+/// The subset of `buf.validate.FieldConstraints` supported by `prost-build`.
+///
+/// The members of the `type` oneof are decoded as independent optional fields.
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct FieldConstraints {
+    #[prost(bool, tag = "25")]
+    pub required: bool,
+    #[prost(message, optional, tag = "1")]
+    pub float: Option<FloatRules>,
+    #[prost(message, optional, tag = "2")]
+    pub double: Option<DoubleRules>,
+    #[prost(message, optional, tag = "3")]
+    pub int32: Option<Int32Rules>,
+    #[prost(message, optional, tag = "4")]
+    pub int64: Option<Int64Rules>,
+    #[prost(message, optional, tag = "5")]
+    pub uint32: Option<UInt32Rules>,
+    #[prost(message, optional, tag = "6")]
+    pub uint64: Option<UInt64Rules>,
+    #[prost(message, optional, tag = "7")]
+    pub sint32: Option<SInt32Rules>,
+    #[prost(message, optional, tag = "8")]
+    pub sint64: Option<SInt64Rules>,
+    #[prost(message, optional, tag = "9")]
+    pub fixed32: Option<Fixed32Rules>,
+    #[prost(message, optional, tag = "10")]
+    pub fixed64: Option<Fixed64Rules>,
+    #[prost(message, optional, tag = "11")]
+    pub sfixed32: Option<SFixed32Rules>,
+    #[prost(message, optional, tag = "12")]
+    pub sfixed64: Option<SFixed64Rules>,
+    #[prost(message, optional, tag = "14")]
+    pub string: Option<StringRules>,
+    #[prost(message, optional, tag = "15")]
+    pub bytes: Option<BytesRules>,
+    #[prost(message, optional, tag = "16")]
+    pub r#enum: Option<EnumRules>,
+    #[prost(message, optional, tag = "18")]
+    pub repeated: Option<Box<RepeatedRules>>,
+    #[prost(message, optional, tag = "19")]
+    pub map: Option<Box<MapRules>>,
+}
+
+impl FieldConstraints {
+    /// Returns an error if a `pattern` rule of the constraints of the field `fq_field_name`,
+    /// including the rules of its items, keys and values, is not a valid regular expression.
+    ///
+    /// The generated code compiles the patterns when it first validates a value, so they are
+    /// checked while generating it instead.
+    pub(crate) fn check_patterns(&self, fq_field_name: &str) -> Result<(), crate::Error> {
+        if let Some(pattern) = self
+            .string
+            .as_ref()
+            .and_then(|rules| rules.pattern.as_ref())
+        {
+            if let Err(error) = Regex::new(pattern) {
+                return Err(crate::Error::new(format!(
+                    "the `buf.validate` pattern of `{}` is not a valid regular expression:\n{}",
+                    &fq_field_name[1..],
+                    error
+                ))
+                .with_element(fq_field_name)
+                .with_help(
+                    "patterns are compiled with the `regex` crate, whose syntax is described at \
+                     https://docs.rs/regex/latest/regex/#syntax",
+                ));
+            }
+        }
+        let repeated = self.repeated.iter().flat_map(|rules| &rules.items);
+        let map = self
+            .map
+            .iter()
+            .flat_map(|rules| rules.keys.iter().chain(&rules.values));
+        repeated
+            .chain(map)
+            .try_for_each(|constraints| constraints.check_patterns(fq_field_name))
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct StringRules {
+    #[prost(string, optional, tag = "1")]
+    pub r#const: Option<String>,
+    #[prost(uint64, optional, tag = "19")]
+    pub len: Option<u64>,
+    #[prost(uint64, optional, tag = "2")]
+    pub min_len: Option<u64>,
+    #[prost(uint64, optional, tag = "3")]
+    pub max_len: Option<u64>,
+    #[prost(uint64, optional, tag = "20")]
+    pub len_bytes: Option<u64>,
+    #[prost(uint64, optional, tag = "4")]
+    pub min_bytes: Option<u64>,
+    #[prost(uint64, optional, tag = "5")]
+    pub max_bytes: Option<u64>,
+    #[prost(string, optional, tag = "6")]
+    pub pattern: Option<String>,
+    #[prost(string, optional, tag = "7")]
+    pub prefix: Option<String>,
+    #[prost(string, optional, tag = "8")]
+    pub suffix: Option<String>,
+    #[prost(string, optional, tag = "9")]
+    pub contains: Option<String>,
+    #[prost(string, optional, tag = "23")]
+    pub not_contains: Option<String>,
+    #[prost(string, repeated, tag = "10")]
+    pub r#in: Vec<String>,
+    #[prost(string, repeated, tag = "11")]
+    pub not_in: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct BytesRules {
+    #[prost(bytes = "vec", optional, tag = "1")]
+    pub r#const: Option<Vec<u8>>,
+    #[prost(uint64, optional, tag = "13")]
+    pub len: Option<u64>,
+    #[prost(uint64, optional, tag = "2")]
+    pub min_len: Option<u64>,
+    #[prost(uint64, optional, tag = "3")]
+    pub max_len: Option<u64>,
+    #[prost(bytes = "vec", optional, tag = "5")]
+    pub prefix: Option<Vec<u8>>,
+    #[prost(bytes = "vec", optional, tag = "6")]
+    pub suffix: Option<Vec<u8>>,
+    #[prost(bytes = "vec", optional, tag = "7")]
+    pub contains: Option<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct EnumRules {
+    #[prost(int32, optional, tag = "1")]
+    pub r#const: Option<i32>,
+    #[prost(bool, optional, tag = "2")]
+    pub defined_only: Option<bool>,
+    #[prost(int32, repeated, packed = "false", tag = "3")]
+    pub r#in: Vec<i32>,
+    #[prost(int32, repeated, packed = "false", tag = "4")]
+    pub not_in: Vec<i32>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct RepeatedRules {
+    #[prost(uint64, optional, tag = "1")]
+    pub min_items: Option<u64>,
+    #[prost(uint64, optional, tag = "2")]
+    pub max_items: Option<u64>,
+    #[prost(bool, optional, tag = "3")]
+    pub unique: Option<bool>,
+    #[prost(message, optional, tag = "4")]
+    pub items: Option<FieldConstraints>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct MapRules {
+    #[prost(uint64, optional, tag = "1")]
+    pub min_pairs: Option<u64>,
+    #[prost(uint64, optional, tag = "2")]
+    pub max_pairs: Option<u64>,
+    #[prost(message, optional, tag = "4")]
+    pub keys: Option<FieldConstraints>,
+    #[prost(message, optional, tag = "5")]
+    pub values: Option<FieldConstraints>,
+}
+
+/// A bound on a numeric value, rendered as Rust source.
+struct Bound {
+    /// The constraint name, e.g. `gte`.
+    name: &'static str,
+    /// The comparison operator which the value must satisfy.
+    op: &'static str,
+    /// The bound as a suffixed Rust literal.
+    literal: String,
+    /// The bound as displayed in violation messages.
+    display: String,
+}
+
+impl Bound {
+    fn describe(&self) -> String {
+        let relation = match self.name {
+            "lt" => "less than",
+            "lte" => "less than or equal to",
+            "gt" => "greater than",
+            _ => "greater than or equal to",
+        };
+        format!("{} {}", relation, self.display)
+    }
+}
+
+/// The rules common to every numeric scalar type, rendered as Rust source.
+struct NumericRules {
+    /// The constraint id prefix, e.g. `int32`.
+    kind: &'static str,
+    r#const: Option<(String, String)>,
+    lower: Option<Bound>,
+    upper: Option<Bound>,
+    /// Whether the lower bound is greater than the upper bound, in which case the value must lie
+    /// outside of the range rather than within it.
+    exclusive: bool,
+    r#in: Vec<(String, String)>,
+    not_in: Vec<(String, String)>,
+}
+
+macro_rules! numeric_rules {
+    ($name:ident, $proto_ty:ident, $ty:ty, $kind:literal) => {
+        #[derive(Clone, PartialEq, Message)]
+        pub(crate) struct $name {
+            #[prost($proto_ty, optional, tag = "1")]
+            pub r#const: Option<$ty>,
+            #[prost($proto_ty, optional, tag = "2")]
+            pub lt: Option<$ty>,
+            #[prost($proto_ty, optional, tag = "3")]
+            pub lte: Option<$ty>,
+            #[prost($proto_ty, optional, tag = "4")]
+            pub gt: Option<$ty>,
+            #[prost($proto_ty, optional, tag = "5")]
+            pub gte: Option<$ty>,
+            #[prost($proto_ty, repeated, packed = "false", tag = "6")]
+            pub r#in: Vec<$ty>,
+            #[prost($proto_ty, repeated, packed = "false", tag = "7")]
+            pub not_in: Vec<$ty>,
+        }
+
+        impl $name {
+            fn numeric_rules(&self) -> NumericRules {
+                let value = |v: $ty| (numeric_literal(format!("{:?}", v), stringify!($ty)), v.to_string());
+                let bound = |name, op, v: $ty| Bound {
+                    name,
+                    op,
+                    literal: numeric_literal(format!("{:?}", v), stringify!($ty)),
+                    display: v.to_string(),
+                };
+                let lower = self
+                    .gt
+                    .map(|v| (v, bound("gt", ">", v)))
+                    .or_else(|| self.gte.map(|v| (v, bound("gte", ">=", v))));
+                let upper = self
+                    .lt
+                    .map(|v| (v, bound("lt", "<", v)))
+                    .or_else(|| self.lte.map(|v| (v, bound("lte", "<=", v))));
+                let exclusive = matches!((&lower, &upper), (Some((l, _)), Some((u, _))) if l > u);
+                NumericRules {
+                    kind: $kind,
+                    r#const: self.r#const.map(value),
+                    lower: lower.map(|(_, bound)| bound),
+                    upper: upper.map(|(_, bound)| bound),
+                    exclusive,
+                    r#in: self.r#in.iter().copied().map(value).collect(),
+                    not_in: self.not_in.iter().copied().map(value).collect(),
+                }
+            }
+        }
+    };
+}
+
+numeric_rules!(FloatRules, float, f32, "float");
+numeric_rules!(DoubleRules, double, f64, "double");
+numeric_rules!(Int32Rules, int32, i32, "int32");
+numeric_rules!(Int64Rules, int64, i64, "int64");
+numeric_rules!(UInt32Rules, uint32, u32, "uint32");
+numeric_rules!(UInt64Rules, uint64, u64, "uint64");
+numeric_rules!(SInt32Rules, sint32, i32, "sint32");
+numeric_rules!(SInt64Rules, sint64, i64, "sint64");
+numeric_rules!(Fixed32Rules, fixed32, u32, "fixed32");
+numeric_rules!(Fixed64Rules, fixed64, u64, "fixed64");
+numeric_rules!(SFixed32Rules, sfixed32, i32, "sfixed32");
+numeric_rules!(SFixed64Rules, sfixed64, i64, "sfixed64");
+
+/// Renders the `Debug` representation of a numeric value as a Rust literal suffixed with its type.
+fn numeric_literal(debug: String, ty: &str) -> String {
+    match debug.as_str() {
+        "NaN" => format!("{}::NAN", ty),
+        "inf" => format!("{}::INFINITY", ty),
+        "-inf" => format!("{}::NEG_INFINITY", ty),
+        _ => format!("{}{}", debug, ty),
+    }
+}
+
+/// Renders a byte string as a Rust byte string literal.
+fn bytes_literal(value: &[u8]) -> String {
+    let mut literal = String::from("b\"");
+    for &b in value {
+        literal.extend(ascii::escape_default(b).map(char::from));
+    }
+    literal.push('"');
+    literal
+}
+
+/// Generates the body of a `Validate::validate_into` implementation.
+pub(crate) struct CheckWriter<'a> {
+    pub buf: &'a mut String,
+    pub prost_path: &'a str,
+}
+
+impl CheckWriter<'_> {
+    /// Appends a statement recording a violation if `condition` holds.
+    ///
+    /// `path` is an expression evaluating to the `String` field path of the checked value.
+    pub(crate) fn push_violation(
+        &mut self,
+        condition: &str,
+        path: &str,
+        constraint_id: &str,
+        message: &str,
+    ) {
+        self.buf.push_str(&format!(
+            "if {} {{ errors.push({}, {:?}, {:?}); }}\n",
+            condition, path, constraint_id, message
+        ));
+    }
+
+    /// Returns an expression evaluating to the path of the field `field_name` of the message.
+    pub(crate) fn field_path(&self, field_name: &str) -> String {
+        format!(
+            "{}::ValidationErrors::field_path(path, {:?})",
+            self.prost_path, field_name
+        )
+    }
+
+    /// Returns an expression evaluating to the path of an element of the field at `path`.
+    pub(crate) fn element_path(&self, path: &str, key: &str) -> String {
+        format!(
+            "{}::ValidationErrors::element_path(&{}, {})",
+            self.prost_path, path, key
+        )
+    }
+
+    /// Appends a call validating the submessage `value` (an expression of type `&M`).
+    pub(crate) fn push_recurse(&mut self, value: &str, path: &str) {
+        self.buf.push_str(&format!(
+            "{}::Validate::validate_into({}, &{}, errors);\n",
+            self.prost_path, value, path
+        ));
+    }
+
+    /// Appends the checks of the type specific rules against `value`, an expression of type `&T`.
+    ///
+    /// `enum_type` is the Rust path of the enum type for enumeration fields.
+    pub(crate) fn push_type_checks(
+        &mut self,
+        constraints: &FieldConstraints,
+        value: &str,
+        path: &str,
+        enum_type: Option<&str>,
+    ) {
+        let numeric = [
+            constraints.float.as_ref().map(FloatRules::numeric_rules),
+            constraints.double.as_ref().map(DoubleRules::numeric_rules),
+            constraints.int32.as_ref().map(Int32Rules::numeric_rules),
+            constraints.int64.as_ref().map(Int64Rules::numeric_rules),
+            constraints.uint32.as_ref().map(UInt32Rules::numeric_rules),
+            constraints.uint64.as_ref().map(UInt64Rules::numeric_rules),
+            constraints.sint32.as_ref().map(SInt32Rules::numeric_rules),
+            constraints.sint64.as_ref().map(SInt64Rules::numeric_rules),
+            constraints
+                .fixed32
+                .as_ref()
+                .map(Fixed32Rules::numeric_rules),
+            constraints
+                .fixed64
+                .as_ref()
+                .map(Fixed64Rules::numeric_rules),
+            constraints
+                .sfixed32
+                .as_ref()
+                .map(SFixed32Rules::numeric_rules),
+            constraints
+                .sfixed64
+                .as_ref()
+                .map(SFixed64Rules::numeric_rules),
+        ];
+        for rules in numeric.iter().flatten() {
+            self.push_numeric_checks(rules, value, path);
+        }
+        if let Some(rules) = &constraints.string {
+            self.push_string_checks(rules, value, path);
+        }
+        if let Some(rules) = &constraints.bytes {
+            self.push_bytes_checks(rules, value, path);
+        }
+        if let Some(rules) = &constraints.r#enum {
+            self.push_enum_checks(rules, value, path, enum_type);
+        }
+    }
+
+    fn push_numeric_checks(&mut self, rules: &NumericRules, value: &str, path: &str) {
+        let kind = rules.kind;
+        if let Some((literal, display)) = &rules.r#const {
+            self.push_violation(
+                &format!("*{} != {}", value, literal),
+                path,
+                &format!("{}.const", kind),
+                &format!("value must equal {}", display),
+            );
+        }
+        match (&rules.lower, &rules.upper) {
+            (Some(lower), Some(upper)) => {
+                let (join, relation, suffix) = if rules.exclusive {
+                    ("||", "or", "_exclusive")
+                } else {
+                    ("&&", "and", "")
+                };
+                self.push_violation(
+                    &format!(
+                        "!(*{value} {} {} {join} *{value} {} {})",
+                        lower.op, lower.literal, upper.op, upper.literal
+                    ),
+                    path,
+                    &format!("{}.{}_{}{}", kind, lower.name, upper.name, suffix),
+                    &format!(
+                        "value must be {} {} {}",
+                        lower.describe(),
+                        relation,
+                        upper.describe()
+                    ),
+                );
+            }
+            (Some(bound), None) | (None, Some(bound)) => {
+                self.push_violation(
+                    &format!("!(*{} {} {})", value, bound.op, bound.literal),
+                    path,
+                    &format!("{}.{}", kind, bound.name),
+                    &format!("value must be {}", bound.describe()),
+                );
+            }
+            (None, None) => {}
+        }
+        self.push_list_checks(kind, &rules.r#in, &rules.not_in, value, path);
+    }
+
+    fn push_list_checks(
+        &mut self,
+        kind: &str,
+        r#in: &[(String, String)],
+        not_in: &[(String, String)],
+        value: &str,
+        path: &str,
+    ) {
+        let list = |values: &[(String, String)]| {
+            (
+                values
+                    .iter()
+                    .map(|(literal, _)| literal.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                values
+                    .iter()
+                    .map(|(_, display)| display.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+        };
+        if !r#in.is_empty() {
+            let (literals, display) = list(r#in);
+            self.push_violation(
+                &format!("![{}].contains({})", literals, value),
+                path,
+                &format!("{}.in", kind),
+                &format!("value must be in list [{}]", display),
+            );
+        }
+        if !not_in.is_empty() {
+            let (literals, display) = list(not_in);
+            self.push_violation(
+                &format!("[{}].contains({})", literals, value),
+                path,
+                &format!("{}.not_in", kind),
+                &format!("value must not be in list [{}]", display),
+            );
+        }
+    }
+
+    fn push_string_checks(&mut self, rules: &StringRules, value: &str, path: &str) {
+        if let Some(expected) = &rules.r#const {
+            self.push_violation(
+                &format!("{} != {:?}", value, expected),
+                path,
+                "string.const",
+                &format!("value must equal `{}`", expected),
+            );
+        }
+        let chars = format!("{}.chars().count()", value);
+        if let Some(len) = rules.len {
+            self.push_violation(
+                &format!("{} != {}", chars, len),
+                path,
+                "string.len",
+                &format!("value length must be {} characters", len),
+            );
+        }
+        if let Some(min_len) = rules.min_len {
+            self.push_violation(
+                &format!("{} < {}", chars, min_len),
+                path,
+                "string.min_len",
+                &format!("value length must be at least {} characters", min_len),
+            );
+        }
+        if let Some(max_len) = rules.max_len {
+            self.push_violation(
+                &format!("{} > {}", chars, max_len),
+                path,
+                "string.max_len",
+                &format!("value length must be at most {} characters", max_len),
+            );
+        }
+        if let Some(len_bytes) = rules.len_bytes {
+            self.push_violation(
+                &format!("{}.len() != {}", value, len_bytes),
+                path,
+                "string.len_bytes",
+                &format!("value length must be {} bytes", len_bytes),
+            );
+        }
+        if let Some(min_bytes) = rules.min_bytes {
+            self.push_violation(
+                &format!("{}.len() < {}", value, min_bytes),
+                path,
+                "string.min_bytes",
+                &format!("value length must be at least {} bytes", min_bytes),
+            );
+        }
+        if let Some(max_bytes) = rules.max_bytes {
+            self.push_violation(
+                &format!("{}.len() > {}", value, max_bytes),
+                path,
+                "string.max_bytes",
+                &format!("value length must be at most {} bytes", max_bytes),
+            );
+        }
+        if let Some(pattern) = &rules.pattern {
+            self.buf.push_str(&format!(
+                "{{\n\
+                 static PATTERN: ::std::sync::OnceLock<::regex::Regex> = ::std::sync::OnceLock::new();\n\
+                 let pattern = PATTERN.get_or_init(|| ::regex::Regex::new({:?}).expect(\"buf.validate pattern checked by prost-build\"));\n",
+                pattern
+            ));
+            self.push_violation(
+                &format!("!pattern.is_match({})", value),
+                path,
+                "string.pattern",
+                &format!("value does not match regex pattern `{}`", pattern),
+            );
+            self.buf.push_str("}\n");
+        }
+        if let Some(prefix) = &rules.prefix {
+            self.push_violation(
+                &format!("!{}.starts_with({:?})", value, prefix),
+                path,
+                "string.prefix",
+                &format!("value does not have prefix `{}`", prefix),
+            );
+        }
+        if let Some(suffix) = &rules.suffix {
+            self.push_violation(
+                &format!("!{}.ends_with({:?})", value, suffix),
+                path,
+                "string.suffix",
+                &format!("value does not have suffix `{}`", suffix),
+            );
+        }
+        if let Some(contains) = &rules.contains {
+            self.push_violation(
+                &format!("!{}.contains({:?})", value, contains),
+                path,
+                "string.contains",
+                &format!("value does not contain substring `{}`", contains),
+            );
+        }
+        if let Some(not_contains) = &rules.not_contains {
+            self.push_violation(
+                &format!("{}.contains({:?})", value, not_contains),
+                path,
+                "string.not_contains",
+                &format!("value contains substring `{}`", not_contains),
+            );
+        }
+        let strings = |values: &[String]| {
+            values
+                .iter()
+                .map(|v| (format!("{:?}", v), v.clone()))
+                .collect::<Vec<_>>()
+        };
+        self.push_list_checks(
+            "string",
+            &strings(&rules.r#in),
+            &strings(&rules.not_in),
+            &format!("&{}.as_str()", value),
+            path,
+        );
+    }
+
+    fn push_bytes_checks(&mut self, rules: &BytesRules, value: &str, path: &str) {
+        if let Some(expected) = &rules.r#const {
+            self.push_violation(
+                &format!("{}[..] != {}[..]", value, bytes_literal(expected)),
+                path,
+                "bytes.const",
+                &format!("value must be {}", bytes_literal(expected)),
+            );
+        }
+        if let Some(len) = rules.len {
+            self.push_violation(
+                &format!("{}.len() != {}", value, len),
+                path,
+                "bytes.len",
+                &format!("value length must be {} bytes", len),
+            );
+        }
+        if let Some(min_len) = rules.min_len {
+            self.push_violation(
+                &format!("{}.len() < {}", value, min_len),
+                path,
+                "bytes.min_len",
+                &format!("value length must be at least {} bytes", min_len),
+            );
+        }
+        if let Some(max_len) = rules.max_len {
+            self.push_violation(
+                &format!("{}.len() > {}", value, max_len),
+                path,
+                "bytes.max_len",
+                &format!("value length must be at most {} bytes", max_len),
+            );
+        }
+        if let Some(prefix) = &rules.prefix {
+            self.push_violation(
+                &format!("!{}.starts_with({})", value, bytes_literal(prefix)),
+                path,
+                "bytes.prefix",
+                &format!("value does not have prefix {}", bytes_literal(prefix)),
+            );
+        }
+        if let Some(suffix) = &rules.suffix {
+            self.push_violation(
+                &format!("!{}.ends_with({})", value, bytes_literal(suffix)),
+                path,
+                "bytes.suffix",
+                &format!("value does not have suffix {}", bytes_literal(suffix)),
+            );
+        }
+        if let Some(contains) = rules.contains.as_ref().filter(|c| !c.is_empty()) {
+            self.push_violation(
+                &format!(
+                    "!{}.windows({}).any(|window| window == {})",
+                    value,
+                    contains.len(),
+                    bytes_literal(contains)
+                ),
+                path,
+                "bytes.contains",
+                &format!("value does not contain {}", bytes_literal(contains)),
+            );
+        }
+    }
+
+    fn push_enum_checks(
+        &mut self,
+        rules: &EnumRules,
+        value: &str,
+        path: &str,
+        enum_type: Option<&str>,
+    ) {
+        if let Some(expected) = rules.r#const {
+            self.push_violation(
+                &format!("*{} != {}", value, expected),
+                path,
+                "enum.const",
+                &format!("value must equal {}", expected),
+            );
+        }
+        if let (Some(true), Some(enum_type)) = (rules.defined_only, enum_type) {
+            self.push_violation(
+                &format!("{}::try_from(*{}).is_err()", enum_type, value),
+                path,
+                "enum.defined_only",
+                "value must be one of the defined enum values",
+            );
+        }
+        let values = |values: &[i32]| {
+            values
+                .iter()
+                .map(|v| (v.to_string(), v.to_string()))
+                .collect::<Vec<_>>()
+        };
+        self.push_list_checks(
+            "enum",
+            &values(&rules.r#in),
+            &values(&rules.not_in),
+            value,
+            path,
+        );
+    }
+
+    /// Appends the checks of the `repeated` rules against `value`, an expression of type `&Vec<T>`.
+    pub(crate) fn push_repeated_checks(&mut self, rules: &RepeatedRules, value: &str, path: &str) {
+        if let Some(min_items) = rules.min_items {
+            self.push_violation(
+                &format!("{}.len() < {}", value, min_items),
+                path,
+                "repeated.min_items",
+                &format!("value must contain at least {} item(s)", min_items),
+            );
+        }
+        if let Some(max_items) = rules.max_items {
+            self.push_violation(
+                &format!("{}.len() > {}", value, max_items),
+                path,
+                "repeated.max_items",
+                &format!("value must contain no more than {} item(s)", max_items),
+            );
+        }
+        if rules.unique == Some(true) {
+            self.push_violation(
+                &format!(
+                    "{value}.iter().enumerate().any(|(idx, item)| {value}[..idx].contains(item))"
+                ),
+                path,
+                "repeated.unique",
+                "repeated value must contain unique items",
+            );
+        }
+    }
+
+    /// Appends the checks of the `map` rules against `value`, an expression of type `&Map<K, V>`.
+    pub(crate) fn push_map_checks(&mut self, rules: &MapRules, value: &str, path: &str) {
+        if let Some(min_pairs) = rules.min_pairs {
+            self.push_violation(
+                &format!("{}.len() < {}", value, min_pairs),
+                path,
+                "map.min_pairs",
+                &format!("map must be at least {} entries", min_pairs),
+            );
+        }
+        if let Some(max_pairs) = rules.max_pairs {
+            self.push_violation(
+                &format!("{}.len() > {}", value, max_pairs),
+                path,
+                "map.max_pairs",
+                &format!("map must be at most {} entries", max_pairs),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::encoding::bytes;
+    use prost_types::{DescriptorProto, FieldDescriptorProto, FileDescriptorProto};
+
+    #[test]
+    fn extract_nested_field_constraints() {
+        let constraints = FieldConstraints {
+            required: true,
+            string: Some(StringRules {
+                min_len: Some(3),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        // prost-types can not represent extensions, so the encoded descriptors are built up by
+        // appending fields to the encoded prefix of each message.
+        let mut options = Vec::new();
+        prost::encoding::message::encode(FIELD_EXTENSION, &constraints, &mut options);
+        let mut field = FieldDescriptorProto {
+            name: Some("name".to_string()),
+            number: Some(1),
+            ..Default::default()
+        }
+        .encode_to_vec();
+        bytes::encode(8, &options, &mut field);
+        let mut inner = DescriptorProto {
+            name: Some("Inner".to_string()),
+            ..Default::default()
+        }
+        .encode_to_vec();
+        bytes::encode(2, &field, &mut inner);
+        let mut outer = DescriptorProto {
+            name: Some("Outer".to_string()),
+            field: vec![FieldDescriptorProto {
+                name: Some("plain".to_string()),
+                number: Some(1),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+        .encode_to_vec();
+        bytes::encode(3, &inner, &mut outer);
+        let mut file = FileDescriptorProto {
+            name: Some("test.proto".to_string()),
+            package: Some("test.pkg".to_string()),
+            ..Default::default()
+        }
+        .encode_to_vec();
+        bytes::encode(4, &outer, &mut file);
+        let mut fds = Vec::new();
+        bytes::encode(1, &file, &mut fds);
+
+        let extracted = extract_field_constraints(&fds, 1).unwrap();
+        assert_eq!(extracted.len(), 1);
+        assert_eq!(
+            extracted.get(".test.pkg.Outer.Inner.name"),
+            Some(&constraints)
+        );
+    }
+
+    #[test]
+    fn numeric_rules() {
+        let rules = Int32Rules {
+            gt: Some(10),
+            lt: Some(5),
+            ..Default::default()
+        }
+        .numeric_rules();
+        assert!(rules.exclusive);
+        assert_eq!(rules.lower.unwrap().literal, "10i32");
+
+        let rules = DoubleRules {
+            gte: Some(f64::NEG_INFINITY),
+            lte: Some(1.5),
+            ..Default::default()
+        }
+        .numeric_rules();
+        assert!(!rules.exclusive);
+        assert_eq!(rules.lower.unwrap().literal, "f64::NEG_INFINITY");
+        assert_eq!(rules.upper.unwrap().literal, "1.5f64");
+    }
+
+    #[test]
+    fn generated_checks() {
+        let mut buf = String::new();
+        let mut writer = CheckWriter {
+            buf: &mut buf,
+            prost_path: "::prost",
+        };
+        let path = writer.field_path("name");
+        writer.push_type_checks(
+            &FieldConstraints {
+                string: Some(StringRules {
+                    max_len: Some(4),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            "value",
+            &path,
+            None,
+        );
+        assert_eq!(
+            buf,
+            "if value.chars().count() > 4 { errors.push(::prost::ValidationErrors::field_path(path, \"name\"), \"string.max_len\", \"value length must be at most 4 characters\"); }\n"
+        );
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        let pattern = |pattern: &str| FieldConstraints {
+            string: Some(StringRules {
+                pattern: Some(pattern.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(pattern(r"^\d+$").check_patterns(".pkg.Foo.bar"), Ok(()));
+
+        let error = pattern("[a-").check_patterns(".pkg.Foo.bar").unwrap_err();
+        assert_eq!(error.element(), Some("pkg.Foo.bar"));
+        assert!(error.message().starts_with(
+            "the `buf.validate` pattern of `pkg.Foo.bar` is not a valid regular expression"
+        ));
+
+        let items = FieldConstraints {
+            repeated: Some(Box::new(RepeatedRules {
+                items: Some(pattern("(")),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        assert!(items.check_patterns(".pkg.Foo.bar").is_err());
+    }
+}
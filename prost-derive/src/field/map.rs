@@ -42,6 +42,7 @@ fn fake_scalar(ty: scalar::Ty) -> scalar::Field {
         ty,
         kind,
         tag: 0, // Not used here
+        required: false,
     }
 }
 
@@ -248,6 +249,23 @@ impl Field {
         }
     }
 
+    /// Returns a statement which appends the paths of the missing required fields of the map's
+    /// message values to `missing`.
+    pub fn missing_fields(&self, ident: TokenStream, name: &str) -> Option<TokenStream> {
+        match self.value_ty {
+            ValueTy::Scalar(_) => None,
+            ValueTy::Message => Some(quote! {
+                for (key, value) in #ident.iter() {
+                    ::prost::encoding::message::missing_fields(
+                        ::core::format_args!("{}[{:?}]", #name, key),
+                        value,
+                        missing,
+                    );
+                }
+            }),
+        }
+    }
+
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         quote!(#ident.clear())
     }
@@ -9,6 +9,8 @@ use crate::field::{set_bool, set_option, tag_attr, word_attr, Label};
 pub struct Field {
     pub label: Label,
     pub tag: u32,
+    /// Whether the field is a required field which tracks its presence, and may be missing.
+    pub required: bool,
 }
 
 impl Field {
@@ -17,6 +19,7 @@ impl Field {
         let mut label = None;
         let mut tag = None;
         let mut boxed = false;
+        let mut presence = false;
 
         let mut unknown_attrs = Vec::new();
 
@@ -25,6 +28,8 @@ impl Field {
                 set_bool(&mut message, "duplicate message attribute")?;
             } else if word_attr("boxed", attr) {
                 set_bool(&mut boxed, "duplicate boxed attribute")?;
+            } else if word_attr("presence", attr) {
+                set_bool(&mut presence, "duplicate presence attributes")?;
             } else if let Some(t) = tag_attr(attr)? {
                 set_option(&mut tag, t, "duplicate tag attributes")?;
             } else if let Some(l) = Label::from_attr(attr) {
@@ -50,9 +55,18 @@ impl Field {
             None => bail!("message field is missing a tag attribute"),
         };
 
+        // Required fields with presence are represented like optional fields.
+        let label = label.unwrap_or(Label::Optional);
+        let (label, required) = match (label, presence) {
+            (Label::Required, true) => (Label::Optional, true),
+            (_, true) => bail!("presence attribute may only be applied to required fields"),
+            (label, false) => (label, false),
+        };
+
         Ok(Some(Field {
-            label: label.unwrap_or(Label::Optional),
+            label,
             tag,
+            required,
         }))
     }
 
@@ -122,6 +136,35 @@ impl Field {
         }
     }
 
+    /// Returns a statement which appends the paths of the missing required fields of the field to
+    /// `missing`.
+    pub fn missing_fields(&self, ident: TokenStream, name: &str) -> TokenStream {
+        let nested = quote!(::prost::encoding::message::missing_fields);
+        match self.label {
+            Label::Optional if self.required => quote! {
+                match #ident {
+                    ::core::option::Option::Some(ref msg) => #nested(#name, msg, missing),
+                    ::core::option::Option::None => {
+                        missing.push(::prost::alloc::string::String::from(#name))
+                    }
+                }
+            },
+            Label::Optional => quote! {
+                if let ::core::option::Option::Some(ref msg) = #ident {
+                    #nested(#name, msg, missing);
+                }
+            },
+            Label::Required => quote! {
+                #nested(#name, &#ident, missing);
+            },
+            Label::Repeated => quote! {
+                for (index, msg) in #ident.iter().enumerate() {
+                    #nested(::core::format_args!("{}[{}]", #name, index), msg, missing);
+                }
+            },
+        }
+    }
+
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         match self.label {
             Label::Optional => quote!(#ident = ::core::option::Option::None),
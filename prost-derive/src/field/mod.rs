@@ -8,10 +8,12 @@ use std::fmt;
 use std::slice;
 
 use anyhow::{bail, Error};
-use proc_macro2::TokenStream;
+use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use syn::punctuated::Punctuated;
-use syn::{Attribute, Expr, ExprLit, Lit, LitBool, LitInt, Meta, MetaNameValue, Token};
+use syn::{
+    Attribute, Expr, ExprLit, Ident, Lit, LitBool, LitInt, Meta, MetaNameValue, Token, Type,
+};
 
 #[derive(Clone)]
 pub enum Field {
@@ -133,6 +135,29 @@ impl Field {
         }
     }
 
+    /// Returns a statement which appends the paths of the missing required fields in the field to
+    /// `missing`, or `None` if the field can not contain missing required fields.
+    pub fn missing_fields(&self, ident: TokenStream, name: &str) -> Option<TokenStream> {
+        match *self {
+            Field::Scalar(ref scalar) => scalar.missing_fields(ident, name),
+            Field::Message(ref message) => Some(message.missing_fields(ident, name)),
+            Field::Map(ref map) => map.missing_fields(ident, name),
+            Field::Oneof(ref oneof) => Some(oneof.missing_fields(ident)),
+            Field::Group(ref group) => Some(group.missing_fields(ident, name)),
+        }
+    }
+
+    /// Returns `true` if the field is a required field which tracks its presence, and may be
+    /// missing.
+    pub fn is_required(&self) -> bool {
+        match *self {
+            Field::Scalar(ref scalar) => scalar.required,
+            Field::Message(ref message) => message.required,
+            Field::Group(ref group) => group.required,
+            Field::Map(_) | Field::Oneof(_) => false,
+        }
+    }
+
     pub fn default(&self) -> TokenStream {
         match *self {
             Field::Scalar(ref scalar) => scalar.default(),
@@ -165,15 +190,71 @@ impl Field {
         }
     }
 
-    pub fn methods(&self, ident: &TokenStream) -> Option<TokenStream> {
-        match *self {
+    /// Returns methods to embed in the message, given the Rust type of the field.
+    ///
+    /// With `accessors`, the fields wrapped in an `Option` also get `has_`, `clear_` and `take_`
+    /// methods, and oneofs a `which_` method.
+    pub fn methods(&self, ident: &TokenStream, ty: &Type, accessors: bool) -> Option<TokenStream> {
+        let methods = match *self {
             Field::Scalar(ref scalar) => scalar.methods(ident),
             Field::Map(ref map) => map.methods(ident),
+            Field::Oneof(ref oneof) if accessors => Some(oneof.methods(ident)),
             _ => None,
+        };
+        let optional = match *self {
+            Field::Scalar(ref scalar) => matches!(scalar.kind, scalar::Kind::Optional(_)),
+            Field::Message(ref message) => message.label == Label::Optional,
+            Field::Group(ref group) => group.label == Label::Optional,
+            Field::Oneof(_) => true,
+            Field::Map(_) => false,
+        };
+        if !accessors || !optional {
+            return methods;
+        }
+        let presence = presence_methods(ident, ty);
+        Some(quote! {
+            #methods
+            #presence
+        })
+    }
+}
+
+/// Returns the `has_`, `clear_` and `take_` methods of a field of type `Option<_>`.
+fn presence_methods(ident: &TokenStream, ty: &Type) -> TokenStream {
+    let name = field_name(ident);
+    let has = Ident::new(&format!("has_{}", name), Span::call_site());
+    let clear = Ident::new(&format!("clear_{}", name), Span::call_site());
+    let take = Ident::new(&format!("take_{}", name), Span::call_site());
+    let has_doc = format!("Returns `true` if `{}` is set.", name);
+    let clear_doc = format!("Unsets `{}`.", name);
+    let take_doc = format!("Takes the value of `{}`, leaving it unset.", name);
+    quote! {
+        #[doc=#has_doc]
+        pub fn #has(&self) -> bool {
+            self.#ident.is_some()
+        }
+
+        #[doc=#clear_doc]
+        pub fn #clear(&mut self) {
+            self.#ident = ::core::option::Option::None;
+        }
+
+        #[doc=#take_doc]
+        pub fn #take(&mut self) -> #ty {
+            self.#ident.take()
         }
     }
 }
 
+/// Returns the name of a field, without the raw identifier prefix.
+pub fn field_name(ident: &TokenStream) -> String {
+    let name = ident.to_string();
+    match name.strip_prefix("r#") {
+        Some(name) => name.to_string(),
+        None => name,
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Label {
     /// An optional field.
@@ -225,7 +306,16 @@ impl fmt::Display for Label {
 }
 
 /// Get the items belonging to the 'prost' list attribute, e.g. `#[prost(foo, bar="baz")]`.
+///
+/// The `name` item, which applies to all types of fields, is skipped.
 fn prost_attrs(attrs: Vec<Attribute>) -> Result<Vec<Meta>, Error> {
+    Ok(prost_metas(&attrs)?
+        .into_iter()
+        .filter(|meta| !meta.path().is_ident("name"))
+        .collect())
+}
+
+fn prost_metas(attrs: &[Attribute]) -> Result<Vec<Meta>, Error> {
     let mut result = Vec::new();
     for attr in attrs.iter() {
         if let Meta::List(meta_list) = &attr.meta {
@@ -241,6 +331,29 @@ fn prost_attrs(attrs: Vec<Attribute>) -> Result<Vec<Meta>, Error> {
     Ok(result)
 }
 
+/// Returns the Protobuf name of a field given by its `name` attribute, e.g.
+/// `#[prost(name = "foo_1")]`, or `None` if the attribute is not present.
+pub fn name_attr(attrs: &[Attribute]) -> Result<Option<String>, Error> {
+    let mut name = None;
+    for meta in prost_metas(attrs)? {
+        if !meta.path().is_ident("name") {
+            continue;
+        }
+        match meta {
+            Meta::NameValue(MetaNameValue {
+                value:
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(ref lit),
+                        ..
+                    }),
+                ..
+            }) => set_option(&mut name, lit.value(), "duplicate name attributes")?,
+            _ => bail!("invalid name attribute: {:?}", meta),
+        }
+    }
+    Ok(name)
+}
+
 pub fn set_option<T>(option: &mut Option<T>, value: T, message: &str) -> Result<(), Error>
 where
     T: fmt::Debug,
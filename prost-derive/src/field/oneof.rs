@@ -1,9 +1,9 @@
 use anyhow::{bail, Error};
-use proc_macro2::TokenStream;
+use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use syn::{parse_str, Expr, ExprLit, Ident, Lit, Meta, MetaNameValue, Path};
 
-use crate::field::{set_option, tags_attr};
+use crate::field::{field_name, set_option, tags_attr};
 
 #[derive(Clone)]
 pub struct Field {
@@ -62,7 +62,7 @@ impl Field {
     /// Returns a statement which encodes the oneof field.
     pub fn encode(&self, ident: TokenStream) -> TokenStream {
         quote! {
-            if let Some(ref oneof) = #ident {
+            if let ::core::option::Option::Some(ref oneof) = #ident {
                 oneof.encode(buf)
             }
         }
@@ -84,6 +84,34 @@ impl Field {
         }
     }
 
+    /// Returns a statement which appends the paths of the missing required fields of the oneof's
+    /// message variants to `missing`.
+    pub fn missing_fields(&self, ident: TokenStream) -> TokenStream {
+        quote! {
+            if let ::core::option::Option::Some(ref oneof) = #ident {
+                oneof.missing_fields_into(missing);
+            }
+        }
+    }
+
+    /// Returns the `which_` method of the oneof field, which relies on the `field_name()` method
+    /// generated for oneofs with `#[prost(accessors)]`.
+    pub fn methods(&self, ident: &TokenStream) -> TokenStream {
+        let ty = &self.ty;
+        let name = field_name(ident);
+        let which = Ident::new(&format!("which_{}", name), Span::call_site());
+        let which_doc = format!(
+            "Returns the Protobuf name of the field which is set in `{}`, or `None` if it is unset.",
+            name
+        );
+        quote! {
+            #[doc=#which_doc]
+            pub fn #which(&self) -> ::core::option::Option<&'static str> {
+                self.#ident.as_ref().map(#ty::field_name)
+            }
+        }
+    }
+
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         quote!(#ident = ::core::option::Option::None)
     }
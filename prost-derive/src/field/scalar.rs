@@ -5,7 +5,7 @@ use proc_macro2::{Span, TokenStream};
 use quote::{quote, ToTokens, TokenStreamExt};
 use syn::{parse_str, Expr, ExprLit, Ident, Index, Lit, LitByteStr, Meta, MetaNameValue, Path};
 
-use crate::field::{bool_attr, set_option, tag_attr, Label};
+use crate::field::{bool_attr, set_bool, set_option, tag_attr, word_attr, Label};
 
 /// A scalar protobuf field.
 #[derive(Clone)]
@@ -13,6 +13,8 @@ pub struct Field {
     pub ty: Ty,
     pub kind: Kind,
     pub tag: u32,
+    /// Whether the field is a required field which tracks its presence, and may be missing.
+    pub required: bool,
 }
 
 impl Field {
@@ -22,6 +24,7 @@ impl Field {
         let mut packed = None;
         let mut default = None;
         let mut tag = None;
+        let mut presence = false;
 
         let mut unknown_attrs = Vec::new();
 
@@ -36,6 +39,8 @@ impl Field {
                 set_option(&mut label, l, "duplicate label attributes")?;
             } else if let Some(d) = DefaultValue::from_attr(attr)? {
                 set_option(&mut default, d, "duplicate default attributes")?;
+            } else if word_attr("presence", attr) {
+                set_bool(&mut presence, "duplicate presence attributes")?;
             } else {
                 unknown_attrs.push(attr);
             }
@@ -86,7 +91,19 @@ impl Field {
             (Some(Label::Repeated), _, false) => Kind::Repeated,
         };
 
-        Ok(Some(Field { ty, kind, tag }))
+        // Required fields with presence are represented like optional fields.
+        let (kind, required) = match (kind, presence) {
+            (Kind::Required(default), true) => (Kind::Optional(default), true),
+            (_, true) => bail!("presence attribute may only be applied to required fields"),
+            (kind, false) => (kind, false),
+        };
+
+        Ok(Some(Field {
+            ty,
+            kind,
+            tag,
+            required,
+        }))
     }
 
     pub fn new_oneof(attrs: &[Meta]) -> Result<Option<Field>, Error> {
@@ -203,6 +220,19 @@ impl Field {
         }
     }
 
+    /// Returns a statement which appends the name of the field to `missing` if it is a required
+    /// field which is not set.
+    pub fn missing_fields(&self, ident: TokenStream, name: &str) -> Option<TokenStream> {
+        if !self.required {
+            return None;
+        }
+        Some(quote! {
+            if #ident.is_none() {
+                missing.push(::prost::alloc::string::String::from(#name));
+            }
+        })
+    }
+
     /// Returns an expression which evaluates to the default value of the field.
     pub fn default(&self) -> TokenStream {
         match self.kind {
@@ -10,12 +10,13 @@ use itertools::Itertools;
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use syn::{
-    punctuated::Punctuated, Data, DataEnum, DataStruct, DeriveInput, Expr, Fields, FieldsNamed,
-    FieldsUnnamed, Ident, Index, Variant,
+    punctuated::Punctuated, Attribute, Data, DataEnum, DataStruct, DeriveInput, Expr, Fields,
+    FieldsNamed, FieldsUnnamed, GenericArgument, Ident, Index, LitStr, Meta, Path, PathArguments,
+    Type, Variant,
 };
 
 mod field;
-use crate::field::Field;
+use crate::field::{field_name, name_attr, Field};
 
 fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
     let input: DeriveInput = syn::parse2(input)?;
@@ -25,9 +26,22 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
     syn::custom_keyword!(skip_debug);
     let skip_debug = input
         .attrs
-        .into_iter()
+        .iter()
         .any(|a| a.path().is_ident("prost") && a.parse_args::<skip_debug>().is_ok());
 
+    // Set on messages whose submessages may be missing required fields.
+    syn::custom_keyword!(required_fields);
+    let required_fields = input
+        .attrs
+        .iter()
+        .any(|a| a.path().is_ident("prost") && a.parse_args::<required_fields>().is_ok());
+
+    syn::custom_keyword!(accessors);
+    let accessors = input
+        .attrs
+        .iter()
+        .any(|a| a.path().is_ident("prost") && a.parse_args::<accessors>().is_ok());
+
     let variant_data = match input.data {
         Data::Struct(variant_data) => variant_data,
         Data::Enum(..) => bail!("Message can not be derived for an enum"),
@@ -56,7 +70,7 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
     };
 
     let mut next_tag: u32 = 1;
-    let mut fields = fields
+    let typed_fields = fields
         .into_iter()
         .enumerate()
         .flat_map(|(i, field)| {
@@ -67,10 +81,24 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                 };
                 quote!(#index)
             });
+            let name = match name_attr(&field.attrs) {
+                Ok(name) => name.unwrap_or_else(|| field_name(&field_ident)),
+                Err(err) => {
+                    return Some(Err(err.context(format!(
+                        "invalid message field {}.{}",
+                        ident, field_ident
+                    ))))
+                }
+            };
             match Field::new(field.attrs, Some(next_tag)) {
-                Ok(Some(field)) => {
-                    next_tag = field.tags().iter().max().map(|t| t + 1).unwrap_or(next_tag);
-                    Some(Ok((field_ident, field)))
+                Ok(Some(prost_field)) => {
+                    next_tag = prost_field
+                        .tags()
+                        .iter()
+                        .max()
+                        .map(|t| t + 1)
+                        .unwrap_or(next_tag);
+                    Some(Ok((field_ident, prost_field, name, field.ty)))
                 }
                 Ok(None) => None,
                 Err(err) => Some(Err(
@@ -80,6 +108,40 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         })
         .collect::<Result<Vec<_>, _>>()?;
 
+    // Messages only check their submessages if they are known to have required fields, so that
+    // messages without required fields are not traversed.
+    let missing_fields = if required_fields
+        || typed_fields
+            .iter()
+            .any(|(_, field, _, _)| field.is_required())
+    {
+        let missing_fields = typed_fields
+            .iter()
+            .filter_map(|(field_ident, field, name, _)| {
+                field.missing_fields(quote!(self.#field_ident), name)
+            });
+        quote! {
+            fn missing_fields_into(
+                &self,
+                missing: &mut ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+            ) {
+                #(#missing_fields)*
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    let methods = typed_fields
+        .iter()
+        .flat_map(|(field_ident, field, _, ty)| field.methods(field_ident, ty, accessors))
+        .collect::<Vec<_>>();
+
+    let mut fields = typed_fields
+        .into_iter()
+        .map(|(field_ident, field, _, _)| (field_ident, field))
+        .collect::<Vec<_>>();
+
     // We want Debug to be in declaration order
     let unsorted_fields = fields.clone();
 
@@ -157,10 +219,6 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         )}
     };
 
-    let methods = fields
-        .iter()
-        .flat_map(|(field_ident, field)| field.methods(field_ident))
-        .collect::<Vec<_>>();
     let methods = if methods.is_empty() {
         quote!()
     } else {
@@ -195,6 +253,8 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                 }
             }
 
+            #missing_fields
+
             #[inline]
             fn encoded_len(&self) -> usize {
                 0 #(+ #encoded_len)*
@@ -376,9 +436,11 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
     syn::custom_keyword!(skip_debug);
     let skip_debug = input
         .attrs
-        .into_iter()
+        .iter()
         .any(|a| a.path().is_ident("prost") && a.parse_args::<skip_debug>().is_ok());
 
+    let accessors = oneof_accessors(&input.attrs)?;
+
     let variants = match input.data {
         Data::Enum(DataEnum { variants, .. }) => variants,
         Data::Struct(..) => bail!("Oneof can not be derived for a struct"),
@@ -390,6 +452,9 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
 
     // Map the variants into 'fields'.
     let mut fields: Vec<(Ident, Field)> = Vec::new();
+    let mut names = Vec::new();
+    let mut field_names = Vec::new();
+    let mut variant_types = Vec::new();
     for Variant {
         attrs,
         ident: variant_ident,
@@ -407,6 +472,10 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
         if variant_fields.len() != 1 {
             bail!("Oneof enum variants must have a single field");
         }
+        variant_types.push(variant_fields[0].ty.clone());
+        let name = name_attr(&attrs)?;
+        field_names.push(name.clone().unwrap_or_else(|| to_snake(&variant_ident)));
+        names.push(name.unwrap_or_else(|| variant_ident.to_string()));
         match Field::new_oneof(attrs)? {
             Some(field) => fields.push((variant_ident, field)),
             None => bail!("invalid oneof variant: oneof variants may not be ignored"),
@@ -459,6 +528,14 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
         quote!(#ident::#variant_ident(ref value) => #encoded_len)
     });
 
+    let missing_fields = fields
+        .iter()
+        .zip(&names)
+        .filter_map(|((variant_ident, field), name)| {
+            let missing_fields = field.missing_fields(quote!(*value), name)?;
+            Some(quote!(#ident::#variant_ident(ref value) => { #missing_fields }))
+        });
+
     let expanded = quote! {
         impl #impl_generics #ident #ty_generics #where_clause {
             /// Encodes the message to a buffer.
@@ -490,9 +567,75 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
                     #(#encoded_len,)*
                 }
             }
+
+            /// Appends the paths of the missing required fields of the message to `missing`.
+            ///
+            /// Meant to be used only by `Message` implementations.
+            #[doc(hidden)]
+            #[allow(unreachable_patterns, unused_variables)]
+            pub fn missing_fields_into(
+                &self,
+                missing: &mut ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+            ) {
+                match *self {
+                    #(#missing_fields,)*
+                    _ => {}
+                }
+            }
         }
 
     };
+    let expanded = match accessors {
+        Some(accessors) => {
+            let field_names = fields
+                .iter()
+                .zip(&field_names)
+                .map(|((variant_ident, _), name)| quote!(#ident::#variant_ident(_) => #name));
+            let tags = fields.iter().map(|(variant_ident, field)| {
+                let tag = field.tags()[0];
+                quote!(#ident::#variant_ident(_) => #tag)
+            });
+            let message_methods = match accessors.message {
+                Some((message, field)) => {
+                    let methods =
+                        fields
+                            .iter()
+                            .zip(&variant_types)
+                            .map(|((variant_ident, _), ty)| {
+                                variant_methods(&ident, variant_ident, ty, &field)
+                            });
+                    quote! {
+                        impl #message {
+                            #(#methods)*
+                        }
+                    }
+                }
+                None => quote!(),
+            };
+            quote! {
+                #expanded
+
+                impl #impl_generics #ident #ty_generics #where_clause {
+                    /// Returns the Protobuf name of the field which is set.
+                    pub fn field_name(&self) -> &'static str {
+                        match *self {
+                            #(#field_names,)*
+                        }
+                    }
+
+                    /// Returns the number of the field which is set.
+                    pub fn tag(&self) -> u32 {
+                        match *self {
+                            #(#tags,)*
+                        }
+                    }
+                }
+
+                #message_methods
+            }
+        }
+        None => expanded,
+    };
     let expanded = if skip_debug {
         expanded
     } else {
@@ -521,6 +664,186 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
     Ok(expanded)
 }
 
+/// The `#[prost(accessors)]` attribute of a oneof.
+struct OneofAccessors {
+    /// The message and its field holding the oneof, given as
+    /// `#[prost(accessors(message = "super::Foo", field = "bar"))]`, which get the accessors of
+    /// the fields of the oneof.
+    message: Option<(Path, Ident)>,
+}
+
+fn oneof_accessors(attrs: &[Attribute]) -> Result<Option<OneofAccessors>, Error> {
+    let mut accessors = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("prost")) {
+        let meta = match attr.parse_args::<Meta>() {
+            Ok(meta) if meta.path().is_ident("accessors") => meta,
+            _ => continue,
+        };
+        if accessors.is_some() {
+            bail!("duplicate accessors attributes");
+        }
+        let mut message = None;
+        let mut field = None;
+        match meta {
+            Meta::Path(_) => (),
+            Meta::List(ref list) => list.parse_nested_meta(|meta| {
+                if meta.path.is_ident("message") {
+                    message = Some(meta.value()?.parse::<LitStr>()?.parse::<Path>()?);
+                } else if meta.path.is_ident("field") {
+                    field = Some(meta.value()?.parse::<LitStr>()?.parse::<Ident>()?);
+                } else {
+                    return Err(meta.error("unknown accessors attribute"));
+                }
+                Ok(())
+            })?,
+            Meta::NameValue(_) => bail!("invalid accessors attribute: {:?}", meta),
+        }
+        let message = match (message, field) {
+            (Some(message), Some(field)) => Some((message, field)),
+            (None, None) => None,
+            _ => bail!("invalid accessors attribute: both a message and a field are required"),
+        };
+        accessors = Some(OneofAccessors { message });
+    }
+    Ok(accessors)
+}
+
+/// Returns the `is_`, `as_`, `as_..._mut`, `set_` and `take_` methods of a variant of the oneof
+/// `oneof`, as methods of the message holding the oneof in `field`.
+fn variant_methods(oneof: &Ident, variant_ident: &Ident, ty: &Type, field: &Ident) -> TokenStream {
+    let name = to_snake(variant_ident);
+    let oneof_name = field.to_string();
+    let oneof_name = oneof_name.trim_start_matches("r#");
+    let method = |prefix: &str, suffix: &str| {
+        Ident::new(&format!("{}{}{}", prefix, name, suffix), Span::call_site())
+    };
+    let (is, as_ref, as_mut, set, take) = (
+        method("is_", ""),
+        method("as_", ""),
+        method("as_", "_mut"),
+        method("set_", ""),
+        method("take_", ""),
+    );
+    let is_doc = format!("Returns `true` if `{}` is set.", name);
+    let as_ref_doc = format!(
+        "Returns a reference to the value of `{}`, if it is set.",
+        name
+    );
+    let as_mut_doc = format!(
+        "Returns a mutable reference to the value of `{}`, if it is set.",
+        name
+    );
+    let set_doc = format!(
+        "Sets `{}`, unsetting the other fields of `{}`.",
+        name, oneof_name
+    );
+    let take_doc = format!(
+        "Takes the value of `{}`, leaving `{}` unset, if it is set.",
+        name, oneof_name
+    );
+    // Boxed values are set and returned unboxed.
+    let (ty, wrap, unwrap, value_ref, value_mut) = match boxed_type(ty) {
+        Some(ty) => (
+            ty,
+            quote!(::prost::alloc::boxed::Box::new(value)),
+            quote!(*value),
+            quote!(&**value),
+            quote!(&mut **value),
+        ),
+        None => (
+            ty,
+            quote!(value),
+            quote!(value),
+            quote!(value),
+            quote!(value),
+        ),
+    };
+    quote! {
+        #[doc=#is_doc]
+        pub fn #is(&self) -> bool {
+            ::core::matches!(self.#field, ::core::option::Option::Some(#oneof::#variant_ident(_)))
+        }
+
+        #[doc=#as_ref_doc]
+        pub fn #as_ref(&self) -> ::core::option::Option<&#ty> {
+            match self.#field {
+                ::core::option::Option::Some(#oneof::#variant_ident(ref value)) => {
+                    ::core::option::Option::Some(#value_ref)
+                }
+                _ => ::core::option::Option::None,
+            }
+        }
+
+        #[doc=#as_mut_doc]
+        pub fn #as_mut(&mut self) -> ::core::option::Option<&mut #ty> {
+            match self.#field {
+                ::core::option::Option::Some(#oneof::#variant_ident(ref mut value)) => {
+                    ::core::option::Option::Some(#value_mut)
+                }
+                _ => ::core::option::Option::None,
+            }
+        }
+
+        #[doc=#set_doc]
+        pub fn #set(&mut self, value: #ty) {
+            self.#field = ::core::option::Option::Some(#oneof::#variant_ident(#wrap));
+        }
+
+        #[doc=#take_doc]
+        pub fn #take(&mut self) -> ::core::option::Option<#ty> {
+            match self.#field.take() {
+                ::core::option::Option::Some(#oneof::#variant_ident(value)) => {
+                    ::core::option::Option::Some(#unwrap)
+                }
+                other => {
+                    self.#field = other;
+                    ::core::option::Option::None
+                }
+            }
+        }
+    }
+}
+
+/// Returns the type in a `Box`, if `ty` is a `Box`.
+fn boxed_type(ty: &Type) -> Option<&Type> {
+    let segment = match *ty {
+        Type::Path(ref path) => path.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != "Box" {
+        return None;
+    }
+    match segment.arguments {
+        PathArguments::AngleBracketed(ref arguments) if arguments.args.len() == 1 => {
+            match arguments.args[0] {
+                GenericArgument::Type(ref ty) => Some(ty),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Returns the snake case name of a oneof variant, like `foo_bar` for `FooBar`.
+fn to_snake(variant_ident: &Ident) -> String {
+    let mut name = String::new();
+    for (i, ch) in variant_ident
+        .to_string()
+        .trim_start_matches("r#")
+        .char_indices()
+    {
+        if ch.is_uppercase() {
+            if i > 0 {
+                name.push('_');
+            }
+            name.extend(ch.to_lowercase());
+        } else {
+            name.push(ch);
+        }
+    }
+    name
+}
+
 #[proc_macro_derive(Oneof, attributes(prost))]
 pub fn oneof(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     try_oneof(input.into()).unwrap().into()
@@ -569,6 +892,23 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_rejects_incomplete_oneof_accessors() {
+        let output = try_oneof(quote!(
+            #[prost(accessors(message = "super::Shape"))]
+            enum Kind {
+                #[prost(bool, tag = "1")]
+                A(bool),
+            }
+        ));
+        assert_eq!(
+            output
+                .expect_err("did not reject accessors without a field")
+                .to_string(),
+            "invalid accessors attribute: both a message and a field are required"
+        );
+    }
+
     #[test]
     fn test_rejects_multiple_tags_oneof_variant() {
         let output = try_oneof(quote!(
@@ -18,24 +18,35 @@ impl Any {
     where
         M: Default + Name + Sized,
     {
-        let expected_type_url = M::type_url();
-
-        if let (Some(expected), Some(actual)) = (
-            TypeUrl::new(&expected_type_url),
-            TypeUrl::new(&self.type_url),
-        ) {
-            if expected == actual {
-                return M::decode(self.value.as_slice());
-            }
+        if self.is::<M>() {
+            return M::decode(self.value.as_slice());
         }
 
         let mut err = DecodeError::new(format!(
             "expected type URL: \"{}\" (got: \"{}\")",
-            expected_type_url, &self.type_url
+            M::type_url(),
+            &self.type_url
         ));
         err.push("unexpected type URL", "type_url");
         Err(err)
     }
+
+    /// Returns `true` if the type URL of this [`Any`] names the message type `M`.
+    ///
+    /// Only the fully-qualified type name is compared, the domain of the type URL is ignored.
+    pub fn is<M>(&self) -> bool
+    where
+        M: Name,
+    {
+        let expected_type_url = M::type_url();
+        match (
+            TypeUrl::new(&expected_type_url),
+            TypeUrl::new(&self.type_url),
+        ) {
+            (Some(expected), Some(actual)) => expected == actual,
+            _ => false,
+        }
+    }
 }
 
 impl Name for Any {
@@ -65,5 +76,8 @@ mod tests {
 
         // Wrong type URL
         assert!(any.to_msg::<Duration>().is_err());
+
+        assert!(any.is::<Timestamp>());
+        assert!(!any.is::<Duration>());
     }
 }
@@ -33,6 +33,25 @@
 //! let message = any.to_msg::<Timestamp>().unwrap();
 //! ```
 //!
+//! ### Type registry
+//!
+//! When the type of the contained message is not known at compile time, a [`TypeRegistry`] can
+//! resolve it from the type URL. Use [`Any::is`] to check the type of the contained message
+//! without decoding it.
+//!
+//! ```rust
+//! # let message = Timestamp::date(2000, 1, 1).unwrap();
+//! # let any = Any::from_msg(&message).unwrap();
+//! #
+//! let mut registry = TypeRegistry::new();
+//! registry.register::<Timestamp>().register::<Duration>();
+//!
+//! assert!(any.is::<Timestamp>());
+//! let message = registry.unpack(&any).unwrap();
+//! assert_eq!(message.downcast_ref::<Timestamp>(), Some(&Timestamp::date(2000, 1, 1).unwrap()));
+//! println!("{:?}", registry.debug(&any));
+//! ```
+//!
 //! ## Feature Flags
 //! - `std`: Enable integration with standard library. Disable this feature for `no_std` support. This feature is enabled by default.
 //! - `arbitrary`: Enable integration with crate `arbitrary`. All types on this crate will implement `trait Arbitrary`.
@@ -79,4 +98,7 @@ pub use timestamp::TimestampError;
 mod type_url;
 pub(crate) use type_url::{type_url_for, TypeUrl};
 
+mod type_registry;
+pub use type_registry::{AnyDebug, TypeRegistry};
+
 mod conversions;
@@ -0,0 +1,169 @@
+use super::*;
+
+use prost::alloc::boxed::Box;
+use prost::alloc::collections::BTreeMap;
+use prost::DynName;
+
+type DecodeFn = fn(&[u8]) -> Result<Box<dyn DynName>, DecodeError>;
+
+fn decode<M>(value: &[u8]) -> Result<Box<dyn DynName>, DecodeError>
+where
+    M: Default + Name + 'static,
+{
+    Ok(Box::new(M::decode(value)?))
+}
+
+/// A set of message types which [`Any`] values can be resolved to at runtime.
+///
+/// [`Any::to_msg`] requires the type of the contained message to be known at compile time.
+/// A `TypeRegistry` maps type URLs to message types implementing [`Name`] instead, so that an
+/// [`Any`] can be unpacked into a [`DynName`] trait object without knowing its type up front.
+///
+/// Types are looked up by the fully-qualified name in the last segment of the type URL, so the
+/// domain of the type URL is not significant.
+#[derive(Clone, Default)]
+pub struct TypeRegistry {
+    types: BTreeMap<String, DecodeFn>,
+}
+
+impl TypeRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the message type `M`.
+    ///
+    /// Registering a type with the same full name as a previously registered type replaces it.
+    pub fn register<M>(&mut self) -> &mut Self
+    where
+        M: Default + Name + 'static,
+    {
+        self.types.insert(M::full_name(), decode::<M>);
+        self
+    }
+
+    /// Returns `true` if a message type is registered for the type URL.
+    pub fn contains(&self, type_url: &str) -> bool {
+        self.get(type_url).is_some()
+    }
+
+    /// Returns the fully-qualified names of the registered message types, in sorted order.
+    pub fn type_names(&self) -> impl Iterator<Item = &str> {
+        self.types.keys().map(String::as_str)
+    }
+
+    /// Decodes the message contained in `any`, using the message type registered for its type
+    /// URL.
+    ///
+    /// An error is returned if no message type is registered for the type URL, or if the message
+    /// fails to decode.
+    pub fn unpack(&self, any: &Any) -> Result<Box<dyn DynName>, DecodeError> {
+        match self.get(&any.type_url) {
+            Some(decode) => decode(any.value.as_slice()),
+            None => {
+                let mut err = DecodeError::new(format!("unknown type URL: \"{}\"", &any.type_url));
+                err.push("unknown type URL", "type_url");
+                Err(err)
+            }
+        }
+    }
+
+    /// Returns a value which formats `any` with [`fmt::Debug`], rendering the contained message
+    /// when its type is registered.
+    ///
+    /// Values with an unknown type URL, or which fail to decode, are formatted as the [`Any`]
+    /// itself.
+    pub fn debug<'a>(&'a self, any: &'a Any) -> AnyDebug<'a> {
+        AnyDebug {
+            registry: self,
+            any,
+        }
+    }
+
+    fn get(&self, type_url: &str) -> core::option::Option<&DecodeFn> {
+        self.types.get(TypeUrl::new(type_url)?.full_name)
+    }
+}
+
+impl fmt::Debug for TypeRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.type_names()).finish()
+    }
+}
+
+/// Formats an [`Any`] with the contents resolved through a [`TypeRegistry`].
+///
+/// This `struct` is created by [`TypeRegistry::debug`].
+pub struct AnyDebug<'a> {
+    registry: &'a TypeRegistry,
+    any: &'a Any,
+}
+
+impl fmt::Debug for AnyDebug<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.registry.unpack(self.any) {
+            Ok(message) => f
+                .debug_struct("Any")
+                .field("type_url", &self.any.type_url)
+                .field("value", &message)
+                .finish(),
+            Err(_) => fmt::Debug::fmt(self.any, f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unpack() {
+        let mut registry = TypeRegistry::new();
+        registry.register::<Timestamp>().register::<Duration>();
+        assert!(registry.contains("type.googleapis.com/google.protobuf.Timestamp"));
+        assert!(registry.contains("example.com/google.protobuf.Duration"));
+        assert!(!registry.contains("type.googleapis.com/google.protobuf.Any"));
+        assert!(!registry.contains("google.protobuf.Duration"));
+
+        let timestamp = Timestamp::date(2000, 1, 1).unwrap();
+        let any = Any::from_msg(&timestamp).unwrap();
+        let message = registry.unpack(&any).unwrap();
+        assert_eq!(message.type_name(), "google.protobuf.Timestamp");
+        assert_eq!(message.downcast_ref::<Timestamp>(), Some(&timestamp));
+
+        // Unknown type URL
+        let any = Any::from_msg(&any).unwrap();
+        assert!(registry.unpack(&any).is_err());
+
+        // Invalid value
+        let any = Any {
+            type_url: Duration::type_url(),
+            value: Vec::from([0xff]),
+        };
+        assert!(registry.unpack(&any).is_err());
+    }
+
+    #[test]
+    fn debug() {
+        let mut registry = TypeRegistry::new();
+        registry.register::<Duration>();
+        assert_eq!(format!("{:?}", registry), "{\"google.protobuf.Duration\"}");
+
+        let duration = Duration {
+            seconds: 1,
+            nanos: 2,
+        };
+        let any = Any::from_msg(&duration).unwrap();
+        assert_eq!(
+            format!("{:?}", registry.debug(&any)),
+            format!(
+                "Any {{ type_url: \"type.googleapis.com/google.protobuf.Duration\", value: {:?} }}",
+                duration
+            )
+        );
+
+        let any = Any::from_msg(&Timestamp::default()).unwrap();
+        assert_eq!(format!("{:?}", registry.debug(&any)), format!("{:?}", any));
+    }
+}
@@ -0,0 +1,197 @@
+//! Dyn-compatible encoding and decoding of messages.
+
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+use core::any::Any;
+
+use bytes::{Buf, BufMut};
+
+use crate::{DecodeError, EncodeError, Message, Name};
+
+/// A [`Message`] which can be encoded, decoded and downcast through a trait object.
+///
+/// Most [`Message`] methods require `Self: Sized`, so that they can be generic over the buffer
+/// type. `DynMessage` provides counterparts of these methods taking `dyn Buf` and `dyn BufMut`
+/// buffers, which makes heterogeneous collections such as `Vec<Box<dyn DynMessage>>` usable.
+///
+/// `DynMessage` is implemented for every [`Message`] type. When the Protobuf type name of the
+/// message is needed as well, use [`DynName`] instead.
+///
+/// # Examples
+///
+/// ```rust
+/// # use prost::{DynMessage, Message};
+/// #[derive(Clone, PartialEq, Message)]
+/// struct Ping {
+///     #[prost(uint32, tag = "1")]
+///     id: u32,
+/// }
+///
+/// let messages: Vec<Box<dyn DynMessage>> = vec![Box::new(Ping { id: 1 }), Box::new(42u32)];
+///
+/// let mut buf = Vec::new();
+/// for message in &messages {
+///     message.encode_length_delimited_dyn(&mut buf).unwrap();
+/// }
+///
+/// assert_eq!(messages[0].downcast_ref::<Ping>(), Some(&Ping { id: 1 }));
+/// assert!(!messages[1].is::<Ping>());
+/// ```
+pub trait DynMessage: Message + Any {
+    /// Encodes the message to a buffer.
+    ///
+    /// An error will be returned if the buffer does not have sufficient capacity.
+    fn encode_dyn(&self, buf: &mut dyn BufMut) -> Result<(), EncodeError>;
+
+    /// Encodes the message with a length-delimiter to a buffer.
+    ///
+    /// An error will be returned if the buffer does not have sufficient capacity.
+    fn encode_length_delimited_dyn(&self, buf: &mut dyn BufMut) -> Result<(), EncodeError>;
+
+    /// Decodes an instance of the message from a buffer, and merges it into `self`.
+    ///
+    /// The entire buffer will be consumed.
+    fn merge_dyn(&mut self, buf: &mut dyn Buf) -> Result<(), DecodeError>;
+
+    /// Decodes a length-delimited instance of the message from buffer, and
+    /// merges it into `self`.
+    fn merge_length_delimited_dyn(&mut self, buf: &mut dyn Buf) -> Result<(), DecodeError>;
+
+    /// Returns the message as `&dyn Any`, to downcast it to its concrete type.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Returns the message as `&mut dyn Any`, to downcast it to its concrete type.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// Converts the boxed message to `Box<dyn Any>`, to downcast it to its concrete type.
+    fn into_any(self: Box<Self>) -> Box<dyn Any + Send + Sync>;
+}
+
+impl<M> DynMessage for M
+where
+    M: Message + 'static,
+{
+    fn encode_dyn(&self, mut buf: &mut dyn BufMut) -> Result<(), EncodeError> {
+        self.encode(&mut buf)
+    }
+
+    fn encode_length_delimited_dyn(&self, mut buf: &mut dyn BufMut) -> Result<(), EncodeError> {
+        self.encode_length_delimited(&mut buf)
+    }
+
+    fn merge_dyn(&mut self, buf: &mut dyn Buf) -> Result<(), DecodeError> {
+        self.merge(buf)
+    }
+
+    fn merge_length_delimited_dyn(&mut self, buf: &mut dyn Buf) -> Result<(), DecodeError> {
+        self.merge_length_delimited(buf)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any + Send + Sync> {
+        self
+    }
+}
+
+/// A [`DynMessage`] which knows its Protobuf type name.
+///
+/// `DynName` is implemented for every [`Message`] type implementing [`Name`].
+pub trait DynName: DynMessage {
+    /// Returns the fully-qualified name of the message, see [`Name::full_name`].
+    fn type_name(&self) -> String;
+
+    /// Returns the type URL of the message, see [`Name::type_url`].
+    fn type_url_dyn(&self) -> String;
+}
+
+impl<M> DynName for M
+where
+    M: Name + 'static,
+{
+    fn type_name(&self) -> String {
+        M::full_name()
+    }
+
+    fn type_url_dyn(&self) -> String {
+        M::type_url()
+    }
+}
+
+macro_rules! impl_dyn_methods {
+    ($ty:ty) => {
+        impl $ty {
+            /// Returns `true` if the message is of type `M`.
+            pub fn is<M: Message + 'static>(&self) -> bool {
+                self.as_any().is::<M>()
+            }
+
+            /// Returns a reference to the message if it is of type `M`.
+            pub fn downcast_ref<M: Message + 'static>(&self) -> Option<&M> {
+                self.as_any().downcast_ref()
+            }
+
+            /// Returns a mutable reference to the message if it is of type `M`.
+            pub fn downcast_mut<M: Message + 'static>(&mut self) -> Option<&mut M> {
+                self.as_any_mut().downcast_mut()
+            }
+
+            /// Downcasts the boxed message to type `M`, or returns it unchanged if it is of
+            /// another type.
+            pub fn downcast<M: Message + 'static>(self: Box<Self>) -> Result<Box<M>, Box<Self>> {
+                if self.is::<M>() {
+                    Ok(self.into_any().downcast().unwrap())
+                } else {
+                    Err(self)
+                }
+            }
+        }
+    };
+}
+
+impl_dyn_methods!(dyn DynMessage);
+impl_dyn_methods!(dyn DynName);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::{vec, vec::Vec};
+
+    const _DYN_MESSAGE_IS_OBJECT_SAFE: Option<&dyn DynMessage> = None;
+    const _DYN_NAME_IS_OBJECT_SAFE: Option<&dyn DynName> = None;
+
+    #[test]
+    fn encode_and_merge() {
+        let messages: Vec<Box<dyn DynMessage>> = vec![Box::new(7u32), Box::new(String::from("x"))];
+        let encoded: Vec<Vec<u8>> = messages
+            .iter()
+            .map(|message| {
+                let mut buf = Vec::new();
+                message.encode_dyn(&mut buf).unwrap();
+                buf
+            })
+            .collect();
+        assert_eq!(
+            encoded,
+            vec![7u32.encode_to_vec(), String::from("x").encode_to_vec()]
+        );
+
+        let mut message: Box<dyn DynMessage> = Box::<u32>::default();
+        message.merge_dyn(&mut encoded[0].as_slice()).unwrap();
+        assert_eq!(message.downcast_ref::<u32>(), Some(&7));
+        *message.downcast_mut::<u32>().unwrap() += 1;
+        assert!(!message.is::<String>());
+        let message = message.downcast::<String>().unwrap_err();
+        assert_eq!(message.downcast::<u32>().unwrap(), Box::new(8));
+    }
+}
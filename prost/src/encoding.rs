@@ -862,6 +862,18 @@ pub mod message {
                 .map(|len| len + encoded_len_varint(len as u64))
                 .sum::<usize>()
     }
+
+    /// Appends the paths of the unset required fields of `msg`, the value of `field`, to `missing`.
+    pub fn missing_fields<M>(field: impl core::fmt::Display, msg: &M, missing: &mut Vec<String>)
+    where
+        M: Message,
+    {
+        let start = missing.len();
+        msg.missing_fields_into(missing);
+        for path in &mut missing[start..] {
+            *path = format!("{}.{}", field, path);
+        }
+    }
 }
 
 pub mod group {
@@ -3,6 +3,9 @@
 use alloc::borrow::Cow;
 #[cfg(not(feature = "std"))]
 use alloc::boxed::Box;
+use alloc::format;
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
@@ -26,6 +29,8 @@ struct Inner {
     /// message type and field where decoding failed. The stack contains an
     /// entry per level of nesting.
     stack: Vec<(&'static str, &'static str)>,
+    /// The paths of the required fields which were not set in the decoded message.
+    missing_fields: Vec<String>,
 }
 
 impl DecodeError {
@@ -39,10 +44,30 @@ impl DecodeError {
             inner: Box::new(Inner {
                 description: description.into(),
                 stack: Vec::new(),
+                missing_fields: Vec::new(),
             }),
         }
     }
 
+    /// Creates a new `DecodeError` for a message missing the given required fields.
+    #[cold]
+    pub(crate) fn new_missing_fields(missing_fields: Vec<String>) -> DecodeError {
+        let mut error = DecodeError::new(format!(
+            "missing required fields: {}",
+            missing_fields.join(", ")
+        ));
+        error.inner.missing_fields = missing_fields;
+        error
+    }
+
+    /// Returns the paths of the required fields which were not set in the decoded message, such
+    /// as `address.city` or `children[2].name`.
+    ///
+    /// The paths are empty unless decoding failed because of missing required fields.
+    pub fn missing_fields(&self) -> &[String] {
+        &self.inner.missing_fields
+    }
+
     /// Pushes a (message, field) name location pair on to the location stack.
     ///
     /// Meant to be used only by `Message` implementations.
@@ -83,13 +108,14 @@ impl From<DecodeError> for std::io::Error {
 
 /// A Protobuf message encoding error.
 ///
-/// `EncodeError` always indicates that a message failed to encode because the
-/// provided buffer had insufficient capacity. Message encoding is otherwise
-/// infallible.
+/// `EncodeError` indicates that a message failed to encode because the
+/// provided buffer had insufficient capacity, or because required fields of the
+/// message were not set. Message encoding is otherwise infallible.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct EncodeError {
     required: usize,
     remaining: usize,
+    missing_fields: usize,
 }
 
 impl EncodeError {
@@ -98,9 +124,28 @@ impl EncodeError {
         EncodeError {
             required,
             remaining,
+            missing_fields: 0,
+        }
+    }
+
+    /// Creates a new `EncodeError` for a message missing the given number of required fields.
+    #[cold]
+    pub(crate) fn new_missing_fields(missing_fields: usize) -> EncodeError {
+        EncodeError {
+            required: 0,
+            remaining: 0,
+            missing_fields,
         }
     }
 
+    /// Returns the number of required fields which were not set in the encoded message.
+    ///
+    /// The count is zero unless encoding failed because of missing required fields, whose paths
+    /// are returned by [`Message::missing_fields`](crate::Message::missing_fields).
+    pub fn missing_field_count(&self) -> usize {
+        self.missing_fields
+    }
+
     /// Returns the required buffer capacity to encode the message.
     pub fn required_capacity(&self) -> usize {
         self.required
@@ -114,6 +159,13 @@ impl EncodeError {
 
 impl fmt::Display for EncodeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.missing_fields > 0 {
+            return write!(
+                f,
+                "failed to encode Protobuf message; {} required fields are not set",
+                self.missing_fields
+            );
+        }
         write!(
             f,
             "failed to encode Protobuf message; insufficient buffer capacity (required: {}, remaining: {})",
@@ -148,3 +200,18 @@ impl fmt::Display for UnknownEnumValue {
 
 #[cfg(feature = "std")]
 impl std::error::Error for UnknownEnumValue {}
+
+/// An error indicating that a string is not the name of a value of an enumeration.
+///
+/// The value is the unrecognized string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownEnumName(pub String);
+
+impl fmt::Display for UnknownEnumName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown enumeration name {:?}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnknownEnumName {}
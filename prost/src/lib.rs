@@ -9,20 +9,29 @@ pub extern crate alloc;
 // Re-export the bytes crate for use within derived code.
 pub use bytes;
 
+mod dyn_message;
 mod error;
 mod message;
 mod name;
+mod proto_enum;
 mod types;
+mod validate;
 
 #[doc(hidden)]
 pub mod encoding;
 
+#[cfg(feature = "service")]
+pub mod service;
+
+pub use crate::dyn_message::{DynMessage, DynName};
 pub use crate::encoding::length_delimiter::{
     decode_length_delimiter, encode_length_delimiter, length_delimiter_len,
 };
-pub use crate::error::{DecodeError, EncodeError, UnknownEnumValue};
+pub use crate::error::{DecodeError, EncodeError, UnknownEnumName, UnknownEnumValue};
 pub use crate::message::Message;
 pub use crate::name::Name;
+pub use crate::proto_enum::ProtoEnum;
+pub use crate::validate::{Validate, ValidationErrors, Violation};
 
 // See `encoding::DecodeContext` for more info.
 // 100 is the default recursion limit in the C++ implementation.
@@ -1,6 +1,8 @@
 #[cfg(not(feature = "std"))]
 use alloc::boxed::Box;
 #[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
 use core::fmt::Debug;
@@ -39,13 +41,34 @@ pub trait Message: Debug + Send + Sync {
     where
         Self: Sized;
 
+    /// Appends the paths of the unset required fields of the message and its submessages to
+    /// `missing`.
+    ///
+    /// Meant to be used only by `Message` implementations.
+    #[doc(hidden)]
+    fn missing_fields_into(&self, missing: &mut Vec<String>) {
+        let _ = missing;
+    }
+
     /// Returns the encoded length of the message without a length delimiter.
     fn encoded_len(&self) -> usize;
 
     /// Encodes the message to a buffer.
     ///
-    /// An error will be returned if the buffer does not have sufficient capacity.
+    /// An error will be returned if the buffer does not have sufficient capacity, or if a required
+    /// field of the message or its submessages is not set, see [`Message::missing_fields`].
     fn encode(&self, buf: &mut impl BufMut) -> Result<(), EncodeError>
+    where
+        Self: Sized,
+    {
+        check_initialized_for_encode(self)?;
+        self.encode_partial(buf)
+    }
+
+    /// Encodes the message to a buffer, without checking that its required fields are set.
+    ///
+    /// An error will be returned if the buffer does not have sufficient capacity.
+    fn encode_partial(&self, buf: &mut impl BufMut) -> Result<(), EncodeError>
     where
         Self: Sized,
     {
@@ -60,6 +83,9 @@ pub trait Message: Debug + Send + Sync {
     }
 
     /// Encodes the message to a newly allocated buffer.
+    ///
+    /// Like [`Message::encode_partial`], this does not check that required fields are set. Use
+    /// [`Message::try_encode_to_vec`] to check them.
     fn encode_to_vec(&self) -> Vec<u8>
     where
         Self: Sized,
@@ -70,13 +96,27 @@ pub trait Message: Debug + Send + Sync {
         buf
     }
 
+    /// Encodes the message to a newly allocated buffer.
+    ///
+    /// An error will be returned if a required field of the message or its submessages is not
+    /// set, see [`Message::missing_fields`].
+    fn try_encode_to_vec(&self) -> Result<Vec<u8>, EncodeError>
+    where
+        Self: Sized,
+    {
+        check_initialized_for_encode(self)?;
+        Ok(self.encode_to_vec())
+    }
+
     /// Encodes the message with a length-delimiter to a buffer.
     ///
-    /// An error will be returned if the buffer does not have sufficient capacity.
+    /// An error will be returned if the buffer does not have sufficient capacity, or if a required
+    /// field of the message or its submessages is not set, see [`Message::missing_fields`].
     fn encode_length_delimited(&self, buf: &mut impl BufMut) -> Result<(), EncodeError>
     where
         Self: Sized,
     {
+        check_initialized_for_encode(self)?;
         let len = self.encoded_len();
         let required = len + encoded_len_varint(len as u64);
         let remaining = buf.remaining_mut();
@@ -89,6 +129,9 @@ pub trait Message: Debug + Send + Sync {
     }
 
     /// Encodes the message with a length-delimiter to a newly allocated buffer.
+    ///
+    /// Like [`Message::encode_partial`], this does not check that required fields are set. Use
+    /// [`Message::try_encode_length_delimited_to_vec`] to check them.
     fn encode_length_delimited_to_vec(&self) -> Vec<u8>
     where
         Self: Sized,
@@ -101,10 +144,36 @@ pub trait Message: Debug + Send + Sync {
         buf
     }
 
+    /// Encodes the message with a length-delimiter to a newly allocated buffer.
+    ///
+    /// An error will be returned if a required field of the message or its submessages is not
+    /// set, see [`Message::missing_fields`].
+    fn try_encode_length_delimited_to_vec(&self) -> Result<Vec<u8>, EncodeError>
+    where
+        Self: Sized,
+    {
+        check_initialized_for_encode(self)?;
+        Ok(self.encode_length_delimited_to_vec())
+    }
+
     /// Decodes an instance of the message from a buffer.
     ///
+    /// The entire buffer will be consumed. An error will be returned if a required field of the
+    /// message or its submessages is not set, see [`Message::missing_fields`].
+    fn decode(buf: impl Buf) -> Result<Self, DecodeError>
+    where
+        Self: Default,
+    {
+        let message = Self::decode_partial(buf)?;
+        check_initialized(&message)?;
+        Ok(message)
+    }
+
+    /// Decodes an instance of the message from a buffer, without checking that its required
+    /// fields are set.
+    ///
     /// The entire buffer will be consumed.
-    fn decode(mut buf: impl Buf) -> Result<Self, DecodeError>
+    fn decode_partial(mut buf: impl Buf) -> Result<Self, DecodeError>
     where
         Self: Default,
     {
@@ -113,12 +182,16 @@ pub trait Message: Debug + Send + Sync {
     }
 
     /// Decodes a length-delimited instance of the message from the buffer.
+    ///
+    /// An error will be returned if a required field of the message or its submessages is not
+    /// set, see [`Message::missing_fields`].
     fn decode_length_delimited(buf: impl Buf) -> Result<Self, DecodeError>
     where
         Self: Default,
     {
         let mut message = Self::default();
         message.merge_length_delimited(buf)?;
+        check_initialized(&message)?;
         Ok(message)
     }
 
@@ -151,10 +224,44 @@ pub trait Message: Debug + Send + Sync {
         )
     }
 
+    /// Returns `true` if all required fields of the message and its submessages are set.
+    fn is_initialized(&self) -> bool {
+        self.missing_fields().is_empty()
+    }
+
+    /// Returns the paths of the required fields of the message and its submessages which are not
+    /// set, such as `address.city` or `children[2].name`.
+    ///
+    /// Only proto2 `required` fields which keep track of their presence can be missing. Other
+    /// required fields always hold a value, which defaults to the field's default value.
+    fn missing_fields(&self) -> Vec<String> {
+        let mut missing = Vec::new();
+        self.missing_fields_into(&mut missing);
+        missing
+    }
+
     /// Clears the message, resetting all fields to their default.
     fn clear(&mut self);
 }
 
+fn check_initialized(message: &impl Message) -> Result<(), DecodeError> {
+    let missing = message.missing_fields();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(DecodeError::new_missing_fields(missing))
+    }
+}
+
+fn check_initialized_for_encode(message: &impl Message) -> Result<(), EncodeError> {
+    let missing = message.missing_fields();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(EncodeError::new_missing_fields(missing.len()))
+    }
+}
+
 impl<M> Message for Box<M>
 where
     M: Message,
@@ -171,6 +278,9 @@ where
     ) -> Result<(), DecodeError> {
         (**self).merge_field(tag, wire_type, buf, ctx)
     }
+    fn missing_fields_into(&self, missing: &mut Vec<String>) {
+        (**self).missing_fields_into(missing)
+    }
     fn encoded_len(&self) -> usize {
         (**self).encoded_len()
     }
@@ -0,0 +1,37 @@
+//! Support for working with the values of generated Protobuf enums.
+
+use core::convert::TryFrom;
+use core::iter::Copied;
+use core::slice::Iter;
+
+use crate::UnknownEnumValue;
+
+/// A Protobuf enum, with the names and options of its values.
+///
+/// `prost-build` implements this trait for the enums configured with its `enum_helpers` option,
+/// so that generic code, like command-line parsers or form builders, can work with any Protobuf
+/// enum.
+pub trait ProtoEnum:
+    Copy + Eq + Into<i32> + TryFrom<i32, Error = UnknownEnumValue> + 'static
+{
+    /// The values of the enum, in the order in which they are declared in the .proto file.
+    ///
+    /// Aliases of a value are not listed again.
+    const VALUES: &'static [Self];
+
+    /// Name of the value as it appears in the .proto file, e.g. `STATUS_ACTIVE`.
+    fn as_str_name(&self) -> &'static str;
+
+    /// Returns the value with the given name in the .proto file.
+    fn from_str_name(name: &str) -> Option<Self>;
+
+    /// Returns `true` if the value is marked with the `deprecated` option.
+    fn is_deprecated(&self) -> bool {
+        false
+    }
+
+    /// Returns an iterator over the values of the enum, in declaration order.
+    fn iter() -> Copied<Iter<'static, Self>> {
+        Self::VALUES.iter().copied()
+    }
+}
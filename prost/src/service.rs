@@ -0,0 +1,441 @@
+//! Runtime support for services generated by `prost_build::ServiceTraitGenerator`.
+//!
+//! A generated service consists of a trait, which is implemented by the application, a client,
+//! and a server which dispatches calls to the trait. Both sides exchange encoded messages through
+//! a [`Transport`], which is left to the application or an RPC framework. A server is itself a
+//! transport, so a client can call it directly, which is useful for tests and in-process calls.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+
+use core::fmt;
+use core::future::{poll_fn, Future};
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use bytes::Bytes;
+pub use futures_core::Stream;
+
+use crate::{DecodeError, Message};
+
+/// An owned, dynamically typed future.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// An owned, dynamically typed stream.
+pub type BoxStream<'a, T> = Pin<Box<dyn Stream<Item = T> + Send + 'a>>;
+
+/// A stream of messages, any of which may be an error.
+pub type Streaming<T> = BoxStream<'static, Result<T, Status>>;
+
+/// Carries encoded messages between a client and a service.
+///
+/// The `path` of a call is `/<package>.<service>/<method>`, using the names from the `.proto`
+/// file. Client-streaming, server-streaming and bidirectional calls all go through
+/// [`streaming`](Self::streaming), with a single message in place of a stream where needed.
+pub trait Transport: Send + Sync {
+    /// Makes a call with a single request and a single response.
+    fn unary(&self, path: &str, request: Bytes) -> BoxFuture<'_, Result<Bytes, Status>>;
+
+    /// Makes a call with a stream of requests and a stream of responses.
+    fn streaming(
+        &self,
+        path: &str,
+        requests: BoxStream<'static, Bytes>,
+    ) -> BoxFuture<'_, Result<Streaming<Bytes>, Status>>;
+}
+
+impl<T: Transport + ?Sized> Transport for &T {
+    fn unary(&self, path: &str, request: Bytes) -> BoxFuture<'_, Result<Bytes, Status>> {
+        (**self).unary(path, request)
+    }
+
+    fn streaming(
+        &self,
+        path: &str,
+        requests: BoxStream<'static, Bytes>,
+    ) -> BoxFuture<'_, Result<Streaming<Bytes>, Status>> {
+        (**self).streaming(path, requests)
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn unary(&self, path: &str, request: Bytes) -> BoxFuture<'_, Result<Bytes, Status>> {
+        (**self).unary(path, request)
+    }
+
+    fn streaming(
+        &self,
+        path: &str,
+        requests: BoxStream<'static, Bytes>,
+    ) -> BoxFuture<'_, Result<Streaming<Bytes>, Status>> {
+        (**self).streaming(path, requests)
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for alloc::sync::Arc<T> {
+    fn unary(&self, path: &str, request: Bytes) -> BoxFuture<'_, Result<Bytes, Status>> {
+        (**self).unary(path, request)
+    }
+
+    fn streaming(
+        &self,
+        path: &str,
+        requests: BoxStream<'static, Bytes>,
+    ) -> BoxFuture<'_, Result<Streaming<Bytes>, Status>> {
+        (**self).streaming(path, requests)
+    }
+}
+
+/// A service with a fully-qualified Protobuf name, like `helloworld.Greeter`.
+pub trait NamedService {
+    /// The fully-qualified name of the service.
+    const NAME: &'static str;
+}
+
+/// Dispatches calls to one of several services by the service name in the path.
+#[derive(Default)]
+pub struct Router {
+    services: BTreeMap<&'static str, Box<dyn Transport>>,
+}
+
+impl Router {
+    /// Creates a router without any services.
+    pub fn new() -> Router {
+        Router::default()
+    }
+
+    /// Adds a service, replacing any previous service with the same name.
+    pub fn add_service<S>(mut self, service: S) -> Router
+    where
+        S: Transport + NamedService + 'static,
+    {
+        self.services.insert(S::NAME, Box::new(service));
+        self
+    }
+
+    fn route(&self, path: &str) -> Result<&dyn Transport, Status> {
+        path.strip_prefix('/')
+            .and_then(|path| path.split_once('/'))
+            .and_then(|(service, _)| self.services.get(service))
+            .map(|service| &**service)
+            .ok_or_else(|| Status::unimplemented(path))
+    }
+}
+
+impl Transport for Router {
+    fn unary(&self, path: &str, request: Bytes) -> BoxFuture<'_, Result<Bytes, Status>> {
+        match self.route(path) {
+            Ok(service) => service.unary(path, request),
+            Err(status) => Box::pin(async move { Err(status) }),
+        }
+    }
+
+    fn streaming(
+        &self,
+        path: &str,
+        requests: BoxStream<'static, Bytes>,
+    ) -> BoxFuture<'_, Result<Streaming<Bytes>, Status>> {
+        match self.route(path) {
+            Ok(service) => service.streaming(path, requests),
+            Err(status) => Box::pin(async move { Err(status) }),
+        }
+    }
+}
+
+impl fmt::Debug for Router {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Router")
+            .field("services", &self.services.keys())
+            .finish()
+    }
+}
+
+/// The outcome of a failed call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Status {
+    code: Code,
+    message: String,
+}
+
+impl Status {
+    /// Creates a status with a code and a message.
+    pub fn new(code: Code, message: impl Into<String>) -> Status {
+        Status {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// Creates a status for a call to a method which does not exist.
+    pub fn unimplemented(path: &str) -> Status {
+        Status::new(Code::Unimplemented, format!("unknown method {}", path))
+    }
+
+    /// Returns the code of the status.
+    pub fn code(&self) -> Code {
+        self.code
+    }
+
+    /// Returns the message of the status.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Status {}
+
+impl From<DecodeError> for Status {
+    fn from(error: DecodeError) -> Status {
+        Status::new(Code::InvalidArgument, format!("{}", error))
+    }
+}
+
+/// The error codes of a [`Status`], which are the same as those used by gRPC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Code {
+    /// The operation was cancelled, typically by the caller.
+    Cancelled = 1,
+    /// An unknown error.
+    Unknown = 2,
+    /// The client specified an invalid argument.
+    InvalidArgument = 3,
+    /// The deadline expired before the operation could complete.
+    DeadlineExceeded = 4,
+    /// Some requested entity was not found.
+    NotFound = 5,
+    /// Some entity that the client attempted to create already exists.
+    AlreadyExists = 6,
+    /// The caller does not have permission to execute the operation.
+    PermissionDenied = 7,
+    /// Some resource has been exhausted.
+    ResourceExhausted = 8,
+    /// The system is not in a state required for the operation's execution.
+    FailedPrecondition = 9,
+    /// The operation was aborted.
+    Aborted = 10,
+    /// The operation was attempted past the valid range.
+    OutOfRange = 11,
+    /// The operation is not implemented or not supported.
+    Unimplemented = 12,
+    /// An internal error.
+    Internal = 13,
+    /// The service is currently unavailable.
+    Unavailable = 14,
+    /// Unrecoverable data loss or corruption.
+    DataLoss = 15,
+    /// The request does not have valid authentication credentials.
+    Unauthenticated = 16,
+}
+
+// Helpers for generated code.
+
+#[doc(hidden)]
+pub fn encode<M: Message>(message: &M) -> Bytes {
+    message.encode_to_vec().into()
+}
+
+#[doc(hidden)]
+pub fn decode<M: Message + Default>(buf: Bytes) -> Result<M, Status> {
+    Ok(M::decode(buf)?)
+}
+
+#[doc(hidden)]
+pub fn encode_requests<M, S>(requests: S) -> BoxStream<'static, Bytes>
+where
+    M: Message + 'static,
+    S: Stream<Item = M> + Send + 'static,
+{
+    Box::pin(Map {
+        stream: Box::pin(requests),
+        f: |request: M| encode(&request),
+    })
+}
+
+#[doc(hidden)]
+pub fn decode_requests<M>(requests: BoxStream<'static, Bytes>) -> Streaming<M>
+where
+    M: Message + Default + 'static,
+{
+    Box::pin(Map {
+        stream: requests,
+        f: decode,
+    })
+}
+
+#[doc(hidden)]
+pub fn encode_responses<M>(responses: Streaming<M>) -> Streaming<Bytes>
+where
+    M: Message + 'static,
+{
+    Box::pin(Map {
+        stream: responses,
+        f: |response: Result<M, Status>| response.map(|response| encode(&response)),
+    })
+}
+
+#[doc(hidden)]
+pub fn decode_responses<M>(responses: Streaming<Bytes>) -> Streaming<M>
+where
+    M: Message + Default + 'static,
+{
+    Box::pin(Map {
+        stream: responses,
+        f: |response: Result<Bytes, Status>| response.and_then(decode),
+    })
+}
+
+#[doc(hidden)]
+pub fn once<T: Send + 'static>(item: T) -> BoxStream<'static, T> {
+    Box::pin(Once(Some(item)))
+}
+
+/// Returns the only item of a stream, for calls with a single request or response.
+#[doc(hidden)]
+pub async fn single<T>(mut stream: BoxStream<'static, T>) -> Result<T, Status> {
+    let expected_one = || Status::new(Code::Internal, "expected exactly one message");
+    let item = poll_fn(|cx| stream.as_mut().poll_next(cx))
+        .await
+        .ok_or_else(expected_one)?;
+    match poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+        None => Ok(item),
+        Some(_) => Err(expected_one()),
+    }
+}
+
+struct Map<T, U> {
+    stream: BoxStream<'static, T>,
+    f: fn(T) -> U,
+}
+
+impl<T, U> Stream for Map<T, U> {
+    type Item = U;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<U>> {
+        let f = self.f;
+        self.stream.as_mut().poll_next(cx).map(|item| item.map(f))
+    }
+}
+
+struct Once<T>(Option<T>);
+
+// The item is never pinned.
+impl<T> Unpin for Once<T> {}
+
+impl<T> Stream for Once<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<T>> {
+        Poll::Ready(self.0.take())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec::Vec;
+    use core::task::{RawWaker, RawWakerVTable, Waker};
+
+    /// Runs a future which never waits.
+    fn now<F: Future>(future: F) -> F::Output {
+        fn raw_waker() -> RawWaker {
+            fn clone(_: *const ()) -> RawWaker {
+                raw_waker()
+            }
+            fn noop(_: *const ()) {}
+            static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+            RawWaker::new(core::ptr::null(), &VTABLE)
+        }
+        // SAFETY: the waker does nothing.
+        let waker = unsafe { Waker::from_raw(raw_waker()) };
+        let mut future = Box::pin(future);
+        match future.as_mut().poll(&mut Context::from_waker(&waker)) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future is pending"),
+        }
+    }
+
+    fn collect<T>(mut stream: BoxStream<'static, T>) -> Vec<T> {
+        now(async move {
+            let mut items = Vec::new();
+            while let Some(item) = poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+                items.push(item);
+            }
+            items
+        })
+    }
+
+    /// Echoes the requests of any method.
+    struct Echo;
+
+    impl NamedService for Echo {
+        const NAME: &'static str = "test.Echo";
+    }
+
+    impl Transport for Echo {
+        fn unary(&self, _: &str, request: Bytes) -> BoxFuture<'_, Result<Bytes, Status>> {
+            Box::pin(async move { Ok(request) })
+        }
+
+        fn streaming(
+            &self,
+            _: &str,
+            requests: BoxStream<'static, Bytes>,
+        ) -> BoxFuture<'_, Result<Streaming<Bytes>, Status>> {
+            Box::pin(async move {
+                Ok(Box::pin(Map {
+                    stream: requests,
+                    f: Ok,
+                }) as Streaming<Bytes>)
+            })
+        }
+    }
+
+    #[test]
+    fn router() {
+        let router = Router::new().add_service(Echo);
+        let request = Bytes::from_static(b"request");
+        assert_eq!(
+            now(router.unary("/test.Echo/Method", request.clone())),
+            Ok(request.clone())
+        );
+        assert_eq!(
+            now(router.unary("/test.Other/Method", request.clone())),
+            Err(Status::new(
+                Code::Unimplemented,
+                "unknown method /test.Other/Method"
+            ))
+        );
+
+        let responses = now(router.streaming("/test.Echo/Method", once(request.clone()))).unwrap();
+        assert_eq!(collect(responses), [Ok(request)]);
+    }
+
+    #[test]
+    fn streams() {
+        let requests = encode_requests(Once(Some(String::from("request"))));
+        let decoded = collect(decode_requests::<String>(requests));
+        assert_eq!(decoded, [Ok(String::from("request"))]);
+
+        let invalid = decode_requests::<String>(once(Bytes::from_static(b"\x0a\x01\xff")));
+        assert_eq!(
+            collect(invalid)[0].as_ref().unwrap_err().code(),
+            Code::InvalidArgument
+        );
+
+        assert_eq!(now(single(once(1))), Ok(1));
+        assert_eq!(
+            now(single(Box::pin(Once(None::<i32>)))).unwrap_err().code(),
+            Code::Internal
+        );
+    }
+}
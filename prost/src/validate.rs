@@ -0,0 +1,186 @@
+//! Support for validating messages against field constraints.
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use core::fmt;
+
+/// A message which can check its fields against a set of validation rules.
+///
+/// Implementations are generated by `prost-build` for messages whose fields carry
+/// `buf.validate` (protovalidate) constraints. See `prost_build::Config::validation`.
+pub trait Validate {
+    /// Checks every field of the message against its constraints, recursing into submessages.
+    ///
+    /// All violations are collected, rather than stopping at the first one.
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        self.validate_into("", &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Checks the fields of the message, pushing any violations on to `errors`.
+    ///
+    /// `path` is the field path of this message relative to the root message being validated,
+    /// or the empty string for the root message itself.
+    ///
+    /// Meant to be used only by `Validate` implementations.
+    #[doc(hidden)]
+    fn validate_into(&self, path: &str, errors: &mut ValidationErrors);
+}
+
+impl<M> Validate for Box<M>
+where
+    M: Validate + ?Sized,
+{
+    fn validate_into(&self, path: &str, errors: &mut ValidationErrors) {
+        (**self).validate_into(path, errors)
+    }
+}
+
+/// A single constraint violation found while validating a message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    /// The path of the offending field relative to the validated message, e.g. `items[2].name`.
+    pub field_path: String,
+    /// The identifier of the violated constraint, e.g. `string.min_len`.
+    pub constraint_id: Cow<'static, str>,
+    /// A human readable description of the violation.
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} [{}]",
+            self.field_path, self.message, self.constraint_id
+        )
+    }
+}
+
+/// The set of constraint violations found while validating a message.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValidationErrors {
+    violations: Vec<Violation>,
+}
+
+impl ValidationErrors {
+    /// Returns the violations, in the order in which they were found.
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    /// Consumes the errors, returning the violations.
+    pub fn into_violations(self) -> Vec<Violation> {
+        self.violations
+    }
+
+    /// Returns `true` if no violations have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Returns the number of recorded violations.
+    pub fn len(&self) -> usize {
+        self.violations.len()
+    }
+
+    /// Records a violation of `constraint_id` by the field at `field_path`.
+    ///
+    /// Meant to be used only by `Validate` implementations.
+    #[doc(hidden)]
+    pub fn push(
+        &mut self,
+        field_path: String,
+        constraint_id: impl Into<Cow<'static, str>>,
+        message: impl Into<String>,
+    ) {
+        self.violations.push(Violation {
+            field_path,
+            constraint_id: constraint_id.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Joins a parent field path and a field name.
+    ///
+    /// Meant to be used only by `Validate` implementations.
+    #[doc(hidden)]
+    pub fn field_path(parent: &str, field: &str) -> String {
+        if parent.is_empty() {
+            String::from(field)
+        } else {
+            format!("{}.{}", parent, field)
+        }
+    }
+
+    /// Appends an element index or map key to a field path.
+    ///
+    /// Meant to be used only by `Validate` implementations.
+    #[doc(hidden)]
+    pub fn element_path(parent: &str, key: impl fmt::Display) -> String {
+        format!("{}[{}]", parent, key)
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("validation failed")?;
+        for (idx, violation) in self.violations.iter().enumerate() {
+            f.write_str(if idx == 0 { ": " } else { "; " })?;
+            fmt::Display::fmt(violation, f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ValidationErrors {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Named(&'static str);
+
+    impl Validate for Named {
+        fn validate_into(&self, path: &str, errors: &mut ValidationErrors) {
+            if self.0.is_empty() {
+                errors.push(
+                    ValidationErrors::field_path(path, "name"),
+                    "required",
+                    "value is required",
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn validate_collects_violations() {
+        assert_eq!(Named("foo").validate(), Ok(()));
+
+        let errors = Named("").validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors.violations()[0].field_path, "name");
+        assert_eq!(
+            errors.to_string(),
+            "validation failed: name: value is required [required]"
+        );
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(ValidationErrors::field_path("", "a"), "a");
+        assert_eq!(ValidationErrors::field_path("a", "b"), "a.b");
+        assert_eq!(ValidationErrors::element_path("a.b", 3), "a.b[3]");
+        assert_eq!(ValidationErrors::element_path("a", "\"key\""), "a[\"key\"]");
+    }
+}
@@ -17,6 +17,7 @@ CRATES=( \
   "prost" \
   "prost-types" \
   "prost-build" \
+  "prost-build-plugin" \
 )
 
 for CRATE in "${CRATES[@]}"; do
@@ -128,6 +128,11 @@ fn main() {
         .compile_protos(&[src.join("submessage_without_package.proto")], includes)
         .unwrap();
 
+    prost_build::Config::new()
+        .accessors(["."])
+        .compile_protos(&[src.join("accessors.proto")], includes)
+        .unwrap();
+
     prost_build::Config::new()
         .protoc_arg("--experimental_allow_proto3_optional")
         .compile_protos(&[src.join("proto3_presence.proto")], includes)
@@ -164,15 +169,103 @@ fn main() {
 
     prost_build::Config::new()
         .enable_type_names()
+        .enable_type_registry()
         .type_name_domain([".type_names.Foo"], "tests")
         .compile_protos(&[src.join("type_names.proto")], includes)
         .unwrap();
 
+    prost_build::Config::new()
+        .service_generator(Box::new(prost_build::ServiceTraitGenerator::new()))
+        .compile_protos(&[src.join("services.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .btree_map([".derive_eq_hash.Key"])
+        .derive_eq_hash(["."])
+        .derive_ord([".derive_eq_hash.Key"])
+        .compile_protos(&[src.join("derive_eq_hash.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .naming_strategy(Box::new(OneofVariantNaming))
+        .rename(".naming.api_v1", "v1")
+        .rename(".naming.api_v1.Shape.kind", "ShapeKind")
+        .rename(".naming.api_v1.Shape.sideCount", "sides")
+        .rename(".naming.api_v1.Color.COLOR_GREY", "Gray")
+        .compile_protos(&[src.join("naming.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .message_generator(Box::new(RegistryGenerator::default()))
+        .compile_protos(&[src.join("message_generator.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .compile_well_known_types()
+        .map_wrapper_types()
+        .bytes([".wrapper_types.Wrappers.bytes"])
+        .compile_protos(&[src.join("wrapper_types.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .strip_embedded_source_info()
+        .compile_protos(&[src.join("file_descriptor_set.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .btree_map(["."])
+        .validation([".validate", ".validate_pattern"])
+        .compile_protos(
+            &[
+                src.join("validate.proto"),
+                src.join("validate_pattern.proto"),
+            ],
+            includes,
+        )
+        .unwrap();
+
+    prost_build::Config::new()
+        .btree_map(["."])
+        .builders(["."])
+        .compile_protos(&[src.join("builders.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .btree_map(["."])
+        .required_field_presence(["."])
+        .compile_protos(&[src.join("required_fields.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .btree_map(["."])
+        .bytes([".arbitrary.Tree.blob"])
+        .arbitrary([".arbitrary.Tree", ".arbitrary.Kind"])
+        .proptest([".arbitrary.Tree", ".arbitrary.Kind"])
+        .arbitrary_unknown_enum_values([".arbitrary.Tree.lenient_kind"])
+        .arbitrary_max_depth(2)
+        .compile_protos(&[src.join("arbitrary.proto")], includes)
+        .unwrap();
+
     prost_build::Config::new()
         .boxed("Foo.bar")
         .compile_protos(&[src.join("boxed_field.proto")], includes)
         .unwrap();
 
+    prost_build::Config::new()
+        .enum_helpers(["."])
+        .compile_protos(&[src.join("enum_helpers.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .oneof_conversions(["."])
+        .compile_protos(&[src.join("oneof_conversions.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .box_large_fields(32)
+        .compile_protos(&[src.join("large_fields.proto")], includes)
+        .unwrap();
+
     // Check that attempting to compile a .proto without a package declaration does not result in an error.
     config
         .compile_protos(&[src.join("no_package.proto")], includes)
@@ -241,3 +334,68 @@ fn main() {
         .compile_protos(&[] as &[&str], &[] as &[&str])
         .unwrap();
 }
+
+/// Names oneof variants with an `Is` prefix.
+struct OneofVariantNaming;
+
+impl prost_build::NamingStrategy for OneofVariantNaming {
+    fn oneof_variant_name(&self, name: &str) -> String {
+        let mut chars = name.chars();
+        let first = chars.next().unwrap().to_ascii_uppercase();
+        format!("Is{}{}", first, chars.as_str())
+    }
+}
+
+/// Generates a constant with the field names and comments of each message, the number of variants
+/// of each enum, and a registry of the messages and enums of each package.
+#[derive(Default)]
+struct RegistryGenerator {
+    messages: Vec<(String, String)>,
+    enums: Vec<(String, String)>,
+}
+
+impl prost_build::MessageGenerator for RegistryGenerator {
+    fn generate_message(&mut self, message: &prost_build::Message, buf: &mut String) {
+        let fields = message
+            .fields
+            .iter()
+            .chain(message.oneofs.iter().flat_map(|oneof| &oneof.fields))
+            .map(|field| format!("{:?}", field.proto_name))
+            .collect::<Vec<_>>();
+        buf.push_str(&format!(
+            "impl {} {{\n    pub const FIELDS: &'static [&'static str] = &[{}];\n    \
+             pub const COMMENTS: &'static str = {:?};\n}}\n",
+            message.name,
+            fields.join(", "),
+            message.comments.leading.join("\n").trim(),
+        ));
+        self.messages
+            .push((message.full_name.clone(), message.rust_path.clone()));
+    }
+
+    fn generate_enum(&mut self, enum_: &prost_build::Enum, buf: &mut String) {
+        buf.push_str(&format!(
+            "impl {} {{\n    pub const VARIANT_COUNT: usize = {};\n}}\n",
+            enum_.name,
+            enum_.variants.len(),
+        ));
+        self.enums
+            .push((enum_.full_name.clone(), enum_.rust_path.clone()));
+    }
+
+    fn finalize_package(&mut self, _package: &str, buf: &mut String) {
+        buf.push_str("pub const MESSAGES: &[(&str, &[&str])] = &[\n");
+        for (full_name, rust_path) in self.messages.drain(..) {
+            buf.push_str(&format!("    ({:?}, {}::FIELDS),\n", full_name, rust_path));
+        }
+        buf.push_str("];\n");
+        buf.push_str("pub const ENUMS: &[(&str, usize)] = &[\n");
+        for (full_name, rust_path) in self.enums.drain(..) {
+            buf.push_str(&format!(
+                "    ({:?}, {}::VARIANT_COUNT),\n",
+                full_name, rust_path
+            ));
+        }
+        buf.push_str("];\n");
+    }
+}
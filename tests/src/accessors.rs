@@ -0,0 +1,127 @@
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+
+use prost::{Message, Oneof};
+
+include!(concat!(env!("OUT_DIR"), "/accessors.rs"));
+
+#[test]
+fn optional_fields() {
+    let mut shape = Shape::default();
+    assert!(!shape.has_name());
+    assert!(!shape.has_color());
+    assert!(!shape.has_origin());
+
+    shape.name = Some("square".to_string());
+    shape.set_color(Color::Red);
+    shape.origin = Some(Point { x: 1, y: 2 });
+    assert!(shape.has_name());
+    assert!(shape.has_color());
+    assert!(shape.has_origin());
+
+    assert_eq!(shape.take_name(), Some("square".to_string()));
+    assert!(!shape.has_name());
+    assert_eq!(shape.take_origin(), Some(Point { x: 1, y: 2 }));
+    assert_eq!(shape.origin, None);
+    shape.clear_color();
+    assert_eq!(shape.color, None);
+}
+
+#[test]
+fn oneof_fields() {
+    let mut shape = Shape::default();
+    assert_eq!(shape.which_kind(), None);
+    assert!(!shape.has_kind());
+    assert!(!shape.is_corner());
+    assert_eq!(shape.take_corner(), None);
+
+    shape.set_corner(Point { x: 3, y: 4 });
+    assert_eq!(shape.which_kind(), Some("corner"));
+    assert!(shape.has_kind());
+    assert!(shape.is_corner());
+    assert!(!shape.is_radius());
+    assert_eq!(shape.as_radius(), None);
+    shape.as_corner_mut().unwrap().x = 5;
+    assert_eq!(shape.as_corner(), Some(&Point { x: 5, y: 4 }));
+
+    let kind = shape.kind.as_ref().unwrap();
+    assert_eq!(kind.field_name(), "corner");
+    assert_eq!(kind.tag(), 5);
+
+    // Taking another field leaves the oneof as it is.
+    assert_eq!(shape.take_label(), None);
+    assert!(shape.is_corner());
+    assert_eq!(shape.take_corner(), Some(Point { x: 5, y: 4 }));
+    assert_eq!(shape.kind, None);
+
+    shape.set_label("circle".to_string());
+    let kind = shape.take_kind().unwrap();
+    assert_eq!(kind, shape::Kind::Label("circle".to_string()));
+    assert!(!shape.has_kind());
+
+    shape.set_radius(1.0);
+    shape.set_label("circle".to_string());
+    assert_eq!(shape.as_radius(), None);
+    shape.clear_kind();
+    assert_eq!(shape.which_kind(), None);
+}
+
+#[test]
+fn oneof_field_names() {
+    let mut shape = Shape::default();
+    // Accessors are named after the variants, and `which_` returns the Protobuf name.
+    shape.set_point1(Point { x: 1, y: 1 });
+    assert!(shape.is_point1());
+    assert_eq!(shape.which_kind(), Some("point_1"));
+    assert_eq!(shape.kind.as_ref().unwrap().tag(), 8);
+
+    // Boxed fields are set and taken unboxed.
+    shape.set_inner(Shape::default());
+    assert_eq!(
+        shape.kind,
+        Some(shape::Kind::Inner(Box::new(Shape::default())))
+    );
+    shape.as_inner_mut().unwrap().set_radius(2.0);
+    assert_eq!(shape.as_inner().unwrap().as_radius(), Some(&2.0));
+    assert_eq!(shape.which_kind(), Some("inner"));
+    let inner = shape.take_inner().unwrap();
+    assert_eq!(inner.which_kind(), Some("radius"));
+}
+
+#[derive(Clone, PartialEq, Message)]
+#[prost(accessors)]
+struct Label {
+    #[prost(string, optional, tag = "1")]
+    text: Option<String>,
+    #[prost(oneof = "Anchor", tags = "2, 3")]
+    anchor: Option<Anchor>,
+}
+
+#[derive(Clone, PartialEq, Oneof)]
+#[prost(accessors(message = "Label", field = "anchor"))]
+enum Anchor {
+    #[prost(message, tag = "2")]
+    TopLeft(Point),
+    #[prost(message, tag = "3")]
+    Center(Box<Point>),
+}
+
+#[test]
+fn derived_accessors() {
+    let mut label = Label::default();
+    assert!(!label.has_text());
+    label.text = Some("label".to_string());
+    assert_eq!(label.take_text(), Some("label".to_string()));
+
+    label.set_center(Point { x: 1, y: 2 });
+    assert!(label.is_center());
+    assert!(!label.is_top_left());
+    assert_eq!(label.as_center(), Some(&Point { x: 1, y: 2 }));
+    assert_eq!(label.which_anchor(), Some("center"));
+    assert_eq!(label.anchor.as_ref().unwrap().tag(), 3);
+
+    label.set_top_left(Point::default());
+    assert_eq!(label.which_anchor(), Some("top_left"));
+    assert_eq!(label.take_top_left(), Some(Point::default()));
+    assert!(!label.has_anchor());
+}
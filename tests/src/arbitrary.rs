@@ -0,0 +1,72 @@
+use alloc::vec::Vec;
+
+use arbitrary::{Arbitrary, Unstructured};
+use proptest::prelude::*;
+use prost::Message;
+
+include!(concat!(env!("OUT_DIR"), "/arbitrary.rs"));
+
+const MAX_DEPTH: u32 = 2;
+
+/// Checks the invariants of a generated tree, returning its depth.
+fn check(tree: &Tree) -> u32 {
+    assert!(Kind::try_from(tree.kind).is_ok());
+    assert!(tree.kinds.iter().all(|&kind| Kind::try_from(kind).is_ok()));
+    assert!(Shade::try_from(tree.shade).is_ok());
+    if let Some(created) = &tree.created {
+        assert!((0..1_000_000_000).contains(&created.nanos));
+    }
+    if let Some(ttl) = &tree.ttl {
+        assert!(ttl.nanos.abs() < 1_000_000_000);
+        assert!(ttl.seconds.signum() * i64::from(ttl.nanos.signum()) >= 0);
+    }
+
+    let mut children: Vec<&Tree> = tree.children.iter().chain(tree.named.values()).collect();
+    children.extend(tree.parent.as_deref());
+    match &tree.value {
+        Some(tree::Value::Subtree(subtree)) => children.push(subtree),
+        Some(tree::Value::OtherKind(kind)) => assert!(Kind::try_from(*kind).is_ok()),
+        _ => {}
+    }
+    let depth = children.into_iter().map(|child| check(child) + 1).max();
+    let depth = depth.unwrap_or(0);
+    assert!(depth <= MAX_DEPTH);
+    depth
+}
+
+fn check_round_trip(tree: &Tree) {
+    let decoded = Tree::decode(tree.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(&decoded, tree);
+}
+
+#[test]
+fn arbitrary_messages() {
+    // A simple linear congruential generator, to get reproducible pseudo-random input.
+    let mut state = 0x2545_f491_u32;
+    let data: Vec<u8> = (0..1 << 16)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as u8
+        })
+        .collect();
+
+    let mut lenient_kind_unknown = false;
+    for offset in (0..data.len()).step_by(1 << 10) {
+        let mut u = Unstructured::new(&data[offset..]);
+        let tree = Tree::arbitrary(&mut u).unwrap();
+        check(&tree);
+        check_round_trip(&tree);
+        lenient_kind_unknown |= Kind::try_from(tree.lenient_kind).is_err();
+    }
+    assert!(lenient_kind_unknown);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn proptest_messages(tree in any::<Tree>()) {
+        check(&tree);
+        check_round_trip(&tree);
+    }
+}
@@ -0,0 +1,87 @@
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use alloc::vec;
+
+include!(concat!(env!("OUT_DIR"), "/builders.rs"));
+
+#[test]
+fn build_message() {
+    let user = User::builder()
+        .name("alice")
+        .role(Role::Admin)
+        .age(30)
+        .tags(["a", "b"])
+        .push_tags("c")
+        .push_roles(Role::Member)
+        .address_with(|address| address.city("Paris").r#type("home"))
+        .push_previous_addresses_with(|address| address.city("Lyon"))
+        .insert_team_roles("core", Role::Admin)
+        .avatar(vec![1, 2, 3])
+        .manager_with(|manager| manager.name("bob"))
+        .build();
+
+    let mut expected = User {
+        name: "alice".to_string(),
+        role: Role::Admin as i32,
+        age: Some(30),
+        tags: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+        roles: vec![Role::Member as i32],
+        address: Some(Address {
+            city: "Paris".to_string(),
+            r#type: Some("home".to_string()),
+        }),
+        previous_addresses: vec![Address {
+            city: "Lyon".to_string(),
+            r#type: None,
+        }],
+        avatar: vec![1, 2, 3],
+        manager: Some(Box::new(User {
+            name: "bob".to_string(),
+            ..Default::default()
+        })),
+        ..Default::default()
+    };
+    expected
+        .team_roles
+        .insert("core".to_string(), Role::Admin as i32);
+    assert_eq!(user, expected);
+}
+
+#[test]
+fn collection_setters_replace_values() {
+    let user = User::builder()
+        .push_roles(Role::Admin)
+        .roles([Role::Member])
+        .push_previous_addresses(Address::default())
+        .previous_addresses([])
+        .insert_team_roles("core", Role::Admin)
+        .team_roles([("web".to_string(), Role::Member)])
+        .build();
+
+    assert_eq!(user.roles, vec![Role::Member as i32]);
+    assert!(user.previous_addresses.is_empty());
+    assert_eq!(user.team_roles.len(), 1);
+    assert_eq!(user.team_roles["web"], Role::Member as i32);
+}
+
+#[test]
+fn oneof_setters() {
+    let user = User::builder().phone("555-0100").build();
+    assert_eq!(
+        user.contact,
+        Some(user::Contact::Phone("555-0100".to_string()))
+    );
+
+    // The last setter called selects the variant.
+    let user: User = User::builder()
+        .phone("555-0100")
+        .mailing_address_with(|address| address.city("Paris"))
+        .into();
+    assert_eq!(
+        user.contact,
+        Some(user::Contact::MailingAddress(Address {
+            city: "Paris".to_string(),
+            r#type: None,
+        }))
+    );
+}
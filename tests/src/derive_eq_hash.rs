@@ -0,0 +1,68 @@
+//! Tests for automatically derived `Eq`, `Hash`, `PartialOrd` and `Ord`.
+
+include!(concat!(env!("OUT_DIR"), "/derive_eq_hash.rs"));
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::marker::PhantomData;
+
+/// Returns whether a type implements the given traits.
+macro_rules! implements {
+    ($ty:ty: $($bounds:tt)+) => {{
+        #[allow(dead_code)]
+        trait DoesNotImplement {
+            const IMPLEMENTS: bool = false;
+        }
+        impl<T> DoesNotImplement for T {}
+
+        struct Wrapper<T>(PhantomData<T>);
+        #[allow(dead_code)]
+        impl<T: $($bounds)+> Wrapper<T> {
+            const IMPLEMENTS: bool = true;
+        }
+
+        <Wrapper<$ty>>::IMPLEMENTS
+    }};
+}
+
+#[test]
+fn derives() {
+    assert!(implements!(Key: Eq + Hash + Ord));
+    assert!(implements!(key::Choice: Eq + Hash + Ord));
+    assert!(implements!(Recursive: Eq + Hash));
+    assert!(!implements!(Recursive: Ord));
+
+    // Maps generated as `HashMap`s don't implement `Hash`.
+    assert!(!implements!(Labels: Eq));
+    // Floating point fields prevent the derives, also through other messages.
+    assert!(!implements!(Reading: Eq));
+    assert!(!implements!(Measurement: Eq));
+    // Well-known types allow the derives they implement.
+    assert!(implements!(Stamped: Eq + Hash));
+    assert!(!implements!(Timed: Eq));
+}
+
+#[test]
+fn ordered_keys() {
+    let key = |name: &str| Key {
+        name: name.to_string(),
+        ..Default::default()
+    };
+    let keys = vec![key("b"), key("a"), key("b")]
+        .into_iter()
+        .collect::<BTreeSet<_>>();
+    assert_eq!(
+        keys.into_iter().collect::<Vec<_>>(),
+        vec![key("a"), key("b")]
+    );
+
+    let nested = Key {
+        choice: Some(key::Choice::Nested(Box::new(key("a")))),
+        ..key("a")
+    };
+    assert!(key("a") < nested);
+}
@@ -0,0 +1,62 @@
+include!(concat!(env!("OUT_DIR"), "/enum_helpers.rs"));
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use prost::{ProtoEnum, UnknownEnumName};
+
+#[test]
+fn values_are_listed_in_declaration_order() {
+    assert_eq!(
+        Status::VALUES,
+        &[
+            Status::Unspecified,
+            Status::Active,
+            Status::Legacy,
+            Status::Done
+        ]
+    );
+    assert_eq!(
+        Status::iter().map(i32::from).collect::<Vec<_>>(),
+        [0, 1, 2, 3]
+    );
+}
+
+#[test]
+fn deprecated_values() {
+    assert!(Status::Legacy.is_deprecated());
+    assert!(!Status::Active.is_deprecated());
+    let task = Task {
+        priority: task::Priority::High.into(),
+    };
+    assert!(!task.priority().is_deprecated());
+}
+
+#[test]
+fn display_and_from_str() {
+    assert_eq!(Status::Done.to_string(), "STATUS_DONE");
+    assert_eq!("STATUS_ACTIVE".parse::<Status>(), Ok(Status::Active));
+    assert_eq!(
+        "DONE".parse::<Status>(),
+        Err(UnknownEnumName("DONE".to_string()))
+    );
+}
+
+fn names<E: ProtoEnum>() -> Vec<String> {
+    E::iter()
+        .filter(|value| !value.is_deprecated())
+        .map(|value| value.as_str_name().to_string())
+        .collect()
+}
+
+#[test]
+fn generic_enums() {
+    assert_eq!(
+        names::<Status>(),
+        ["STATUS_UNSPECIFIED", "STATUS_ACTIVE", "STATUS_DONE"]
+    );
+    assert_eq!(names::<task::Priority>(), ["PRIORITY_LOW", "PRIORITY_HIGH"]);
+    assert_eq!(
+        <task::Priority as ProtoEnum>::from_str_name("PRIORITY_HIGH"),
+        Some(task::Priority::High)
+    );
+}
@@ -0,0 +1,33 @@
+use alloc::vec::Vec;
+
+use prost::Message;
+use prost_types::FileDescriptorSet;
+
+include!(concat!(env!("OUT_DIR"), "/file_descriptor_set.rs"));
+
+#[test]
+fn embedded_file_descriptor_set() {
+    let set = FileDescriptorSet::decode(FILE_DESCRIPTOR_SET).unwrap();
+    let names = set.file.iter().map(|file| file.name()).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "google/protobuf/timestamp.proto",
+            "file_descriptor_set.proto"
+        ]
+    );
+    assert_eq!(set.file[1].message_type[0].name(), "Event");
+    assert!(set.file.iter().all(|file| file.source_code_info.is_none()));
+}
+
+#[test]
+fn message_descriptors() {
+    assert_eq!(
+        Event::message_descriptor(),
+        ("file_descriptor_set.Event", FILE_DESCRIPTOR_SET)
+    );
+    assert_eq!(
+        event::Details::message_descriptor(),
+        ("file_descriptor_set.Event.Details", FILE_DESCRIPTOR_SET)
+    );
+}
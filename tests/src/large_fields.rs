@@ -0,0 +1,25 @@
+include!(concat!(env!("OUT_DIR"), "/large_fields.rs"));
+
+use alloc::boxed::Box;
+use core::mem::size_of;
+use prost::Message;
+
+#[test]
+fn large_fields_are_boxed() {
+    let event = Event {
+        kind: Some(event::Kind::Snapshot(Box::new(Snapshot {
+            a: 1,
+            ..Snapshot::default()
+        }))),
+        previous: Some(Box::new(Snapshot::default())),
+    };
+    assert!(size_of::<Event>() < size_of::<Snapshot>());
+
+    let decoded = Event::decode(event.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(event, decoded);
+}
+
+#[test]
+fn small_fields_are_not_boxed() {
+    let _ = event::Kind::Heartbeat(Heartbeat { sequence: 1 });
+}
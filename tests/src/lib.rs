@@ -30,6 +30,8 @@ pub mod no_root_packages;
 pub mod packages;
 pub mod unittest;
 
+#[cfg(test)]
+mod accessors;
 #[cfg(test)]
 mod bootstrap;
 #[cfg(test)]
@@ -39,22 +41,54 @@ mod deprecated_field;
 #[cfg(test)]
 mod derive_copy;
 #[cfg(test)]
+mod derive_eq_hash;
+#[cfg(test)]
+mod enum_helpers;
+#[cfg(test)]
 mod enum_keyword_variant;
 #[cfg(test)]
+mod file_descriptor_set;
+#[cfg(test)]
 mod generic_derive;
 #[cfg(test)]
+mod large_fields;
+#[cfg(test)]
 mod message_encoding;
 #[cfg(test)]
+mod message_generator;
+#[cfg(test)]
+mod naming;
+#[cfg(test)]
 mod no_shadowed_types;
 #[cfg(test)]
 mod no_unused_results;
 #[cfg(test)]
+mod oneof_conversions;
+#[cfg(test)]
+mod required_fields;
+#[cfg(test)]
 mod submessage_without_package;
 #[cfg(test)]
 mod type_names;
+#[cfg(test)]
+mod validate;
+#[cfg(test)]
+mod wrapper_types;
 
 #[cfg(test)]
 mod boxed_field;
+#[cfg(test)]
+mod builders;
+
+// Generated services require the 2018 edition.
+#[cfg(all(test, feature = "std", not(feature = "edition-2015")))]
+mod services;
+
+#[cfg(all(test, feature = "dev-deps"))]
+mod arbitrary;
+
+#[cfg(all(test, feature = "dev-deps"))]
+mod validate_pattern;
 
 #[cfg(test)]
 mod custom_debug;
@@ -0,0 +1,34 @@
+//! Tests for code generated by a `MessageGenerator`.
+
+include!(concat!(env!("OUT_DIR"), "/message_generator.rs"));
+
+#[test]
+fn generated_items() {
+    assert_eq!(
+        Sensor::FIELDS,
+        &["name", "kind", "readings", "room", "floor"]
+    );
+    assert_eq!(Sensor::COMMENTS, "A sensor in a building.");
+    assert_eq!(sensor::Reading::FIELDS, &["value"]);
+    assert_eq!(sensor::Reading::COMMENTS, "A single reading.");
+    assert_eq!(sensor::Kind::VARIANT_COUNT, 3);
+    assert_eq!(Unit::VARIANT_COUNT, 2);
+}
+
+#[test]
+fn package_registry() {
+    assert_eq!(
+        MESSAGES,
+        &[
+            ("message_generator.Sensor.Reading", sensor::Reading::FIELDS),
+            ("message_generator.Sensor", Sensor::FIELDS),
+        ]
+    );
+    assert_eq!(
+        ENUMS,
+        &[
+            ("message_generator.Sensor.Kind", 3),
+            ("message_generator.Unit", 2),
+        ]
+    );
+}
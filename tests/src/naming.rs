@@ -0,0 +1,29 @@
+//! Tests for naming strategies and renames.
+
+include!(concat!(env!("OUT_DIR"), "/naming.v1.rs"));
+
+use alloc::string::ToString;
+
+#[test]
+fn renamed_items() {
+    let shape = Shape {
+        kind: Some(shape::ShapeKind::IsNamed(shape::Kind {
+            name: "square".to_string(),
+        })),
+        color: Color::Gray.into(),
+        sides: 4,
+    };
+    crate::check_message(&shape);
+
+    assert_eq!(Color::Gray.as_str_name(), "COLOR_GREY");
+    assert_eq!(
+        Color::from_str_name("COLOR_UNSPECIFIED"),
+        Some(Color::Unspecified)
+    );
+
+    let circle = Shape {
+        kind: Some(shape::ShapeKind::IsRadius(1.0)),
+        ..Default::default()
+    };
+    crate::check_message(&circle);
+}
@@ -0,0 +1,59 @@
+include!(concat!(env!("OUT_DIR"), "/oneof_conversions.rs"));
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use core::convert::TryFrom;
+
+#[test]
+fn members_convert_into_oneof() {
+    let heartbeat = Heartbeat { sequence: 1 };
+    assert_eq!(
+        event::Kind::from(heartbeat),
+        event::Kind::Heartbeat(heartbeat)
+    );
+    assert_eq!(
+        event::Kind::from("stop".to_string()),
+        event::Kind::Message("stop".to_string())
+    );
+    assert_eq!(
+        event::Kind::from(Event::default()),
+        event::Kind::Nested(Box::new(Event::default()))
+    );
+    assert_eq!(
+        record::Value::from(vec![1, 2]),
+        record::Value::Data(vec![1, 2])
+    );
+}
+
+#[test]
+fn oneof_converts_into_members() {
+    let kind = event::Kind::from(Shutdown {
+        reason: "done".to_string(),
+    });
+    assert_eq!(
+        Shutdown::try_from(kind.clone()),
+        Ok(Shutdown {
+            reason: "done".to_string()
+        })
+    );
+    assert_eq!(Heartbeat::try_from(kind.clone()), Err(kind));
+
+    let kind = event::Kind::Message("stop".to_string());
+    assert_eq!(String::try_from(kind), Ok("stop".to_string()));
+}
+
+#[test]
+fn oneof_converts_into_message() {
+    let event = Event::from(event::Kind::from(Heartbeat { sequence: 2 }));
+    assert_eq!(
+        event,
+        Event {
+            kind: Some(event::Kind::Heartbeat(Heartbeat { sequence: 2 })),
+        }
+    );
+    assert_eq!(
+        Event::try_from(event::Kind::Code(1)).unwrap().kind,
+        Some(event::Kind::Code(1))
+    );
+}
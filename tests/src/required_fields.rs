@@ -0,0 +1,133 @@
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::Message;
+
+include!(concat!(env!("OUT_DIR"), "/required_fields.rs"));
+
+fn address(city: &str) -> Address {
+    Address {
+        city: Some(city.to_string()),
+        street: None,
+    }
+}
+
+fn person() -> Person {
+    Person {
+        id: Some(1),
+        name: Some("alice".to_string()),
+        address: Some(address("Paris")),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn initialized() {
+    let person = person();
+    assert!(person.is_initialized());
+    assert_eq!(person.missing_fields(), Vec::<String>::new());
+    assert_eq!(
+        Person::decode(person.encode_to_vec().as_slice()),
+        Ok(person)
+    );
+}
+
+#[test]
+fn missing_fields() {
+    let mut person = Person {
+        id: None,
+        address: Some(Address::default()),
+        previous_addresses: vec![address("Lyon"), Address::default()],
+        manager: Some(Box::new(Person::default())),
+        contact: Some(person::Contact::MailingAddress(Address::default())),
+        home_address: Some(Address::default()),
+        ..person()
+    };
+    person
+        .addresses
+        .insert("work".to_string(), Address::default());
+
+    assert!(!person.is_initialized());
+    assert_eq!(
+        person.missing_fields(),
+        vec![
+            "id",
+            "address.city",
+            "previous_addresses[1].city",
+            "addresses[\"work\"].city",
+            "manager.id",
+            "manager.name",
+            "manager.address",
+            "homeAddress.city",
+            "mailing_address.city",
+        ]
+    );
+
+    person.contact = Some(person::Contact::Address2(Address::default()));
+    assert_eq!(person.missing_fields()[8], "address_2.city");
+}
+
+#[test]
+fn encode_missing_fields() {
+    let person = Person {
+        id: None,
+        address: Some(Address::default()),
+        ..person()
+    };
+
+    let mut buf = Vec::new();
+    let error = person.encode(&mut buf).unwrap_err();
+    assert_eq!(error.missing_field_count(), 2);
+    assert_eq!(
+        error.to_string(),
+        "failed to encode Protobuf message; 2 required fields are not set"
+    );
+    let error = person.encode_length_delimited(&mut buf).unwrap_err();
+    assert_eq!(error.missing_field_count(), 2);
+    assert!(buf.is_empty());
+    let error = person.try_encode_to_vec().unwrap_err();
+    assert_eq!(error.missing_field_count(), 2);
+    let error = person.try_encode_length_delimited_to_vec().unwrap_err();
+    assert_eq!(error.missing_field_count(), 2);
+
+    person.encode_partial(&mut buf).unwrap();
+    assert_eq!(buf, person.encode_to_vec());
+    assert_eq!(Person::decode_partial(buf.as_slice()), Ok(person));
+
+    let person = self::person();
+    let mut buf = Vec::new();
+    person.encode(&mut buf).unwrap();
+    assert_eq!(person.try_encode_to_vec(), Ok(buf.clone()));
+    assert_eq!(
+        person.try_encode_length_delimited_to_vec(),
+        Ok(person.encode_length_delimited_to_vec())
+    );
+    assert_eq!(Person::decode(buf.as_slice()), Ok(person));
+}
+
+#[test]
+fn decode_missing_fields() {
+    let person = Person {
+        name: None,
+        address: Some(Address::default()),
+        ..person()
+    };
+    let buf = person.encode_to_vec();
+
+    let error = Person::decode(buf.as_slice()).unwrap_err();
+    assert_eq!(error.missing_fields(), ["name", "address.city"]);
+    assert_eq!(
+        error.to_string(),
+        "failed to decode Protobuf message: missing required fields: name, address.city"
+    );
+    let error = Person::decode_length_delimited(person.encode_length_delimited_to_vec().as_slice())
+        .unwrap_err();
+    assert_eq!(error.missing_fields(), ["name", "address.city"]);
+
+    let decoded = Person::decode_partial(buf.as_slice()).unwrap();
+    assert_eq!(decoded, person);
+    // The default value of a missing required field is still available.
+    assert_eq!(decoded.name(), "anonymous");
+}
@@ -0,0 +1,184 @@
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::future::{poll_fn, Future};
+use core::pin::Pin;
+use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+use prost::service::{BoxFuture, Code, Router, Status, Stream, Streaming, Transport};
+
+include!(concat!(env!("OUT_DIR"), "/services.rs"));
+
+/// Runs a future to completion, which must not wait on anything, as every call in these tests is
+/// in-process.
+fn now<F: Future>(future: F) -> F::Output {
+    fn raw_waker() -> RawWaker {
+        fn clone(_: *const ()) -> RawWaker {
+            raw_waker()
+        }
+        fn noop(_: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+        RawWaker::new(core::ptr::null(), &VTABLE)
+    }
+    // SAFETY: the waker does nothing.
+    let waker = unsafe { Waker::from_raw(raw_waker()) };
+    let mut future = Box::pin(future);
+    match future.as_mut().poll(&mut Context::from_waker(&waker)) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("future is pending"),
+    }
+}
+
+async fn collect<T>(mut stream: Streaming<T>) -> Result<Vec<T>, Status> {
+    let mut items = Vec::new();
+    while let Some(item) = poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+        items.push(item?);
+    }
+    Ok(items)
+}
+
+/// A stream of the given items.
+struct Iter<I>(I);
+
+impl<I: Iterator + Unpin> Stream for Iter<I> {
+    type Item = I::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<I::Item>> {
+        Poll::Ready(self.0.next())
+    }
+}
+
+fn numbers(values: &[i64]) -> Vec<Number> {
+    values.iter().map(|&value| Number { value }).collect()
+}
+
+struct Calc;
+
+impl Calculator for Calc {
+    fn increment(&self, request: Number) -> BoxFuture<'_, Result<Number, Status>> {
+        Box::pin(async move {
+            let value = request
+                .value
+                .checked_add(1)
+                .ok_or_else(|| Status::new(Code::OutOfRange, "overflow"))?;
+            Ok(Number { value })
+        })
+    }
+
+    fn countdown(&self, request: Number) -> BoxFuture<'_, Result<Streaming<Number>, Status>> {
+        Box::pin(async move {
+            let numbers = (0..=request.value).rev().map(|value| Ok(Number { value }));
+            Ok(Box::pin(Iter(numbers)) as Streaming<Number>)
+        })
+    }
+
+    fn sum(&self, requests: Streaming<Number>) -> BoxFuture<'_, Result<Number, Status>> {
+        Box::pin(async move {
+            let value = collect(requests).await?.iter().map(|n| n.value).sum();
+            Ok(Number { value })
+        })
+    }
+
+    fn running_total(
+        &self,
+        requests: Streaming<Number>,
+    ) -> BoxFuture<'_, Result<Streaming<Number>, Status>> {
+        Box::pin(async move {
+            let mut total = 0;
+            let totals = collect(requests)
+                .await?
+                .into_iter()
+                .map(|n| {
+                    total += n.value;
+                    Ok(Number { value: total })
+                })
+                .collect::<Vec<_>>();
+            Ok(Box::pin(Iter(totals.into_iter())) as Streaming<Number>)
+        })
+    }
+}
+
+#[test]
+fn in_process_calls() {
+    let client = CalculatorClient::new(CalculatorServer::new(Calc));
+    now(async {
+        assert_eq!(
+            client.increment(Number { value: 1 }).await,
+            Ok(Number { value: 2 })
+        );
+        assert_eq!(
+            client
+                .increment(Number { value: i64::MAX })
+                .await
+                .unwrap_err()
+                .code(),
+            Code::OutOfRange
+        );
+
+        let countdown = client.countdown(Number { value: 2 }).await.unwrap();
+        assert_eq!(collect(countdown).await, Ok(numbers(&[2, 1, 0])));
+
+        let requests = Iter(numbers(&[1, 2, 3]).into_iter());
+        assert_eq!(client.sum(requests).await, Ok(Number { value: 6 }));
+
+        let requests = Iter(numbers(&[1, 2, 3]).into_iter());
+        let totals = client.running_total(requests).await.unwrap();
+        assert_eq!(collect(totals).await, Ok(numbers(&[1, 3, 6])));
+    });
+
+    let server = client.into_inner();
+    let _: &Calc = server.get_ref();
+    let _: Calc = server.into_inner();
+}
+
+struct NoMethods;
+
+impl Empty for NoMethods {}
+
+#[test]
+fn service_without_methods() {
+    let client = EmptyClient::new(EmptyServer::new(NoMethods));
+    let server = client.into_inner();
+    let status = now(server.unary("/services.Empty/Method", vec![].into())).unwrap_err();
+    assert_eq!(status.code(), Code::Unimplemented);
+    let _: &NoMethods = server.get_ref();
+    let _: NoMethods = server.into_inner();
+}
+
+#[test]
+fn routing() {
+    let router = Router::new()
+        .add_service(CalculatorServer::new(Calc))
+        .add_service(EmptyServer::new(NoMethods));
+    let client = CalculatorClient::new(&router);
+    now(async {
+        assert_eq!(
+            client.increment(Number { value: 41 }).await,
+            Ok(Number { value: 42 })
+        );
+
+        let status = router
+            .unary("/services.Calculator/Unknown", vec![].into())
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unimplemented);
+        assert_eq!(
+            status.message(),
+            "unknown method /services.Calculator/Unknown"
+        );
+
+        // Methods are only found by the matching kind of call.
+        let status = router
+            .unary("/services.Calculator/Sum", vec![].into())
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unimplemented);
+
+        // Invalid requests are rejected.
+        let status = router
+            .unary("/services.Calculator/Increment", vec![0x08].into())
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    });
+}
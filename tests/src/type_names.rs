@@ -1,4 +1,9 @@
-use prost::Name;
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::{DynName, Name};
+use prost_types::{Any, TypeRegistry};
 
 include!(concat!(env!("OUT_DIR"), "/type_names.rs"));
 
@@ -19,3 +24,30 @@ fn valid_type_names() {
     assert_eq!("type_names.Baz", Baz::full_name());
     assert_eq!("/type_names.Baz", Baz::type_url());
 }
+
+#[test]
+fn dyn_type_names() {
+    let messages: Vec<Box<dyn DynName>> = vec![Box::new(Foo {}), Box::new(foo::Bar {})];
+    assert_eq!(messages[0].type_name(), "type_names.Foo");
+    assert_eq!(messages[1].type_url_dyn(), "tests/type_names.Foo.Bar");
+    assert!(messages[0].is::<Foo>());
+    assert_eq!(messages[1].downcast_ref::<foo::Bar>(), Some(&foo::Bar {}));
+    assert!(messages[1].downcast_ref::<Baz>().is_none());
+}
+
+#[test]
+fn type_registry() {
+    let mut registry = TypeRegistry::new();
+    register_types(&mut registry);
+    assert_eq!(
+        registry.type_names().collect::<Vec<_>>(),
+        ["type_names.Baz", "type_names.Foo", "type_names.Foo.Bar"]
+    );
+
+    let any = Any::from_msg(&foo::Bar {}).unwrap();
+    assert!(any.is::<foo::Bar>());
+    assert!(!any.is::<Foo>());
+    let message = registry.unpack(&any).unwrap();
+    assert_eq!(message.type_name(), "type_names.Foo.Bar");
+    assert!(message.is::<foo::Bar>());
+}
@@ -0,0 +1,170 @@
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::Validate;
+
+include!(concat!(env!("OUT_DIR"), "/validate.rs"));
+
+fn valid_user() -> User {
+    User {
+        name: "u_alice".to_string(),
+        age: 30,
+        email: Some("alice@example.com".to_string()),
+        role: Role::Admin as i32,
+        tags: vec!["a".to_string(), "b".to_string()],
+        address: Some(Address {
+            city: "Paris".to_string(),
+        }),
+        token: vec![1, 2, 3, 4],
+        score: 2.5,
+        ..Default::default()
+    }
+}
+
+fn violations(user: &User) -> Vec<(String, String)> {
+    user.validate()
+        .unwrap_err()
+        .into_violations()
+        .into_iter()
+        .map(|violation| (violation.field_path, violation.constraint_id.into_owned()))
+        .collect()
+}
+
+#[test]
+fn valid_message() {
+    assert_eq!(valid_user().validate(), Ok(()));
+}
+
+#[test]
+fn scalar_constraints() {
+    let user = User {
+        name: "bob".to_string(),
+        age: 150,
+        email: Some("bob".to_string()),
+        role: 7,
+        token: vec![1],
+        score: 0.5,
+        ..valid_user()
+    };
+    assert_eq!(
+        violations(&user),
+        vec![
+            ("name".to_string(), "string.prefix".to_string()),
+            ("age".to_string(), "int32.gte_lt".to_string()),
+            ("email".to_string(), "string.contains".to_string()),
+            ("role".to_string(), "enum.defined_only".to_string()),
+            ("token".to_string(), "bytes.len".to_string()),
+            ("score".to_string(), "double.gt_lt_exclusive".to_string()),
+        ]
+    );
+
+    let user = User {
+        name: "u_".to_string(),
+        role: Role::Unspecified as i32,
+        email: None,
+        score: -1.0,
+        ..valid_user()
+    };
+    assert_eq!(
+        violations(&user),
+        vec![
+            ("name".to_string(), "string.min_len".to_string()),
+            ("role".to_string(), "enum.not_in".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn collection_constraints() {
+    let user = User {
+        tags: vec![
+            "a".to_string(),
+            "".to_string(),
+            "a".to_string(),
+            "b".to_string(),
+        ],
+        ..valid_user()
+    };
+    assert_eq!(
+        violations(&user),
+        vec![
+            ("tags".to_string(), "repeated.max_items".to_string()),
+            ("tags".to_string(), "repeated.unique".to_string()),
+            ("tags[1]".to_string(), "string.min_len".to_string()),
+        ]
+    );
+
+    let mut user = valid_user();
+    for label in ["home", "work", "moon"] {
+        user.addresses.insert(label.to_string(), Address::default());
+    }
+    let mut found = violations(&user);
+    found.sort();
+    assert_eq!(
+        found,
+        vec![
+            ("addresses".to_string(), "map.max_pairs".to_string()),
+            (
+                "addresses[\"home\"].city".to_string(),
+                "required".to_string()
+            ),
+            ("addresses[\"moon\"]".to_string(), "string.in".to_string()),
+            (
+                "addresses[\"moon\"].city".to_string(),
+                "required".to_string()
+            ),
+            (
+                "addresses[\"work\"].city".to_string(),
+                "required".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn nested_messages() {
+    let user = User {
+        address: None,
+        previous_addresses: vec![
+            Address {
+                city: "Lyon".to_string(),
+            },
+            Address::default(),
+        ],
+        contact: Some(user::Contact::MailingAddress(Address::default())),
+        ..valid_user()
+    };
+    assert_eq!(
+        violations(&user),
+        vec![
+            ("address".to_string(), "required".to_string()),
+            (
+                "previous_addresses[1].city".to_string(),
+                "required".to_string()
+            ),
+            ("mailing_address.city".to_string(), "required".to_string()),
+        ]
+    );
+
+    let user = User {
+        contact: Some(user::Contact::Phone("123".to_string())),
+        ..valid_user()
+    };
+    assert_eq!(
+        violations(&user),
+        vec![("phone".to_string(), "string.min_len".to_string())]
+    );
+}
+
+#[test]
+fn error_display() {
+    let user = User {
+        address: Some(Address::default()),
+        ..valid_user()
+    };
+    assert_eq!(
+        user.validate().unwrap_err().to_string(),
+        "validation failed: address.city: value is required [required]"
+    );
+}
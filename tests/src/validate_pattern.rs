@@ -0,0 +1,39 @@
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::Validate;
+
+include!(concat!(env!("OUT_DIR"), "/validate_pattern.rs"));
+
+fn violations(account: &Account) -> Vec<(String, String)> {
+    match account.validate() {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors
+            .into_violations()
+            .into_iter()
+            .map(|violation| (violation.field_path, violation.constraint_id.into_owned()))
+            .collect(),
+    }
+}
+
+#[test]
+fn pattern_constraints() {
+    let account = Account {
+        handle: "alice_1".to_string(),
+        codes: vec!["123".to_string(), "456".to_string()],
+    };
+    assert_eq!(violations(&account), vec![]);
+
+    let account = Account {
+        handle: "1alice".to_string(),
+        codes: vec!["123".to_string(), "12".to_string()],
+    };
+    assert_eq!(
+        violations(&account),
+        vec![
+            ("handle".to_string(), "string.pattern".to_string()),
+            ("codes[1]".to_string(), "string.pattern".to_string()),
+        ]
+    );
+}
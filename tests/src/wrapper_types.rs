@@ -0,0 +1,84 @@
+//! Tests for wrapper well-known types mapped to Rust types, while compiling the well-known types.
+
+include!(concat!(env!("OUT_DIR"), "/wrapper_types.rs"));
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use prost::bytes::Bytes;
+use prost::Message;
+
+/// The wrapper messages, as they would be generated without `map_wrapper_types`.
+#[derive(Clone, PartialEq, Message)]
+struct Int32Value {
+    #[prost(int32, tag = "1")]
+    value: i32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct StringValue {
+    #[prost(string, tag = "1")]
+    value: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct BytesValue {
+    #[prost(bytes = "vec", tag = "1")]
+    value: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct BoolValue {
+    #[prost(bool, tag = "1")]
+    value: bool,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct WrapperMessages {
+    #[prost(message, optional, tag = "1")]
+    int32: Option<Int32Value>,
+    #[prost(message, optional, tag = "2")]
+    string: Option<StringValue>,
+    #[prost(message, optional, tag = "3")]
+    bytes: Option<BytesValue>,
+    #[prost(message, repeated, tag = "4")]
+    bools: Vec<BoolValue>,
+}
+
+#[test]
+fn wrapper_types_are_mapped() {
+    let wrappers = Wrappers {
+        int32: Some(0),
+        string: Some("value".into()),
+        bytes: Some(Bytes::from_static(b"value")),
+        bools: vec![true, false],
+    };
+    crate::check_message(&wrappers);
+
+    let wrapper_messages = WrapperMessages {
+        int32: Some(Int32Value { value: 0 }),
+        string: Some(StringValue {
+            value: "value".into(),
+        }),
+        bytes: Some(BytesValue {
+            value: b"value".to_vec(),
+        }),
+        bools: vec![BoolValue { value: true }, BoolValue { value: false }],
+    };
+    assert_eq!(wrappers.encode_to_vec(), wrapper_messages.encode_to_vec());
+    assert_eq!(
+        WrapperMessages::decode(&*wrappers.encode_to_vec()).unwrap(),
+        wrapper_messages
+    );
+}
+
+#[test]
+fn empty_wrappers_are_not_none() {
+    let wrappers = Wrappers {
+        string: Some(Default::default()),
+        ..Default::default()
+    };
+    let decoded = Wrappers::decode(&*wrappers.encode_to_vec()).unwrap();
+    assert_eq!(decoded.string.as_deref(), Some(""));
+    assert_eq!(decoded.int32, None);
+}